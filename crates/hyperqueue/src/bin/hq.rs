@@ -8,8 +8,8 @@ use cli_table::ColorChoice;
 use hyperqueue::client::commands::autoalloc::{command_autoalloc, AutoAllocOpts};
 use hyperqueue::client::commands::event::{command_event_log, EventLogOpts};
 use hyperqueue::client::commands::job::{
-    cancel_job, output_job_cat, output_job_detail, output_job_list, JobCancelOpts, JobCatOpts,
-    JobInfoOpts, JobListOpts,
+    cancel_job, output_job_cat, output_job_detail, output_job_list, output_job_stats,
+    JobCancelOpts, JobCatOpts, JobInfoOpts, JobListOpts, JobStatsOpts,
 };
 use hyperqueue::client::commands::log::{command_log, LogOpts};
 use hyperqueue::client::commands::server::{command_server, ServerOpts};
@@ -208,6 +208,9 @@ enum JobCommand {
     List(JobListOpts),
     /// Display detailed information of the selected job
     Info(JobInfoOpts),
+    /// Display statistics of task execution of the selected job(s), e.g. the distribution
+    /// of task durations, throughput or an estimated time to completion
+    Stats(JobStatsOpts),
     /// Cancel a specific job
     Cancel(JobCancelOpts),
     /// Shows task(s) streams(stdout, stderr) of a specific job
@@ -537,6 +540,9 @@ async fn main() -> hyperqueue::Result<()> {
         SubCommand::Job(JobOpts {
             subcmd: JobCommand::Info(opts),
         }) => command_job_detail(&gsettings, opts).await,
+        SubCommand::Job(JobOpts {
+            subcmd: JobCommand::Stats(opts),
+        }) => output_job_stats(&gsettings, opts).await,
         SubCommand::Job(JobOpts {
             subcmd: JobCommand::Cat(opts),
         }) => command_job_cat(&gsettings, opts).await,
@@ -565,7 +571,7 @@ async fn main() -> hyperqueue::Result<()> {
         SubCommand::Dashboard(opts) => command_dashboard_start(&gsettings, opts).await,
         SubCommand::Log(opts) => command_log(&gsettings, opts),
         SubCommand::AutoAlloc(opts) => command_autoalloc(&gsettings, opts).await,
-        SubCommand::EventLog(opts) => command_event_log(opts),
+        SubCommand::EventLog(opts) => command_event_log(&gsettings, opts).await,
         SubCommand::GenerateCompletion(opts) => generate_completion(opts),
     };
 
@@ -6,9 +6,12 @@ use crate::client::commands::worker::ArgServerLostPolicy;
 use crate::client::globalsettings::GlobalSettings;
 use crate::client::utils::PassThroughArgument;
 use crate::common::manager::info::ManagerType;
+use crate::common::schedule::{parse_time_window_text, ActiveSchedule, TimeWindow};
 use crate::common::utils::time::{ArgDuration, ExtendedArgDuration};
 use crate::rpc_call;
-use crate::server::autoalloc::{Allocation, AllocationState, QueueId};
+use crate::server::autoalloc::{
+    Allocation, AllocationId, AllocationState, NodeHourBudget, QueueId,
+};
 use crate::server::bootstrap::get_client_session;
 use crate::transfer::connection::ClientSession;
 use crate::transfer::messages::{
@@ -28,6 +31,11 @@ enum AutoAllocCommand {
     List,
     /// Display allocations of the specified allocation queue
     Info(AllocationsOpts),
+    /// Display finished allocations of the specified allocation queue, together with the
+    /// last lines of their output
+    History(HistoryOpts),
+    /// Display the output (stdout and stderr) of the specified allocation
+    Log(LogOpts),
     /// Add new allocation queue
     Add(AddQueueOpts),
     /// Pause an existing allocation queue.
@@ -81,6 +89,32 @@ impl FromStr for Backlog {
     }
 }
 
+/// Amount of node-hours, e.g. `5000node-h`.
+struct NodeHours(f64);
+
+impl FromStr for NodeHours {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s
+            .strip_suffix("node-hours")
+            .or_else(|| s.strip_suffix("node-h"))
+            .unwrap_or(s);
+        let value: f64 = value.trim().parse().map_err(|_| {
+            anyhow::anyhow!("Invalid node-hour budget `{s}`, use e.g. `5000node-h`")
+        })?;
+        if !value.is_finite() || value <= 0.0 {
+            Err(anyhow::anyhow!(
+                "Node-hour budget has to be a positive number"
+            ))
+        } else {
+            Ok(NodeHours(value))
+        }
+    }
+}
+
+crate::arg_wrapper!(ArgTimeWindow, TimeWindow, parse_time_window_text);
+
 #[derive(Parser)]
 #[clap(trailing_var_arg(true))]
 struct SharedQueueOpts {
@@ -100,6 +134,12 @@ struct SharedQueueOpts {
     #[clap(long)]
     max_worker_count: Option<u32>,
 
+    /// Minimum number of workers that should be queued or running at any given time in this
+    /// queue, even if there are no waiting tasks. Up to this number of workers will not be stopped
+    /// because of their idle timeout.
+    #[clap(long, default_value = "0")]
+    min_workers: u32,
+
     /// Name of the allocation queue (for debug purposes only)
     #[clap(long, short)]
     name: Option<String>,
@@ -120,6 +160,32 @@ struct SharedQueueOpts {
     #[clap(long)]
     idle_timeout: Option<ArgDuration>,
 
+    /// Maximum amount of node-hours that can be consumed by allocations of this queue
+    /// (e.g. `5000node-h`). When the budget is exhausted, no new allocations will be submitted.
+    #[clap(long)]
+    budget: Option<NodeHours>,
+
+    /// Sliding time window (e.g. `7d`) in which is the node-hour budget accounted.
+    /// If not set, the budget is accounted over the whole lifetime of the queue.
+    #[clap(long, requires = "budget")]
+    budget_window: Option<ArgDuration>,
+
+    /// Time window in which new allocations can be submitted, e.g. `Mon-Fri 20:00-06:00`,
+    /// `Sat,Sun` or `22:00-04:00`. Times are in the local time zone of the server.
+    /// Can be used multiple times; allocations can be submitted if any of the windows is active.
+    #[clap(long, multiple_occurrences(true))]
+    active: Vec<ArgTimeWindow>,
+
+    /// Shorten the time limit of submitted allocations so that they end before the current
+    /// active time window closes.
+    #[clap(long, requires = "active")]
+    cap_time_limit: bool,
+
+    /// Submit allocations that are created at the same time as a single job array
+    /// (Slurm `--array`, PBS `-J`). Each array element is still tracked as a separate allocation.
+    #[clap(long)]
+    job_arrays: bool,
+
     /// Disables dry-run, which submits an allocation with the specified parameters to verify
     /// whether the parameters are correct.
     // This flag currently cannot be in [`AddQueueOpts`] because of a bug in clap:
@@ -162,6 +228,18 @@ struct AllocationsOpts {
     filter: Option<AllocationStateFilter>,
 }
 
+#[derive(Parser)]
+struct HistoryOpts {
+    /// ID of the allocation queue
+    queue: QueueId,
+}
+
+#[derive(Parser)]
+struct LogOpts {
+    /// ID of the allocation
+    allocation_id: AllocationId,
+}
+
 #[derive(clap::ArgEnum, Clone, Eq, PartialEq)]
 enum AllocationStateFilter {
     Queued,
@@ -198,6 +276,12 @@ pub async fn command_autoalloc(
         AutoAllocCommand::Info(opts) => {
             print_allocations(gsettings, session, opts).await?;
         }
+        AutoAllocCommand::History(opts) => {
+            print_allocation_history(gsettings, session, opts).await?;
+        }
+        AutoAllocCommand::Log(opts) => {
+            print_allocation_log(gsettings, session, opts).await?;
+        }
         AutoAllocCommand::Remove(opts) => {
             remove_queue(session, opts.queue_id, opts.force).await?;
         }
@@ -214,18 +298,24 @@ pub async fn command_autoalloc(
     Ok(())
 }
 
-fn args_to_params(args: SharedQueueOpts) -> AllocationQueueParams {
+fn args_to_params(args: SharedQueueOpts) -> anyhow::Result<AllocationQueueParams> {
     let SharedQueueOpts {
         backlog,
         time_limit,
         workers_per_alloc,
         max_worker_count,
+        min_workers,
         name,
         cpus,
         resource,
         idle_timeout,
         additional_args,
         on_server_lost,
+        budget,
+        budget_window,
+        active,
+        cap_time_limit,
+        job_arrays,
         no_dry_run: _,
     } = args;
 
@@ -238,7 +328,15 @@ wasted allocation duration."
         }
     }
 
-    AllocationQueueParams {
+    if let Some(max_worker_count) = max_worker_count {
+        if min_workers > max_worker_count {
+            return Err(anyhow::anyhow!(
+                "Minimum number of workers ({min_workers}) cannot be larger than the maximum number of workers ({max_worker_count})"
+            ));
+        }
+    }
+
+    Ok(AllocationQueueParams {
         workers_per_alloc,
         backlog: backlog.0,
         timelimit: time_limit.unpack(),
@@ -247,15 +345,29 @@ wasted allocation duration."
         worker_cpu_arg: cpus.map(|v| v.into()),
         worker_resources_args: resource.into_iter().map(|v| v.into()).collect(),
         max_worker_count,
+        min_workers,
         on_server_lost: on_server_lost.into(),
         idle_timeout: idle_timeout.map(|d| d.unpack()),
-    }
+        budget: budget.map(|budget| NodeHourBudget {
+            limit: budget.0,
+            window: budget_window.map(|d| d.unpack()),
+        }),
+        active_schedule: if active.is_empty() {
+            None
+        } else {
+            Some(ActiveSchedule::new(
+                active.into_iter().map(|w| w.unpack()).collect(),
+            ))
+        },
+        cap_timelimit: cap_time_limit,
+        job_arrays,
+    })
 }
 
 async fn dry_run_command(mut session: ClientSession, opts: DryRunOpts) -> anyhow::Result<()> {
     let (manager, parameters) = match opts.subcmd {
-        DryRunCommand::Pbs(params) => (ManagerType::Pbs, args_to_params(params)),
-        DryRunCommand::Slurm(params) => (ManagerType::Slurm, args_to_params(params)),
+        DryRunCommand::Pbs(params) => (ManagerType::Pbs, args_to_params(params)?),
+        DryRunCommand::Slurm(params) => (ManagerType::Slurm, args_to_params(params)?),
     };
     let message = FromClientMessage::AutoAlloc(AutoAllocRequest::DryRun {
         manager,
@@ -278,11 +390,11 @@ async fn add_queue(mut session: ClientSession, opts: AddQueueOpts) -> anyhow::Re
     let (manager, parameters, dry_run) = match opts.subcmd {
         AddQueueCommand::Pbs(params) => {
             let no_dry_run = params.no_dry_run;
-            (ManagerType::Pbs, args_to_params(params), !no_dry_run)
+            (ManagerType::Pbs, args_to_params(params)?, !no_dry_run)
         }
         AddQueueCommand::Slurm(params) => {
             let no_dry_run = params.no_dry_run;
-            (ManagerType::Slurm, args_to_params(params), !no_dry_run)
+            (ManagerType::Slurm, args_to_params(params)?, !no_dry_run)
         }
     };
 
@@ -355,6 +467,38 @@ async fn print_allocations(
     Ok(())
 }
 
+async fn print_allocation_history(
+    gsettings: &GlobalSettings,
+    mut session: ClientSession,
+    opts: HistoryOpts,
+) -> anyhow::Result<()> {
+    let message = FromClientMessage::AutoAlloc(AutoAllocRequest::History {
+        queue_id: opts.queue,
+    });
+    let history = rpc_call!(session.connection(), message,
+        ToClientMessage::AutoAllocResponse(AutoAllocResponse::History(history)) => history
+    )
+    .await?;
+    gsettings.printer().print_allocation_history(history);
+    Ok(())
+}
+
+async fn print_allocation_log(
+    gsettings: &GlobalSettings,
+    mut session: ClientSession,
+    opts: LogOpts,
+) -> anyhow::Result<()> {
+    let message = FromClientMessage::AutoAlloc(AutoAllocRequest::Log {
+        allocation_id: opts.allocation_id,
+    });
+    let log = rpc_call!(session.connection(), message,
+        ToClientMessage::AutoAllocResponse(AutoAllocResponse::Log(log)) => log
+    )
+    .await?;
+    gsettings.printer().print_allocation_log(log);
+    Ok(())
+}
+
 async fn pause_queue(mut session: ClientSession, opts: PauseQueueOpts) -> anyhow::Result<()> {
     let PauseQueueOpts { queue_id } = opts;
     let message = FromClientMessage::AutoAlloc(AutoAllocRequest::PauseQueue { queue_id });
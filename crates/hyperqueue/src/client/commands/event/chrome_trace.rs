@@ -0,0 +1,524 @@
+//! Conversion of events into the [Trace Event Format], which can be displayed e.g. by
+//! [Perfetto](https://ui.perfetto.dev) or `chrome://tracing`.
+//!
+//! Each worker is displayed as a process and each task that runs concurrently on a worker
+//! occupies a separate thread (slot) of that process. Allocations are displayed as asynchronous
+//! events of a separate process, with one thread per allocation queue.
+//!
+//! [Trace Event Format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use crate::server::autoalloc::{AllocationId, QueueId};
+use crate::server::event::events::{MonitoringEventPayload, TaskIds};
+use crate::server::event::MonitoringEvent;
+use crate::transfer::messages::JobDescription;
+use crate::{JobId, JobTaskId, Map, Set, TakoTaskId, WorkerId};
+use tako::InstanceId;
+
+/// Process that contains allocation events (worker IDs start at 1).
+const ALLOCATION_PID: u32 = 0;
+
+struct RunningTask {
+    worker_id: WorkerId,
+    slot: usize,
+    instance_id: InstanceId,
+    start: SystemTime,
+}
+
+#[derive(Default)]
+struct WorkerSlots {
+    /// Tasks that currently occupy individual slots of the worker
+    slots: Vec<Option<TakoTaskId>>,
+}
+
+/// Converts events into Trace Event Format JSON in a streaming fashion.
+///
+/// Tasks are only written once they end, tasks and allocations that have not ended when
+/// [`ChromeTraceWriter::finish`] is called are ended at the time of the last event.
+pub struct ChromeTraceWriter<W: Write> {
+    output: W,
+    event_count: usize,
+    last_time: Option<SystemTime>,
+    /// Job and job task IDs of tasks from known jobs
+    task_ids: Map<TakoTaskId, (JobId, JobTaskId)>,
+    workers: Map<WorkerId, WorkerSlots>,
+    running_tasks: Map<TakoTaskId, RunningTask>,
+    /// Allocations that have been queued or started and have not yet finished
+    allocations: Map<AllocationId, (QueueId, bool)>,
+    allocation_queues: Set<QueueId>,
+}
+
+impl<W: Write> ChromeTraceWriter<W> {
+    pub fn new(mut output: W) -> std::io::Result<Self> {
+        write!(output, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")?;
+        let mut writer = Self {
+            output,
+            event_count: 0,
+            last_time: None,
+            task_ids: Default::default(),
+            workers: Default::default(),
+            running_tasks: Default::default(),
+            allocations: Default::default(),
+            allocation_queues: Default::default(),
+        };
+        writer.write(json!({
+            "ph": "M",
+            "name": "process_name",
+            "pid": ALLOCATION_PID,
+            "args": { "name": "Allocations" }
+        }))?;
+        Ok(writer)
+    }
+
+    pub fn add_event(&mut self, event: MonitoringEvent) -> std::io::Result<()> {
+        let time = event.time;
+        self.last_time = Some(self.last_time.map_or(time, |last| last.max(time)));
+
+        match event.payload {
+            MonitoringEventPayload::JobCreated(job_id, info) => {
+                let base = info.base_task_id.as_num();
+                let job_task_ids: Vec<JobTaskId> = match &info.job_desc {
+                    JobDescription::Array { ids, .. } => ids.iter().map(JobTaskId::new).collect(),
+                    JobDescription::Graph { tasks } => tasks.iter().map(|task| task.id).collect(),
+                };
+                for (index, job_task_id) in job_task_ids.into_iter().enumerate() {
+                    self.task_ids
+                        .insert(TakoTaskId::new(base + index as u32), (job_id, job_task_id));
+                }
+            }
+            MonitoringEventPayload::WorkerConnected(worker_id, configuration) => {
+                self.add_worker(worker_id, Some(&configuration.hostname))?;
+                self.write(json!({
+                    "ph": "i",
+                    "s": "p",
+                    "name": "Worker connected",
+                    "pid": worker_id,
+                    "tid": 0,
+                    "ts": timestamp(time),
+                }))?;
+            }
+            MonitoringEventPayload::WorkerLost(worker_id, reason) => {
+                let tasks: Vec<TakoTaskId> = self
+                    .running_tasks
+                    .iter()
+                    .filter(|(_, task)| task.worker_id == worker_id)
+                    .map(|(task_id, _)| *task_id)
+                    .collect();
+                for task_id in tasks {
+                    self.end_task(task_id, time, "worker-lost", None)?;
+                }
+                self.write(json!({
+                    "ph": "i",
+                    "s": "p",
+                    "name": "Worker lost",
+                    "pid": worker_id,
+                    "tid": 0,
+                    "ts": timestamp(time),
+                    "args": { "reason": reason }
+                }))?;
+            }
+            MonitoringEventPayload::TaskStarted {
+                task_id,
+                instance_id,
+                worker_id,
+            } => {
+                // The task might have been started again without its previous instance ending
+                self.end_task(task_id, time, "restarted", None)?;
+                self.add_worker(worker_id, None)?;
+                let slots = &mut self.workers.get_mut(&worker_id).unwrap().slots;
+                let slot = match slots.iter().position(|slot| slot.is_none()) {
+                    Some(slot) => slot,
+                    None => {
+                        slots.push(None);
+                        let slot = slots.len() - 1;
+                        self.write(json!({
+                            "ph": "M",
+                            "name": "thread_name",
+                            "pid": worker_id,
+                            "tid": slot + 1,
+                            "args": { "name": format!("Slot {}", slot + 1) }
+                        }))?;
+                        slot
+                    }
+                };
+                self.workers.get_mut(&worker_id).unwrap().slots[slot] = Some(task_id);
+                self.running_tasks.insert(
+                    task_id,
+                    RunningTask {
+                        worker_id,
+                        slot,
+                        instance_id,
+                        start: time,
+                    },
+                );
+            }
+            MonitoringEventPayload::TaskFinished { task_id, .. } => {
+                self.end_task(task_id, time, "finished", None)?;
+            }
+            MonitoringEventPayload::TaskFailed {
+                task: TaskIds { task_id, .. },
+                error,
+                ..
+            } => {
+                self.end_task(task_id, time, "failed", Some(error))?;
+            }
+            MonitoringEventPayload::TaskCanceled(TaskIds { task_id, .. }) => {
+                self.end_task(task_id, time, "canceled", None)?;
+            }
+            MonitoringEventPayload::TaskRestarted {
+                task: TaskIds { task_id, .. },
+                ..
+            } => {
+                self.end_task(task_id, time, "restarted", None)?;
+            }
+            MonitoringEventPayload::AllocationQueued {
+                queue_id,
+                allocation_id,
+                worker_count,
+            } => {
+                self.add_allocation_queue(queue_id)?;
+                self.write(json!({
+                    "ph": "b",
+                    "cat": "allocation",
+                    "name": format!("Allocation {allocation_id}"),
+                    "id": allocation_id,
+                    "pid": ALLOCATION_PID,
+                    "tid": queue_id,
+                    "ts": timestamp(time),
+                    "args": { "queue": queue_id, "worker-count": worker_count }
+                }))?;
+                self.allocations.insert(allocation_id, (queue_id, false));
+            }
+            MonitoringEventPayload::AllocationStarted(queue_id, allocation_id) => {
+                self.add_allocation_queue(queue_id)?;
+                if !self.allocations.contains_key(&allocation_id) {
+                    // The allocation was queued before the log has started
+                    self.write(json!({
+                        "ph": "b",
+                        "cat": "allocation",
+                        "name": format!("Allocation {allocation_id}"),
+                        "id": allocation_id,
+                        "pid": ALLOCATION_PID,
+                        "tid": queue_id,
+                        "ts": timestamp(time),
+                        "args": { "queue": queue_id }
+                    }))?;
+                }
+                self.write(json!({
+                    "ph": "b",
+                    "cat": "allocation",
+                    "name": "Running",
+                    "id": allocation_id,
+                    "pid": ALLOCATION_PID,
+                    "tid": queue_id,
+                    "ts": timestamp(time),
+                }))?;
+                self.allocations.insert(allocation_id, (queue_id, true));
+            }
+            MonitoringEventPayload::AllocationFinished(_, allocation_id) => {
+                self.end_allocation(allocation_id, time)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Ends all running tasks and allocations and finishes the JSON document.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        if let Some(time) = self.last_time {
+            let tasks: Vec<TakoTaskId> = self.running_tasks.keys().copied().collect();
+            for task_id in tasks {
+                self.end_task(task_id, time, "running", None)?;
+            }
+            let allocations: Vec<AllocationId> = self.allocations.keys().cloned().collect();
+            for allocation_id in allocations {
+                self.end_allocation(allocation_id, time)?;
+            }
+        }
+        writeln!(self.output, "\n]}}")?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+
+    fn add_worker(&mut self, worker_id: WorkerId, hostname: Option<&str>) -> std::io::Result<()> {
+        if self.workers.contains_key(&worker_id) && hostname.is_none() {
+            return Ok(());
+        }
+        self.workers.entry(worker_id).or_default();
+        let name = match hostname {
+            Some(hostname) => format!("Worker {worker_id} ({hostname})"),
+            None => format!("Worker {worker_id}"),
+        };
+        self.write(json!({
+            "ph": "M",
+            "name": "process_name",
+            "pid": worker_id,
+            "args": { "name": name }
+        }))?;
+        self.write(json!({
+            "ph": "M",
+            "name": "process_sort_index",
+            "pid": worker_id,
+            "args": { "sort_index": worker_id }
+        }))
+    }
+
+    fn add_allocation_queue(&mut self, queue_id: QueueId) -> std::io::Result<()> {
+        if !self.allocation_queues.insert(queue_id) {
+            return Ok(());
+        }
+        self.write(json!({
+            "ph": "M",
+            "name": "thread_name",
+            "pid": ALLOCATION_PID,
+            "tid": queue_id,
+            "args": { "name": format!("Queue {queue_id}") }
+        }))
+    }
+
+    fn end_task(
+        &mut self,
+        task_id: TakoTaskId,
+        time: SystemTime,
+        status: &str,
+        error: Option<String>,
+    ) -> std::io::Result<()> {
+        let task = match self.running_tasks.remove(&task_id) {
+            Some(task) => task,
+            None => return Ok(()),
+        };
+        if let Some(slots) = self.workers.get_mut(&task.worker_id) {
+            slots.slots[task.slot] = None;
+        }
+
+        let duration = time
+            .duration_since(task.start)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        let (name, mut args) = match self.task_ids.get(&task_id) {
+            Some((job_id, job_task_id)) => (
+                format!("Job {job_id}, task {job_task_id}"),
+                json!({ "job": job_id, "task": job_task_id }),
+            ),
+            None => (format!("Task {task_id}"), json!({})),
+        };
+        args["instance"] = json!(task.instance_id);
+        args["status"] = json!(status);
+        if let Some(error) = error {
+            args["error"] = json!(error);
+        }
+        self.write(json!({
+            "ph": "X",
+            "cat": "task",
+            "name": name,
+            "pid": task.worker_id,
+            "tid": task.slot + 1,
+            "ts": timestamp(task.start),
+            "dur": duration,
+            "args": args
+        }))
+    }
+
+    fn end_allocation(
+        &mut self,
+        allocation_id: AllocationId,
+        time: SystemTime,
+    ) -> std::io::Result<()> {
+        let (queue_id, started) = match self.allocations.remove(&allocation_id) {
+            Some(allocation) => allocation,
+            None => return Ok(()),
+        };
+        if started {
+            self.write(json!({
+                "ph": "e",
+                "cat": "allocation",
+                "name": "Running",
+                "id": allocation_id,
+                "pid": ALLOCATION_PID,
+                "tid": queue_id,
+                "ts": timestamp(time),
+            }))?;
+        }
+        self.write(json!({
+            "ph": "e",
+            "cat": "allocation",
+            "name": format!("Allocation {allocation_id}"),
+            "id": allocation_id,
+            "pid": ALLOCATION_PID,
+            "tid": queue_id,
+            "ts": timestamp(time),
+        }))
+    }
+
+    fn write(&mut self, event: Value) -> std::io::Result<()> {
+        if self.event_count > 0 {
+            write!(self.output, ",")?;
+        }
+        write!(self.output, "\n{event}")?;
+        self.event_count += 1;
+        Ok(())
+    }
+}
+
+/// Trace timestamps are in microseconds.
+fn timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use serde_json::Value;
+
+    use crate::client::commands::event::chrome_trace::ChromeTraceWriter;
+    use crate::server::event::events::{MonitoringEventPayload, TaskIds};
+    use crate::server::event::MonitoringEvent;
+
+    fn create_trace(events: Vec<(u64, MonitoringEventPayload)>) -> Vec<Value> {
+        let start = SystemTime::now();
+        let mut writer = ChromeTraceWriter::new(vec![]).unwrap();
+        for (id, (offset, payload)) in events.into_iter().enumerate() {
+            writer
+                .add_event(MonitoringEvent {
+                    id: id as u32,
+                    time: start + Duration::from_secs(offset),
+                    payload,
+                })
+                .unwrap();
+        }
+        let output = writer.finish().unwrap();
+        let mut trace: Value = serde_json::from_slice(&output).unwrap();
+        match trace["traceEvents"].take() {
+            Value::Array(events) => events,
+            _ => panic!("Trace events are not an array"),
+        }
+    }
+
+    fn task_spans(events: &[Value]) -> Vec<(u64, u64, u64, &str)> {
+        let mut spans: Vec<_> = events
+            .iter()
+            .filter(|event| event["ph"] == "X")
+            .map(|event| {
+                (
+                    event["pid"].as_u64().unwrap(),
+                    event["tid"].as_u64().unwrap(),
+                    event["dur"].as_u64().unwrap() / 1_000_000,
+                    event["args"]["status"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        spans.sort();
+        spans
+    }
+
+    fn task_started(task_id: u32, worker_id: u32) -> MonitoringEventPayload {
+        MonitoringEventPayload::TaskStarted {
+            task_id: task_id.into(),
+            instance_id: 0.into(),
+            worker_id: worker_id.into(),
+        }
+    }
+
+    fn task_finished(task_id: u32) -> MonitoringEventPayload {
+        MonitoringEventPayload::TaskFinished {
+            task_id: task_id.into(),
+            resource_usage: None,
+        }
+    }
+
+    #[test]
+    fn concurrent_tasks_use_separate_slots() {
+        let events = create_trace(vec![
+            (0, task_started(1, 1)),
+            (1, task_started(2, 1)),
+            (2, task_finished(1)),
+            (3, task_started(3, 1)),
+            (4, task_started(4, 2)),
+            (5, task_finished(2)),
+            (6, task_finished(3)),
+            (7, task_finished(4)),
+        ]);
+        assert_eq!(
+            task_spans(&events),
+            vec![
+                (1, 1, 2, "finished"),
+                (1, 1, 3, "finished"),
+                (1, 2, 4, "finished"),
+                (2, 1, 3, "finished")
+            ]
+        );
+    }
+
+    #[test]
+    fn unfinished_tasks_are_ended() {
+        let events = create_trace(vec![
+            (0, task_started(1, 1)),
+            (1, task_started(2, 1)),
+            (
+                2,
+                MonitoringEventPayload::TaskFailed {
+                    task: TaskIds {
+                        task_id: 2.into(),
+                        job_id: 1.into(),
+                        job_task_id: 1.into(),
+                    },
+                    instance_id: 0.into(),
+                    error: "error".to_string(),
+                    resource_usage: None,
+                },
+            ),
+            (5, MonitoringEventPayload::AllocationQueueRemoved(1)),
+        ]);
+        assert_eq!(
+            task_spans(&events),
+            vec![(1, 1, 5, "running"), (1, 2, 1, "failed")]
+        );
+    }
+
+    #[test]
+    fn allocation_spans() {
+        let events = create_trace(vec![
+            (
+                0,
+                MonitoringEventPayload::AllocationQueued {
+                    queue_id: 1,
+                    allocation_id: "a".to_string(),
+                    worker_count: 1,
+                },
+            ),
+            (
+                1,
+                MonitoringEventPayload::AllocationStarted(1, "a".to_string()),
+            ),
+            (
+                2,
+                MonitoringEventPayload::AllocationFinished(1, "a".to_string()),
+            ),
+        ]);
+        let phases: Vec<_> = events
+            .iter()
+            .filter(|event| event["cat"] == "allocation")
+            .map(|event| {
+                (
+                    event["ph"].as_str().unwrap(),
+                    event["name"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            phases,
+            vec![
+                ("b", "Allocation a"),
+                ("b", "Running"),
+                ("e", "Running"),
+                ("e", "Allocation a")
+            ]
+        );
+    }
+}
@@ -1,12 +1,27 @@
-mod output;
+mod chrome_trace;
+pub(crate) mod output;
 
-use crate::client::commands::event::output::format_event;
+pub use output::format_event;
+
+use crate::client::commands::event::chrome_trace::ChromeTraceWriter;
+use crate::client::globalsettings::GlobalSettings;
 use crate::common::utils::str::pluralize;
-use crate::server::event::log::EventLogReader;
+use crate::common::utils::time::ArgTimestamp;
+use crate::server::bootstrap::get_client_session;
+use crate::server::event::events::EVENT_TYPES;
+use crate::server::event::log::{
+    resolve_log_files, EventLogQuery, EventLogReader, EventLogSummary,
+};
+use crate::server::event::MonitoringEventId;
+use crate::transfer::messages::{
+    EventFilter, FromClientMessage, StreamEventsRequest, ToClientMessage,
+};
+use crate::{JobId, WorkerId};
 use anyhow::anyhow;
+use clap::builder::PossibleValuesParser;
 use clap::{Parser, ValueHint};
 use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 pub struct EventLogOpts {
@@ -17,32 +32,152 @@ pub struct EventLogOpts {
 
 #[derive(Parser)]
 enum EventCommand {
-    /// Export events from a log file to NDJSON (line-delimited JSON).
+    /// Export events from a log file to NDJSON (line-delimited JSON) or to a Chrome trace.
     /// Events will be exported to `stdout`, you can redirect it e.g. to a file.
     Export(ExportOpts),
+    /// Stream events from a running server to NDJSON (line-delimited JSON).
+    /// Events are printed to `stdout` as soon as they are created, until the command is
+    /// interrupted or the server stops.
+    Stream(StreamOpts),
+    /// Search a log file for events that match the given filters.
+    /// Events are evaluated while the log file is being read, so even large log files
+    /// can be queried without exporting them first.
+    Query(QueryOpts),
 }
 
 #[derive(Parser)]
 struct ExportOpts {
     /// Path to a file containing the event log.
     /// The file had to be created with `hq server start --event-log-path=<PATH>`.
-    #[clap(value_hint = ValueHint::FilePath)]
+    /// It can also be a directory or a (quoted) glob pattern, in which case all matching files
+    /// are read in order as a single event log. Segments of a rotated event log are read when
+    /// the path passed to `--event-log-path` is used.
+    #[clap(value_hint = ValueHint::AnyPath)]
+    logfile: PathBuf,
+
+    /// Format of the exported events.
+    #[clap(long, default_value = "json", arg_enum)]
+    format: ExportFormat,
+}
+
+#[derive(clap::ArgEnum, Clone)]
+enum ExportFormat {
+    /// Each event is exported as a single line of JSON.
+    Json,
+    /// Task executions, workers and allocations are exported as a timeline in the Trace Event
+    /// Format, which can be opened in Perfetto or `chrome://tracing`.
+    ChromeTrace,
+}
+
+#[derive(Parser)]
+struct StreamOpts {
+    /// Also output events stored in the server that have an ID larger than this value.
+    /// Use `0` to output all stored events.
+    #[clap(long)]
+    after_id: Option<MonitoringEventId>,
+
+    /// Only output events of the given type.
+    /// Can be used multiple times.
+    #[clap(long = "type", value_parser = PossibleValuesParser::new(EVENT_TYPES))]
+    event_types: Vec<String>,
+
+    /// Only output events related to the given job.
+    /// Can be used multiple times.
+    #[clap(long = "job")]
+    job_ids: Vec<JobId>,
+
+    /// Only output events related to the given worker.
+    /// Can be used multiple times.
+    #[clap(long = "worker")]
+    worker_ids: Vec<WorkerId>,
+}
+
+#[derive(Parser)]
+struct QueryOpts {
+    /// Path to a file containing the event log.
+    /// The file had to be created with `hq server start --event-log-path=<PATH>`.
+    /// It can also be a directory or a (quoted) glob pattern, in which case all matching files
+    /// are read in order as a single event log. Segments of a rotated event log are read when
+    /// the path passed to `--event-log-path` is used.
+    #[clap(value_hint = ValueHint::AnyPath)]
     logfile: PathBuf,
+
+    /// Only output events of the given type.
+    /// Multiple types can be separated by a comma.
+    #[clap(
+        long = "type",
+        value_parser = PossibleValuesParser::new(EVENT_TYPES),
+        use_value_delimiter(true)
+    )]
+    event_types: Vec<String>,
+
+    /// Only output events related to the given job.
+    /// Multiple jobs can be separated by a comma.
+    #[clap(long = "job", use_value_delimiter(true))]
+    job_ids: Vec<JobId>,
+
+    /// Only output events related to the given worker.
+    /// Multiple workers can be separated by a comma.
+    #[clap(long = "worker", use_value_delimiter(true))]
+    worker_ids: Vec<WorkerId>,
+
+    /// Only output events created at this time or later.
+    /// Use either RFC 3339 (`2022-06-01T10:00:00+02:00`) or local time
+    /// (`2022-06-01 10:00[:00]` or `2022-06-01`).
+    #[clap(long)]
+    since: Option<ArgTimestamp>,
+
+    /// Only output events created at this time or earlier.
+    /// Uses the same format as `--since`.
+    #[clap(long)]
+    until: Option<ArgTimestamp>,
+
+    /// Output the number of matching events per event type instead of the events themselves.
+    #[clap(long, conflicts_with = "ndjson")]
+    summary: bool,
+
+    /// Output the matching events to NDJSON (line-delimited JSON) as soon as they are read,
+    /// instead of using the selected output mode.
+    #[clap(long)]
+    ndjson: bool,
 }
 
-pub fn command_event_log(opts: EventLogOpts) -> anyhow::Result<()> {
+pub async fn command_event_log(
+    gsettings: &GlobalSettings,
+    opts: EventLogOpts,
+) -> anyhow::Result<()> {
     match opts.command {
-        EventCommand::Export(opts) => export_json(opts),
+        EventCommand::Export(opts) => match opts.format {
+            ExportFormat::Json => export_json(opts),
+            ExportFormat::ChromeTrace => export_chrome_trace(opts),
+        },
+        EventCommand::Stream(opts) => stream_json(gsettings, opts).await,
+        EventCommand::Query(opts) => query_events(gsettings, opts),
     }
 }
 
+/// Opens the event log at `path`, which can be a single event log file, a directory with event
+/// log files, a glob pattern or the base path of a rotated event log.
+pub(crate) fn open_log_file(path: &Path) -> anyhow::Result<EventLogReader> {
+    resolve_log_files(path)
+        .and_then(EventLogReader::open_all)
+        .map_err(|error| {
+            anyhow!(
+                "Cannot open event log file at `{}`: {error:?}",
+                path.display()
+            )
+        })
+}
+
+pub(crate) fn log_read_error(error: rmp_serde::decode::Error) {
+    log::error!(
+        "Encountered an error while reading event log file: {error:?}.\n
+The file might have been incomplete."
+    )
+}
+
 fn export_json(opts: ExportOpts) -> anyhow::Result<()> {
-    let file = EventLogReader::open(&opts.logfile).map_err(|error| {
-        anyhow!(
-            "Cannot open event log file at `{}`: {error:?}",
-            opts.logfile.display()
-        )
-    })?;
+    let file = open_log_file(&opts.logfile)?;
 
     let stdout = std::io::stdout();
     let stdout = stdout.lock();
@@ -55,12 +190,7 @@ fn export_json(opts: ExportOpts) -> anyhow::Result<()> {
                 writeln!(stdout, "{}", format_event(event))?;
                 count += 1;
             }
-            Err(error) => {
-                log::error!(
-                    "Encountered an error while reading event log file: {error:?}.\n
-The file might have been incomplete."
-                )
-            }
+            Err(error) => log_read_error(error),
         }
     }
 
@@ -69,3 +199,80 @@ The file might have been incomplete."
     stdout.flush()?;
     Ok(())
 }
+
+fn export_chrome_trace(opts: ExportOpts) -> anyhow::Result<()> {
+    let file = open_log_file(&opts.logfile)?;
+
+    let stdout = std::io::stdout();
+    let stdout = stdout.lock();
+    let mut writer = ChromeTraceWriter::new(BufWriter::new(stdout))?;
+    for event in file {
+        match event {
+            Ok(event) => writer.add_event(event)?,
+            Err(error) => log_read_error(error),
+        }
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+fn query_events(gsettings: &GlobalSettings, opts: QueryOpts) -> anyhow::Result<()> {
+    let events = open_log_file(&opts.logfile)?.query(EventLogQuery {
+        filter: EventFilter {
+            event_types: opts.event_types,
+            job_ids: opts.job_ids,
+            worker_ids: opts.worker_ids,
+        },
+        since: opts.since.map(|time| time.unpack()),
+        until: opts.until.map(|time| time.unpack()),
+    });
+    let events = events.filter_map(|event| event.map_err(log_read_error).ok());
+
+    if opts.summary {
+        let mut summary = EventLogSummary::default();
+        for event in events {
+            summary.add(&event);
+        }
+        gsettings.printer().print_event_log_summary(summary);
+    } else if opts.ndjson {
+        let stdout = std::io::stdout();
+        let stdout = stdout.lock();
+        let mut stdout = BufWriter::new(stdout);
+        for event in events {
+            writeln!(stdout, "{}", format_event(event))?;
+        }
+        stdout.flush()?;
+    } else {
+        gsettings.printer().print_event_log_events(events.collect());
+    }
+    Ok(())
+}
+
+async fn stream_json(gsettings: &GlobalSettings, opts: StreamOpts) -> anyhow::Result<()> {
+    let mut session = get_client_session(gsettings.server_directory()).await?;
+    let connection = session.connection();
+    connection
+        .send(FromClientMessage::StreamEvents(StreamEventsRequest {
+            after_id: opts.after_id,
+            filter: EventFilter {
+                event_types: opts.event_types,
+                job_ids: opts.job_ids,
+                worker_ids: opts.worker_ids,
+            },
+        }))
+        .await?;
+
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    while let Some(message) = connection.receive().await {
+        match message? {
+            ToClientMessage::Event(event) => {
+                writeln!(stdout, "{}", format_event(event))?;
+                stdout.flush()?;
+            }
+            ToClientMessage::Error(error) => return Err(anyhow!(error)),
+            message => return Err(anyhow!("Received an invalid message {:?}", message)),
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,5 @@
-use crate::client::output::json::format_datetime;
-use crate::server::event::events::MonitoringEventPayload;
+use crate::client::output::json::{format_datetime, format_resource_usage};
+use crate::server::event::events::{MonitoringEventPayload, TaskIds};
 use crate::server::event::MonitoringEvent;
 use serde_json::json;
 use tako::worker::WorkerOverview;
@@ -24,7 +24,12 @@ fn format_payload(event: MonitoringEventPayload) -> serde_json::Value {
             "id": id,
             "reason": reason
         }),
-        MonitoringEventPayload::WorkerOverviewReceived(WorkerOverview { id, hw_state, .. }) => {
+        MonitoringEventPayload::WorkerStopRequested(id) => json!({
+            "type": "worker-stop-requested",
+            "id": id
+        }),
+        MonitoringEventPayload::WorkerOverviewReceived(overview) => {
+            let WorkerOverview { id, hw_state, .. } = *overview;
             json!({
                 "type": "worker-overview",
                 "id": id,
@@ -43,6 +48,19 @@ fn format_payload(event: MonitoringEventPayload) -> serde_json::Value {
                 "queue-id": id
             })
         }
+        MonitoringEventPayload::AllocationQueuePaused { queue_id, reason } => {
+            json!({
+                "type": "autoalloc-queue-paused",
+                "queue-id": queue_id,
+                "reason": reason
+            })
+        }
+        MonitoringEventPayload::AllocationQueueResumed(id) => {
+            json!({
+                "type": "autoalloc-queue-resumed",
+                "queue-id": id
+            })
+        }
         MonitoringEventPayload::AllocationQueued {
             queue_id,
             allocation_id,
@@ -69,26 +87,76 @@ fn format_payload(event: MonitoringEventPayload) -> serde_json::Value {
                 "allocation-id": allocation_id,
             })
         }
-        MonitoringEventPayload::TaskStarted { task_id, worker_id } => json!({
+        MonitoringEventPayload::TaskStarted {
+            task_id,
+            instance_id,
+            worker_id,
+        } => json!({
             "type": "task-started",
             "id": task_id,
+            "instance-id": instance_id,
             "worker": worker_id
         }),
-        MonitoringEventPayload::TaskFinished(task_id) => json!({
-            "type": "task-finished",
-            "id": task_id
-        }),
-        MonitoringEventPayload::TaskFailed(task_id) => json!({
-            "type": "task-failed",
-            "id": task_id
-        }),
+        MonitoringEventPayload::TaskFinished {
+            task_id,
+            resource_usage,
+        } => {
+            let mut data = json!({
+                "type": "task-finished",
+                "id": task_id
+            });
+            if let Some(usage) = resource_usage {
+                data["resource-usage"] = format_resource_usage(&usage);
+            }
+            data
+        }
+        MonitoringEventPayload::TaskFailed {
+            task,
+            instance_id,
+            error,
+            resource_usage,
+        } => {
+            let mut data = format_task_ids("task-failed", task);
+            data["instance-id"] = json!(instance_id);
+            data["error"] = json!(error);
+            if let Some(usage) = resource_usage {
+                data["resource-usage"] = format_resource_usage(&usage);
+            }
+            data
+        }
+        MonitoringEventPayload::TaskCanceled(task) => format_task_ids("task-canceled", task),
+        MonitoringEventPayload::TaskRestarted {
+            task,
+            instance_id,
+            worker_id,
+        } => {
+            let mut data = format_task_ids("task-restarted", task);
+            data["instance-id"] = json!(instance_id);
+            data["worker"] = json!(worker_id);
+            data
+        }
         MonitoringEventPayload::JobCreated(job_id, job_info) => json!({
+            "type": "job-created",
             "job-id": job_id,
             "job-info": job_info,
         }),
         MonitoringEventPayload::JobCompleted(job_id, completion_date) => json!({
+            "type": "job-completed",
             "job-id": job_id,
             "completion-date": completion_date
         }),
+        MonitoringEventPayload::JobCanceled(job_id) => json!({
+            "type": "job-canceled",
+            "job-id": job_id
+        }),
     }
 }
+
+fn format_task_ids(event_type: &str, task: TaskIds) -> serde_json::Value {
+    json!({
+        "type": event_type,
+        "id": task.task_id,
+        "job-id": task.job_id,
+        "task-id": task.job_task_id
+    })
+}
@@ -1,3 +1,4 @@
+use crate::client::commands::event::{log_read_error, open_log_file};
 use crate::client::globalsettings::GlobalSettings;
 use crate::client::job::get_worker_map;
 use crate::client::output::outputs::OutputStream;
@@ -5,13 +6,20 @@ use crate::client::output::resolve_task_paths;
 use crate::client::status::{job_status, Status};
 use crate::common::cli::{get_id_selector, IdSelectorArg, JobSelectorArg, TaskSelectorArg};
 use crate::rpc_call;
+use crate::server::bootstrap::get_client_session;
+use crate::server::job::JobTaskInfo;
+use crate::server::job_stats::EventLogJobStats;
 use crate::transfer::connection::{ClientConnection, ClientSession};
 use crate::transfer::messages::{
-    CancelJobResponse, CancelRequest, FromClientMessage, IdSelector, JobDetailRequest,
-    JobInfoRequest, TaskIdSelector, TaskSelector, TaskStatusSelector, ToClientMessage,
+    CancelJobResponse, CancelRequest, FromClientMessage, IdSelector, JobDescription,
+    JobDetailRequest, JobInfoRequest, JobStatsRequest, JobStatsResponse, TaskIdSelector,
+    TaskOutputRequest, TaskSelector, TaskStatusSelector, ToClientMessage,
 };
 use crate::JobId;
-use clap::Parser;
+use clap::{Parser, ValueHint};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tako::program::StdioDef;
 
 #[derive(Parser)]
 pub struct JobListOpts {
@@ -31,6 +39,18 @@ pub struct JobInfoOpts {
     pub selector_arg: IdSelectorArg,
 }
 
+#[derive(Parser)]
+pub struct JobStatsOpts {
+    /// Single ID, ID range, `last` or `all` to display statistics of the selected job(s)
+    pub selector_arg: IdSelectorArg,
+
+    /// Compute the statistics from an event log file instead of a running server.
+    /// The file had to be created with `hq server start --event-log-path=<PATH>`.
+    /// It can also be a directory or a glob pattern that selects multiple event log files.
+    #[clap(long, value_hint = ValueHint::AnyPath)]
+    pub event_log: Option<PathBuf>,
+}
+
 #[derive(Parser)]
 pub struct JobCancelOpts {
     /// Select job(s) to cancel
@@ -131,6 +151,46 @@ pub async fn output_job_detail(
     Ok(())
 }
 
+pub async fn output_job_stats(
+    gsettings: &GlobalSettings,
+    opts: JobStatsOpts,
+) -> anyhow::Result<()> {
+    let selector: IdSelector = opts.selector_arg.into();
+    let response = match opts.event_log {
+        Some(path) => job_stats_from_event_log(&path, &selector)?,
+        None => {
+            let mut session = get_client_session(gsettings.server_directory()).await?;
+            let message = FromClientMessage::JobStats(JobStatsRequest { selector });
+            rpc_call!(session.connection(), message, ToClientMessage::JobStatsResponse(r) => r)
+                .await?
+        }
+    };
+
+    let mut stats = Vec::with_capacity(response.len());
+    for (job_id, job_stats) in response {
+        match job_stats {
+            Some(job_stats) => stats.push(job_stats),
+            None => log::error!("Job {job_id} not found"),
+        }
+    }
+    gsettings.printer().print_job_stats(stats);
+    Ok(())
+}
+
+fn job_stats_from_event_log(
+    path: &Path,
+    selector: &IdSelector,
+) -> anyhow::Result<JobStatsResponse> {
+    let mut stats = EventLogJobStats::default();
+    for event in open_log_file(path)? {
+        match event {
+            Ok(event) => stats.add_event(&event),
+            Err(error) => log_read_error(error),
+        }
+    }
+    Ok(stats.compute(selector))
+}
+
 pub async fn output_job_cat(
     gsettings: &GlobalSettings,
     session: &mut ClientSession,
@@ -161,6 +221,29 @@ pub async fn output_job_cat(
                     return Ok(());
                 }
 
+                if let JobDescription::Array { task_desc, .. } = &job.job_desc {
+                    if task_desc.spool {
+                        let (stdio, stream_name) = match output_stream {
+                            OutputStream::Stdout => (&task_desc.program.stdout, "stdout"),
+                            OutputStream::Stderr => (&task_desc.program.stderr, "stderr"),
+                        };
+                        if matches!(stdio, StdioDef::Null) {
+                            log::warn!(
+                                "Job {job_id} has no `{stream_name}` stream associated with it"
+                            );
+                            return Ok(());
+                        }
+                        return print_spooled_output(
+                            session,
+                            job_id,
+                            job.tasks,
+                            output_stream,
+                            task_header,
+                        )
+                        .await;
+                    }
+                }
+
                 return gsettings.printer().print_job_output(
                     job.tasks,
                     output_stream,
@@ -175,6 +258,48 @@ pub async fn output_job_cat(
     Ok(())
 }
 
+/// Prints output of tasks that was spooled on workers, it is fetched from them through the server.
+async fn print_spooled_output(
+    session: &mut ClientSession,
+    job_id: JobId,
+    tasks: Vec<JobTaskInfo>,
+    output_stream: OutputStream,
+    task_header: bool,
+) -> anyhow::Result<()> {
+    let channel = match output_stream {
+        OutputStream::Stdout => 0,
+        OutputStream::Stderr => 1,
+    };
+    let stdout = std::io::stdout();
+    for task in tasks {
+        if task_header {
+            writeln!(stdout.lock(), "# Task {}", task.task_id)?;
+        }
+        let mut offset = 0;
+        loop {
+            let message = FromClientMessage::TaskOutput(TaskOutputRequest {
+                job_id,
+                task_id: task.task_id,
+                channel,
+                offset,
+            });
+            let response = match rpc_call!(session.connection(), message, ToClientMessage::TaskOutputResponse(r) => r).await {
+                Ok(response) => response,
+                Err(error) => {
+                    log::warn!("Cannot fetch output of task {}: {error}", task.task_id);
+                    break;
+                }
+            };
+            stdout.lock().write_all(&response.data)?;
+            offset += response.data.len() as u64;
+            if response.eof {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
 pub async fn cancel_job(
     _gsettings: &GlobalSettings,
     session: &mut ClientSession,
@@ -26,6 +26,10 @@ pub struct ShowOpts {
     /// Show close message even for tasks with empty stream
     #[clap(long)]
     pub show_empty: bool,
+
+    /// Keep printing new output as it is appended to the log file, until the job finishes
+    #[clap(long)]
+    pub follow: bool,
 }
 
 #[derive(Parser)]
@@ -41,6 +45,11 @@ pub struct CatOpts {
     /// Allow unfinished channel
     #[clap(long)]
     pub allow_unfinished: bool,
+
+    /// Keep printing new output as it is appended to the log file, until the streams of all
+    /// selected tasks are finished
+    #[clap(long)]
+    pub follow: bool,
 }
 
 #[derive(Parser)]
@@ -50,6 +59,39 @@ pub struct ExportOpts {
     pub task: Option<IntArray>,
 }
 
+#[derive(Parser)]
+pub struct SplitOpts {
+    /// Directory where the output files will be created
+    #[clap(long)]
+    pub output_dir: PathBuf,
+
+    /// Filename of each output file (relative to the output directory).
+    /// You can use placeholders %{TASK_ID}, %{INSTANCE_ID} and %{CHANNEL} ("stdout" or "stderr").
+    #[clap(long, default_value = "%{TASK_ID}.%{CHANNEL}")]
+    pub pattern: String,
+
+    /// Extract only the specified task(s) output. You can use the array syntax to specify multiple tasks.
+    #[clap(long)]
+    pub task: Option<IntArray>,
+
+    /// Extract also the output of superseded instances of tasks.
+    /// The pattern has to contain the %{INSTANCE_ID} placeholder.
+    #[clap(long)]
+    pub include_superseded: bool,
+
+    /// Extract only the output of tasks that have failed
+    #[clap(long)]
+    pub failed: bool,
+}
+
+#[derive(Parser)]
+pub struct RepairOpts {
+    /// Path where the repaired log file will be written.
+    /// If it is not specified, the original file is replaced.
+    #[clap(long)]
+    pub output: Option<PathBuf>,
+}
+
 #[derive(Parser)]
 pub enum LogCommand {
     /// Prints summary of log file
@@ -63,6 +105,12 @@ pub enum LogCommand {
 
     /// Export log into JSON
     Export(ExportOpts),
+
+    /// Writes the output of each task into separate files
+    Split(SplitOpts),
+
+    /// Repairs a log file that was not finished properly (e.g. because the server has crashed)
+    Repair(RepairOpts),
 }
 
 #[derive(clap::ArgEnum, Clone)]
@@ -72,7 +120,16 @@ pub enum Channel {
 }
 
 pub fn command_log(gsettings: &GlobalSettings, opts: LogOpts) -> anyhow::Result<()> {
-    let mut log_file = LogFile::open(&opts.filename)?;
+    let follow = match &opts.command {
+        LogCommand::Show(show_opts) => show_opts.follow,
+        LogCommand::Cat(cat_opts) => cat_opts.follow,
+        _ => false,
+    };
+    let mut log_file = if follow {
+        LogFile::open_for_follow(&opts.filename)?
+    } else {
+        LogFile::open(&opts.filename)?
+    };
     match opts.command {
         LogCommand::Summary(_) => {
             gsettings
@@ -88,6 +145,32 @@ pub fn command_log(gsettings: &GlobalSettings, opts: LogOpts) -> anyhow::Result<
         LogCommand::Export(export_opts) => {
             log_file.export(&export_opts)?;
         }
+        LogCommand::Split(split_opts) => {
+            let count = log_file.split(&split_opts)?;
+            log::info!(
+                "{} file(s) written into {}",
+                count,
+                split_opts.output_dir.display()
+            );
+        }
+        LogCommand::Repair(repair_opts) => {
+            let output = match &repair_opts.output {
+                Some(output) => output.clone(),
+                None => {
+                    let mut path = opts.filename.clone().into_os_string();
+                    path.push(".repaired");
+                    PathBuf::from(path)
+                }
+            };
+            let closed = log_file.repair(&output)?;
+            if repair_opts.output.is_none() {
+                std::fs::rename(&output, &opts.filename)?;
+            }
+            log::info!(
+                "Log file repaired, {} unfinished stream(s) marked as incomplete",
+                closed
+            );
+        }
     }
 
     Ok(())
@@ -1,15 +1,22 @@
 use crate::client::globalsettings::GlobalSettings;
 use crate::client::server::client_stop_server;
+use crate::common::format::ArgSize;
 use crate::common::utils::network::get_hostname;
 use crate::common::utils::time::ArgDuration;
 use crate::rpc_call;
 use crate::server::bootstrap::{
     get_client_session, init_hq_server, print_server_info, ServerConfig,
 };
+use crate::server::event::hooks::{EventHook, HookRunnerConfig};
+use crate::server::event::log::EventLogRotation;
 use crate::transfer::connection::ClientSession;
-use crate::transfer::messages::{FromClientMessage, StatsResponse, ToClientMessage};
+use crate::transfer::messages::{
+    FromClientMessage, StatsHistoryRequest, StatsHistoryResponse, StatsResponse, ToClientMessage,
+};
 use clap::Parser;
+use std::net::IpAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 pub struct ServerOpts {
@@ -25,6 +32,8 @@ enum ServerCommand {
     Stop(ServerStopOpts),
     /// Show info of running HyperQueue server
     Info(ServerInfoOpts),
+    /// Show recent history of task, worker and scheduler statistics of the running server
+    Stats(ServerStatsOpts),
 }
 
 #[derive(Parser)]
@@ -52,6 +61,58 @@ struct ServerStartOpts {
     /// Path to a log file where events will be stored.
     #[clap(long, hide(true))]
     event_log_path: Option<PathBuf>,
+
+    /// Start a new event log file once the current one reaches this size (e.g. `1GiB`).
+    /// Event log files are then numbered (`<event-log-path>.1`, `<event-log-path>.2`, ...).
+    #[clap(long, hide(true), requires("event-log-path"))]
+    event_log_max_size: Option<ArgSize>,
+
+    /// Start a new event log file periodically.
+    /// Event log files are then numbered (`<event-log-path>.1`, `<event-log-path>.2`, ...).
+    #[clap(long, hide(true), requires("event-log-path"), arg_enum)]
+    event_log_rotate: Option<EventLogRotateInterval>,
+
+    /// Port on which metrics of the server are served over HTTP (at `/metrics`)
+    /// in the Prometheus text format
+    #[clap(long)]
+    metrics_port: Option<u16>,
+
+    /// Address on which the metrics are served. The metrics endpoint is not authenticated,
+    /// use `0.0.0.0` only if the metrics should be accessible from other machines.
+    #[clap(long, default_value = "127.0.0.1")]
+    metrics_host: IpAddr,
+
+    /// Command that will be executed whenever an event of the given type is created,
+    /// e.g. `--hook job-completed=/path/notify.sh`. Can be used multiple times.
+    /// The event is described by environment variables (`HQ_EVENT_TYPE`, `HQ_JOB_ID`, ...)
+    /// and passed to the command as JSON on its standard input.
+    #[clap(long = "hook", multiple_occurrences(true))]
+    hooks: Vec<EventHook>,
+
+    /// Hooks that run for longer than this duration are killed
+    #[clap(long, default_value = "1m")]
+    hook_timeout: ArgDuration,
+
+    /// Maximum number of hooks that are executed at the same time
+    #[clap(long, default_value = "4")]
+    max_concurrent_hooks: usize,
+}
+
+#[derive(clap::ArgEnum, Clone)]
+enum EventLogRotateInterval {
+    /// A new file is started every hour
+    Hourly,
+    /// A new file is started every day
+    Daily,
+}
+
+impl EventLogRotateInterval {
+    fn duration(&self) -> Duration {
+        match self {
+            EventLogRotateInterval::Hourly => Duration::from_secs(60 * 60),
+            EventLogRotateInterval::Daily => Duration::from_secs(24 * 60 * 60),
+        }
+    }
 }
 
 #[derive(Parser)]
@@ -64,11 +125,19 @@ struct ServerInfoOpts {
     stats: bool,
 }
 
+#[derive(Parser)]
+struct ServerStatsOpts {
+    /// Show statistics from this duration before now (at most one day is kept by the server)
+    #[clap(long, default_value = "1h")]
+    window: ArgDuration,
+}
+
 pub async fn command_server(gsettings: &GlobalSettings, opts: ServerOpts) -> anyhow::Result<()> {
     match opts.subcmd {
         ServerCommand::Start(opts) => start_server(gsettings, opts).await,
         ServerCommand::Stop(opts) => stop_server(gsettings, opts).await,
         ServerCommand::Info(opts) => command_server_info(gsettings, opts).await,
+        ServerCommand::Stats(opts) => command_server_stats(gsettings, opts).await,
     }
 }
 
@@ -80,6 +149,17 @@ async fn start_server(gsettings: &GlobalSettings, opts: ServerStartOpts) -> anyh
         worker_port: opts.worker_port,
         event_buffer_size: opts.event_store_size,
         event_log_path: opts.event_log_path,
+        event_log_rotation: EventLogRotation {
+            max_size: opts.event_log_max_size.map(|size| size.unpack()),
+            interval: opts.event_log_rotate.map(|interval| interval.duration()),
+        },
+        metrics_host: opts.metrics_host,
+        metrics_port: opts.metrics_port,
+        hooks: opts.hooks,
+        hook_runner: HookRunnerConfig {
+            timeout: opts.hook_timeout.unpack(),
+            max_concurrent: opts.max_concurrent_hooks,
+        },
     };
 
     init_hq_server(gsettings, server_cfg).await
@@ -117,3 +197,20 @@ async fn print_server_stats(
     gsettings.printer().print_server_stats(response);
     Ok(())
 }
+
+async fn command_server_stats(
+    gsettings: &GlobalSettings,
+    opts: ServerStatsOpts,
+) -> anyhow::Result<()> {
+    let mut session = get_client_session(gsettings.server_directory()).await?;
+    let response: StatsHistoryResponse = rpc_call!(
+        session.connection(),
+        FromClientMessage::StatsHistory(StatsHistoryRequest {
+            window: opts.window.unpack(),
+        }),
+        ToClientMessage::StatsHistoryResponse(r) => r
+    )
+    .await?;
+    gsettings.printer().print_server_stats_history(response);
+    Ok(())
+}
@@ -18,6 +18,7 @@ use crate::client::job::get_worker_map;
 use crate::client::resources::{parse_allocation_request, parse_resource_request};
 use crate::client::status::Status;
 use crate::common::arraydef::IntArray;
+use crate::common::format::ArgSize;
 use crate::common::placeholders::{
     get_unknown_placeholders, parse_resolvable_string, StringPart, CWD_PLACEHOLDER,
     JOB_ID_PLACEHOLDER, SUBMIT_DIR_PLACEHOLDER, TASK_ID_PLACEHOLDER,
@@ -25,10 +26,11 @@ use crate::common::placeholders::{
 use crate::common::utils::fs::get_current_dir;
 use crate::common::utils::str::pluralize;
 use crate::common::utils::time::ArgDuration;
+use crate::stream::compression::LogCompression;
 use crate::transfer::connection::ClientSession;
 use crate::transfer::messages::{
-    FromClientMessage, IdSelector, JobDescription, PinMode, ResubmitRequest, SubmitRequest,
-    TaskDescription, ToClientMessage,
+    FromClientMessage, IdSelector, JobDescription, OutputLimit, OutputLimitPolicy, PinMode,
+    ResubmitRequest, SubmitRequest, TaskDescription, ToClientMessage,
 };
 use crate::{arg_wrapper, rpc_call, JobTaskCount, Map};
 
@@ -231,6 +233,16 @@ pub struct SubmitJobConfOpts {
     #[clap(long)]
     log: Option<PathBuf>,
 
+    /// Compress the output of tasks streamed into the log file.
+    /// [default: none]
+    #[clap(long, arg_enum)]
+    log_compression: Option<LogCompression>,
+
+    /// Store the output of tasks in the working directory of the worker that executes them.
+    /// The output can be fetched from the worker using `hq job cat`.
+    #[clap(long, conflicts_with("log"))]
+    spool: bool,
+
     /// Create a temporary directory for task, path is provided in HQ_TASK_DIR
     /// The directory is automatically deleted when task is finished
     #[clap(long)]
@@ -242,6 +254,23 @@ pub struct SubmitJobConfOpts {
     /// [default: 5]
     #[clap(long)]
     crash_limit: Option<u32>,
+
+    /// Maximum size of the output of each task, e.g. `100MiB`.
+    /// The limit is applied to stdout and stderr separately. Output beyond the limit is dropped
+    /// and a truncation marker is appended to it.
+    #[clap(long)]
+    max_output_size: Option<ArgSize>,
+
+    /// What happens when a task exceeds the output size limit.
+    /// [default: truncate]
+    #[clap(long, arg_enum)]
+    on_output_limit: Option<OutputLimitPolicy>,
+
+    /// Shell command that will be executed by the server when the job completes.
+    /// The event is described by environment variables (e.g. `HQ_JOB_ID`) and passed to the
+    /// command as JSON on its standard input.
+    #[clap(long)]
+    on_finish: Option<String>,
 }
 
 impl SubmitJobConfOpts {
@@ -279,7 +308,12 @@ impl SubmitJobConfOpts {
             priority: self.priority.or(other.priority),
             time_limit: self.time_limit.or(other.time_limit),
             log: self.log.or(other.log),
+            log_compression: self.log_compression.or(other.log_compression),
+            spool: self.spool || other.spool,
             crash_limit: self.crash_limit.or(other.crash_limit),
+            max_output_size: self.max_output_size.or(other.max_output_size),
+            on_output_limit: self.on_output_limit.or(other.on_output_limit),
+            on_finish: self.on_finish.or(other.on_finish),
         }
     }
 }
@@ -387,12 +421,12 @@ impl JobSubmitOpts {
     }
 }
 
-fn create_stdio(arg: Option<StdioArg>, log: &Option<PathBuf>, default: &str) -> StdioDef {
+fn create_stdio(arg: Option<StdioArg>, piped: bool, default: &str) -> StdioDef {
     arg.map(|x| x.0).unwrap_or_else(|| {
-        if log.is_none() {
-            StdioDef::File(default.into())
-        } else {
+        if piped {
             StdioDef::Pipe
+        } else {
+            StdioDef::File(default.into())
         }
     })
 }
@@ -480,10 +514,27 @@ pub async fn submit_computation(
                 priority,
                 time_limit,
                 log,
+                log_compression,
+                spool,
                 crash_limit,
+                max_output_size,
+                on_output_limit,
+                on_finish,
             },
     } = opts;
 
+    if spool {
+        if log.is_some() {
+            bail!("--spool cannot be combined with --log");
+        }
+        let is_file = |arg: &Option<StdioArg>| matches!(arg, Some(StdioArg(StdioDef::File(_))));
+        if is_file(&stdout) || is_file(&stderr) {
+            bail!(
+                "--spool cannot be combined with --stdout/--stderr paths, only `none` can be used"
+            );
+        }
+    }
+
     let name = if let Some(name) = name {
         validate_name(name)?
     } else {
@@ -495,11 +546,22 @@ pub async fn submit_computation(
 
     let args: Vec<BString> = commands.into_iter().map(|arg| arg.into()).collect();
 
-    let stdout = create_stdio(stdout, &log, DEFAULT_STDOUT_PATH);
-    let stderr = create_stdio(stderr, &log, DEFAULT_STDERR_PATH);
+    let piped = log.is_some() || spool;
+    let stdout = create_stdio(stdout, piped, DEFAULT_STDOUT_PATH);
+    let stderr = create_stdio(stderr, piped, DEFAULT_STDERR_PATH);
     let cwd = cwd.unwrap_or_else(|| PathBuf::from("%{SUBMIT_DIR}"));
     let priority = priority.unwrap_or(0);
     let time_limit = time_limit.map(|x| x.unpack());
+    let output_limit = match (max_output_size, on_output_limit) {
+        (Some(max_size), policy) => Some(OutputLimit {
+            max_size: max_size.unpack(),
+            policy: policy.unwrap_or(OutputLimitPolicy::Truncate),
+        }),
+        (None, Some(_)) => {
+            anyhow::bail!("--on-output-limit can only be used together with --max-output-size")
+        }
+        (None, None) => None,
+    };
 
     let env_count = env.len();
     let env: Map<_, _> = env.into_iter().map(|env| (env.key, env.value)).collect();
@@ -534,6 +596,8 @@ pub async fn submit_computation(
         time_limit,
         task_dir,
         crash_limit: crash_limit.unwrap_or(DEFAULT_CRASH_LIMIT),
+        output_limit,
+        spool,
     };
 
     let job_desc = JobDescription::Array {
@@ -548,6 +612,8 @@ pub async fn submit_computation(
         max_fails,
         submit_dir: get_current_dir(),
         log,
+        log_compression: log_compression.unwrap_or_default(),
+        on_finish,
     });
 
     let response =
@@ -607,7 +673,7 @@ fn warn_array_task_count(opts: &JobSubmitOpts, task_count: u32) {
         task_files += task_count;
         active_dirs.push("stderr");
     }
-    if task_files > SUBMIT_ARRAY_LIMIT && opts.conf.log.is_none() {
+    if task_files > SUBMIT_ARRAY_LIMIT && opts.conf.log.is_none() && !opts.conf.spool {
         log::warn!(
             "The job will create {} files for{}. \
             Consider using the `--log` option to stream all outputs into a single file",
@@ -11,6 +11,7 @@ use tempdir::TempDir;
 use tokio::time::sleep;
 
 use crate::client::globalsettings::GlobalSettings;
+use crate::common::format::ArgSize;
 use crate::common::manager::info::{ManagerInfo, WORKER_EXTRA_MANAGER_KEY};
 use crate::common::utils::network::get_hostname;
 use crate::common::utils::time::ArgDuration;
@@ -22,8 +23,10 @@ use crate::transfer::messages::{
 use crate::worker::bootstrap::{
     finalize_configuration, initialize_worker, try_get_pbs_info, try_get_slurm_info,
 };
+use crate::worker::cgroup::WORKER_EXTRA_CGROUPS_KEY;
 use crate::worker::hwdetect::{detect_additional_resources, detect_cpus, prune_hyper_threading};
 use crate::worker::parser::{ArgCpuDefinition, ArgResourceItemDef};
+use crate::worker::spool::{SpoolConfig, WORKER_EXTRA_SPOOL_KEY};
 use crate::WorkerId;
 use crate::{rpc_call, DEFAULT_WORKER_GROUP_NAME};
 
@@ -79,6 +82,11 @@ pub struct WorkerStartOpts {
     /// Ignore hyper-threading while detecting CPU cores
     pub no_hyper_threading: bool,
 
+    #[clap(long = "no-task-cgroups")]
+    /// Do not execute tasks in cgroups, even if the worker is able to create them.
+    /// Memory limits of tasks will not be enforced.
+    pub no_task_cgroups: bool,
+
     /// How often should the worker announce its existence to the server. (default: "8s")
     #[clap(long, default_value = "8s")]
     pub heartbeat: ArgDuration,
@@ -107,6 +115,16 @@ pub struct WorkerStartOpts {
     /// It should *NOT* be placed on a network filesystem.
     #[clap(long)]
     pub work_dir: Option<PathBuf>,
+
+    /// How long is the output of finished tasks submitted with `--spool` kept in the working
+    /// directory of the worker.
+    #[clap(long, default_value = "1h")]
+    pub spool_retention: ArgDuration,
+
+    /// Maximum total size of the output of finished tasks submitted with `--spool`, e.g. `10GiB`.
+    /// When it is exceeded, the oldest output is removed.
+    #[clap(long)]
+    pub spool_max_size: Option<ArgSize>,
 }
 
 pub async fn start_hq_worker(
@@ -122,6 +140,7 @@ pub async fn start_hq_worker(
         id: worker.id,
         configuration: worker.configuration,
         ended: None,
+        hw_state: None,
     });
     future.await.map_err(|e| e.into())
 }
@@ -171,6 +190,20 @@ fn gather_configuration(opts: WorkerStartOpts) -> anyhow::Result<WorkerConfigura
     let manager_info = gather_manager_info(opts.manager)?;
     let mut extra = Map::new();
 
+    let spool_config = SpoolConfig {
+        retention: opts.spool_retention.unpack(),
+        max_size: opts.spool_max_size.map(|size| size.unpack()),
+    };
+    extra.insert(
+        WORKER_EXTRA_SPOOL_KEY.to_string(),
+        serde_json::to_string(&spool_config)?,
+    );
+
+    extra.insert(
+        WORKER_EXTRA_CGROUPS_KEY.to_string(),
+        serde_json::to_string(&!opts.no_task_cgroups)?,
+    );
+
     if let Some(manager_info) = &manager_info {
         extra.insert(
             WORKER_EXTRA_MANAGER_KEY.to_string(),
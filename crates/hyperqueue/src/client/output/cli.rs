@@ -4,6 +4,7 @@ use cli_table::{print_stdout, Cell, CellStruct, Color, ColorChoice, Style, Table
 use std::fmt::{Display, Write};
 use std::io::Write as write;
 
+use crate::client::commands::event::format_event;
 use crate::client::job::WorkerMap;
 use crate::client::output::outputs::{Output, OutputStream, MAX_DISPLAYED_WORKERS};
 use crate::client::status::{get_task_status, job_status, Status};
@@ -11,12 +12,16 @@ use crate::common::env::is_hq_env;
 use crate::common::format::{human_duration, human_size};
 use crate::common::manager::info::GetManagerInfo;
 use crate::common::serverdir::AccessRecord;
-use crate::server::autoalloc::{Allocation, AllocationState};
+use crate::server::autoalloc::{Allocation, AllocationState, NodeHourBudget};
+use crate::server::event::log::EventLogSummary;
+use crate::server::event::MonitoringEvent;
 use crate::server::job::{JobTaskCounters, JobTaskInfo, JobTaskState, StartedTaskData};
 use crate::stream::reader::logfile::Summary;
 use crate::transfer::messages::{
-    AutoAllocListResponse, JobDescription, JobDetail, JobInfo, PinMode, QueueData, QueueState,
-    StatsResponse, TaskDescription, WaitForJobsResponse, WorkerExitInfo, WorkerInfo,
+    AllocationHistoryEntry, AllocationLog, AutoAllocListResponse, DurationStats, JobDescription,
+    JobDetail, JobInfo, JobStats, OutputLimitPolicy, PinMode, QueueData, QueueState, ScheduleState,
+    StatsHistoryResponse, StatsResponse, StatsSample, TaskDescription, WaitForJobsResponse,
+    WorkerExitInfo, WorkerInfo,
 };
 use crate::{JobId, JobTaskCount, WorkerId};
 
@@ -30,6 +35,7 @@ use std::time::SystemTime;
 
 use tako::program::StdioDef;
 use tako::resources::{ResourceDescriptor, ResourceDescriptorItem, ResourceDescriptorKind};
+use tako::task::TaskResourceUsage;
 
 use crate::client::output::common::{resolve_task_paths, TaskToPathsMap};
 use crate::client::output::Verbosity;
@@ -41,6 +47,7 @@ use colored::Colorize;
 use std::collections::BTreeSet;
 use std::fs::File;
 use tako::gateway::{LostWorkerReason, ResourceRequest, ResourceRequestEntry};
+use tako::hwstats::WorkerHwState;
 use tako::{format_comma_delimited, Map};
 
 pub const TASK_COLOR_CANCELED: Colorization = Colorization::Magenta;
@@ -104,7 +111,15 @@ impl CliOutput {
             priority,
             task_dir: _,
             crash_limit,
+            output_limit,
+            spool,
         } = task_desc;
+        let stdio_to_str = |stdio: &StdioDef| -> String {
+            match stdio {
+                StdioDef::Pipe if *spool => "<Spooled on worker>".to_string(),
+                _ => stdio_to_str(stdio).to_string(),
+            }
+        };
 
         let resources = format_resource_request(resources);
         rows.push(vec![
@@ -163,6 +178,20 @@ impl CliOutput {
         ]);
 
         rows.push(vec!["Crash limit".cell().bold(true), crash_limit.cell()]);
+
+        rows.push(vec![
+            "Output limit".cell().bold(true),
+            output_limit
+                .map(|limit| {
+                    let policy = match limit.policy {
+                        OutputLimitPolicy::Truncate => "truncate",
+                        OutputLimitPolicy::Fail => "fail",
+                    };
+                    format!("{} ({})", human_size(limit.max_size), policy)
+                })
+                .unwrap_or_else(|| "None".to_string())
+                .cell(),
+        ]);
     }
 
     fn print_task_summary(&self, tasks: &[JobTaskInfo], info: &JobInfo, worker_map: &WorkerMap) {
@@ -273,10 +302,11 @@ impl Output for CliOutput {
             id,
             configuration,
             ended: _ended,
+            hw_state,
         } = worker_info;
 
         let manager_info = configuration.get_manager_info();
-        let rows = vec![
+        let mut rows = vec![
             vec!["Worker ID".cell().bold(true), id.cell()],
             vec!["Hostname".cell().bold(true), configuration.hostname.cell()],
             vec![
@@ -344,6 +374,7 @@ impl Output for CliOutput {
                     .cell(),
             ],
         ];
+        rows.extend(hw_state_rows(hw_state.as_ref().map(|msg| &msg.state)));
         self.print_vertical_table(rows);
     }
 
@@ -395,6 +426,82 @@ impl Output for CliOutput {
         self.print_vertical_table(rows);
     }
 
+    fn print_server_stats_history(&self, history: StatsHistoryResponse) {
+        let StatsHistoryResponse { interval, samples } = history;
+        if samples.is_empty() {
+            log::info!("No statistics have been collected yet");
+            return;
+        }
+
+        let per_interval = |name: &str| format!("{name} / {}", format_duration(interval));
+        let rows: Vec<Vec<CellStruct>> = [
+            (
+                per_interval("Tasks finished"),
+                column(&samples, |s| Some(s.tasks_finished as f64)),
+            ),
+            (
+                "Waiting tasks".to_string(),
+                column(&samples, |s| Some(s.tasks_waiting as f64)),
+            ),
+            (
+                "Running tasks".to_string(),
+                column(&samples, |s| Some(s.tasks_running as f64)),
+            ),
+            (
+                "Workers".to_string(),
+                column(&samples, |s| Some(s.workers as f64)),
+            ),
+            (
+                "CPU utilization (%)".to_string(),
+                column(&samples, |s| s.cpu_usage),
+            ),
+            (
+                per_interval("Scheduler runs"),
+                column(&samples, |s| Some(s.scheduler_runs as f64)),
+            ),
+            (
+                "Scheduler duration (ms)".to_string(),
+                column(&samples, |s| {
+                    s.scheduler_duration
+                        .map(|duration| duration.as_secs_f64() * 1000.0)
+                }),
+            ),
+        ]
+        .into_iter()
+        .map(|(name, values)| {
+            let present = || values.iter().filter_map(|value| *value);
+            let format_value =
+                |value: Option<f64>| value.map(format_stat_value).unwrap_or_else(|| "N/A".into());
+            vec![
+                name.cell().bold(true),
+                sparkline(&values, SPARKLINE_MAX_WIDTH).cell(),
+                format_value(values.last().copied().flatten()).cell(),
+                format_value(present().reduce(f64::min)).cell(),
+                format_value(present().reduce(f64::max)).cell(),
+            ]
+        })
+        .collect();
+
+        let first: DateTime<Local> = samples[0].time.into();
+        let last: DateTime<Local> = samples[samples.len() - 1].time.into();
+        println!(
+            "{} {} from {} to {} (one sample per {})",
+            samples.len(),
+            pluralize("sample", samples.len()),
+            first.format("%F %T"),
+            last.format("%F %T"),
+            format_duration(interval)
+        );
+        let header = vec![
+            "Statistic".cell().bold(true),
+            "History".cell().bold(true),
+            "Last".cell().bold(true),
+            "Min".cell().bold(true),
+            "Max".cell().bold(true),
+        ];
+        self.print_horizontal_table(rows, header);
+    }
+
     fn print_job_submitted(&self, job: JobDetail) {
         println!(
             "Job submitted {}, job ID: {}",
@@ -501,6 +608,97 @@ impl Output for CliOutput {
         self.print_task_summary(&tasks, &info, &worker_map);
     }
 
+    fn print_job_stats(&self, stats: Vec<JobStats>) {
+        let format_duration_stats = |stats: Option<DurationStats>| match stats {
+            Some(stats) => format!(
+                "min: {}, median: {}, p95: {}, max: {}",
+                format_std_duration(stats.min),
+                format_std_duration(stats.median),
+                format_std_duration(stats.p95),
+                format_std_duration(stats.max)
+            ),
+            None => "N/A".to_string(),
+        };
+
+        for stats in stats {
+            let JobStats {
+                job_id,
+                name,
+                n_tasks,
+                counters,
+                task_duration,
+                wait_time,
+                throughput,
+                failures_per_worker,
+                estimated_time_to_completion,
+            } = stats;
+
+            let throughput = if throughput.is_empty() {
+                "N/A".to_string()
+            } else {
+                throughput
+                    .into_iter()
+                    .map(|interval| {
+                        format!(
+                            "{}: {} {} ({:.2}/s)",
+                            format_time(interval.start),
+                            interval.n_completed_tasks,
+                            pluralize("task", interval.n_completed_tasks as usize),
+                            interval.n_completed_tasks as f64 / interval.duration.as_secs_f64()
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            let failures = if failures_per_worker.is_empty() {
+                "None".to_string()
+            } else {
+                failures_per_worker
+                    .into_iter()
+                    .map(|(worker_id, count)| format!("Worker {worker_id}: {count}"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            let estimate = match estimated_time_to_completion {
+                Some(duration) if duration.is_zero() => "Completed".to_string(),
+                Some(duration) => format_std_duration(duration),
+                None => "N/A".to_string(),
+            };
+
+            let rows = vec![
+                vec!["ID".cell().bold(true), job_id.cell()],
+                vec!["Name".cell().bold(true), name.cell()],
+                vec![
+                    "Tasks".cell().bold(true),
+                    format!(
+                        "{n_tasks} ({} waiting, {} running, {} finished, {} failed, {} canceled)",
+                        counters.n_waiting_tasks(n_tasks),
+                        counters.n_running_tasks,
+                        counters.n_finished_tasks,
+                        counters.n_failed_tasks,
+                        counters.n_canceled_tasks
+                    )
+                    .cell(),
+                ],
+                vec![
+                    "Task duration".cell().bold(true),
+                    format_duration_stats(task_duration).cell(),
+                ],
+                vec![
+                    "Wait time".cell().bold(true),
+                    format_duration_stats(wait_time).cell(),
+                ],
+                vec!["Throughput".cell().bold(true), throughput.cell()],
+                vec!["Failures per worker".cell().bold(true), failures.cell()],
+                vec![
+                    "Estimated time to completion".cell().bold(true),
+                    estimate.cell(),
+                ],
+            ];
+            self.print_vertical_table(rows);
+        }
+    }
+
     fn print_job_wait(
         &self,
         duration: Duration,
@@ -704,6 +902,20 @@ impl Output for CliOutput {
                         ("Stderr", stderr),
                     ]),
                 ],
+                vec![
+                    "Resource usage".cell().bold(true),
+                    match &task.state {
+                        JobTaskState::Finished {
+                            resource_usage: Some(usage),
+                            ..
+                        }
+                        | JobTaskState::Failed {
+                            resource_usage: Some(usage),
+                            ..
+                        } => format_resource_usage(usage),
+                        _ => "".cell(),
+                    },
+                ],
                 vec![
                     "Error".cell().bold(true),
                     match (verbosity, &task.state) {
@@ -722,6 +934,15 @@ impl Output for CliOutput {
                         _ => "".cell(),
                     },
                 ],
+                vec![
+                    "Note".cell().bold(true),
+                    match &task.state {
+                        JobTaskState::Finished {
+                            note: Some(note), ..
+                        } => note.as_str().cell(),
+                        _ => "".cell(),
+                    },
+                ],
                 vec![
                     "Resources".cell().bold(true),
                     format_resource_request(&task_desc.resources).cell(),
@@ -741,7 +962,7 @@ impl Output for CliOutput {
     }
 
     fn print_summary(&self, filename: &Path, summary: Summary) {
-        let rows = vec![
+        let mut rows = vec![
             vec!["Filename".cell().bold(true), filename.display().cell()],
             vec![
                 "Tasks".cell().bold(true),
@@ -774,6 +995,85 @@ impl Output for CliOutput {
                 .cell(),
             ],
         ];
+        rows.push(vec![
+            "Incomplete streams".cell().bold(true),
+            summary.n_incomplete.to_string().cell(),
+        ]);
+        if summary.truncated_size > 0 {
+            rows.push(vec![
+                "Truncated data".cell().bold(true),
+                human_size(summary.truncated_size).cell(),
+            ]);
+        }
+        if summary.compression.is_compressed() {
+            rows.push(vec![
+                "Compression".cell().bold(true),
+                summary.compression.to_string().cell(),
+            ]);
+            rows.push(vec![
+                "Compressed stdout/stderr size".cell().bold(true),
+                format!(
+                    "{} / {}",
+                    human_size(summary.compressed_stdout_size),
+                    human_size(summary.compressed_stderr_size)
+                )
+                .cell(),
+            ]);
+        }
+        self.print_vertical_table(rows);
+    }
+
+    fn print_event_log_events(&self, events: Vec<MonitoringEvent>) {
+        if events.is_empty() {
+            log::info!("No events were found");
+            return;
+        }
+        let rows: Vec<_> = events
+            .into_iter()
+            .map(|event| {
+                let id = event.id;
+                let time = event.time;
+                let type_name = event.payload.type_name();
+                vec![
+                    id.cell(),
+                    format_systemtime(time).cell(),
+                    type_name.cell(),
+                    format_event_details(event).cell(),
+                ]
+            })
+            .collect();
+        let header = vec![
+            "ID".cell().bold(true),
+            "Time".cell().bold(true),
+            "Type".cell().bold(true),
+            "Details".cell().bold(true),
+        ];
+        self.print_horizontal_table(rows, header);
+    }
+
+    fn print_event_log_summary(&self, summary: EventLogSummary) {
+        let format_time = |time: Option<SystemTime>| match time {
+            Some(time) => format_systemtime(time).cell(),
+            None => "".cell(),
+        };
+
+        let mut rows = vec![
+            vec!["Events".cell().bold(true), summary.event_count.cell()],
+            vec![
+                "First event".cell().bold(true),
+                format_time(summary.first_event_time),
+            ],
+            vec![
+                "Last event".cell().bold(true),
+                format_time(summary.last_event_time),
+            ],
+        ];
+        rows.extend(
+            summary
+                .type_counts
+                .into_iter()
+                .map(|(name, count)| vec![name.cell().bold(true), count.cell()]),
+        );
         self.print_vertical_table(rows);
     }
 
@@ -789,6 +1089,8 @@ impl Output for CliOutput {
                     name,
                     manager_type,
                     state,
+                    remaining_budget,
+                    schedule_state,
                 } = data;
 
                 vec![
@@ -803,6 +1105,8 @@ impl Output for CliOutput {
                     format_duration(info.timelimit()).to_string().cell(),
                     manager_type.cell(),
                     name.unwrap_or_else(|| "".to_string()).cell(),
+                    format_budget(info.budget(), remaining_budget).cell(),
+                    format_schedule_state(schedule_state).cell(),
                     info.additional_args().join(",").cell(),
                 ]
             })
@@ -816,6 +1120,8 @@ impl Output for CliOutput {
             "Timelimit".cell().bold(true),
             "Manager".cell().bold(true),
             "Name".cell().bold(true),
+            "Budget".cell().bold(true),
+            "Schedule".cell().bold(true),
             "Args".cell().bold(true),
         ];
         self.print_horizontal_table(rows, header);
@@ -856,6 +1162,79 @@ impl Output for CliOutput {
         self.print_horizontal_table(rows, header);
     }
 
+    fn print_allocation_history(&self, history: Vec<AllocationHistoryEntry>) {
+        if history.is_empty() {
+            log::info!("No allocations have finished yet");
+            return;
+        }
+        let format_time = |time: Option<SystemTime>| match time {
+            Some(time) => format_systemtime(time).cell(),
+            None => "".cell(),
+        };
+
+        for (index, entry) in history.into_iter().enumerate() {
+            let AllocationHistoryEntry {
+                allocation,
+                output_tail,
+            } = entry;
+            let times = allocation_times_from_alloc(&allocation);
+            let workers = allocation
+                .worker_ids()
+                .into_iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            let rows = vec![
+                vec!["ID".cell().bold(true), allocation.id.cell()],
+                vec![
+                    "State".cell().bold(true),
+                    allocation_status_to_cell(&allocation.status),
+                ],
+                vec![
+                    "Worker count".cell().bold(true),
+                    allocation.target_worker_count.cell(),
+                ],
+                vec!["Workers".cell().bold(true), workers.cell()],
+                vec![
+                    "Queue time".cell().bold(true),
+                    format_time(Some(times.get_queued_at())),
+                ],
+                vec![
+                    "Start time".cell().bold(true),
+                    format_time(times.get_started_at()),
+                ],
+                vec![
+                    "Finish time".cell().bold(true),
+                    format_time(times.get_finished_at()),
+                ],
+                vec!["Stdout".cell().bold(true), output_tail.stdout.cell()],
+                vec!["Stderr".cell().bold(true), output_tail.stderr.cell()],
+            ];
+            if index > 0 {
+                println!();
+            }
+            self.print_vertical_table(rows);
+        }
+    }
+
+    fn print_allocation_log(&self, log: AllocationLog) {
+        let AllocationLog {
+            allocation,
+            output,
+            truncated,
+        } = log;
+        if truncated {
+            log::warn!(
+                "The directory of allocation {} was already removed, only the last lines of its output are available",
+                allocation.id
+            );
+        }
+        println!("{}", "# stdout".bold());
+        println!("{}", output.stdout);
+        println!("{}", "# stderr".bold());
+        println!("{}", output.stderr);
+    }
+
     fn print_hw(&self, descriptor: &ResourceDescriptor) {
         println!("Summary:\n{}\n", resources_summary(descriptor, true));
         println!("Full Description:\n{}", resources_full_describe(descriptor));
@@ -884,6 +1263,33 @@ impl AllocationTimes {
     }
 }
 
+fn format_budget(budget: Option<&NodeHourBudget>, remaining: Option<f64>) -> String {
+    match (budget, remaining) {
+        (Some(budget), Some(remaining)) => {
+            let mut result = format!("{:.1}/{} node-h", remaining, budget.limit);
+            if let Some(window) = budget.window {
+                write!(result, " per {}", format_duration(window)).unwrap();
+            }
+            result
+        }
+        _ => "".to_string(),
+    }
+}
+
+fn format_schedule_state(state: Option<ScheduleState>) -> String {
+    match state {
+        Some(ScheduleState::Active { until: Some(until) }) => {
+            format!("active until {}", format_systemtime(until))
+        }
+        Some(ScheduleState::Active { until: None }) => "active".to_string(),
+        Some(ScheduleState::Inactive {
+            opens_at: Some(opens_at),
+        }) => format!("opens at {}", format_systemtime(opens_at)),
+        Some(ScheduleState::Inactive { opens_at: None }) => "inactive".to_string(),
+        None => "".to_string(),
+    }
+}
+
 fn multiline_cell<T: AsRef<str>>(rows: Vec<(&'static str, T)>) -> CellStruct {
     if rows.iter().all(|(_, value)| value.as_ref().is_empty()) {
         return "".cell();
@@ -1211,6 +1617,33 @@ fn format_task_paths<'a>(
     }
 }
 
+fn format_resource_usage(usage: &TaskResourceUsage) -> CellStruct {
+    let duration = |duration: Duration| {
+        human_duration(
+            chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero()),
+        )
+    };
+    multiline_cell(vec![
+        (
+            "CPU time (user/system)",
+            format!(
+                "{} / {}",
+                duration(usage.user_time),
+                duration(usage.system_time)
+            ),
+        ),
+        ("Max RSS", human_size(usage.max_rss)),
+        (
+            "Disk read/write",
+            format!(
+                "{} / {}",
+                human_size(usage.read_bytes),
+                human_size(usage.write_bytes)
+            ),
+        ),
+    ])
+}
+
 fn format_task_duration(start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) -> String {
     match (start, end) {
         (Some(start), None) => human_duration(Utc::now() - start),
@@ -1224,6 +1657,33 @@ fn format_systemtime(time: SystemTime) -> impl Display {
     datetime.format("%d.%m.%Y %H:%M:%S")
 }
 
+/// Formats the attributes of an event (except for its type) as `key: value` pairs.
+fn format_event_details(event: MonitoringEvent) -> String {
+    let mut json = format_event(event);
+    let attributes = match json["event"].as_object_mut() {
+        Some(attributes) => attributes,
+        None => return String::new(),
+    };
+    attributes.remove("type");
+    attributes
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            format!("{key}: {}", truncate_middle(&value, 50))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_std_duration(duration: Duration) -> String {
+    human_duration(
+        chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero()),
+    )
+}
+
 fn format_time(time: DateTime<Utc>) -> impl Display {
     let datetime: DateTime<Local> = time.into();
     datetime.format("%d.%m.%Y %H:%M:%S")
@@ -1273,6 +1733,121 @@ fn resource_summary_kind(kind: &ResourceDescriptorKind) -> String {
     }
 }
 
+/// Sparklines that are wider than this are downsampled.
+const SPARKLINE_MAX_WIDTH: usize = 60;
+
+fn column(samples: &[StatsSample], f: impl Fn(&StatsSample) -> Option<f64>) -> Vec<Option<f64>> {
+    samples.iter().map(f).collect()
+}
+
+fn format_stat_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{value}")
+    } else {
+        format!("{value:.2}")
+    }
+}
+
+/// Renders the values as a line of block characters whose height is proportional to the value.
+/// Missing values are rendered as spaces. If there are more than `max_width` values, consecutive
+/// values are averaged.
+fn sparkline(values: &[Option<f64>], max_width: usize) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let chunk_size = ((values.len() + max_width - 1) / max_width).max(1);
+    let values: Vec<Option<f64>> = values
+        .chunks(chunk_size)
+        .map(|chunk| {
+            let present: Vec<f64> = chunk.iter().filter_map(|value| *value).collect();
+            (!present.is_empty()).then(|| present.iter().sum::<f64>() / present.len() as f64)
+        })
+        .collect();
+
+    let max = values.iter().filter_map(|value| *value).fold(0.0, f64::max);
+    values
+        .into_iter()
+        .map(|value| match value {
+            Some(value) if max > 0.0 => {
+                let index = (value / max * (BLOCKS.len() - 1) as f64).round() as usize;
+                BLOCKS[index.min(BLOCKS.len() - 1)]
+            }
+            Some(_) => BLOCKS[0],
+            None => ' ',
+        })
+        .collect()
+}
+
+/// Rows describing the hardware utilization of a worker, taken from its last overview.
+fn hw_state_rows(hw_state: Option<&WorkerHwState>) -> Vec<Vec<CellStruct>> {
+    let na = || "N/A".to_string();
+    let format_usage = |total: u64, free: u64| {
+        let used = total.saturating_sub(free);
+        let percent = if total > 0 {
+            used as f64 / total as f64 * 100.0
+        } else {
+            0.0
+        };
+        format!(
+            "{} / {} ({:.1} %)",
+            human_size(used),
+            human_size(total),
+            percent
+        )
+    };
+
+    let cpu = hw_state
+        .map(|state| {
+            let usage = &state.worker_cpu_usage.cpu_per_core_percent_usage;
+            let average = usage.iter().sum::<f32>() / usage.len().max(1) as f32;
+            format!("{:.1} %", average)
+        })
+        .unwrap_or_else(na);
+    let memory = hw_state
+        .map(|state| {
+            let memory = &state.worker_memory_usage;
+            format_usage(memory.total, memory.free)
+        })
+        .unwrap_or_else(na);
+    let load = hw_state
+        .and_then(|state| state.load_average.as_ref())
+        .map(|load| format!("{:.2} {:.2} {:.2}", load.one, load.five, load.fifteen))
+        .unwrap_or_else(na);
+    let disk = hw_state
+        .map(|state| {
+            let disk = &state.worker_disk_usage;
+            format!(
+                "read {}/s, write {}/s",
+                human_size(disk.read_throughput),
+                human_size(disk.write_throughput)
+            )
+        })
+        .unwrap_or_else(na);
+    let filesystem = hw_state
+        .and_then(|state| state.worker_filesystem_usage.as_ref())
+        .map(|fs| format_usage(fs.total, fs.free))
+        .unwrap_or_else(na);
+    let numa = hw_state
+        .filter(|state| !state.numa_memory_usage.is_empty())
+        .map(|state| {
+            state
+                .numa_memory_usage
+                .iter()
+                .map(|node| format!("{}: {}", node.node, format_usage(node.total, node.free)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_else(na);
+
+    vec![
+        vec!["CPU utilization".cell().bold(true), cpu.cell()],
+        vec!["Memory usage".cell().bold(true), memory.cell()],
+        vec!["Load average".cell().bold(true), load.cell()],
+        vec!["Disk IO".cell().bold(true), disk.cell()],
+        vec!["Work dir filesystem".cell().bold(true), filesystem.cell()],
+        vec!["NUMA memory usage".cell().bold(true), numa.cell()],
+    ]
+}
+
 fn resources_summary(resources: &ResourceDescriptor, multiline: bool) -> String {
     let special_format = |descriptor: &ResourceDescriptorItem| -> Option<String> {
         if descriptor.name == tako::resources::MEM_RESOURCE_NAME {
@@ -1308,7 +1883,7 @@ fn resources_summary(resources: &ResourceDescriptor, multiline: bool) -> String
 
 #[cfg(test)]
 mod tests {
-    use crate::client::output::cli::{resources_full_describe, resources_summary};
+    use crate::client::output::cli::{resources_full_describe, resources_summary, sparkline};
     use tako::resources::{
         ResourceDescriptor, ResourceDescriptorItem, ResourceDescriptorKind, MEM_RESOURCE_NAME,
     };
@@ -1410,4 +1985,19 @@ mod tests {
         }]);
         assert_eq!(resources_summary(&d, false), "mem 4.12 GiB");
     }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[], 10), "");
+        assert_eq!(
+            sparkline(&[Some(0.0), Some(7.0), None, Some(3.5)], 10),
+            "▁█ ▅"
+        );
+        assert_eq!(sparkline(&[Some(0.0), Some(0.0)], 10), "▁▁");
+        // Values are averaged when there are more of them than the width
+        assert_eq!(
+            sparkline(&[Some(0.0), Some(0.0), Some(4.0), Some(10.0)], 2),
+            "▁█"
+        );
+    }
 }
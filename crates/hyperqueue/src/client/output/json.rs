@@ -10,8 +10,10 @@ use tako::Map;
 use tako::gateway::ResourceRequest;
 use tako::program::{ProgramDefinition, StdioDef};
 use tako::resources::{ResourceDescriptor, ResourceDescriptorItem, ResourceDescriptorKind};
+use tako::task::TaskResourceUsage;
 use tako::worker::WorkerConfiguration;
 
+use crate::client::commands::event::format_event;
 use crate::client::job::WorkerMap;
 use crate::client::output::common::{resolve_task_paths, TaskToPathsMap};
 use crate::client::output::outputs::{Output, OutputStream};
@@ -19,11 +21,14 @@ use crate::client::output::Verbosity;
 use crate::common::manager::info::ManagerType;
 use crate::common::serverdir::AccessRecord;
 use crate::server::autoalloc::{Allocation, AllocationState, QueueId};
+use crate::server::event::log::EventLogSummary;
+use crate::server::event::MonitoringEvent;
 use crate::server::job::{JobTaskInfo, JobTaskState, StartedTaskData};
 use crate::stream::reader::logfile::Summary;
 use crate::transfer::messages::{
-    AutoAllocListResponse, JobDescription, JobDetail, JobInfo, QueueData, StatsResponse,
-    TaskDescription, WaitForJobsResponse, WorkerInfo,
+    AllocationHistoryEntry, AllocationLog, AutoAllocListResponse, DurationStats, JobDescription,
+    JobDetail, JobInfo, JobStats, QueueData, ScheduleState, StatsHistoryResponse, StatsResponse,
+    StatsSample, TaskDescription, WaitForJobsResponse, WorkerInfo,
 };
 use crate::{JobId, JobTaskId};
 
@@ -65,6 +70,24 @@ impl Output for JsonOutput {
         self.print(json!(stats));
     }
 
+    fn print_server_stats_history(&self, history: StatsHistoryResponse) {
+        let StatsHistoryResponse { interval, samples } = history;
+        let column = |f: &dyn Fn(&StatsSample) -> Value| samples.iter().map(f).collect::<Vec<_>>();
+        self.print(json!({
+            "interval": format_duration(interval),
+            "time": column(&|sample| format_datetime(sample.time)),
+            "tasks_finished": column(&|sample| json!(sample.tasks_finished)),
+            "tasks_waiting": column(&|sample| json!(sample.tasks_waiting)),
+            "tasks_running": column(&|sample| json!(sample.tasks_running)),
+            "workers": column(&|sample| json!(sample.workers)),
+            "cpu_usage": column(&|sample| json!(sample.cpu_usage)),
+            "scheduler_runs": column(&|sample| json!(sample.scheduler_runs)),
+            "scheduler_duration": column(&|sample| {
+                sample.scheduler_duration.map(format_duration).unwrap_or(Value::Null)
+            }),
+        }));
+    }
+
     fn print_job_submitted(&self, job: JobDetail) {
         self.print(json!({
             "id": job.info.id
@@ -125,6 +148,8 @@ impl Output for JsonOutput {
                     priority,
                     task_dir,
                     crash_limit,
+                    output_limit,
+                    spool,
                 },
             ..
         } = job_desc
@@ -146,12 +171,18 @@ impl Output for JsonOutput {
             json["time_limit"] = json!(time_limit.map(format_duration));
             json["task_dir"] = json!(task_dir);
             json["crash_limit"] = json!(crash_limit);
+            json["output_limit"] = json!(output_limit);
+            json["spool"] = json!(spool);
         }
 
         json["tasks"] = format_tasks(tasks, task_paths);
         self.print(json);
     }
 
+    fn print_job_stats(&self, stats: Vec<JobStats>) {
+        self.print(stats.into_iter().map(format_job_stats).collect());
+    }
+
     fn print_job_wait(
         &self,
         duration: Duration,
@@ -201,12 +232,14 @@ impl Output for JsonOutput {
 
     fn print_task_info(
         &self,
-        _job: (JobId, JobDetail),
-        _tasks: Vec<JobTaskInfo>,
+        job: (JobId, JobDetail),
+        tasks: Vec<JobTaskInfo>,
         _worker_map: WorkerMap,
-        _server_uid: &str,
+        server_uid: &str,
         _verbosity: Verbosity,
     ) {
+        let map = resolve_task_paths(&job.1, server_uid);
+        self.print(format_tasks(tasks, map));
     }
 
     fn print_summary(&self, filename: &Path, summary: Summary) {
@@ -217,6 +250,25 @@ impl Output for JsonOutput {
         self.print(json);
     }
 
+    fn print_event_log_events(&self, events: Vec<MonitoringEvent>) {
+        self.print(events.into_iter().map(format_event).collect());
+    }
+
+    fn print_event_log_summary(&self, summary: EventLogSummary) {
+        let EventLogSummary {
+            event_count,
+            type_counts,
+            first_event_time,
+            last_event_time,
+        } = summary;
+        self.print(json!({
+            "event_count": event_count,
+            "types": type_counts,
+            "first_event_time": first_event_time.map(format_datetime),
+            "last_event_time": last_event_time.map(format_datetime),
+        }));
+    }
+
     fn print_autoalloc_queues(&self, info: AutoAllocListResponse) {
         let mut queues: Vec<_> = info.queues.into_iter().collect();
         queues.sort_by_key(|descriptor| descriptor.0);
@@ -233,6 +285,44 @@ impl Output for JsonOutput {
         self.print(allocations.into_iter().map(format_allocation).collect());
     }
 
+    fn print_allocation_history(&self, history: Vec<AllocationHistoryEntry>) {
+        self.print(
+            history
+                .into_iter()
+                .map(|entry| {
+                    let AllocationHistoryEntry {
+                        allocation,
+                        output_tail,
+                    } = entry;
+                    let workers = allocation.worker_ids();
+                    let mut value = format_allocation(allocation);
+                    value["workers"] = workers
+                        .into_iter()
+                        .map(|id| id.as_num().into())
+                        .collect::<Vec<Value>>()
+                        .into();
+                    value["stdout_tail"] = output_tail.stdout.into();
+                    value["stderr_tail"] = output_tail.stderr.into();
+                    value
+                })
+                .collect(),
+        );
+    }
+
+    fn print_allocation_log(&self, log: AllocationLog) {
+        let AllocationLog {
+            allocation,
+            output,
+            truncated,
+        } = log;
+        self.print(json!({
+            "id": allocation.id,
+            "stdout": output.stdout,
+            "stderr": output.stderr,
+            "truncated": truncated,
+        }));
+    }
+
     fn print_hw(&self, descriptor: &ResourceDescriptor) {
         self.print(format_resource_descriptor(descriptor));
     }
@@ -290,6 +380,55 @@ fn format_job_info(info: JobInfo) -> serde_json::Value {
     })
 }
 
+fn format_job_stats(stats: JobStats) -> serde_json::Value {
+    let JobStats {
+        job_id,
+        name,
+        n_tasks,
+        counters,
+        task_duration,
+        wait_time,
+        throughput,
+        failures_per_worker,
+        estimated_time_to_completion,
+    } = stats;
+
+    let format_duration_stats = |stats: Option<DurationStats>| {
+        stats.map(|stats| {
+            json!({
+                "min": format_duration(stats.min),
+                "median": format_duration(stats.median),
+                "p95": format_duration(stats.p95),
+                "max": format_duration(stats.max),
+            })
+        })
+    };
+
+    json!({
+        "info": format_job_info(JobInfo {
+            id: job_id,
+            name,
+            n_tasks,
+            counters,
+        }),
+        "task_duration": format_duration_stats(task_duration),
+        "wait_time": format_duration_stats(wait_time),
+        "throughput": throughput
+            .into_iter()
+            .map(|interval| json!({
+                "start": format_datetime(interval.start),
+                "duration": format_duration(interval.duration),
+                "completed_tasks": interval.n_completed_tasks,
+            }))
+            .collect::<Vec<_>>(),
+        "failures_per_worker": failures_per_worker
+            .into_iter()
+            .map(|(worker_id, count)| (worker_id.to_string(), count))
+            .collect::<Map<_, _>>(),
+        "estimated_time_to_completion": estimated_time_to_completion.map(format_duration),
+    })
+}
+
 fn format_tasks(tasks: Vec<JobTaskInfo>, map: TaskToPathsMap) -> serde_json::Value {
     tasks
         .into_iter()
@@ -314,18 +453,30 @@ fn format_tasks(tasks: Vec<JobTaskInfo>, map: TaskToPathsMap) -> serde_json::Val
                 JobTaskState::Finished {
                     started_data,
                     end_date,
+                    resource_usage,
+                    note,
                 } => {
                     fill_task_started_data(&mut data, started_data);
                     data["finished_at"] = format_datetime(end_date);
+                    if let Some(usage) = resource_usage {
+                        data["resource_usage"] = format_resource_usage(&usage);
+                    }
+                    if let Some(note) = note {
+                        data["note"] = note.into();
+                    }
                 }
                 JobTaskState::Failed {
                     started_data,
                     end_date,
                     error,
+                    resource_usage,
                 } => {
                     fill_task_started_data(&mut data, started_data);
                     data["finished_at"] = format_datetime(end_date);
                     data["error"] = error.into();
+                    if let Some(usage) = resource_usage {
+                        data["resource_usage"] = format_resource_usage(&usage);
+                    }
                 }
                 _ => {}
             };
@@ -340,6 +491,8 @@ fn format_autoalloc_queue(id: QueueId, descriptor: QueueData) -> serde_json::Val
         name,
         manager_type,
         state,
+        remaining_budget,
+        schedule_state,
     } = descriptor;
 
     let manager = match manager_type {
@@ -357,9 +510,29 @@ fn format_autoalloc_queue(id: QueueId, descriptor: QueueData) -> serde_json::Val
         "workers_per_alloc": info.workers_per_alloc(),
         "timelimit": format_duration(info.timelimit()),
         "max_worker_count": info.max_worker_count(),
+        "min_workers": info.min_workers(),
+        "job_arrays": info.job_arrays(),
         "worker_cpu_args": info.worker_cpu_args(),
         "worker_resource_args": info.worker_resource_args(),
         "on_server_lost": crate::common::format::server_lost_policy_to_str(info.on_server_lost()),
+        "budget": info.budget().map(|budget| json!({
+            "limit": budget.limit,
+            "window": budget.window.map(format_duration),
+            "remaining": remaining_budget,
+        })),
+        "active_schedule": info.active_schedule().map(|schedule| json!({
+            "windows": schedule.windows().iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+            "cap_time_limit": info.cap_timelimit(),
+            "active": matches!(schedule_state, Some(ScheduleState::Active { .. })),
+            "active_until": match schedule_state {
+                Some(ScheduleState::Active { until }) => until.map(format_datetime),
+                _ => None
+            },
+            "opens_at": match schedule_state {
+                Some(ScheduleState::Inactive { opens_at }) => opens_at.map(format_datetime),
+                _ => None
+            },
+        })),
     })
 }
 fn format_allocation(allocation: Allocation) -> serde_json::Value {
@@ -421,6 +594,7 @@ fn format_worker_info(worker_info: WorkerInfo) -> serde_json::Value {
                 extra: _,
             },
         ended,
+        hw_state,
     } = worker_info;
 
     json!({
@@ -439,7 +613,8 @@ fn format_worker_info(worker_info: WorkerInfo) -> serde_json::Value {
         }),
         "ended": ended.map(|info| json!({
             "at": format_datetime(info.ended_at)
-        }))
+        })),
+        "hw_state": hw_state.map(|msg| msg.state)
     })
 }
 fn format_resource_descriptor(descriptor: &ResourceDescriptor) -> Value {
@@ -487,3 +662,14 @@ fn format_duration(duration: Duration) -> serde_json::Value {
 pub fn format_datetime<T: Into<DateTime<Utc>>>(time: T) -> serde_json::Value {
     json!(time.into())
 }
+
+/// Times are formatted in seconds, sizes in bytes.
+pub fn format_resource_usage(usage: &TaskResourceUsage) -> serde_json::Value {
+    json!({
+        "user_time": usage.user_time.as_secs_f64(),
+        "system_time": usage.system_time.as_secs_f64(),
+        "max_rss": usage.max_rss,
+        "read_bytes": usage.read_bytes,
+        "write_bytes": usage.write_bytes,
+    })
+}
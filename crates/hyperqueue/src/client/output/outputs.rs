@@ -1,10 +1,13 @@
 use crate::common::serverdir::AccessRecord;
 use crate::transfer::messages::{
-    AutoAllocListResponse, JobDetail, JobInfo, StatsResponse, WaitForJobsResponse, WorkerInfo,
+    AllocationHistoryEntry, AllocationLog, AutoAllocListResponse, JobDetail, JobInfo, JobStats,
+    StatsHistoryResponse, StatsResponse, WaitForJobsResponse, WorkerInfo,
 };
 
 use crate::client::job::WorkerMap;
 use crate::server::autoalloc::Allocation;
+use crate::server::event::log::EventLogSummary;
+use crate::server::event::MonitoringEvent;
 use crate::stream::reader::logfile::Summary;
 use std::path::Path;
 
@@ -40,11 +43,13 @@ pub trait Output {
     // Server
     fn print_server_record(&self, server_dir: &Path, record: &AccessRecord);
     fn print_server_stats(&self, stats: StatsResponse);
+    fn print_server_stats_history(&self, history: StatsHistoryResponse);
 
     // Jobs
     fn print_job_submitted(&self, job: JobDetail);
     fn print_job_list(&self, jobs: Vec<JobInfo>, total_jobs: usize);
     fn print_job_detail(&self, job: JobDetail, worker_map: WorkerMap, server_uid: &str);
+    fn print_job_stats(&self, stats: Vec<JobStats>);
     fn print_job_wait(
         &self,
         duration: Duration,
@@ -80,9 +85,15 @@ pub trait Output {
     // Log
     fn print_summary(&self, filename: &Path, summary: Summary);
 
+    // Event log
+    fn print_event_log_events(&self, events: Vec<MonitoringEvent>);
+    fn print_event_log_summary(&self, summary: EventLogSummary);
+
     // Autoalloc
     fn print_autoalloc_queues(&self, info: AutoAllocListResponse);
     fn print_allocations(&self, allocations: Vec<Allocation>);
+    fn print_allocation_history(&self, history: Vec<AllocationHistoryEntry>);
+    fn print_allocation_log(&self, log: AllocationLog);
 
     // Hw
     fn print_hw(&self, descriptor: &ResourceDescriptor);
@@ -13,11 +13,13 @@ use crate::client::output::outputs::{Output, OutputStream};
 use crate::client::status::{job_status, Status};
 use crate::common::serverdir::AccessRecord;
 use crate::server::autoalloc::Allocation;
+use crate::server::event::log::EventLogSummary;
+use crate::server::event::MonitoringEvent;
 use crate::server::job::JobTaskInfo;
 use crate::stream::reader::logfile::Summary;
 use crate::transfer::messages::{
-    AutoAllocListResponse, JobDetail, JobInfo, StatsResponse, WaitForJobsResponse, WorkerExitInfo,
-    WorkerInfo,
+    AllocationHistoryEntry, AllocationLog, AutoAllocListResponse, JobDetail, JobInfo, JobStats,
+    StatsHistoryResponse, StatsResponse, WaitForJobsResponse, WorkerExitInfo, WorkerInfo,
 };
 use crate::JobId;
 
@@ -61,6 +63,7 @@ impl Output for Quiet {
         println!("{}", server_dir.to_str().unwrap())
     }
     fn print_server_stats(&self, _stats: StatsResponse) {}
+    fn print_server_stats_history(&self, _history: StatsHistoryResponse) {}
 
     // Jobs
     fn print_job_submitted(&self, job: JobDetail) {
@@ -74,6 +77,8 @@ impl Output for Quiet {
     }
     fn print_job_detail(&self, _job: JobDetail, _worker_map: WorkerMap, _server_uid: &str) {}
 
+    fn print_job_stats(&self, _stats: Vec<JobStats>) {}
+
     fn print_job_wait(
         &self,
         _duration: Duration,
@@ -115,9 +120,15 @@ impl Output for Quiet {
     // Log
     fn print_summary(&self, _filename: &Path, _summary: Summary) {}
 
+    // Event log
+    fn print_event_log_events(&self, _events: Vec<MonitoringEvent>) {}
+    fn print_event_log_summary(&self, _summary: EventLogSummary) {}
+
     // Autoalloc
     fn print_autoalloc_queues(&self, _info: AutoAllocListResponse) {}
     fn print_allocations(&self, _allocations: Vec<Allocation>) {}
+    fn print_allocation_history(&self, _history: Vec<AllocationHistoryEntry>) {}
+    fn print_allocation_log(&self, _log: AllocationLog) {}
 
     // Hw
     fn print_hw(&self, _descriptor: &ResourceDescriptor) {}
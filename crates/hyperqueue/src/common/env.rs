@@ -23,3 +23,7 @@ pub const HQ_TASK_DIR: &str = create_hq_env!("TASK_DIR");
 pub const HQ_ERROR_FILENAME: &str = create_hq_env!("ERROR_FILENAME");
 pub const HQ_CPUS: &str = create_hq_env!("CPUS");
 pub const HQ_NODE_FILE: &str = create_hq_env!("NODE_FILE");
+pub const HQ_WORKER_ID: &str = create_hq_env!("WORKER_ID");
+pub const HQ_EVENT_TYPE: &str = create_hq_env!("EVENT_TYPE");
+pub const HQ_EVENT_ID: &str = create_hq_env!("EVENT_ID");
+pub const HQ_EVENT_TIME: &str = create_hq_env!("EVENT_TIME");
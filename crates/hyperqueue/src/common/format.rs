@@ -26,6 +26,34 @@ pub fn human_size(size: u64) -> String {
     }
 }
 
+crate::arg_wrapper!(ArgSize, u64, parse_human_size);
+
+/// Parses a size in bytes with an optional unit, e.g. `512`, `10KiB`, `100MiB`, `2GB`.
+pub fn parse_human_size(text: &str) -> anyhow::Result<u64> {
+    const UNITS: [(&str, u64); 9] = [
+        ("KiB", 1024),
+        ("MiB", 1024 * 1024),
+        ("GiB", 1024 * 1024 * 1024),
+        ("TiB", 1024 * 1024 * 1024 * 1024),
+        ("kB", 1000),
+        ("MB", 1000 * 1000),
+        ("GB", 1000 * 1000 * 1000),
+        ("TB", 1000 * 1000 * 1000 * 1000),
+        ("B", 1),
+    ];
+    let text = text.trim();
+    let (value, multiplier) = UNITS
+        .iter()
+        .find_map(|(unit, multiplier)| text.strip_suffix(unit).map(|v| (v, *multiplier)))
+        .unwrap_or((text, 1));
+    let value: u64 = value.trim().parse().map_err(|_| {
+        anyhow::anyhow!("Invalid size `{text}`, use e.g. `100MiB`, `2GB` or a number of bytes")
+    })?;
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| anyhow::anyhow!("Size `{text}` is too large"))
+}
+
 pub fn server_lost_policy_to_str(policy: &ServerLostPolicy) -> &str {
     match policy {
         ServerLostPolicy::Stop => "stop",
@@ -35,7 +63,7 @@ pub fn server_lost_policy_to_str(policy: &ServerLostPolicy) -> &str {
 
 #[cfg(test)]
 mod tests {
-    use crate::common::format::{human_duration, human_size};
+    use crate::common::format::{human_duration, human_size, parse_human_size};
     use chrono::Duration;
 
     #[test]
@@ -48,6 +76,18 @@ mod tests {
         assert_eq!(human_size(500_250_000_000).as_str(), "465.89 GiB");
     }
 
+    #[test]
+    fn test_parse_sizes() {
+        assert_eq!(parse_human_size("0").unwrap(), 0);
+        assert_eq!(parse_human_size("512B").unwrap(), 512);
+        assert_eq!(parse_human_size("10KiB").unwrap(), 10 * 1024);
+        assert_eq!(parse_human_size("100 MiB").unwrap(), 100 * 1024 * 1024);
+        assert_eq!(parse_human_size("2GB").unwrap(), 2_000_000_000);
+        assert!(parse_human_size("1.5GiB").is_err());
+        assert!(parse_human_size("10XB").is_err());
+        assert!(parse_human_size("100000000TiB").is_err());
+    }
+
     #[test]
     fn test_durations() {
         assert_eq!(human_duration(Duration::nanoseconds(123456)).as_str(), "0s");
@@ -19,6 +19,7 @@ pub const JOB_ID_PLACEHOLDER: &str = "JOB_ID";
 pub const INSTANCE_ID_PLACEHOLDER: &str = "INSTANCE_ID";
 pub const CWD_PLACEHOLDER: &str = "CWD";
 pub const SUBMIT_DIR_PLACEHOLDER: &str = "SUBMIT_DIR";
+pub const CHANNEL_PLACEHOLDER: &str = "CHANNEL";
 
 const KNOWN_PLACEHOLDERS: [&str; 6] = [
     SERVER_UID_PLACEHOLDER,
@@ -29,6 +30,13 @@ const KNOWN_PLACEHOLDERS: [&str; 6] = [
     SUBMIT_DIR_PLACEHOLDER,
 ];
 
+/// Placeholders that can be used in filenames created by `hq log <file> split`.
+const LOG_SPLIT_PLACEHOLDERS: [&str; 3] = [
+    TASK_ID_PLACEHOLDER,
+    INSTANCE_ID_PLACEHOLDER,
+    CHANNEL_PLACEHOLDER,
+];
+
 type PlaceholderMap<'a> = Map<&'static str, Cow<'a, str>>;
 
 pub struct CompletePlaceholderCtx<'a> {
@@ -100,12 +108,36 @@ pub fn fill_placeholders_log(
         .into();
 }
 
+/// Fills placeholders in the filename `pattern` of a task output extracted from a log file.
+pub fn fill_placeholders_log_split(
+    pattern: &str,
+    task_id: JobTaskId,
+    instance_id: InstanceId,
+    channel: &str,
+) -> PathBuf {
+    let mut placeholders = PlaceholderMap::new();
+    placeholders.insert(TASK_ID_PLACEHOLDER, task_id.to_string().into());
+    placeholders.insert(INSTANCE_ID_PLACEHOLDER, instance_id.to_string().into());
+    placeholders.insert(CHANNEL_PLACEHOLDER, channel.into());
+    resolve(&placeholders, pattern).into_owned().into()
+}
+
 /// Find placeholders in the input that are not supported by HyperQueue.
 pub fn get_unknown_placeholders(input: &str) -> Vec<&str> {
+    find_unknown_placeholders(input, &KNOWN_PLACEHOLDERS)
+}
+
+/// Find placeholders in the input that are not supported in filenames created by
+/// `hq log <file> split`.
+pub fn get_unknown_log_split_placeholders(input: &str) -> Vec<&str> {
+    find_unknown_placeholders(input, &LOG_SPLIT_PLACEHOLDERS)
+}
+
+fn find_unknown_placeholders<'a>(input: &'a str, known: &[&str]) -> Vec<&'a str> {
     let mut unknown = Vec::new();
     for placeholder in parse_resolvable_string(input) {
         if let StringPart::Placeholder(placeholder) = placeholder {
-            if !KNOWN_PLACEHOLDERS.contains(&placeholder) {
+            if !known.contains(&placeholder) {
                 unknown.push(placeholder);
             }
         }
@@ -241,8 +273,9 @@ mod tests {
 
     use crate::common::env::{HQ_INSTANCE_ID, HQ_JOB_ID, HQ_SUBMIT_DIR, HQ_TASK_ID};
     use crate::common::placeholders::{
-        fill_placeholders_after_submit, fill_placeholders_in_paths, parse_resolvable_string,
-        CompletePlaceholderCtx, ResolvablePaths, StringPart,
+        fill_placeholders_after_submit, fill_placeholders_in_paths, fill_placeholders_log_split,
+        get_unknown_log_split_placeholders, parse_resolvable_string, CompletePlaceholderCtx,
+        ResolvablePaths, StringPart,
     };
     use crate::Map;
 
@@ -365,6 +398,20 @@ mod tests {
         assert_eq!(paths.stderr, StdioDef::File("/foo/bar/1/2.err".into()));
     }
 
+    #[test]
+    fn test_fill_log_split_pattern() {
+        let pattern = "out/%{TASK_ID}-%{INSTANCE_ID}.%{CHANNEL}";
+        assert!(get_unknown_log_split_placeholders(pattern).is_empty());
+        assert_eq!(
+            fill_placeholders_log_split(pattern, 5.into(), 1.into(), "stderr"),
+            PathBuf::from("out/5-1.stderr")
+        );
+        assert_eq!(
+            get_unknown_log_split_placeholders("%{JOB_ID}/%{TASK_ID}"),
+            vec!["JOB_ID"]
+        );
+    }
+
     struct ResolvedPaths {
         cwd: PathBuf,
         stdout: StdioDef,
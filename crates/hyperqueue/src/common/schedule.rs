@@ -0,0 +1,362 @@
+use std::fmt::{Display, Formatter};
+use std::time::SystemTime;
+
+use chrono::{Datelike, Duration, Local, NaiveDateTime, NaiveTime, Weekday};
+use chumsky::primitive::just;
+use chumsky::text::{ident, TextParser};
+use chumsky::Parser;
+use serde::{Deserialize, Serialize};
+
+use crate::common::parser2::{all_consuming, parse_u32, CharParser, ParseError};
+use crate::common::utils::time::local_to_system_time;
+
+/// How many days into the future are searched when looking for an (end of an) active window.
+const MAX_LOOKAHEAD_DAYS: i64 = 8;
+
+/// A recurring time window, e.g. `Mon-Fri 20:00-06:00`.
+///
+/// The window opens at `start` on each of its `days`. If `end` is not later than `start`,
+/// the window closes on the following day.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimeWindow {
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl TimeWindow {
+    fn duration(&self) -> Duration {
+        let duration = self.end - self.start;
+        if duration <= Duration::zero() {
+            duration + Duration::days(1)
+        } else {
+            duration
+        }
+    }
+
+    /// Returns all openings of this window that start within `[from - 1 day, from + days]`.
+    fn openings(&self, from: NaiveDateTime, days: i64) -> impl Iterator<Item = NaiveDateTime> + '_ {
+        let date = from.date();
+        (-1..=days)
+            .map(move |offset| date + Duration::days(offset))
+            .filter(|date| self.days.contains(&date.weekday()))
+            .map(|date| date.and_time(self.start))
+    }
+}
+
+impl Display for TimeWindow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let days = self
+            .days
+            .iter()
+            .map(|day| day.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        write!(
+            f,
+            "{days} {}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// Set of time windows in which some action (e.g. allocation submission) is allowed.
+/// Times are interpreted in the local time zone.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ActiveSchedule {
+    windows: Vec<TimeWindow>,
+}
+
+impl ActiveSchedule {
+    pub fn new(windows: Vec<TimeWindow>) -> Self {
+        assert!(!windows.is_empty());
+        Self { windows }
+    }
+
+    pub fn windows(&self) -> &[TimeWindow] {
+        &self.windows
+    }
+
+    pub fn is_active(&self, now: SystemTime) -> bool {
+        self.active_until(now).is_some()
+    }
+
+    /// If the schedule is active at `now`, returns the time when it stops being active.
+    /// Windows that follow each other without a gap are merged.
+    /// Returns `Some(None)` if the schedule does not close in the foreseeable future.
+    pub fn active_until(&self, now: SystemTime) -> Option<Option<SystemTime>> {
+        self.active_until_local(to_local(now))
+            .map(|end| end.map(local_to_system_time))
+    }
+
+    /// Returns the time when the schedule will become active next, or `None` if it is
+    /// already active.
+    pub fn next_opening(&self, now: SystemTime) -> Option<SystemTime> {
+        self.next_opening_local(to_local(now))
+            .map(local_to_system_time)
+    }
+
+    fn window_end_at(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.windows
+            .iter()
+            .flat_map(|window| {
+                let duration = window.duration();
+                window
+                    .openings(time, 0)
+                    .map(move |start| (start, start + duration))
+            })
+            .filter(|(start, end)| *start <= time && time < *end)
+            .map(|(_, end)| end)
+            .max()
+    }
+
+    fn active_until_local(&self, now: NaiveDateTime) -> Option<Option<NaiveDateTime>> {
+        let mut end = self.window_end_at(now)?;
+        let limit = now + Duration::days(MAX_LOOKAHEAD_DAYS);
+        while let Some(next_end) = self.window_end_at(end) {
+            if next_end >= limit {
+                return Some(None);
+            }
+            end = next_end;
+        }
+        Some(Some(end))
+    }
+
+    fn next_opening_local(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.window_end_at(now).is_some() {
+            return None;
+        }
+        self.windows
+            .iter()
+            .flat_map(|window| window.openings(now, MAX_LOOKAHEAD_DAYS))
+            .filter(|start| *start > now)
+            .min()
+    }
+}
+
+impl Display for ActiveSchedule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let windows = self
+            .windows
+            .iter()
+            .map(|window| window.to_string())
+            .collect::<Vec<_>>()
+            .join("; ");
+        f.write_str(&windows)
+    }
+}
+
+fn to_local(time: SystemTime) -> NaiveDateTime {
+    chrono::DateTime::<Local>::from(time).naive_local()
+}
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+fn parse_weekday() -> impl CharParser<Weekday> {
+    ident()
+        .try_map(|day: String, span| {
+            day.parse::<Weekday>().map_err(|_| {
+                ParseError::custom(span, format!("Invalid day `{day}`, use e.g. `Mon`"))
+            })
+        })
+        .labelled("day")
+}
+
+/// Parses a single day (`Mon`) or a range of days (`Mon-Fri`).
+fn parse_day_range() -> impl CharParser<Vec<Weekday>> {
+    parse_weekday()
+        .then(just('-').ignore_then(parse_weekday()).or_not())
+        .map(|(start, end)| {
+            let end = end.unwrap_or(start);
+            let mut days = vec![start];
+            let mut day = start;
+            while day != end {
+                day = day.succ();
+                days.push(day);
+            }
+            days
+        })
+}
+
+/// Parses days separated by commas, e.g. `Mon-Wed,Sat`.
+fn parse_days() -> impl CharParser<Vec<Weekday>> {
+    parse_day_range()
+        .separated_by(just(','))
+        .at_least(1)
+        .map(|ranges| {
+            WEEKDAYS
+                .into_iter()
+                .filter(|day| ranges.iter().any(|range| range.contains(day)))
+                .collect()
+        })
+}
+
+/// Parses time in the format `HH:MM`.
+fn parse_time() -> impl CharParser<NaiveTime> {
+    parse_u32()
+        .then_ignore(just(':'))
+        .then(parse_u32())
+        .try_map(|(hours, minutes), span| {
+            NaiveTime::from_hms_opt(hours, minutes, 0)
+                .ok_or_else(|| ParseError::custom(span, "Invalid time"))
+        })
+        .labelled("time in HH:MM format")
+}
+
+/// Parses a time window in the format `[<days>] [HH:MM-HH:MM]`.
+/// Days default to the whole week and time defaults to the whole day.
+fn parse_time_window() -> impl CharParser<TimeWindow> {
+    let time_range = parse_time().then_ignore(just('-')).then(parse_time());
+    let midnight = NaiveTime::from_hms(0, 0, 0);
+
+    let days_and_time =
+        parse_days()
+            .then(time_range.clone().padded().or_not())
+            .map(move |(days, time)| {
+                let (start, end) = time.unwrap_or((midnight, midnight));
+                TimeWindow { days, start, end }
+            });
+    let time_only = time_range.map(|(start, end)| TimeWindow {
+        days: WEEKDAYS.to_vec(),
+        start,
+        end,
+    });
+    time_only.or(days_and_time).padded()
+}
+
+fn parse_time_window_inner() -> impl CharParser<TimeWindow> {
+    all_consuming(parse_time_window())
+}
+
+/// Parses a time window, e.g. `Mon-Fri 20:00-06:00`, `Sat,Sun` or `22:00-04:00`.
+pub fn parse_time_window_text(input: &str) -> anyhow::Result<TimeWindow> {
+    parse_time_window_inner().parse_text(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+    use crate::common::schedule::{parse_time_window_text, ActiveSchedule, TimeWindow};
+
+    fn window(days: Vec<Weekday>, start: (u32, u32), end: (u32, u32)) -> TimeWindow {
+        TimeWindow {
+            days,
+            start: NaiveTime::from_hms(start.0, start.1, 0),
+            end: NaiveTime::from_hms(end.0, end.1, 0),
+        }
+    }
+
+    // 2022-10-03 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2022, 10, day).and_hms(hour, minute, 0)
+    }
+
+    #[test]
+    fn parse_days_and_time() {
+        assert_eq!(
+            parse_time_window_text("Mon-Fri 20:00-06:00").unwrap(),
+            window(
+                vec![
+                    Weekday::Mon,
+                    Weekday::Tue,
+                    Weekday::Wed,
+                    Weekday::Thu,
+                    Weekday::Fri
+                ],
+                (20, 0),
+                (6, 0)
+            )
+        );
+    }
+
+    #[test]
+    fn parse_days_only() {
+        assert_eq!(
+            parse_time_window_text("Sun,Sat").unwrap(),
+            window(vec![Weekday::Sat, Weekday::Sun], (0, 0), (0, 0))
+        );
+    }
+
+    #[test]
+    fn parse_time_only() {
+        let window = parse_time_window_text("22:30-04:00").unwrap();
+        assert_eq!(window.days.len(), 7);
+        assert_eq!(window.start, NaiveTime::from_hms(22, 30, 0));
+    }
+
+    #[test]
+    fn parse_wrapping_day_range() {
+        assert_eq!(
+            parse_time_window_text("Sat-Mon").unwrap().days,
+            vec![Weekday::Mon, Weekday::Sat, Weekday::Sun]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_window() {
+        assert!(parse_time_window_text("Foo 10:00-12:00").is_err());
+        assert!(parse_time_window_text("Mon 25:00-12:00").is_err());
+        assert!(parse_time_window_text("").is_err());
+    }
+
+    #[test]
+    fn active_overnight_window() {
+        let schedule = ActiveSchedule::new(vec![window(
+            vec![Weekday::Mon, Weekday::Tue],
+            (20, 0),
+            (6, 0),
+        )]);
+        assert_eq!(schedule.active_until_local(at(3, 12, 0)), None);
+        assert_eq!(
+            schedule.next_opening_local(at(3, 12, 0)),
+            Some(at(3, 20, 0))
+        );
+        assert_eq!(
+            schedule.active_until_local(at(3, 21, 0)),
+            Some(Some(at(4, 6, 0)))
+        );
+        // Tuesday early morning belongs to the Monday window
+        assert_eq!(
+            schedule.active_until_local(at(4, 5, 0)),
+            Some(Some(at(4, 6, 0)))
+        );
+        // Wednesday early morning belongs to the Tuesday window
+        assert_eq!(
+            schedule.active_until_local(at(5, 5, 0)),
+            Some(Some(at(5, 6, 0)))
+        );
+        assert_eq!(
+            schedule.next_opening_local(at(5, 7, 0)),
+            Some(at(10, 20, 0))
+        );
+    }
+
+    #[test]
+    fn merge_adjacent_windows() {
+        let schedule = ActiveSchedule::new(vec![window(
+            vec![Weekday::Sat, Weekday::Sun],
+            (0, 0),
+            (0, 0),
+        )]);
+        assert_eq!(
+            schedule.active_until_local(at(8, 10, 0)),
+            Some(Some(at(10, 0, 0)))
+        );
+    }
+
+    #[test]
+    fn always_active_schedule() {
+        let schedule = ActiveSchedule::new(vec![parse_time_window_text("Mon-Sun").unwrap()]);
+        assert_eq!(schedule.active_until_local(at(8, 10, 0)), Some(None));
+    }
+}
@@ -1,7 +1,7 @@
 use std::time::{Duration, SystemTime};
 
 use anyhow::anyhow;
-use chrono::TimeZone;
+use chrono::{LocalResult, TimeZone};
 use chumsky::prelude::just;
 use chumsky::Parser;
 
@@ -11,6 +11,9 @@ crate::arg_wrapper!(ArgDuration, Duration, humantime::parse_duration);
 // Allows specifying humantime format or HH:MM:SS
 crate::arg_wrapper!(ExtendedArgDuration, Duration, parse_hms_or_human_time);
 
+// Allows specifying a point in time in RFC 3339 or local `YYYY-MM-DD[ HH:MM[:SS]]` format
+crate::arg_wrapper!(ArgTimestamp, SystemTime, parse_timestamp);
+
 fn parse_hms_or_human_time(text: &str) -> anyhow::Result<Duration> {
     parse_hms_time(text)
         .or_else(|_| humantime::parse_duration(text))
@@ -21,8 +24,63 @@ fn parse_hms_or_human_time(text: &str) -> anyhow::Result<Duration> {
         })
 }
 
+/// Parses a timestamp either in RFC 3339 format (`2022-06-01T10:00:00+02:00`) or in local time
+/// (`2022-06-01 10:00:00`, `2022-06-01 10:00` or `2022-06-01`).
+pub fn parse_timestamp(text: &str) -> anyhow::Result<SystemTime> {
+    parse_timestamp_in(&chrono::Local, text)
+}
+
+/// Parses a timestamp, local times are interpreted in the given time zone.
+///
+/// Local times that are ambiguous (inside a DST fold) resolve to their earliest occurrence.
+/// Local times that do not exist (inside a DST gap) are rejected.
+fn parse_timestamp_in<Tz: TimeZone>(tz: &Tz, text: &str) -> anyhow::Result<SystemTime> {
+    if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Ok(datetime.into());
+    }
+    let datetime = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_hms(0, 0, 0))
+        })
+        .ok_or_else(|| {
+            anyhow!(
+                "Could not parse timestamp `{text}`. Use either RFC 3339 (2022-06-01T10:00:00+02:00) or `YYYY-MM-DD[ HH:MM[:SS]]` in local time"
+            )
+        })?;
+    match tz.from_local_datetime(&datetime).earliest() {
+        Some(datetime) => Ok(datetime.into()),
+        None => Err(anyhow!(
+            "Timestamp `{text}` does not exist in the local time zone (it falls into a daylight saving time transition)"
+        )),
+    }
+}
+
 pub fn local_to_system_time(datetime: chrono::NaiveDateTime) -> SystemTime {
-    chrono::Local.from_local_datetime(&datetime).unwrap().into()
+    resolve_local_datetime(&chrono::Local, datetime).into()
+}
+
+/// Converts a local time in the given time zone to a point in time.
+///
+/// Local times that are ambiguous (inside a DST fold) resolve to their earliest occurrence.
+/// Local times that do not exist (inside a DST gap) are moved forward to the end of the gap.
+fn resolve_local_datetime<Tz: TimeZone>(
+    tz: &Tz,
+    datetime: chrono::NaiveDateTime,
+) -> chrono::DateTime<Tz> {
+    match tz.from_local_datetime(&datetime) {
+        LocalResult::Single(time) => time,
+        LocalResult::Ambiguous(earliest, _) => earliest,
+        LocalResult::None => (1..=24 * 60)
+            .find_map(|minutes| {
+                tz.from_local_datetime(&(datetime + chrono::Duration::minutes(minutes)))
+                    .earliest()
+            })
+            .unwrap_or_else(|| tz.from_utc_datetime(&datetime)),
+    }
 }
 
 fn parse_hms_time_inner() -> impl CharParser<Duration> {
@@ -96,8 +154,73 @@ pub mod mock_time {
 #[cfg(test)]
 mod tests {
     use crate::common::parser2::{all_consuming, CharParser};
-    use crate::common::utils::time::parse_hms_time_inner;
+    use crate::common::utils::time::{
+        local_to_system_time, parse_hms_time_inner, parse_timestamp, parse_timestamp_in,
+        resolve_local_datetime,
+    };
     use crate::tests::utils::expect_parser_error;
+    use chrono::{
+        FixedOffset, LocalResult, NaiveDate, NaiveDateTime, Offset, TimeZone, Timelike, Utc,
+    };
+
+    /// Time zone with CET/CEST rules of the year 2022 (UTC+1, UTC+2 between
+    /// 2022-03-27 01:00 UTC and 2022-10-30 01:00 UTC).
+    #[derive(Clone)]
+    struct TestZone;
+
+    impl TestZone {
+        fn offsets() -> [FixedOffset; 2] {
+            [FixedOffset::east(2 * 3600), FixedOffset::east(3600)]
+        }
+    }
+
+    impl TimeZone for TestZone {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self {
+            TestZone
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms(0, 0, 0))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let offsets: Vec<_> = Self::offsets()
+                .into_iter()
+                .filter(|offset| {
+                    let utc = *local - chrono::Duration::seconds(offset.local_minus_utc() as i64);
+                    self.offset_from_utc_datetime(&utc) == *offset
+                })
+                .collect();
+            match offsets[..] {
+                [] => LocalResult::None,
+                [offset] => LocalResult::Single(offset),
+                [earliest, latest] => LocalResult::Ambiguous(earliest, latest),
+                _ => unreachable!(),
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms(0, 0, 0))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let summer_start = NaiveDate::from_ymd(2022, 3, 27).and_hms(1, 0, 0);
+            let summer_end = NaiveDate::from_ymd(2022, 10, 30).and_hms(1, 0, 0);
+            if *utc >= summer_start && *utc < summer_end {
+                FixedOffset::east(2 * 3600)
+            } else {
+                FixedOffset::east(3600)
+            }
+        }
+    }
+
+    fn resolve_to_utc(datetime: NaiveDateTime) -> NaiveDateTime {
+        resolve_local_datetime(&TestZone, datetime)
+            .with_timezone(&Utc)
+            .naive_utc()
+    }
 
     #[test]
     fn parse_hms_seconds() {
@@ -155,4 +278,79 @@ mod tests {
              --- Unexpected token `x`
         "###);
     }
+
+    #[test]
+    fn parse_timestamp_rfc3339() {
+        let time = parse_timestamp("2022-06-01T10:00:00+02:00").unwrap();
+        let expected: std::time::SystemTime =
+            chrono::DateTime::parse_from_rfc3339("2022-06-01T08:00:00Z")
+                .unwrap()
+                .into();
+        assert_eq!(time, expected);
+    }
+
+    #[test]
+    fn parse_timestamp_local() {
+        let expected =
+            local_to_system_time(chrono::NaiveDate::from_ymd(2022, 6, 1).and_hms(10, 20, 0));
+        assert_eq!(parse_timestamp("2022-06-01 10:20:00").unwrap(), expected);
+        assert_eq!(parse_timestamp("2022-06-01 10:20").unwrap(), expected);
+        assert_eq!(
+            parse_timestamp("2022-06-01").unwrap(),
+            local_to_system_time(chrono::NaiveDate::from_ymd(2022, 6, 1).and_hms(0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn parse_timestamp_in_gap() {
+        let error = parse_timestamp_in(&TestZone, "2022-03-27 02:30").unwrap_err();
+        assert!(error.to_string().contains("does not exist"));
+    }
+
+    #[test]
+    fn parse_timestamp_in_fold() {
+        let expected: std::time::SystemTime = Utc.ymd(2022, 10, 30).and_hms(0, 30, 0).into();
+        assert_eq!(
+            parse_timestamp_in(&TestZone, "2022-10-30 02:30").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn resolve_local_time_single() {
+        assert_eq!(
+            resolve_to_utc(NaiveDate::from_ymd(2022, 6, 1).and_hms(10, 0, 0)),
+            NaiveDate::from_ymd(2022, 6, 1).and_hms(8, 0, 0)
+        );
+    }
+
+    #[test]
+    fn resolve_local_time_in_gap() {
+        // 02:00-03:00 does not exist on 2022-03-27, it is moved to the end of the gap
+        let time = resolve_local_datetime(
+            &TestZone,
+            NaiveDate::from_ymd(2022, 3, 27).and_hms(2, 30, 0),
+        );
+        assert_eq!(
+            time.with_timezone(&Utc).naive_utc(),
+            NaiveDate::from_ymd(2022, 3, 27).and_hms(1, 0, 0)
+        );
+        assert_eq!(time.hour(), 3);
+        assert_eq!(time.offset().fix(), FixedOffset::east(2 * 3600));
+    }
+
+    #[test]
+    fn resolve_local_time_in_fold() {
+        // 02:00-03:00 happens twice on 2022-10-30, the earlier occurrence is used
+        assert_eq!(
+            resolve_to_utc(NaiveDate::from_ymd(2022, 10, 30).and_hms(2, 30, 0)),
+            NaiveDate::from_ymd(2022, 10, 30).and_hms(0, 30, 0)
+        );
+    }
+
+    #[test]
+    fn parse_timestamp_invalid() {
+        assert!(parse_timestamp("10:20").is_err());
+        assert!(parse_timestamp("2022-13-01").is_err());
+    }
 }
@@ -1,4 +1,4 @@
-use crate::server::autoalloc::{AllocationId, QueueId};
+use crate::server::autoalloc::{compute_node_hours, AllocationId, QueueId};
 use crate::server::event::events::MonitoringEventPayload;
 use crate::server::event::MonitoringEvent;
 use crate::transfer::messages::AllocationQueueParams;
@@ -9,6 +9,8 @@ pub struct AllocationQueueInfo {
     pub queue_params: AllocationQueueParams,
     pub creation_time: SystemTime,
     pub removal_time: Option<SystemTime>,
+    /// Times at which the queue was paused (`true`) or resumed (`false`)
+    pub pause_history: Vec<(SystemTime, bool)>,
 
     pub allocations: Map<AllocationId, AllocationInfo>,
 }
@@ -49,6 +51,16 @@ pub struct AllocationTimeline {
 }
 
 impl AllocationQueueInfo {
+    /// Returns true if the queue was paused at the given time.
+    pub fn is_paused_at(&self, time: SystemTime) -> bool {
+        self.pause_history
+            .iter()
+            .rev()
+            .find(|(change_time, _)| *change_time <= time)
+            .map(|(_, paused)| *paused)
+            .unwrap_or(false)
+    }
+
     // Add a new allocation that has been queued.
     pub fn add_queued_allocation(
         &mut self,
@@ -67,6 +79,30 @@ impl AllocationQueueInfo {
         );
     }
 
+    /// Computes node-hours consumed by allocations of the queue until `time`.
+    /// If the queue has a budget window, only node-hours within the window are accounted.
+    pub fn consumed_node_hours_at(&self, time: SystemTime) -> f64 {
+        let since = self
+            .queue_params
+            .budget
+            .as_ref()
+            .and_then(|budget| budget.window)
+            .and_then(|window| time.checked_sub(window));
+        self.allocations
+            .values()
+            .filter_map(|info| {
+                let start_time = info.start_time.filter(|start| *start < time)?;
+                let finish_time = info.finish_time.unwrap_or(time).min(time);
+                Some(compute_node_hours(
+                    info.worker_count,
+                    start_time,
+                    finish_time,
+                    since,
+                ))
+            })
+            .sum()
+    }
+
     // Update the state of an existing allocation in the queue.
     fn update_allocation_state(
         &mut self,
@@ -104,6 +140,7 @@ impl AllocationTimeline {
                             queue_params: *params.clone(),
                             creation_time: event.time,
                             removal_time: None,
+                            pause_history: Default::default(),
                             allocations: Default::default(),
                         },
                     );
@@ -112,6 +149,16 @@ impl AllocationTimeline {
                     let queue_state = self.queue_timelines.get_mut(queue_id).unwrap();
                     queue_state.removal_time = Some(event.time);
                 }
+                MonitoringEventPayload::AllocationQueuePaused { queue_id, .. } => {
+                    if let Some(queue_state) = self.queue_timelines.get_mut(queue_id) {
+                        queue_state.pause_history.push((event.time, true));
+                    }
+                }
+                MonitoringEventPayload::AllocationQueueResumed(queue_id) => {
+                    if let Some(queue_state) = self.queue_timelines.get_mut(queue_id) {
+                        queue_state.pause_history.push((event.time, false));
+                    }
+                }
                 MonitoringEventPayload::AllocationQueued {
                     queue_id,
                     allocation_id,
@@ -160,6 +207,13 @@ impl AllocationTimeline {
             .map(|queue_info| &queue_info.queue_params)
     }
 
+    pub fn get_consumed_node_hours_at(&self, queue_id: &QueueId, time: SystemTime) -> f64 {
+        self.queue_timelines
+            .get(queue_id)
+            .map(|queue_info| queue_info.consumed_node_hours_at(time))
+            .unwrap_or(0.0)
+    }
+
     pub fn get_allocations_for_queue(
         &self,
         queue_id: QueueId,
@@ -3,7 +3,8 @@ use crate::server::event::MonitoringEvent;
 use crate::{JobId, JobTaskId, TakoTaskId, WorkerId};
 use chrono::{DateTime, Utc};
 use std::time::SystemTime;
-use tako::Map;
+use tako::task::TaskResourceUsage;
+use tako::{InstanceId, Map};
 
 pub struct DashboardJobInfo {
     pub job_info: JobInfo,
@@ -11,12 +12,15 @@ pub struct DashboardJobInfo {
     pub job_creation_time: SystemTime,
 
     pub completion_date: Option<DateTime<Utc>>,
+    pub cancellation_time: Option<SystemTime>,
 }
 
 pub struct TaskInfo {
     pub worker_id: WorkerId,
+    pub instance_id: InstanceId,
     pub start_time: SystemTime,
     pub end_time: Option<SystemTime>,
+    pub resource_usage: Option<TaskResourceUsage>,
     task_end_state: Option<DashboardTaskState>,
 }
 
@@ -30,6 +34,9 @@ pub enum DashboardTaskState {
     Running,
     Finished,
     Failed,
+    Canceled,
+    /// The worker of the task was lost, the task will be started again
+    Restarted,
 }
 
 impl TaskInfo {
@@ -61,6 +68,7 @@ impl JobTimeline {
                             job_tasks_info: Default::default(),
                             job_creation_time: event.time,
                             completion_date: None,
+                            cancellation_time: None,
                         },
                     );
                 }
@@ -71,7 +79,17 @@ impl JobTimeline {
                     }
                 }
 
-                MonitoringEventPayload::TaskStarted { task_id, worker_id } => {
+                MonitoringEventPayload::JobCanceled(job_id) => {
+                    if let Some(job_info) = self.job_timeline.get_mut(job_id) {
+                        job_info.cancellation_time = Some(event.time);
+                    }
+                }
+
+                MonitoringEventPayload::TaskStarted {
+                    task_id,
+                    instance_id,
+                    worker_id,
+                } => {
                     if let Some((_, info)) = self
                         .job_timeline
                         .iter_mut()
@@ -81,26 +99,55 @@ impl JobTimeline {
                             *task_id,
                             TaskInfo {
                                 worker_id: *worker_id,
+                                instance_id: *instance_id,
                                 start_time: event.time,
                                 end_time: None,
+                                resource_usage: None,
                                 task_end_state: None,
                             },
                         );
                     }
                 }
-                MonitoringEventPayload::TaskFinished(finished_id) => {
-                    update_task_status(
+                MonitoringEventPayload::TaskFinished {
+                    task_id,
+                    resource_usage,
+                } => {
+                    if let Some(task_info) = update_task_status(
                         &mut self.job_timeline,
-                        finished_id,
+                        task_id,
                         DashboardTaskState::Finished,
                         &event.time,
+                    ) {
+                        task_info.resource_usage = resource_usage.as_deref().copied();
+                    }
+                }
+                MonitoringEventPayload::TaskFailed {
+                    task,
+                    resource_usage,
+                    ..
+                } => {
+                    if let Some(task_info) = update_task_status(
+                        &mut self.job_timeline,
+                        &task.task_id,
+                        DashboardTaskState::Failed,
+                        &event.time,
+                    ) {
+                        task_info.resource_usage = resource_usage.as_deref().copied();
+                    }
+                }
+                MonitoringEventPayload::TaskCanceled(task) => {
+                    update_task_status(
+                        &mut self.job_timeline,
+                        &task.task_id,
+                        DashboardTaskState::Canceled,
+                        &event.time,
                     );
                 }
-                MonitoringEventPayload::TaskFailed(failed_id) => {
+                MonitoringEventPayload::TaskRestarted { task, .. } => {
                     update_task_status(
                         &mut self.job_timeline,
-                        failed_id,
-                        DashboardTaskState::Failed,
+                        &task.task_id,
+                        DashboardTaskState::Restarted,
                         &event.time,
                     );
                 }
@@ -156,18 +203,17 @@ impl JobTimeline {
     }
 }
 
-fn update_task_status(
-    job_timeline: &mut Map<JobId, DashboardJobInfo>,
+/// Returns the updated task, if it was found.
+fn update_task_status<'a>(
+    job_timeline: &'a mut Map<JobId, DashboardJobInfo>,
     task_id: &TakoTaskId,
     task_status: DashboardTaskState,
     at_time: &SystemTime,
-) {
-    if let Some((_, job_info)) = job_timeline
+) -> Option<&'a mut TaskInfo> {
+    let (_, job_info) = job_timeline
         .iter_mut()
-        .find(|(_, info)| info.job_info.task_ids.contains(task_id))
-    {
-        if let Some(task_info) = job_info.job_tasks_info.get_mut(task_id) {
-            task_info.set_end_time_and_status(at_time, task_status);
-        }
-    };
+        .find(|(_, info)| info.job_info.task_ids.contains(task_id))?;
+    let task_info = job_info.job_tasks_info.get_mut(task_id)?;
+    task_info.set_end_time_and_status(at_time, task_status);
+    Some(task_info)
 }
@@ -1,6 +1,6 @@
 use crate::dashboard::data::worker_timeline::WorkerTimeline;
+use anyhow::anyhow;
 use std::time::{Duration, SystemTime};
-use tako::gateway::MonitoringEventRequest;
 use tako::worker::WorkerConfiguration;
 use tako::worker::WorkerOverview;
 use tako::WrappedRcRefCell;
@@ -11,9 +11,11 @@ use crate::dashboard::data::alloc_timeline::{
 use crate::dashboard::data::job_timeline::{DashboardJobInfo, JobTimeline, TaskInfo};
 use crate::server::autoalloc::{AllocationId, QueueId};
 use crate::server::event::MonitoringEvent;
-use crate::transfer::connection::{ClientConnection, ClientSession};
-use crate::transfer::messages::{AllocationQueueParams, FromClientMessage, ToClientMessage};
-use crate::{rpc_call, JobId, JobTaskId, WorkerId};
+use crate::transfer::connection::ClientSession;
+use crate::transfer::messages::{
+    AllocationQueueParams, FromClientMessage, StreamEventsRequest, ToClientMessage,
+};
+use crate::{JobId, JobTaskId, WorkerId};
 
 pub mod alloc_timeline;
 pub mod job_timeline;
@@ -95,6 +97,12 @@ impl DashboardData {
         self.alloc_timeline.get_queue_params_for(&queue_id)
     }
 
+    /// Gets the amount of node-hours consumed by allocations of a given queue until `time`.
+    pub fn query_consumed_node_hours_at(&self, queue_id: QueueId, time: SystemTime) -> f64 {
+        self.alloc_timeline
+            .get_consumed_node_hours_at(&queue_id, time)
+    }
+
     /// The Queued and Running allocations at `time` for a queue.
     pub fn query_allocations_info_at(
         &self,
@@ -110,6 +118,11 @@ impl DashboardData {
         self.worker_timeline.get_worker_info_for(worker_id)
     }
 
+    /// Gets the time at which a user has requested the worker to stop, if it has happened.
+    pub fn query_worker_stop_requested_time(&self, worker_id: &WorkerId) -> Option<SystemTime> {
+        self.worker_timeline.get_stop_requested_time_for(worker_id)
+    }
+
     /// Calculates the number of workers connected to the cluster at the specified `time`.
     pub fn query_connected_worker_ids(
         &self,
@@ -133,32 +146,40 @@ impl DashboardData {
     }
 }
 
+/// Subscribes to events of the server and periodically applies the received events to `data`.
 pub async fn create_data_fetch_process(
     refresh_interval: Duration,
     data: WrappedRcRefCell<DashboardData>,
     mut session: ClientSession,
 ) -> anyhow::Result<()> {
+    let after_id = data.get().last_fetched_id().unwrap_or(0);
+    let connection = session.connection();
+    connection
+        .send(FromClientMessage::StreamEvents(StreamEventsRequest {
+            after_id: Some(after_id),
+            filter: Default::default(),
+        }))
+        .await?;
+
     let mut tick_duration = tokio::time::interval(refresh_interval);
+    let mut events = vec![];
     loop {
-        let fetched_until = data.get().last_fetched_id();
-        let events = fetch_events_after(session.connection(), fetched_until).await?;
-        data.get_mut().update_data(events);
-        tick_duration.tick().await;
+        tokio::select! {
+            message = connection.receive() => {
+                match message {
+                    Some(Ok(ToClientMessage::Event(event))) => events.push(event),
+                    Some(Ok(message)) => {
+                        return Err(anyhow!("Received an invalid message {:?}", message))
+                    }
+                    Some(Err(error)) => return Err(error.into()),
+                    None => return Err(anyhow!("The server has closed the connection")),
+                }
+            }
+            _ = tick_duration.tick() => {
+                if !events.is_empty() {
+                    data.get_mut().update_data(std::mem::take(&mut events));
+                }
+            }
+        }
     }
 }
-
-/// Gets the events from the server after the event_id specified
-async fn fetch_events_after(
-    connection: &mut ClientConnection,
-    after_id: Option<u32>,
-) -> crate::Result<Vec<MonitoringEvent>> {
-    rpc_call!(
-        connection,
-        FromClientMessage::MonitoringEvents (
-            MonitoringEventRequest {
-                after_id,
-            }),
-        ToClientMessage::MonitoringEventsResponse(response) => response
-    )
-    .await
-}
@@ -11,6 +11,7 @@ pub struct WorkerHistory {
     connection_time: SystemTime,
     worker_info: WorkerConfiguration,
     worker_overviews: Vec<WorkerOverview>,
+    stop_requested_time: Option<SystemTime>,
 
     lost_info: Option<(SystemTime, LostWorkerReason)>,
 }
@@ -38,6 +39,7 @@ impl WorkerTimeline {
                         connection_time: event.time,
                         worker_info: *info.clone(),
                         worker_overviews: vec![],
+                        stop_requested_time: None,
                         lost_info: None,
                     });
                 }
@@ -48,13 +50,24 @@ impl WorkerTimeline {
                         }
                     }
                 }
+                MonitoringEventPayload::WorkerStopRequested(worker_id) => {
+                    if let Some(worker_history) = self
+                        .worker_timeline
+                        .iter_mut()
+                        .find(|history| history.worker_id == *worker_id)
+                    {
+                        worker_history.stop_requested_time = Some(event.time);
+                    }
+                }
                 MonitoringEventPayload::WorkerOverviewReceived(overview) => {
                     if let Some(worker_history) = self
                         .worker_timeline
                         .iter_mut()
                         .find(|history| history.worker_id == overview.id)
                     {
-                        worker_history.worker_overviews.push(overview.clone());
+                        worker_history
+                            .worker_overviews
+                            .push(overview.as_ref().clone());
                     }
                 }
                 _ => {}
@@ -70,6 +83,14 @@ impl WorkerTimeline {
             .map(|info| &info.worker_info);
     }
 
+    /// Gets the time at which a user has requested the worker to stop, if it has happened.
+    pub fn get_stop_requested_time_for(&self, worker_id: &WorkerId) -> Option<SystemTime> {
+        self.worker_timeline
+            .iter()
+            .find(|info| info.worker_id == *worker_id)
+            .and_then(|info| info.stop_requested_time)
+    }
+
     pub fn get_connected_worker_ids(
         &self,
         time: SystemTime,
@@ -79,12 +79,16 @@ impl AutoAllocatorFragment {
             self.allocations_chart.update(data, descriptor);
         }
 
-        if let Some(queue_params) = self
+        if let Some((queue_id, queue_params)) = self
             .queue_info_table
             .get_selected_queue_descriptor()
-            .and_then(|queue_id| data.query_allocation_params(queue_id))
+            .and_then(|queue_id| {
+                data.query_allocation_params(queue_id)
+                    .map(|params| (queue_id, params))
+            })
         {
-            self.queue_params_table.update(queue_params)
+            let consumed = data.query_consumed_node_hours_at(queue_id, SystemTime::now());
+            self.queue_params_table.update(queue_params, consumed)
         }
 
         if let Some(allocations_map) = self
@@ -3,6 +3,7 @@ use crate::dashboard::ui::terminal::DashboardFrame;
 use crate::dashboard::ui::widgets::table::{StatefulTable, TableColumnHeaders};
 use crate::server::autoalloc::QueueId;
 use chrono::{DateTime, Local};
+use std::time::SystemTime;
 use termion::event::Key;
 use tui::layout::{Constraint, Rect};
 use tui::style::Style;
@@ -42,20 +43,23 @@ impl AllocationQueueInfoTable {
                 table_headers: Some(vec![
                     "Descriptor ID",
                     "#Allocations",
+                    "State",
                     "Creation Time",
                     "Removal Time",
                 ]),
                 column_widths: vec![
-                    Constraint::Percentage(25),
-                    Constraint::Percentage(25),
-                    Constraint::Percentage(25),
-                    Constraint::Percentage(25),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(20),
                 ],
             },
             |data| {
                 Row::new(vec![
                     Cell::from(data.queue_id.to_string()),
                     Cell::from(data.num_allocations.to_string()),
+                    Cell::from(data.state),
                     Cell::from(data.creation_time.as_str()),
                     Cell::from(data.removal_time.as_str()),
                 ])
@@ -76,6 +80,7 @@ impl AllocationQueueInfoTable {
 struct QueueInfoRow {
     queue_id: QueueId,
     num_allocations: u32,
+    state: &'static str,
     creation_time: String,
     removal_time: String,
 }
@@ -97,6 +102,13 @@ fn create_rows(mut queues: Vec<(&QueueId, &AllocationQueueInfo)>) -> Vec<QueueIn
             QueueInfoRow {
                 queue_id: **queue_id,
                 num_allocations: info.allocations.len() as u32,
+                state: if info.removal_time.is_some() {
+                    "REMOVED"
+                } else if info.is_paused_at(SystemTime::now()) {
+                    "PAUSED"
+                } else {
+                    "RUNNING"
+                },
                 creation_time: creation_time.format("%b %e, %T").to_string(),
                 removal_time,
             }
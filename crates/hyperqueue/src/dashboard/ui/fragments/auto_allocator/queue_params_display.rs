@@ -19,8 +19,8 @@ struct QueueParamsDataRow {
 }
 
 impl QueueParamsTable {
-    pub fn update(&mut self, queue_params: &AllocationQueueParams) {
-        let rows = create_rows(queue_params);
+    pub fn update(&mut self, queue_params: &AllocationQueueParams, consumed_node_hours: f64) {
+        let rows = create_rows(queue_params, consumed_node_hours);
         self.table.set_items(rows);
     }
 
@@ -40,7 +40,10 @@ impl QueueParamsTable {
     }
 }
 
-fn create_rows(params: &AllocationQueueParams) -> Vec<QueueParamsDataRow> {
+fn create_rows(
+    params: &AllocationQueueParams,
+    consumed_node_hours: f64,
+) -> Vec<QueueParamsDataRow> {
     vec![
         QueueParamsDataRow {
             label: "Workers Per Alloc: ",
@@ -84,5 +87,35 @@ fn create_rows(params: &AllocationQueueParams) -> Vec<QueueParamsDataRow> {
                 .map(|count| count.to_string())
                 .unwrap_or_default(),
         },
+        QueueParamsDataRow {
+            label: "Min Workers: ",
+            data: params.min_workers.to_string(),
+        },
+        QueueParamsDataRow {
+            label: "Node-hour Budget (remaining): ",
+            data: params
+                .budget
+                .as_ref()
+                .map(|budget| {
+                    format!(
+                        "{:.1}/{}",
+                        (budget.limit - consumed_node_hours).max(0.0),
+                        budget.limit
+                    )
+                })
+                .unwrap_or_default(),
+        },
+        QueueParamsDataRow {
+            label: "Job Arrays: ",
+            data: params.job_arrays.to_string(),
+        },
+        QueueParamsDataRow {
+            label: "Active Schedule: ",
+            data: params
+                .active_schedule
+                .as_ref()
+                .map(|schedule| schedule.to_string())
+                .unwrap_or_default(),
+        },
     ]
 }
@@ -46,6 +46,10 @@ fn create_rows(params: &DashboardJobInfo) -> Vec<JobInfoDataRow> {
         let end_time: DateTime<Local> = time.into();
         end_time.format("%b %e, %T").to_string().into()
     });
+    let cancellation_time: Option<Cow<'static, str>> = params.cancellation_time.map(|time| {
+        let time: DateTime<Local> = time.into();
+        time.format("%b %e, %T").to_string().into()
+    });
     let log_path = params
         .job_info
         .log
@@ -69,6 +73,10 @@ fn create_rows(params: &DashboardJobInfo) -> Vec<JobInfoDataRow> {
             label: "Completion Time: ",
             data: completion_time.unwrap_or_default(),
         },
+        JobInfoDataRow {
+            label: "Cancellation Time: ",
+            data: cancellation_time.unwrap_or_default(),
+        },
         JobInfoDataRow {
             label: "Num Tasks: ",
             data: params.job_info.task_ids.len().to_string().into(),
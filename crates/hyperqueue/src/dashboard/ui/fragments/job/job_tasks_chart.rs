@@ -135,6 +135,9 @@ fn get_task_counts_for_job(
                 DashboardTaskState::Running => (running + 1, failed, finished),
                 DashboardTaskState::Failed => (running, failed + 1, finished),
                 DashboardTaskState::Finished => (running, failed, finished + 1),
+                DashboardTaskState::Canceled | DashboardTaskState::Restarted => {
+                    (running, failed, finished)
+                }
             },
         )
 }
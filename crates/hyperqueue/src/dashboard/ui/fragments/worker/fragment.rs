@@ -13,6 +13,7 @@ use crate::dashboard::ui::fragments::worker::cpu_util_table::{
     get_column_constraints, render_cpu_util_table,
 };
 use crate::dashboard::ui::fragments::worker::worker_config_table::WorkerConfigTable;
+use crate::dashboard::ui::fragments::worker::worker_hw_chart::WorkerHwChart;
 use crate::dashboard::ui::widgets::tasks_table::TasksTable;
 use crate::JobTaskId;
 use tako::WorkerId;
@@ -24,6 +25,7 @@ pub struct WorkerOverviewFragment {
     worker_id: Option<WorkerId>,
     worker_info_table: WorkerConfigTable,
     worker_tasks_table: TasksTable,
+    worker_hw_chart: WorkerHwChart,
 
     worker_per_core_cpu_util: Vec<f32>,
 }
@@ -35,6 +37,7 @@ impl WorkerOverviewFragment {
 
     pub fn set_worker_id(&mut self, worker_id: WorkerId) {
         self.worker_id = Some(worker_id);
+        self.worker_hw_chart.set_worker_id(worker_id);
     }
 }
 
@@ -70,6 +73,11 @@ impl WorkerOverviewFragment {
             table_style_deselected(),
         );
 
+        self.worker_hw_chart
+            .draw_utilization(layout.utilization_chart_chunk, frame);
+        self.worker_hw_chart
+            .draw_disk(layout.disk_chart_chunk, frame);
+
         self.worker_tasks_table.draw(
             "Tasks On Worker",
             layout.tasks_table_chunk,
@@ -93,13 +101,16 @@ impl WorkerOverviewFragment {
             {
                 self.worker_per_core_cpu_util = cpu_util.clone()
             }
+            // Update hardware utilization charts
+            self.worker_hw_chart.update(data);
             // Update Tasks Table
             let tasks_info: Vec<(JobTaskId, &TaskInfo)> =
                 data.query_task_history_for_worker(worker_id).collect();
             self.worker_tasks_table.update(tasks_info);
             // Update Worker Configuration Information
             if let Some(configuration) = data.query_worker_info_for(&worker_id) {
-                self.worker_info_table.update(configuration);
+                let stop_requested = data.query_worker_stop_requested_time(&worker_id);
+                self.worker_info_table.update(configuration, stop_requested);
             }
         }
     }
@@ -119,9 +130,11 @@ impl WorkerOverviewFragment {
 /**
 *  __________________________
    |--------Header---------|
-   |       Cpu Util        |
+   | Cpu Util | Worker Info|
+   |-----------------------|
+   | Util Chart|Disk Chart |
    |-----------------------|
-   |     Worker Info       |
+   |      Tasks Table      |
    |-----------------------|
    |--------Footer---------|
    |-----------------------|
@@ -131,6 +144,8 @@ struct WorkerFragmentLayout {
     tasks_table_chunk: Rect,
     worker_util_chunk: Rect,
     worker_info_table_chunk: Rect,
+    utilization_chart_chunk: Rect,
+    disk_chart_chunk: Rect,
     footer_chunk: Rect,
 }
 
@@ -139,8 +154,9 @@ impl WorkerFragmentLayout {
         let base_chunks = tui::layout::Layout::default()
             .constraints(vec![
                 Constraint::Percentage(5),
-                Constraint::Percentage(50),
-                Constraint::Percentage(40),
+                Constraint::Percentage(35),
+                Constraint::Percentage(30),
+                Constraint::Percentage(25),
                 Constraint::Percentage(5),
             ])
             .direction(Direction::Vertical)
@@ -152,12 +168,20 @@ impl WorkerFragmentLayout {
             .margin(0)
             .split(base_chunks[1]);
 
+        let chart_chunks = Layout::default()
+            .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+            .direction(Direction::Horizontal)
+            .margin(0)
+            .split(base_chunks[2]);
+
         Self {
             header_chunk: base_chunks[0],
             worker_util_chunk: info_chunks[0],
             worker_info_table_chunk: info_chunks[1],
-            tasks_table_chunk: base_chunks[2],
-            footer_chunk: base_chunks[3],
+            utilization_chart_chunk: chart_chunks[0],
+            disk_chart_chunk: chart_chunks[1],
+            tasks_table_chunk: base_chunks[3],
+            footer_chunk: base_chunks[4],
         }
     }
 }
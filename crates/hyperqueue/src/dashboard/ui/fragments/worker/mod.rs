@@ -1,3 +1,4 @@
 pub mod cpu_util_table;
 pub mod fragment;
 pub mod worker_config_table;
+pub mod worker_hw_chart;
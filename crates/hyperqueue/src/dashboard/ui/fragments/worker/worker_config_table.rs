@@ -1,6 +1,8 @@
 use crate::dashboard::ui::styles::table_style_deselected;
 use crate::dashboard::ui::terminal::DashboardFrame;
 use crate::dashboard::ui::widgets::table::{StatefulTable, TableColumnHeaders};
+use chrono::{DateTime, Local};
+use std::time::SystemTime;
 use tako::worker::WorkerConfiguration;
 use tui::layout::{Constraint, Rect};
 use tui::widgets::{Cell, Row};
@@ -17,8 +19,12 @@ struct WorkerConfigDataRow {
 }
 
 impl WorkerConfigTable {
-    pub fn update(&mut self, configuration: &WorkerConfiguration) {
-        let rows = create_rows(configuration);
+    pub fn update(
+        &mut self,
+        configuration: &WorkerConfiguration,
+        stop_requested_time: Option<SystemTime>,
+    ) {
+        let rows = create_rows(configuration, stop_requested_time);
         self.table.set_items(rows);
     }
 
@@ -38,7 +44,10 @@ impl WorkerConfigTable {
     }
 }
 
-fn create_rows(worker_info: &WorkerConfiguration) -> Vec<WorkerConfigDataRow> {
+fn create_rows(
+    worker_info: &WorkerConfiguration,
+    stop_requested_time: Option<SystemTime>,
+) -> Vec<WorkerConfigDataRow> {
     let missing_data_str = String::new();
     vec![
         WorkerConfigDataRow {
@@ -90,5 +99,14 @@ fn create_rows(worker_info: &WorkerConfiguration) -> Vec<WorkerConfigDataRow> {
                 .map(|interval| humantime::format_duration(interval).to_string())
                 .unwrap_or_else(|| missing_data_str.clone()),
         },
+        WorkerConfigDataRow {
+            label: "Stop Requested: ",
+            data: stop_requested_time
+                .map(|time| {
+                    let time: DateTime<Local> = time.into();
+                    time.format("%b %e, %T").to_string()
+                })
+                .unwrap_or_else(|| missing_data_str.clone()),
+        },
     ]
 }
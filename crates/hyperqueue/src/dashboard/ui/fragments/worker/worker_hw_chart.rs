@@ -0,0 +1,150 @@
+use crate::dashboard::data::DashboardData;
+use crate::dashboard::ui::terminal::DashboardFrame;
+use crate::dashboard::ui::widgets::chart::{ChartPlotter, DashboardChart, PlotStyle};
+use crate::dashboard::utils::{calculate_memory_usage_percent, get_average_cpu_usage_for_worker};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tako::hwstats::WorkerHwState;
+use tako::{Map, WorkerId};
+use tui::layout::Rect;
+use tui::style::Color;
+use tui::symbols::Marker;
+
+const CPU_TAG: &str = "CPU %";
+const MEMORY_TAG: &str = "Memory %";
+const FILESYSTEM_TAG: &str = "Work dir fs %";
+const LOAD_TAG: &str = "Load (1 min)";
+const DISK_READ_TAG: &str = "Read MiB/s";
+const DISK_WRITE_TAG: &str = "Write MiB/s";
+
+/// Plots the history of hardware utilization of a single worker.
+#[derive(Default)]
+pub struct WorkerHwChart {
+    worker_id: Option<WorkerId>,
+    utilization_chart: DashboardChart,
+    disk_chart: DashboardChart,
+}
+
+impl WorkerHwChart {
+    pub fn set_worker_id(&mut self, worker_id: WorkerId) {
+        if self.worker_id == Some(worker_id) {
+            return;
+        }
+        self.worker_id = Some(worker_id);
+
+        self.utilization_chart = Default::default();
+        self.utilization_chart.set_chart_name("Utilization");
+        self.utilization_chart
+            .add_chart_plotter(Box::new(UtilizationPlotter(worker_id)));
+
+        self.disk_chart = Default::default();
+        self.disk_chart.set_chart_name("Disk IO");
+        self.disk_chart
+            .add_chart_plotter(Box::new(DiskPlotter(worker_id)));
+    }
+
+    pub fn update(&mut self, data: &DashboardData) {
+        self.utilization_chart.update(data);
+        self.disk_chart.update(data);
+    }
+
+    pub fn draw_utilization(&mut self, rect: Rect, frame: &mut DashboardFrame) {
+        self.utilization_chart.draw(rect, frame);
+    }
+
+    pub fn draw_disk(&mut self, rect: Rect, frame: &mut DashboardFrame) {
+        self.disk_chart.draw(rect, frame);
+    }
+}
+
+/// Plots CPU, memory and work dir filesystem usage (in percents) and the load average.
+struct UtilizationPlotter(WorkerId);
+
+impl ChartPlotter for UtilizationPlotter {
+    fn get_charts(&self) -> Map<String, PlotStyle> {
+        [
+            (CPU_TAG, Color::Yellow),
+            (MEMORY_TAG, Color::Cyan),
+            (FILESYSTEM_TAG, Color::Magenta),
+            (LOAD_TAG, Color::White),
+        ]
+        .into_iter()
+        .map(|(tag, color)| (tag.to_string(), braille_style(color)))
+        .collect()
+    }
+
+    fn fetch_data_points(&self, data: &DashboardData, time: SystemTime) -> Map<String, (f64, f64)> {
+        let mut data_points: Map<String, (f64, f64)> = Default::default();
+        if let Some(hw_state) = data
+            .query_worker_overview_at(self.0, time)
+            .and_then(|overview| overview.hw_state.as_ref())
+        {
+            let x = get_time_as_secs(time);
+            let state: &WorkerHwState = &hw_state.state;
+            data_points.insert(
+                CPU_TAG.to_string(),
+                (x, get_average_cpu_usage_for_worker(hw_state) as f64),
+            );
+            data_points.insert(
+                MEMORY_TAG.to_string(),
+                (
+                    x,
+                    calculate_memory_usage_percent(&state.worker_memory_usage) as f64,
+                ),
+            );
+            if let Some(filesystem) = &state.worker_filesystem_usage {
+                if filesystem.total > 0 {
+                    let used = filesystem.total.saturating_sub(filesystem.free);
+                    data_points.insert(
+                        FILESYSTEM_TAG.to_string(),
+                        (x, used as f64 / filesystem.total as f64 * 100.0),
+                    );
+                }
+            }
+            if let Some(load) = &state.load_average {
+                data_points.insert(LOAD_TAG.to_string(), (x, load.one));
+            }
+        }
+        data_points
+    }
+}
+
+/// Plots the disk read and write throughput (in MiB/s).
+struct DiskPlotter(WorkerId);
+
+impl ChartPlotter for DiskPlotter {
+    fn get_charts(&self) -> Map<String, PlotStyle> {
+        [(DISK_READ_TAG, Color::Green), (DISK_WRITE_TAG, Color::Red)]
+            .into_iter()
+            .map(|(tag, color)| (tag.to_string(), braille_style(color)))
+            .collect()
+    }
+
+    fn fetch_data_points(&self, data: &DashboardData, time: SystemTime) -> Map<String, (f64, f64)> {
+        let mut data_points: Map<String, (f64, f64)> = Default::default();
+        if let Some(hw_state) = data
+            .query_worker_overview_at(self.0, time)
+            .and_then(|overview| overview.hw_state.as_ref())
+        {
+            let x = get_time_as_secs(time);
+            let disk = &hw_state.state.worker_disk_usage;
+            let to_mib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+            data_points.insert(DISK_READ_TAG.to_string(), (x, to_mib(disk.read_throughput)));
+            data_points.insert(
+                DISK_WRITE_TAG.to_string(),
+                (x, to_mib(disk.write_throughput)),
+            );
+        }
+        data_points
+    }
+}
+
+fn braille_style(color: Color) -> PlotStyle {
+    PlotStyle {
+        color,
+        marker: Marker::Braille,
+    }
+}
+
+fn get_time_as_secs(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH).unwrap().as_secs() as f64
+}
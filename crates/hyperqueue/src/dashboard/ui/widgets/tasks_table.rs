@@ -1,4 +1,4 @@
-use crate::common::format::human_duration;
+use crate::common::format::{human_duration, human_size};
 use crate::dashboard::data::job_timeline::{DashboardTaskState, TaskInfo};
 
 use crate::dashboard::ui::terminal::DashboardFrame;
@@ -6,7 +6,7 @@ use crate::dashboard::ui::widgets::table::{StatefulTable, TableColumnHeaders};
 use crate::JobTaskId;
 use chrono::{DateTime, Local};
 use std::time::SystemTime;
-use tako::WorkerId;
+use tako::{InstanceId, WorkerId};
 use termion::event::Key;
 use tui::layout::{Constraint, Rect};
 use tui::style::{Color, Modifier, Style};
@@ -16,6 +16,8 @@ use tui::widgets::{Cell, Row};
 const RUNNING: &str = "RUNNING";
 const FINISHED: &str = "FINISHED";
 const FAILED: &str = "FAILED";
+const CANCELED: &str = "CANCELED";
+const RESTARTED: &str = "RESTARTED";
 
 #[derive(Default)]
 pub struct TasksTable {
@@ -68,30 +70,39 @@ impl TasksTable {
                 inline_help: "",
                 table_headers: Some(vec![
                     "Task ID",
+                    "Instance",
                     "Worker ID",
                     "State",
                     "Start",
                     "End",
                     "Makespan",
+                    "CPU time",
+                    "Max RSS",
                 ]),
                 column_widths: vec![
-                    Constraint::Percentage(16),
-                    Constraint::Percentage(16),
-                    Constraint::Percentage(16),
-                    Constraint::Percentage(16),
-                    Constraint::Percentage(16),
-                    Constraint::Percentage(16),
+                    Constraint::Percentage(10),
+                    Constraint::Percentage(8),
+                    Constraint::Percentage(10),
+                    Constraint::Percentage(10),
+                    Constraint::Percentage(13),
+                    Constraint::Percentage(13),
+                    Constraint::Percentage(12),
+                    Constraint::Percentage(12),
+                    Constraint::Percentage(12),
                 ],
             },
             |task_row| {
                 Row::new(vec![
                     Cell::from(task_row.task_id.to_string()),
+                    Cell::from(task_row.instance_id.to_string()),
                     Cell::from(task_row.worker_id.to_string()),
                     Cell::from(task_row.task_state.as_str())
                         .style(get_task_state_color(&task_row.task_state)),
                     Cell::from(task_row.start_time.as_str()),
                     Cell::from(task_row.end_time.as_str()),
                     Cell::from(task_row.run_time.as_str()),
+                    Cell::from(task_row.cpu_time.as_str()),
+                    Cell::from(task_row.max_rss.as_str()),
                 ])
             },
             table_style,
@@ -101,11 +112,14 @@ impl TasksTable {
 
 struct TaskRow {
     worker_id: WorkerId,
+    instance_id: InstanceId,
     task_id: JobTaskId,
     task_state: String,
     start_time: String,
     end_time: String,
     run_time: String,
+    cpu_time: String,
+    max_rss: String,
 }
 
 fn create_rows(mut rows: Vec<(JobTaskId, &TaskInfo)>) -> Vec<TaskRow> {
@@ -114,6 +128,8 @@ fn create_rows(mut rows: Vec<(JobTaskId, &TaskInfo)>) -> Vec<TaskRow> {
             DashboardTaskState::Running => 0,
             DashboardTaskState::Finished => 1,
             DashboardTaskState::Failed => 2,
+            DashboardTaskState::Canceled => 3,
+            DashboardTaskState::Restarted => 4,
         };
         match task_info.end_time {
             None => (status_index, task_info.start_time),
@@ -142,14 +158,26 @@ fn create_rows(mut rows: Vec<(JobTaskId, &TaskInfo)>) -> Vec<TaskRow> {
             TaskRow {
                 task_id: *task_id,
                 worker_id: task_info.worker_id,
+                instance_id: task_info.instance_id,
                 task_state: match task_info.get_task_state_at(SystemTime::now()).unwrap() {
                     DashboardTaskState::Running => RUNNING.to_string(),
                     DashboardTaskState::Finished => FINISHED.to_string(),
                     DashboardTaskState::Failed => FAILED.to_string(),
+                    DashboardTaskState::Canceled => CANCELED.to_string(),
+                    DashboardTaskState::Restarted => RESTARTED.to_string(),
                 },
                 run_time: human_duration(chrono::Duration::from_std(run_time).unwrap()),
                 start_time: start_time.format("%b %e, %T").to_string(),
                 end_time,
+                cpu_time: task_info
+                    .resource_usage
+                    .and_then(|usage| chrono::Duration::from_std(usage.cpu_time()).ok())
+                    .map(human_duration)
+                    .unwrap_or_default(),
+                max_rss: task_info
+                    .resource_usage
+                    .map(|usage| human_size(usage.max_rss))
+                    .unwrap_or_default(),
             }
         })
         .collect()
@@ -160,6 +188,8 @@ pub fn get_task_state_color(task_status: &str) -> Style {
         Color::Yellow
     } else if task_status == FINISHED {
         Color::Green
+    } else if task_status == CANCELED || task_status == RESTARTED {
+        Color::Magenta
     } else {
         Color::Red
     };
@@ -2,7 +2,20 @@ use std::time::Duration;
 
 /// How many directories of completed allocations should be kept on disk across all allocation
 /// queues.
-pub const MAX_KEPT_DIRECTORIES: usize = 20;
+pub fn max_kept_directories() -> usize {
+    std::env::var("HQ_AUTOALLOC_MAX_KEPT_DIRECTORIES")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(20)
+}
+
+/// How many lines from the end of stdout/stderr of allocations are displayed in allocation
+/// history. These lines are also kept in memory after the allocation directory is removed.
+pub const ALLOCATION_OUTPUT_TAIL_LINES: usize = 10;
+
+/// Allocations whose time limit would be capped by the active schedule of their queue below this
+/// duration will not be submitted.
+pub const MIN_CAPPED_TIMELIMIT: Duration = Duration::from_secs(5 * 60);
 
 /// If no autoalloc messages arrive after this duration, queues will be refreshed.
 pub fn get_refresh_timeout() -> Duration {
@@ -1,11 +1,11 @@
-use crate::server::autoalloc::state::AllocationQueue;
+use crate::server::autoalloc::state::{AllocationQueue, AllocationState};
 use crate::server::autoalloc::QueueInfo;
 use crate::server::job::Job;
 use crate::server::state::State;
 use crate::server::worker::Worker;
 use crate::transfer::messages::{JobDescription, TaskDescription};
 use crate::JobId;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use tako::Map;
 
 pub type WaitingTaskCount = u64;
@@ -84,3 +84,22 @@ pub fn count_active_workers(queue: &AllocationQueue) -> u64 {
         .map(|allocation| allocation.target_worker_count)
         .sum()
 }
+
+/// Estimates how many node-hours can still be consumed by active allocations of the queue,
+/// assuming that they will run until their time limit.
+pub fn count_reserved_node_hours(queue: &AllocationQueue, now: SystemTime) -> f64 {
+    let timelimit = queue.info().timelimit();
+    queue
+        .active_allocations()
+        .map(|allocation| {
+            let remaining = match allocation.status {
+                AllocationState::Running { started_at, .. } => {
+                    let elapsed = now.duration_since(started_at).unwrap_or(Duration::ZERO);
+                    timelimit.saturating_sub(elapsed)
+                }
+                _ => timelimit,
+            };
+            allocation.target_worker_count as f64 * remaining.as_secs_f64() / 3600.0
+        })
+        .sum()
+}
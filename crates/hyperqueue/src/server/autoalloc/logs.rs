@@ -0,0 +1,168 @@
+use std::fs::File;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Name of the file into which the job manager writes stdout of an allocation.
+pub const ALLOCATION_STDOUT_FILE: &str = "stdout";
+/// Name of the file into which the job manager writes stderr of an allocation.
+pub const ALLOCATION_STDERR_FILE: &str = "stderr";
+
+/// Name of the directory (inside the directory of a queue) into which the output of allocations
+/// is moved before their directories are removed.
+const ALLOCATION_LOG_ARCHIVE_DIR: &str = "logs";
+
+/// Maximum number of bytes that are read from the end of an output file to find its last lines.
+const MAX_TAIL_BYTES: u64 = 64 * 1024;
+
+/// Standard output and error of an allocation.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AllocationOutput {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Output of an allocation whose directory has been removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedOutput {
+    /// Last lines of stdout and stderr.
+    pub tail: AllocationOutput,
+    /// Directory that contains the full stdout and stderr, if they could be archived.
+    pub directory: Option<PathBuf>,
+}
+
+/// Moves stdout and stderr of an allocation from its working directory into the log archive of
+/// its queue, so that they survive the removal of the working directory.
+/// Returns the directory that contains the archived output.
+pub fn archive_allocation_output(
+    working_dir: &Path,
+    queue_dir: &Path,
+    allocation_id: &str,
+) -> std::io::Result<PathBuf> {
+    let directory = queue_dir
+        .join(ALLOCATION_LOG_ARCHIVE_DIR)
+        .join(allocation_id.replace('/', "_"));
+    std::fs::create_dir_all(&directory)?;
+    for name in [ALLOCATION_STDOUT_FILE, ALLOCATION_STDERR_FILE] {
+        match std::fs::rename(working_dir.join(name), directory.join(name)) {
+            Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+            _ => {}
+        }
+    }
+    Ok(directory)
+}
+
+/// Reads the last `lines` lines of stdout and stderr of an allocation with the given working
+/// directory. Missing files are treated as empty.
+pub fn read_allocation_output_tail(directory: &Path, lines: usize) -> AllocationOutput {
+    let read = |name: &str| {
+        let path = directory.join(name);
+        match read_tail(&path, lines) {
+            Ok(tail) => tail,
+            Err(error) => {
+                if error.kind() != ErrorKind::NotFound {
+                    log::warn!("Cannot read allocation output {path:?}: {error:?}");
+                }
+                String::new()
+            }
+        }
+    };
+    AllocationOutput {
+        stdout: read(ALLOCATION_STDOUT_FILE),
+        stderr: read(ALLOCATION_STDERR_FILE),
+    }
+}
+
+/// Reads the whole stdout and stderr of an allocation with the given working directory.
+/// Missing files are treated as empty.
+pub async fn read_allocation_output(directory: &Path) -> AllocationOutput {
+    let read = |name: &'static str| async move {
+        let path = directory.join(name);
+        match tokio::fs::read(&path).await {
+            Ok(data) => String::from_utf8_lossy(&data).into_owned(),
+            Err(error) => {
+                if error.kind() != ErrorKind::NotFound {
+                    log::warn!("Cannot read allocation output {path:?}: {error:?}");
+                }
+                String::new()
+            }
+        }
+    };
+    AllocationOutput {
+        stdout: read(ALLOCATION_STDOUT_FILE).await,
+        stderr: read(ALLOCATION_STDERR_FILE).await,
+    }
+}
+
+fn read_tail(path: &Path, lines: usize) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    file.seek(SeekFrom::Start(size.saturating_sub(MAX_TAIL_BYTES)))?;
+
+    let mut data = vec![];
+    file.read_to_end(&mut data)?;
+    Ok(get_last_lines(&String::from_utf8_lossy(&data), lines))
+}
+
+fn get_last_lines(text: &str, lines: usize) -> String {
+    if lines == 0 {
+        return String::new();
+    }
+    let text = text.trim_end_matches('\n');
+    let start = text
+        .rmatch_indices('\n')
+        .nth(lines - 1)
+        .map(|(index, _)| index + 1)
+        .unwrap_or(0);
+    text[start..].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::autoalloc::logs::{
+        archive_allocation_output, get_last_lines, read_allocation_output,
+        read_allocation_output_tail, AllocationOutput,
+    };
+    use tempdir::TempDir;
+
+    #[test]
+    fn last_lines() {
+        assert_eq!(get_last_lines("a\nb\nc\n", 2), "b\nc");
+        assert_eq!(get_last_lines("a\nb\nc", 5), "a\nb\nc");
+        assert_eq!(get_last_lines("a\nb\nc", 0), "");
+        assert_eq!(get_last_lines("", 2), "");
+    }
+
+    #[test]
+    fn read_missing_output() {
+        let dir = TempDir::new("hq").unwrap();
+        std::fs::write(dir.path().join("stdout"), "1\n2\n3\n").unwrap();
+        assert_eq!(
+            read_allocation_output_tail(dir.path(), 2),
+            AllocationOutput {
+                stdout: "2\n3".to_string(),
+                stderr: "".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn archive_output() {
+        let queue_dir = TempDir::new("hq").unwrap();
+        let working_dir = queue_dir.path().join("001");
+        std::fs::create_dir(&working_dir).unwrap();
+        std::fs::write(working_dir.join("stdout"), "1\n2\n3\n").unwrap();
+
+        let archived = archive_allocation_output(&working_dir, queue_dir.path(), "1.pbs").unwrap();
+        assert_eq!(archived, queue_dir.path().join("logs").join("1.pbs"));
+        assert!(!working_dir.join("stdout").exists());
+        assert_eq!(
+            read_allocation_output(&archived).await,
+            AllocationOutput {
+                stdout: "1\n2\n3\n".to_string(),
+                stderr: "".to_string()
+            }
+        );
+    }
+}
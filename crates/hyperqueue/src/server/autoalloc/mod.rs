@@ -6,6 +6,7 @@
 //! HQ jobs.
 mod config;
 mod estimator;
+mod logs;
 mod process;
 mod queue;
 mod service;
@@ -13,7 +14,11 @@ mod state;
 
 pub type AutoAllocResult<T> = anyhow::Result<T>;
 
+pub use config::ALLOCATION_OUTPUT_TAIL_LINES;
+pub use logs::{
+    read_allocation_output, read_allocation_output_tail, AllocationOutput, ArchivedOutput,
+};
 pub use process::try_submit_allocation;
-pub use queue::QueueInfo;
-pub use service::{create_autoalloc_service, AutoAllocService};
-pub use state::{Allocation, AllocationId, AllocationState, QueueId};
+pub use queue::{NodeHourBudget, QueueInfo};
+pub use service::{create_autoalloc_service, ArchivedAllocation, AutoAllocService};
+pub use state::{compute_node_hours, Allocation, AllocationId, AllocationState, QueueId};
@@ -1,34 +1,42 @@
 use std::future::Future;
-use std::path::PathBuf;
-use std::time::SystemTime;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use futures::future::join_all;
 use tempdir::TempDir;
 
-use tako::gateway::LostWorkerReason;
+use tako::gateway::{
+    FromGatewayMessage, IdleTimeoutExemptionRequest, LostWorkerReason, ToGatewayMessage,
+};
 use tako::WorkerId;
 use tako::{Map, Set};
 
 use crate::common::manager::info::{ManagerInfo, ManagerType};
 use crate::common::rpc::RpcReceiver;
 use crate::server::autoalloc::config::{
-    get_refresh_timeout, get_status_check_interval, max_allocation_fails, MAX_SUBMISSION_FAILS,
-    SUBMISSION_DELAYS,
+    get_refresh_timeout, get_status_check_interval, max_allocation_fails,
+    ALLOCATION_OUTPUT_TAIL_LINES, MAX_SUBMISSION_FAILS, MIN_CAPPED_TIMELIMIT, SUBMISSION_DELAYS,
 };
 use crate::server::autoalloc::estimator::{
-    can_worker_execute_job, count_active_workers, get_server_task_state,
+    can_worker_execute_job, count_active_workers, count_reserved_node_hours, get_server_task_state,
+};
+use crate::server::autoalloc::logs::{
+    archive_allocation_output, read_allocation_output_tail, ArchivedOutput,
 };
 use crate::server::autoalloc::queue::pbs::PbsHandler;
 use crate::server::autoalloc::queue::slurm::SlurmHandler;
-use crate::server::autoalloc::queue::{AllocationExternalStatus, QueueHandler, SubmitMode};
+use crate::server::autoalloc::queue::{
+    get_array_directory, AllocationExternalStatus, QueueHandler, SubmitMode,
+};
 use crate::server::autoalloc::service::AutoAllocMessage;
 use crate::server::autoalloc::state::{
     AllocationQueue, AllocationQueueState, AllocationState, AutoAllocState, RateLimiter,
     RateLimiterStatus,
 };
 use crate::server::autoalloc::{Allocation, AllocationId, AutoAllocResult, QueueId, QueueInfo};
+use crate::server::rpc::Backend;
 use crate::server::state::StateRef;
-use crate::transfer::messages::{AllocationQueueParams, QueueData, QueueState};
+use crate::transfer::messages::{AllocationQueueParams, QueueData, QueueState, ScheduleState};
 use crate::{get_or_return, JobId};
 
 #[derive(Copy, Clone)]
@@ -40,6 +48,7 @@ enum RefreshReason {
 
 pub async fn autoalloc_process(
     state_ref: StateRef,
+    backend: Backend,
     mut autoalloc: AutoAllocState,
     mut receiver: RpcReceiver<AutoAllocMessage>,
 ) {
@@ -58,6 +67,7 @@ pub async fn autoalloc_process(
         };
         if let Some(reason) = refresh_reason {
             refresh_state(&state_ref, &mut autoalloc, reason).await;
+            sync_idle_exempt_workers(&backend, &mut autoalloc).await;
         }
     }
     stop_all_allocations(&autoalloc).await;
@@ -110,6 +120,8 @@ async fn handle_message(
                                 AllocationQueueState::Running => QueueState::Running,
                                 AllocationQueueState::Paused => QueueState::Paused,
                             },
+                            remaining_budget: queue.remaining_budget(SystemTime::now()),
+                            schedule_state: get_schedule_state(queue.info(), SystemTime::now()),
                         },
                     )
                 })
@@ -144,6 +156,10 @@ async fn handle_message(
                 Some(queue) => {
                     log::debug!("Pausing queue {id}");
                     queue.pause();
+                    state_ref
+                        .get_mut()
+                        .event_storage_mut()
+                        .on_allocation_queue_paused(id, "Paused by user".to_string());
                     Ok(())
                 }
                 None => Err(anyhow::anyhow!("Queue {id} not found")),
@@ -156,6 +172,10 @@ async fn handle_message(
                 Some(queue) => {
                     log::debug!("Resuming queue {id}");
                     queue.resume();
+                    state_ref
+                        .get_mut()
+                        .event_storage_mut()
+                        .on_allocation_queue_resumed(id);
                     Ok(())
                 }
                 None => Err(anyhow::anyhow!("Queue {id} not found")),
@@ -171,6 +191,43 @@ async fn handle_message(
             response.respond(result);
             None
         }
+        AutoAllocMessage::GetAllocationHistory(queue_id, response) => {
+            let result = match autoalloc.get_queue(queue_id) {
+                Some(queue) => {
+                    let mut allocations: Vec<_> = queue
+                        .all_allocations()
+                        .filter(|allocation| !allocation.is_active())
+                        .map(|allocation| {
+                            (
+                                allocation.clone(),
+                                queue.archived_output(&allocation.id).cloned(),
+                            )
+                        })
+                        .collect();
+                    allocations.sort_by_key(|(allocation, _)| allocation.queued_at);
+                    Ok(allocations)
+                }
+                None => Err(anyhow::anyhow!("Queue {queue_id} not found")),
+            };
+            response.respond(result);
+            None
+        }
+        AutoAllocMessage::GetAllocation(allocation_id, response) => {
+            let result = autoalloc
+                .get_queue_id_by_allocation(&allocation_id)
+                .and_then(|queue_id| autoalloc.get_queue(queue_id))
+                .and_then(|queue| {
+                    queue.get_allocation(&allocation_id).map(|allocation| {
+                        (
+                            allocation.clone(),
+                            queue.archived_output(&allocation_id).cloned(),
+                        )
+                    })
+                })
+                .ok_or_else(|| anyhow::anyhow!("Allocation {allocation_id} not found"));
+            response.respond(result);
+            None
+        }
     }
 }
 
@@ -185,19 +242,19 @@ pub async fn try_submit_allocation(
     let queue_info = create_queue_info(params);
 
     let allocation = handler
-        .submit_allocation(0, &queue_info, worker_count as u64, SubmitMode::DryRun)
+        .submit_allocation(0, &queue_info, worker_count as u64, 1, SubmitMode::DryRun)
         .await
         .map_err(|e| anyhow::anyhow!("Could not submit allocation: {:?}", e))?;
 
-    let working_dir = allocation.working_dir().to_path_buf();
-    let id = allocation
-        .into_id()
+    let allocations = allocation
+        .into_allocations()
         .map_err(|e| anyhow::anyhow!("Could not submit allocation: {:?}", e))?;
-    let allocation = Allocation::new(id.to_string(), worker_count as u64, working_dir);
-    handler
-        .remove_allocation(&allocation)
-        .await
-        .map_err(|e| anyhow::anyhow!("Could not cancel allocation {}: {:?}", allocation.id, e))?;
+    for (id, working_dir) in allocations {
+        let allocation = Allocation::new(id, worker_count as u64, working_dir);
+        handler.remove_allocation(&allocation).await.map_err(|e| {
+            anyhow::anyhow!("Could not cancel allocation {}: {:?}", allocation.id, e)
+        })?;
+    }
 
     Ok(())
 }
@@ -231,8 +288,13 @@ pub fn create_queue_info(params: AllocationQueueParams) -> QueueInfo {
         worker_cpu_arg,
         worker_resources_args,
         max_worker_count,
+        min_workers,
         on_server_lost,
         idle_timeout,
+        budget,
+        active_schedule,
+        cap_timelimit,
+        job_arrays,
     } = params;
     QueueInfo::new(
         backlog,
@@ -243,7 +305,12 @@ pub fn create_queue_info(params: AllocationQueueParams) -> QueueInfo {
         worker_cpu_arg,
         worker_resources_args,
         max_worker_count,
+        min_workers,
         idle_timeout,
+        budget,
+        active_schedule,
+        cap_timelimit,
+        job_arrays,
     )
 }
 
@@ -331,6 +398,44 @@ not be removed. Use `--force` if you want to remove the queue anyway"
     Ok(())
 }
 
+/// Notifies workers whose idle timeout exemption has changed.
+/// Exempt workers form the minimal worker pool of their queue.
+async fn sync_idle_exempt_workers(backend: &Backend, autoalloc: &mut AutoAllocState) {
+    let queue_ids: Vec<QueueId> = autoalloc.queue_ids().collect();
+    let mut changes: Vec<(WorkerId, bool)> = vec![];
+    for id in queue_ids {
+        if let Some(queue) = autoalloc.get_queue_mut(id) {
+            changes.extend(queue.update_idle_exempt_workers());
+        }
+    }
+
+    for (worker_id, exempt) in changes {
+        log::debug!("Setting idle timeout exemption of worker {worker_id} to {exempt}");
+        let response = backend
+            .send_tako_message(FromGatewayMessage::SetIdleTimeoutExemption(
+                IdleTimeoutExemptionRequest { worker_id, exempt },
+            ))
+            .await;
+        match response {
+            Ok(ToGatewayMessage::IdleTimeoutExemptionUpdated) => {}
+            Ok(ToGatewayMessage::Error(error)) => {
+                log::warn!(
+                    "Could not update idle timeout exemption of worker {worker_id}: {}",
+                    error.message
+                );
+            }
+            Ok(msg) => panic!(
+                "Received invalid response to idle timeout exemption of worker {worker_id}: {msg:?}"
+            ),
+            Err(error) => {
+                log::error!(
+                    "Could not update idle timeout exemption of worker {worker_id}: {error:?}"
+                );
+            }
+        }
+    }
+}
+
 /// Removes all remaining active allocations
 async fn stop_all_allocations(autoalloc: &AutoAllocState) {
     let futures = autoalloc
@@ -371,6 +476,7 @@ async fn refresh_state(state: &StateRef, autoalloc: &mut AutoAllocState, reason:
     }
 
     remove_inactive_directories(autoalloc).await;
+    remove_archived_outputs(autoalloc).await;
 }
 
 async fn process_queue(
@@ -383,6 +489,26 @@ async fn process_queue(
         let queue = get_or_return!(autoalloc.get_queue_mut(id));
         if !queue.state().is_running() {
             false
+        } else if !is_schedule_active(queue.info(), SystemTime::now()) {
+            log::debug!("The queue {id} is outside of its active schedule");
+            false
+        } else if is_budget_exhausted(queue) {
+            // Without a time window, the budget cannot be replenished anymore
+            if queue.info().budget().and_then(|b| b.window).is_none() {
+                log::error!(
+                    "The queue {id} has exhausted its node-hour budget, it will be paused."
+                );
+                queue.pause();
+                state
+                    .get_mut()
+                    .event_storage_mut()
+                    .on_allocation_queue_paused(id, "Node-hour budget was exhausted".to_string());
+            } else {
+                log::debug!(
+                    "The queue {id} has exhausted its node-hour budget in the current window"
+                );
+            }
+            false
         } else {
             let limiter = queue.limiter_mut();
 
@@ -402,7 +528,58 @@ async fn process_queue(
     if try_to_submit {
         queue_try_submit(id, autoalloc, state, new_job_id).await;
     }
-    try_pause_queue(autoalloc, id);
+    try_pause_queue(state, autoalloc, id);
+}
+
+fn get_schedule_state(info: &QueueInfo, now: SystemTime) -> Option<ScheduleState> {
+    info.active_schedule()
+        .map(|schedule| match schedule.active_until(now) {
+            Some(until) => ScheduleState::Active { until },
+            None => ScheduleState::Inactive {
+                opens_at: schedule.next_opening(now),
+            },
+        })
+}
+
+fn is_schedule_active(info: &QueueInfo, now: SystemTime) -> bool {
+    info.active_schedule()
+        .map(|schedule| schedule.is_active(now))
+        .unwrap_or(true)
+}
+
+/// Returns queue info that should be used for submitting new allocations at the given time.
+/// If the queue caps its time limit by its active schedule, the time limit is shortened so that
+/// the allocations end before the current schedule window closes.
+///
+/// Returns `None` if the remaining time in the window is too short to submit an allocation.
+fn get_submission_queue_info(info: &QueueInfo, now: SystemTime) -> Option<QueueInfo> {
+    if !info.cap_timelimit() {
+        return Some(info.clone());
+    }
+    let window_end = match info
+        .active_schedule()
+        .and_then(|schedule| schedule.active_until(now))
+    {
+        Some(Some(end)) => end,
+        _ => return Some(info.clone()),
+    };
+    let remaining = window_end.duration_since(now).unwrap_or_default();
+    // Round down to whole seconds, as job managers do not work with fractional time limits
+    let remaining = Duration::from_secs(remaining.as_secs());
+    if remaining >= info.timelimit() {
+        Some(info.clone())
+    } else if remaining < MIN_CAPPED_TIMELIMIT {
+        None
+    } else {
+        Some(info.with_timelimit(remaining))
+    }
+}
+
+fn is_budget_exhausted(queue: &AllocationQueue) -> bool {
+    queue
+        .remaining_budget(SystemTime::now())
+        .map(|remaining| remaining <= 0.0)
+        .unwrap_or(false)
 }
 
 fn get_data_from_worker<'a>(
@@ -457,6 +634,7 @@ async fn refresh_queue_allocations(
     match result {
         Ok(mut status_map) => {
             let queue = get_or_return!(autoalloc.get_queue_mut(id));
+            let mut finished_directories = vec![];
             for allocation_id in allocation_ids {
                 let status = status_map.remove(&allocation_id).unwrap_or_else(|| {
                     Ok(AllocationExternalStatus::Failed {
@@ -467,13 +645,15 @@ async fn refresh_queue_allocations(
                 match status {
                     Ok(status) => match status {
                         AllocationExternalStatus::Failed { .. }
-                        | AllocationExternalStatus::Finished { .. } => sync_allocation_status(
-                            state_ref,
-                            id,
-                            queue,
-                            &allocation_id,
-                            Some(status),
-                        ),
+                        | AllocationExternalStatus::Finished { .. } => {
+                            finished_directories.extend(sync_allocation_status(
+                                state_ref,
+                                id,
+                                queue,
+                                &allocation_id,
+                                Some(status),
+                            ))
+                        }
                         _ => {}
                     },
                     Err(error) => {
@@ -481,6 +661,9 @@ async fn refresh_queue_allocations(
                     }
                 }
             }
+            for directory in finished_directories {
+                autoalloc.add_inactive_directory(directory);
+            }
         }
         Err(error) => {
             log::error!("Failed to get allocations status from queue {id}: {error:?}",);
@@ -558,7 +741,11 @@ fn on_worker_lost(
                 log::warn!("Worker {worker_id} has disconnected multiple times!");
             }
             disconnected_workers.insert(worker_id, reason);
-            sync_allocation_status(state_ref, queue_id, queue, &allocation_id, None);
+            if let Some(working_dir) =
+                sync_allocation_status(state_ref, queue_id, queue, &allocation_id, None)
+            {
+                state.add_inactive_directory(working_dir);
+            }
         }
         AllocationState::Finished { .. } => {
             log::warn!(
@@ -575,14 +762,17 @@ fn on_worker_lost(
     }
 }
 
+/// Updates the state of an allocation based on its workers or on its external status.
+/// If the allocation has just finished, returns its working directory, so that it can be
+/// scheduled for removal.
 fn sync_allocation_status(
     state_ref: &StateRef,
     queue_id: QueueId,
     queue: &mut AllocationQueue,
     allocation_id: &str,
     external_status: Option<AllocationExternalStatus>,
-) {
-    let allocation = get_or_return!(queue.get_allocation_mut(allocation_id));
+) -> Option<PathBuf> {
+    let allocation = queue.get_allocation_mut(allocation_id)?;
 
     enum Action {
         Failure,
@@ -604,7 +794,7 @@ fn sync_allocation_status(
                 ),
                 AllocationState::Finished { .. } | AllocationState::Invalid { .. } => {
                     // The allocation was already finished before
-                    return;
+                    return None;
                 }
             };
             let failed = matches!(status, AllocationExternalStatus::Failed { .. });
@@ -685,6 +875,11 @@ fn sync_allocation_status(
             .event_storage_mut()
             .on_allocation_finished(queue_id, allocation_id.to_string());
     }
+    action.and_then(|_| {
+        queue
+            .get_allocation(allocation_id)
+            .map(|allocation| allocation.working_dir.clone())
+    })
 }
 
 async fn queue_try_submit(
@@ -693,24 +888,57 @@ async fn queue_try_submit(
     state_ref: &StateRef,
     new_job_id: Option<JobId>,
 ) {
-    let (max_allocs_to_spawn, workers_per_alloc, mut task_state, mut max_workers_to_spawn) = {
+    let (
+        submission_info,
+        max_allocs_to_spawn,
+        workers_per_alloc,
+        mut task_state,
+        mut max_workers_to_spawn,
+        mut missing_pool_workers,
+        mut available_node_hours,
+        timelimit_hours,
+    ) = {
         let queue = get_or_return!(autoalloc.get_queue(queue_id));
 
         let allocs_in_queue = queue.queued_allocations().count();
 
-        let info = queue.info();
+        let now = SystemTime::now();
+        let info = match get_submission_queue_info(queue.info(), now) {
+            Some(info) => info,
+            None => {
+                log::debug!("The active schedule of queue {queue_id} closes too soon, no new allocations will be created");
+                return;
+            }
+        };
+        let info = &info;
         let task_state = get_server_task_state(&state_ref.get(), info);
         let active_workers = count_active_workers(queue);
         let max_workers_to_spawn = match info.max_worker_count() {
             Some(max) => (max as u64).saturating_sub(active_workers),
             None => u64::MAX,
         };
+        // Workers that have to be spawned to reach the minimal worker pool, regardless of
+        // waiting tasks.
+        let missing_pool_workers = (info.min_workers() as u64).saturating_sub(active_workers);
+        let workers_per_alloc = info.workers_per_alloc() as u64;
+        let backlog_allocs = info.backlog().saturating_sub(allocs_in_queue as u32);
+        let pool_allocs =
+            ((missing_pool_workers + workers_per_alloc - 1) / workers_per_alloc) as u32;
+        // Node-hours that are still available for new allocations, after accounting for the
+        // remaining walltime of active allocations.
+        let available_node_hours = queue
+            .remaining_budget(now)
+            .map(|remaining| remaining - count_reserved_node_hours(queue, now));
 
         (
-            info.backlog().saturating_sub(allocs_in_queue as u32),
-            info.workers_per_alloc() as u64,
+            info.clone(),
+            std::cmp::max(backlog_allocs, pool_allocs),
+            workers_per_alloc,
             task_state,
             max_workers_to_spawn,
+            missing_pool_workers,
+            available_node_hours,
+            info.timelimit().as_secs_f64() / 3600.0,
         )
     };
 
@@ -732,12 +960,14 @@ async fn queue_try_submit(
         }
     }
 
-    log::debug!("Task state: {task_state:?}, max. workers to spawn: {max_workers_to_spawn}");
+    log::debug!("Task state: {task_state:?}, max. workers to spawn: {max_workers_to_spawn}, missing pool workers: {missing_pool_workers}");
 
-    for _ in 0..max_allocs_to_spawn {
-        // If there are no more waiting tasks, stop creating allocations
+    let mut remaining_allocs = max_allocs_to_spawn as u64;
+    while remaining_allocs > 0 {
+        // If there are no more waiting tasks and the minimal worker pool is filled,
+        // stop creating allocations.
         // Assume that each worker will handle at least a single task
-        if task_state.waiting_tasks() == 0 {
+        if task_state.waiting_tasks() == 0 && missing_pool_workers == 0 {
             log::debug!("No more waiting tasks found, no new allocations will be created");
             break;
         }
@@ -748,13 +978,35 @@ async fn queue_try_submit(
         }
 
         let workers_to_spawn = std::cmp::min(workers_per_alloc, max_workers_to_spawn);
+        let required_node_hours = workers_to_spawn as f64 * timelimit_hours;
+        // If the allocation could exceed the node-hour budget, stop creating new allocations
+        if let Some(available) = available_node_hours {
+            if required_node_hours > available {
+                log::debug!("Node-hour budget limit reached, no new allocations will be created");
+                break;
+            }
+        }
+
+        let allocation_count = if submission_info.job_arrays() {
+            count_array_allocations(
+                remaining_allocs,
+                workers_to_spawn,
+                task_state.waiting_tasks(),
+                missing_pool_workers,
+                max_workers_to_spawn,
+                available_node_hours.map(|available| available / required_node_hours),
+            )
+        } else {
+            1
+        };
+
         let schedule_fut = {
             let queue = get_or_return!(autoalloc.get_queue_mut(queue_id));
-            let info = queue.info().clone();
             queue.handler_mut().submit_allocation(
                 queue_id,
-                &info,
+                &submission_info,
                 workers_to_spawn,
+                allocation_count,
                 SubmitMode::Submit,
             )
         };
@@ -766,23 +1018,30 @@ async fn queue_try_submit(
         match result {
             Ok(submission_result) => {
                 let working_dir = submission_result.working_dir().to_path_buf();
-                match submission_result.into_id() {
-                    Ok(allocation_id) => {
-                        log::info!("Queued {workers_to_spawn} worker(s) into queue {queue_id}: allocation ID {allocation_id}");
-                        event_manager.on_allocation_queued(
-                            queue_id,
-                            allocation_id.clone(),
-                            workers_to_spawn,
-                        );
-                        let allocation =
-                            Allocation::new(allocation_id, workers_to_spawn, working_dir);
-                        autoalloc.add_allocation(allocation, queue_id);
+                match submission_result.into_allocations() {
+                    Ok(allocations) => {
+                        for (allocation_id, working_dir) in allocations {
+                            log::info!("Queued {workers_to_spawn} worker(s) into queue {queue_id}: allocation ID {allocation_id}");
+                            event_manager.on_allocation_queued(
+                                queue_id,
+                                allocation_id.clone(),
+                                workers_to_spawn,
+                            );
+                            let allocation =
+                                Allocation::new(allocation_id, workers_to_spawn, working_dir);
+                            autoalloc.add_allocation(allocation, queue_id);
+
+                            task_state.remove_waiting_tasks(workers_to_spawn);
+                            max_workers_to_spawn =
+                                max_workers_to_spawn.saturating_sub(workers_to_spawn);
+                            missing_pool_workers =
+                                missing_pool_workers.saturating_sub(workers_to_spawn);
+                            available_node_hours = available_node_hours
+                                .map(|available| available - required_node_hours);
+                        }
                         let queue = get_or_return!(autoalloc.get_queue_mut(queue_id));
                         queue.limiter_mut().on_submission_success();
-
-                        task_state.remove_waiting_tasks(workers_to_spawn);
-                        max_workers_to_spawn =
-                            max_workers_to_spawn.saturating_sub(workers_to_spawn);
+                        remaining_allocs = remaining_allocs.saturating_sub(allocation_count);
                     }
                     Err(err) => {
                         log::error!("Failed to submit allocation into queue {queue_id}: {err:?}");
@@ -803,8 +1062,36 @@ async fn queue_try_submit(
     }
 }
 
+/// Computes how many allocations with the same number of workers can be submitted together as a
+/// single job array, using the same constraints as the submission loop in [`queue_try_submit`].
+/// `budget_allocs` is the number of allocations that fit into the remaining node-hour budget.
+fn count_array_allocations(
+    max_allocs: u64,
+    workers_per_alloc: u64,
+    mut waiting_tasks: u64,
+    mut missing_pool_workers: u64,
+    mut max_workers: u64,
+    budget_allocs: Option<f64>,
+) -> u64 {
+    let mut count = 0;
+    while count < max_allocs
+        && (waiting_tasks > 0 || missing_pool_workers > 0)
+        && max_workers >= workers_per_alloc
+        && budget_allocs
+            .map(|allocs| (count + 1) as f64 <= allocs)
+            .unwrap_or(true)
+    {
+        count += 1;
+        waiting_tasks = waiting_tasks.saturating_sub(workers_per_alloc);
+        missing_pool_workers = missing_pool_workers.saturating_sub(workers_per_alloc);
+        max_workers -= workers_per_alloc;
+    }
+    count.max(1)
+}
+
 async fn remove_inactive_directories(autoalloc: &mut AutoAllocState) {
     let to_remove = autoalloc.get_directories_for_removal();
+    archive_allocation_outputs(autoalloc, &to_remove);
     let futures = to_remove.into_iter().map(|dir| async move {
         let result = tokio::fs::remove_dir_all(&dir).await;
         (result, dir)
@@ -812,24 +1099,127 @@ async fn remove_inactive_directories(autoalloc: &mut AutoAllocState) {
     for (result, directory) in join_all(futures).await {
         if let Err(err) = result {
             log::error!("Failed to remove stale allocation directory {directory:?}: {err:?}",);
+        } else if let Some(array_directory) = get_array_directory(&directory) {
+            remove_empty_array_directory(array_directory).await;
+        }
+    }
+}
+
+/// Moves the output of allocations whose directories are about to be removed into the log
+/// archive of their queue, so that it remains available in the allocation history.
+fn archive_allocation_outputs(autoalloc: &mut AutoAllocState, directories: &[PathBuf]) {
+    if directories.is_empty() {
+        return;
+    }
+    let mut archived_directories = Vec::new();
+    for (queue_id, queue) in autoalloc.queues_mut() {
+        let archived: Vec<(usize, AllocationId, ArchivedOutput)> = queue
+            .all_allocations()
+            .filter_map(|allocation| {
+                let index = directories
+                    .iter()
+                    .position(|directory| directory == &allocation.working_dir)?;
+                let tail = read_allocation_output_tail(
+                    &allocation.working_dir,
+                    ALLOCATION_OUTPUT_TAIL_LINES,
+                );
+                let directory = archive_full_output(allocation);
+                Some((
+                    index,
+                    allocation.id.clone(),
+                    ArchivedOutput { tail, directory },
+                ))
+            })
+            .collect();
+        for (index, id, output) in archived {
+            if let Some(directory) = &output.directory {
+                archived_directories.push((index, queue_id, id.clone(), directory.clone()));
+            }
+            queue.archive_output(id, output);
+        }
+    }
+    // Keep the order in which the directories are removed (oldest first), so that the oldest
+    // output is also removed first from the archive
+    archived_directories.sort_by_key(|(index, ..)| *index);
+    for (_, queue_id, allocation_id, directory) in archived_directories {
+        autoalloc.add_archived_output_directory(queue_id, allocation_id, directory);
+    }
+}
+
+/// Removes the oldest archived output of allocations, so that the archive does not grow without
+/// bounds.
+async fn remove_archived_outputs(autoalloc: &mut AutoAllocState) {
+    let to_remove = autoalloc.get_archived_output_directories_for_removal();
+    let futures = to_remove.into_iter().map(|dir| async move {
+        let result = tokio::fs::remove_dir_all(&dir).await;
+        (result, dir)
+    });
+    for (result, directory) in join_all(futures).await {
+        if let Err(err) = result {
+            log::error!("Failed to remove archived allocation output {directory:?}: {err:?}");
         }
     }
 }
 
-fn try_pause_queue(autoalloc: &mut AutoAllocState, id: QueueId) {
+fn archive_full_output(allocation: &Allocation) -> Option<PathBuf> {
+    let working_dir = &allocation.working_dir;
+    let queue_dir = get_array_directory(working_dir)
+        .unwrap_or(working_dir)
+        .parent()?;
+    match archive_allocation_output(working_dir, queue_dir, &allocation.id) {
+        Ok(directory) => Some(directory),
+        Err(error) => {
+            log::warn!(
+                "Cannot archive output of allocation {}: {error:?}",
+                allocation.id
+            );
+            None
+        }
+    }
+}
+
+/// Removes the directory of a job array once the directories of all its elements were removed.
+async fn remove_empty_array_directory(directory: &Path) {
+    let has_elements = std::fs::read_dir(directory)
+        .map(|mut entries| {
+            entries.any(|entry| {
+                entry
+                    .and_then(|entry| entry.file_type())
+                    .map(|file_type| file_type.is_dir())
+                    .unwrap_or(false)
+            })
+        })
+        .unwrap_or(true);
+    if !has_elements {
+        if let Err(err) = tokio::fs::remove_dir_all(directory).await {
+            log::error!("Failed to remove stale allocation array directory {directory:?}: {err:?}",);
+        }
+    }
+}
+
+fn try_pause_queue(state: &StateRef, autoalloc: &mut AutoAllocState, id: QueueId) {
     let queue = get_or_return!(autoalloc.get_queue_mut(id));
+    if !queue.state().is_running() {
+        return;
+    }
     let limiter = queue.limiter();
 
     let status = limiter.submission_status();
     match status {
         RateLimiterStatus::TooManyFailedSubmissions
         | RateLimiterStatus::TooManyFailedAllocations => {
-            if let RateLimiterStatus::TooManyFailedSubmissions = status {
+            let reason = if let RateLimiterStatus::TooManyFailedSubmissions = status {
                 log::error!("The queue {id} had too many failed submissions, it will be paused.");
+                "Too many failed submissions"
             } else {
                 log::error!("The queue {id} had too many failed allocations, it will be paused.");
-            }
+                "Too many failed allocations"
+            };
             queue.pause();
+            state
+                .get_mut()
+                .event_storage_mut()
+                .on_allocation_queue_paused(id, reason.to_string());
         }
         RateLimiterStatus::Ok | RateLimiterStatus::Wait => (),
     }
@@ -852,8 +1242,9 @@ pub fn prepare_queue_cleanup(
 #[cfg(test)]
 mod tests {
     use std::future::Future;
+    use std::path::PathBuf;
     use std::pin::Pin;
-    use std::time::{Duration, Instant};
+    use std::time::{Duration, Instant, SystemTime};
 
     use anyhow::anyhow;
     use derive_builder::Builder;
@@ -869,9 +1260,12 @@ mod tests {
 
     use crate::common::arraydef::IntArray;
     use crate::common::manager::info::{ManagerInfo, ManagerType};
+    use crate::common::schedule::{parse_time_window_text, ActiveSchedule};
     use crate::common::utils::time::mock_time::MockTime;
     use crate::server::autoalloc::process::{
-        on_worker_connected, on_worker_lost, queue_try_submit, refresh_state, RefreshReason,
+        count_array_allocations, get_submission_queue_info, on_worker_connected, on_worker_lost,
+        queue_try_submit, refresh_state, remove_archived_outputs, remove_inactive_directories,
+        RefreshReason,
     };
     use crate::server::autoalloc::queue::{
         AllocationExternalStatus, AllocationStatusMap, AllocationSubmissionResult, QueueHandler,
@@ -880,7 +1274,11 @@ mod tests {
     use crate::server::autoalloc::state::{
         AllocationQueue, AllocationQueueState, AllocationState, AutoAllocState, RateLimiter,
     };
-    use crate::server::autoalloc::{Allocation, AllocationId, AutoAllocResult, QueueId, QueueInfo};
+    use crate::server::autoalloc::{
+        Allocation, AllocationId, AllocationOutput, AutoAllocResult, NodeHourBudget, QueueId,
+        QueueInfo,
+    };
+    use crate::server::event::events::MonitoringEventPayload;
     use crate::server::job::Job;
     use crate::server::state::StateRef;
     use crate::tests::utils::create_hq_state;
@@ -1228,6 +1626,309 @@ mod tests {
         assert_eq!(allocations[1].target_worker_count, 2);
     }
 
+    #[tokio::test]
+    async fn fill_min_worker_pool_without_tasks() {
+        let hq_state = new_hq_state(0);
+        let mut state = AutoAllocState::new();
+
+        let handler = always_queued_handler();
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default()
+                .backlog(1)
+                .workers_per_alloc(2)
+                .min_workers(3),
+        );
+
+        queue_try_submit(queue_id, &mut state, &hq_state, None).await;
+        let allocations = get_allocations(&state, queue_id);
+        assert_eq!(allocations.len(), 2);
+
+        // The pool is already filled
+        queue_try_submit(queue_id, &mut state, &hq_state, None).await;
+        assert_eq!(get_allocations(&state, queue_id).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn min_worker_pool_respects_max_worker_count() {
+        let hq_state = new_hq_state(0);
+        let mut state = AutoAllocState::new();
+
+        let handler = always_queued_handler();
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default()
+                .workers_per_alloc(4)
+                .min_workers(4)
+                .max_worker_count(Some(3)),
+        );
+
+        queue_try_submit(queue_id, &mut state, &hq_state, None).await;
+        let allocations = get_allocations(&state, queue_id);
+        assert_eq!(allocations.len(), 1);
+        assert_eq!(allocations[0].target_worker_count, 3);
+    }
+
+    #[tokio::test]
+    async fn exempt_min_worker_pool_from_idle_timeout() {
+        let hq_state = new_hq_state(0);
+        let mut state = AutoAllocState::new();
+
+        let handler = always_queued_handler();
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default()
+                .backlog(1)
+                .workers_per_alloc(3)
+                .min_workers(2),
+        );
+
+        queue_try_submit(queue_id, &mut state, &hq_state, None).await;
+        let allocations = get_allocations(&state, queue_id);
+        for id in [1, 2, 3] {
+            on_worker_connected(
+                &hq_state,
+                &mut state,
+                id.into(),
+                &create_worker(&allocations[0].id),
+            );
+        }
+
+        let queue = state.get_queue_mut(queue_id).unwrap();
+        assert_eq!(
+            queue.update_idle_exempt_workers(),
+            vec![(1.into(), true), (2.into(), true)]
+        );
+        assert!(queue.update_idle_exempt_workers().is_empty());
+
+        on_worker_lost(
+            &hq_state,
+            &mut state,
+            1.into(),
+            &create_worker(&allocations[0].id),
+            LostWorkerReason::ConnectionLost,
+        );
+        let queue = state.get_queue_mut(queue_id).unwrap();
+        assert_eq!(queue.update_idle_exempt_workers(), vec![(3.into(), true)]);
+    }
+
+    #[tokio::test]
+    async fn submit_allocations_as_job_array() {
+        let hq_state = new_hq_state(100);
+        let mut state = AutoAllocState::new();
+
+        let handler = always_queued_handler();
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default()
+                .backlog(4)
+                .workers_per_alloc(2)
+                .max_worker_count(Some(7))
+                .job_arrays(true),
+        );
+
+        queue_try_submit(queue_id, &mut state, &hq_state, None).await;
+        let allocations = get_allocations(&state, queue_id);
+        assert_eq!(allocations.len(), 4);
+        assert_eq!(
+            allocations
+                .iter()
+                .map(|a| a.target_worker_count)
+                .collect::<Vec<_>>(),
+            vec![2, 2, 2, 1]
+        );
+    }
+
+    #[test]
+    fn count_allocations_in_job_array() {
+        // Limited by allocation count
+        assert_eq!(count_array_allocations(3, 2, 100, 0, u64::MAX, None), 3);
+        // Limited by waiting tasks
+        assert_eq!(count_array_allocations(10, 2, 5, 0, u64::MAX, None), 3);
+        // Limited by minimal worker pool
+        assert_eq!(count_array_allocations(10, 2, 0, 4, u64::MAX, None), 2);
+        // Limited by maximum worker count
+        assert_eq!(count_array_allocations(10, 2, 100, 0, 5, None), 2);
+        // Limited by node-hour budget
+        assert_eq!(
+            count_array_allocations(10, 2, 100, 0, u64::MAX, Some(2.5)),
+            2
+        );
+        // At least a single allocation is always submitted
+        assert_eq!(count_array_allocations(10, 2, 1, 0, 1, None), 1);
+    }
+
+    #[tokio::test]
+    async fn do_not_submit_outside_active_schedule() {
+        let hq_state = new_hq_state(100);
+        let mut state = AutoAllocState::new();
+
+        let handler = always_queued_handler();
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default().active_schedule(Some(schedule_relative_to_now(120, 180))),
+        );
+
+        refresh_state(&hq_state, &mut state, RefreshReason::UpdateQueue(queue_id)).await;
+        assert!(get_allocations(&state, queue_id).is_empty());
+    }
+
+    #[tokio::test]
+    async fn submit_inside_active_schedule() {
+        let hq_state = new_hq_state(100);
+        let mut state = AutoAllocState::new();
+
+        let handler = always_queued_handler();
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default().active_schedule(Some(schedule_relative_to_now(-60, 120))),
+        );
+
+        refresh_state(&hq_state, &mut state, RefreshReason::UpdateQueue(queue_id)).await;
+        assert_eq!(get_allocations(&state, queue_id).len(), 1);
+    }
+
+    #[test]
+    fn cap_timelimit_by_active_schedule() {
+        let (info, _) = QueueBuilder::default()
+            .timelimit(Duration::from_secs(3 * 60 * 60))
+            .active_schedule(Some(schedule_relative_to_now(-60, 120)))
+            .cap_timelimit(true)
+            .build();
+        let timelimit = get_submission_queue_info(&info, SystemTime::now())
+            .unwrap()
+            .timelimit();
+        assert!(timelimit <= Duration::from_secs(2 * 60 * 60));
+        assert!(timelimit > Duration::from_secs(60 * 60));
+    }
+
+    #[test]
+    fn do_not_cap_shorter_timelimit() {
+        let (info, _) = QueueBuilder::default()
+            .timelimit(Duration::from_secs(60 * 60))
+            .active_schedule(Some(schedule_relative_to_now(-60, 120)))
+            .cap_timelimit(true)
+            .build();
+        let timelimit = get_submission_queue_info(&info, SystemTime::now())
+            .unwrap()
+            .timelimit();
+        assert_eq!(timelimit, Duration::from_secs(60 * 60));
+    }
+
+    #[tokio::test]
+    async fn do_not_submit_if_active_schedule_closes_soon() {
+        let hq_state = new_hq_state(100);
+        let mut state = AutoAllocState::new();
+
+        let handler = always_queued_handler();
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default()
+                .active_schedule(Some(schedule_relative_to_now(-60, 2)))
+                .cap_timelimit(true),
+        );
+
+        queue_try_submit(queue_id, &mut state, &hq_state, None).await;
+        assert!(get_allocations(&state, queue_id).is_empty());
+    }
+
+    #[tokio::test]
+    async fn respect_node_hour_budget() {
+        let hq_state = new_hq_state(100);
+        let mut state = AutoAllocState::new();
+
+        let handler = always_queued_handler();
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default()
+                .backlog(10)
+                .workers_per_alloc(2)
+                .timelimit(Duration::from_secs(60 * 60))
+                .budget(Some(NodeHourBudget {
+                    limit: 7.0,
+                    window: None,
+                })),
+        );
+
+        // Each allocation can consume at most 2 node-hours
+        queue_try_submit(queue_id, &mut state, &hq_state, None).await;
+        assert_eq!(get_allocations(&state, queue_id).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn pause_queue_when_budget_is_exhausted() {
+        let hq_state = new_hq_state(100);
+        let mut state = AutoAllocState::new();
+
+        let handler = always_queued_handler();
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default().budget(Some(NodeHourBudget {
+                limit: 1.0,
+                window: None,
+            })),
+        );
+
+        queue_try_submit(queue_id, &mut state, &hq_state, None).await;
+        let allocations = get_allocations(&state, queue_id);
+        assert_eq!(allocations.len(), 1);
+
+        let queue = state.get_queue_mut(queue_id).unwrap();
+        let allocation = queue.get_allocation_mut(&allocations[0].id).unwrap();
+        let finished_at = SystemTime::now();
+        allocation.status = AllocationState::Finished {
+            started_at: finished_at - Duration::from_secs(60 * 60),
+            finished_at,
+            disconnected_workers: Default::default(),
+        };
+
+        refresh_state(&hq_state, &mut state, RefreshReason::UpdateQueue(queue_id)).await;
+        check_queue_paused(&state, queue_id);
+        assert_eq!(get_allocations(&state, queue_id).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn do_not_pause_queue_with_budget_window() {
+        let hq_state = new_hq_state(100);
+        let mut state = AutoAllocState::new();
+
+        let handler = always_queued_handler();
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default().budget(Some(NodeHourBudget {
+                limit: 1.0,
+                window: Some(Duration::from_secs(2 * 60 * 60)),
+            })),
+        );
+
+        queue_try_submit(queue_id, &mut state, &hq_state, None).await;
+        let allocations = get_allocations(&state, queue_id);
+
+        let queue = state.get_queue_mut(queue_id).unwrap();
+        let allocation = queue.get_allocation_mut(&allocations[0].id).unwrap();
+        let finished_at = SystemTime::now();
+        allocation.status = AllocationState::Finished {
+            started_at: finished_at - Duration::from_secs(60 * 60),
+            finished_at,
+            disconnected_workers: Default::default(),
+        };
+
+        refresh_state(&hq_state, &mut state, RefreshReason::UpdateQueue(queue_id)).await;
+        check_queue_exists(&state, queue_id);
+        assert!(state.get_queue(queue_id).unwrap().state().is_running());
+        assert_eq!(get_allocations(&state, queue_id).len(), 1);
+    }
+
     #[tokio::test]
     async fn delete_stale_directories_of_unsubmitted_allocations() {
         let hq_state = new_hq_state(100);
@@ -1257,6 +1958,108 @@ mod tests {
         assert!(!dirs[1].exists());
     }
 
+    #[tokio::test]
+    async fn archive_output_of_finished_allocations() {
+        let hq_state = new_hq_state(1000);
+        let mut state = AutoAllocState::new();
+
+        let shared = WrappedRcRefCell::wrap(HandlerState::default());
+        let handler = stateful_handler(shared.clone());
+        state.set_max_kept_directories(0);
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default().backlog(1).workers_per_alloc(1),
+        );
+
+        queue_try_submit(queue_id, &mut state, &hq_state, None).await;
+        let allocation = get_allocations(&state, queue_id).pop().unwrap();
+        std::fs::write(allocation.working_dir.join("stdout"), "foo\nbar\n").unwrap();
+        std::fs::write(allocation.working_dir.join("stderr"), "error\n").unwrap();
+
+        fail_allocation(&hq_state, &mut state, &allocation.id);
+        remove_inactive_directories(&mut state).await;
+
+        assert!(!allocation.working_dir.exists());
+        let queue = state.get_queue(queue_id).unwrap();
+        let archived = queue.archived_output(&allocation.id).unwrap();
+        assert_eq!(
+            archived.tail,
+            AllocationOutput {
+                stdout: "foo\nbar".to_string(),
+                stderr: "error".to_string(),
+            }
+        );
+        let directory = archived.directory.as_ref().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(directory.join("stdout")).unwrap(),
+            "foo\nbar\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(directory.join("stderr")).unwrap(),
+            "error\n"
+        );
+        assert_eq!(
+            queue.get_allocation(&allocation.id).unwrap().worker_ids(),
+            vec![WorkerId::from(0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn remove_oldest_archived_outputs() {
+        let hq_state = new_hq_state(1000);
+        let mut state = AutoAllocState::new();
+
+        let shared = WrappedRcRefCell::wrap(HandlerState::default());
+        let handler = stateful_handler(shared.clone());
+        state.set_max_kept_directories(1);
+        let queue_id = add_queue(
+            &mut state,
+            handler,
+            QueueBuilder::default().backlog(3).workers_per_alloc(1),
+        );
+
+        queue_try_submit(queue_id, &mut state, &hq_state, None).await;
+        let allocations = get_allocations(&state, queue_id);
+        assert_eq!(allocations.len(), 3);
+        for allocation in &allocations {
+            std::fs::write(
+                allocation.working_dir.join("stdout"),
+                format!("{}\n", allocation.id),
+            )
+            .unwrap();
+            fail_allocation(&hq_state, &mut state, &allocation.id);
+        }
+        remove_inactive_directories(&mut state).await;
+        remove_archived_outputs(&mut state).await;
+
+        let queue = state.get_queue(queue_id).unwrap();
+        let log_dir = |allocation: &Allocation| {
+            allocation
+                .working_dir
+                .parent()
+                .unwrap()
+                .join("logs")
+                .join(&allocation.id)
+        };
+
+        // Only the last lines of the oldest output are kept
+        let oldest = queue.archived_output(&allocations[0].id).unwrap();
+        assert_eq!(oldest.tail.stdout, "0");
+        assert_eq!(oldest.directory, None);
+        assert!(!log_dir(&allocations[0]).exists());
+
+        let archived = queue.archived_output(&allocations[1].id).unwrap();
+        assert_eq!(archived.directory, Some(log_dir(&allocations[1])));
+        assert_eq!(
+            std::fs::read_to_string(log_dir(&allocations[1]).join("stdout")).unwrap(),
+            "1\n"
+        );
+
+        assert!(queue.archived_output(&allocations[2].id).is_none());
+        assert!(allocations[2].working_dir.exists());
+    }
+
     #[tokio::test]
     async fn pause_queue_when_submission_fails_too_many_times() {
         let hq_state = new_hq_state(100);
@@ -1279,6 +2082,21 @@ mod tests {
         check_queue_exists(&state, queue_id);
         refresh_state(&hq_state, &mut state, RefreshReason::UpdateAllQueues).await;
         check_queue_paused(&state, queue_id);
+
+        // The pause is recorded only once, even if the queue is refreshed again
+        refresh_state(&hq_state, &mut state, RefreshReason::UpdateAllQueues).await;
+        let pause_events = hq_state
+            .get()
+            .event_storage()
+            .get_events_after(0)
+            .filter(|event| {
+                matches!(
+                    event.payload(),
+                    MonitoringEventPayload::AllocationQueuePaused { .. }
+                )
+            })
+            .count();
+        assert_eq!(pause_events, 1);
     }
 
     #[tokio::test]
@@ -1427,12 +2245,38 @@ mod tests {
             _queue_id: QueueId,
             _queue_info: &QueueInfo,
             worker_count: u64,
+            allocation_count: u64,
             _mode: SubmitMode,
         ) -> Pin<Box<dyn Future<Output = AutoAllocResult<AllocationSubmissionResult>>>> {
             let schedule_fn = self.schedule_fn.clone();
             let custom_state = self.custom_state.clone();
 
-            Box::pin(async move { (schedule_fn.get())(custom_state.clone(), worker_count).await })
+            Box::pin(async move {
+                if allocation_count == 1 {
+                    return (schedule_fn.get())(custom_state.clone(), worker_count).await;
+                }
+
+                // Simulate a job array by submitting each element separately
+                let mut elements = vec![];
+                let mut working_dir = PathBuf::default();
+                for _ in 0..allocation_count {
+                    let result = (schedule_fn.get())(custom_state.clone(), worker_count).await?;
+                    working_dir = result.working_dir().to_path_buf();
+                    match result.into_allocations() {
+                        Ok(allocations) => elements.extend(allocations),
+                        Err(error) => {
+                            return Ok(AllocationSubmissionResult::new_array(
+                                Err(error),
+                                working_dir,
+                            ))
+                        }
+                    }
+                }
+                Ok(AllocationSubmissionResult::new_array(
+                    Ok(elements),
+                    working_dir,
+                ))
+            })
         }
 
         fn get_status_of_allocations(
@@ -1521,7 +2365,8 @@ mod tests {
             move |state, _worker_count| async move {
                 let mut state = state.get_mut();
                 let tempdir = TempDir::new("hq").unwrap();
-                let dir = tempdir.into_path();
+                let dir = tempdir.into_path().join("001");
+                std::fs::create_dir(&dir).unwrap();
 
                 state.allocation_attempts += 1;
 
@@ -1565,6 +2410,16 @@ mod tests {
         timelimit: Duration,
         #[builder(default)]
         max_worker_count: Option<u32>,
+        #[builder(default)]
+        min_workers: u32,
+        #[builder(default)]
+        budget: Option<NodeHourBudget>,
+        #[builder(default)]
+        active_schedule: Option<ActiveSchedule>,
+        #[builder(default)]
+        cap_timelimit: bool,
+        #[builder(default)]
+        job_arrays: bool,
         #[builder(default = "100")]
         limiter_max_alloc_fails: u64,
         #[builder(default = "100")]
@@ -1580,6 +2435,11 @@ mod tests {
                 workers_per_alloc,
                 timelimit,
                 max_worker_count,
+                min_workers,
+                budget,
+                active_schedule,
+                cap_timelimit,
+                job_arrays,
                 limiter_max_alloc_fails,
                 limiter_max_submit_fails,
                 limiter_delays,
@@ -1594,7 +2454,12 @@ mod tests {
                     None,
                     vec![],
                     max_worker_count,
+                    min_workers,
                     None,
+                    budget,
+                    active_schedule,
+                    cap_timelimit,
+                    job_arrays,
                 ),
                 RateLimiter::new(
                     limiter_delays,
@@ -1606,6 +2471,15 @@ mod tests {
         }
     }
 
+    /// Creates a daily schedule with a single window that starts and ends at the given
+    /// offsets (in minutes) from the current time.
+    fn schedule_relative_to_now(start: i64, end: i64) -> ActiveSchedule {
+        let now = chrono::Local::now();
+        let format = |offset: i64| (now + chrono::Duration::minutes(offset)).format("%H:%M");
+        let window = parse_time_window_text(&format!("{}-{}", format(start), format(end)));
+        ActiveSchedule::new(vec![window.unwrap()])
+    }
+
     fn add_queue(
         autoalloc: &mut AutoAllocState,
         handler: Box<dyn QueueHandler>,
@@ -1667,6 +2541,8 @@ mod tests {
                     time_limit: None,
                     priority: 0,
                     crash_limit: 5,
+                    output_limit: None,
+                    spool: false,
                 },
             },
             job_id.into(),
@@ -1674,6 +2550,7 @@ mod tests {
             "job".to_string(),
             None,
             None,
+            None,
             Default::default(),
         )
     }
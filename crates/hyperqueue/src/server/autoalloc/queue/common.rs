@@ -63,6 +63,29 @@ pub fn create_allocation_dir(
     Ok(dir)
 }
 
+/// Creates a directory for each element of a job array inside the given array `directory`.
+/// The directories are named by the index of the corresponding array element.
+pub fn create_array_element_dirs(
+    directory: &Path,
+    allocation_count: u64,
+) -> Result<Vec<PathBuf>, std::io::Error> {
+    (0..allocation_count)
+        .map(|index| {
+            let dir = directory.join(index.to_string());
+            std::fs::create_dir_all(&dir)?;
+            Ok(dir)
+        })
+        .collect()
+}
+
+/// If the given allocation directory belongs to an element of a job array, returns the
+/// directory of the whole array.
+pub fn get_array_directory(directory: &Path) -> Option<&Path> {
+    directory
+        .parent()
+        .filter(|parent| parent.join(SUBMIT_SCRIPT_NAME).is_file())
+}
+
 /// Submits a script into PBS/Slurm and creates debug information in the given allocation `directory`.
 pub async fn submit_script<F>(
     script: String,
@@ -2,6 +2,9 @@ mod common;
 pub mod pbs;
 pub mod slurm;
 
+pub use common::get_array_directory;
+
+use crate::common::schedule::ActiveSchedule;
 use crate::server::autoalloc::state::AllocationId;
 use crate::server::autoalloc::{Allocation, AutoAllocResult, QueueId};
 use serde::{Deserialize, Serialize};
@@ -22,7 +25,12 @@ pub struct QueueInfo {
     worker_cpu_arg: Option<String>,
     worker_resource_args: Vec<String>,
     max_worker_count: Option<u32>,
+    min_workers: u32,
     idle_timeout: Option<Duration>,
+    budget: Option<NodeHourBudget>,
+    active_schedule: Option<ActiveSchedule>,
+    cap_timelimit: bool,
+    job_arrays: bool,
 }
 
 impl QueueInfo {
@@ -36,7 +44,12 @@ impl QueueInfo {
         worker_cpu_arg: Option<String>,
         worker_resource_args: Vec<String>,
         max_worker_count: Option<u32>,
+        min_workers: u32,
         idle_timeout: Option<Duration>,
+        budget: Option<NodeHourBudget>,
+        active_schedule: Option<ActiveSchedule>,
+        cap_timelimit: bool,
+        job_arrays: bool,
     ) -> Self {
         Self {
             backlog,
@@ -46,8 +59,13 @@ impl QueueInfo {
             worker_cpu_arg,
             worker_resource_args,
             max_worker_count,
+            min_workers,
             on_server_lost,
             idle_timeout,
+            budget,
+            active_schedule,
+            cap_timelimit,
+            job_arrays,
         }
     }
 
@@ -82,27 +100,83 @@ impl QueueInfo {
     pub fn max_worker_count(&self) -> Option<u32> {
         self.max_worker_count
     }
+
+    pub fn min_workers(&self) -> u32 {
+        self.min_workers
+    }
+
+    pub fn budget(&self) -> Option<&NodeHourBudget> {
+        self.budget.as_ref()
+    }
+
+    pub fn active_schedule(&self) -> Option<&ActiveSchedule> {
+        self.active_schedule.as_ref()
+    }
+
+    /// If true, the time limit of allocations is shortened so that they end before the
+    /// active schedule window closes.
+    pub fn cap_timelimit(&self) -> bool {
+        self.cap_timelimit
+    }
+
+    /// If true, allocations that are created at the same time are submitted as a single job array.
+    pub fn job_arrays(&self) -> bool {
+        self.job_arrays
+    }
+
+    /// Returns a copy of this queue info with a different time limit.
+    pub(crate) fn with_timelimit(&self, timelimit: Duration) -> Self {
+        Self {
+            timelimit,
+            ..self.clone()
+        }
+    }
+}
+
+/// Limits the amount of node-hours that can be consumed by allocations of a single queue.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NodeHourBudget {
+    /// Maximum number of node-hours that can be consumed.
+    pub limit: f64,
+    /// If set, only node-hours consumed within this (sliding) time window are accounted.
+    pub window: Option<Duration>,
 }
 
 #[derive(Debug)]
 pub struct AllocationSubmissionResult {
     /// Directory containing stdout/stderr of the allocation (if submission was successful)
     /// and with debug information.
+    /// If a job array was submitted, it contains directories of the individual array elements.
     ///
     /// It is returned always because we need to delete regularly to avoid too many directories and
     /// files being created.
     working_dir: PathBuf,
-    /// ID of the created allocation, if it was successfully submitted.
-    id: AutoAllocResult<AllocationId>,
+    /// IDs and working directories of the created allocations, if they were successfully submitted.
+    allocations: AutoAllocResult<Vec<(AllocationId, PathBuf)>>,
 }
 
 impl AllocationSubmissionResult {
     pub fn new(id: AutoAllocResult<AllocationId>, working_dir: PathBuf) -> Self {
-        Self { id, working_dir }
+        Self {
+            allocations: id.map(|id| vec![(id, working_dir.clone())]),
+            working_dir,
+        }
+    }
+
+    /// Creates a result of a job array submission, where each array element has its own
+    /// allocation ID and working directory.
+    pub fn new_array(
+        allocations: AutoAllocResult<Vec<(AllocationId, PathBuf)>>,
+        working_dir: PathBuf,
+    ) -> Self {
+        Self {
+            allocations,
+            working_dir,
+        }
     }
 
-    pub fn into_id(self) -> AutoAllocResult<AllocationId> {
-        self.id
+    pub fn into_allocations(self) -> AutoAllocResult<Vec<(AllocationId, PathBuf)>> {
+        self.allocations
     }
 
     pub fn working_dir(&self) -> &Path {
@@ -136,15 +210,18 @@ pub type AllocationStatusMap = Map<AllocationId, AutoAllocResult<AllocationExter
 /// Handler that can communicate with some allocation queue (e.g. PBS/Slurm queue)
 pub trait QueueHandler {
     /// Submit an allocation that will start the corresponding number of workers.
+    /// If `allocation_count` is larger than one, a job array with the given number of elements
+    /// will be submitted, and each element will be returned as a separate allocation.
     ///
     /// If the method returns an error, no directory was created on disk.
     /// If it returns Ok, the directory was created and submission result can be read out of the
-    /// `id` field of `AllocationSubmissionResult`.
+    /// `allocations` field of `AllocationSubmissionResult`.
     fn submit_allocation(
         &mut self,
         queue_id: QueueId,
         queue_info: &QueueInfo,
         worker_count: u64,
+        allocation_count: u64,
         mode: SubmitMode,
     ) -> Pin<Box<dyn Future<Output = AutoAllocResult<AllocationSubmissionResult>>>>;
 
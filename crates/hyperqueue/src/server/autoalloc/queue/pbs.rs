@@ -10,9 +10,10 @@ use tako::Map;
 use crate::common::manager::info::ManagerType;
 use crate::common::manager::pbs::{format_pbs_duration, parse_pbs_datetime};
 use crate::common::utils::time::local_to_system_time;
+use crate::server::autoalloc::logs::{ALLOCATION_STDERR_FILE, ALLOCATION_STDOUT_FILE};
 use crate::server::autoalloc::queue::common::{
-    build_worker_args, check_command_output, create_allocation_dir, create_command, submit_script,
-    ExternalHandler,
+    build_worker_args, check_command_output, create_allocation_dir, create_array_element_dirs,
+    create_command, submit_script, ExternalHandler,
 };
 use crate::server::autoalloc::queue::{
     AllocationExternalStatus, AllocationStatusMap, AllocationSubmissionResult, QueueHandler,
@@ -37,6 +38,7 @@ impl QueueHandler for PbsHandler {
         queue_id: QueueId,
         queue_info: &QueueInfo,
         worker_count: u64,
+        allocation_count: u64,
         mode: SubmitMode,
     ) -> Pin<Box<dyn Future<Output = AutoAllocResult<AllocationSubmissionResult>>>> {
         let queue_info = queue_info.clone();
@@ -56,23 +58,49 @@ impl QueueHandler for PbsHandler {
             let worker_args =
                 build_worker_args(&hq_path, ManagerType::Pbs, &server_directory, &queue_info);
 
+            let is_array = allocation_count > 1;
+            // Each array element writes its output into its own directory
+            let output_dir = if is_array {
+                directory.join("^array_index^")
+            } else {
+                directory.clone()
+            };
+
             let script = build_pbs_submit_script(
                 worker_count,
                 timelimit,
                 &format!("hq-alloc-{}", queue_id),
-                &directory.join("stdout").display().to_string(),
-                &directory.join("stderr").display().to_string(),
+                &output_dir
+                    .join(ALLOCATION_STDOUT_FILE)
+                    .display()
+                    .to_string(),
+                &output_dir
+                    .join(ALLOCATION_STDERR_FILE)
+                    .display()
+                    .to_string(),
                 &queue_info.additional_args.join(" "),
                 &worker_args,
                 mode,
+                is_array.then_some(allocation_count),
             );
-            let job_id =
-                submit_script(script, "qsub", &directory, |output| Ok(output.to_string())).await;
+            let submit = submit_script(script, "qsub", &directory, |output| Ok(output.to_string()));
 
-            Ok(AllocationSubmissionResult {
-                id: job_id,
-                working_dir: directory,
-            })
+            if is_array {
+                let element_dirs = create_array_element_dirs(&directory, allocation_count)?;
+                let allocations = submit.await.and_then(|array_id| {
+                    element_dirs
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, dir)| Ok((get_array_element_id(&array_id, index)?, dir)))
+                        .collect()
+                });
+                Ok(AllocationSubmissionResult::new_array(
+                    allocations,
+                    directory,
+                ))
+            } else {
+                Ok(AllocationSubmissionResult::new(submit.await, directory))
+            }
         })
     }
 
@@ -80,13 +108,27 @@ impl QueueHandler for PbsHandler {
         &self,
         allocations: &[&Allocation],
     ) -> Pin<Box<dyn Future<Output = AutoAllocResult<AllocationStatusMap>>>> {
-        let mut arguments = vec!["qstat"];
+        // Elements of a job array are queried together using the ID of the array
+        let mut job_ids: Vec<String> = Vec::with_capacity(allocations.len());
         for &allocation in allocations {
-            arguments.extend_from_slice(&["-f", &allocation.id]);
+            let job_id = get_array_job_id(&allocation.id).unwrap_or_else(|| allocation.id.clone());
+            if !job_ids.contains(&job_id) {
+                job_ids.push(job_id);
+            }
+        }
+        let has_arrays = job_ids.iter().any(|id| id.contains("[]"));
+
+        let mut arguments = vec!["qstat"];
+        for job_id in &job_ids {
+            arguments.extend_from_slice(&["-f", job_id]);
         }
 
         // -x will also display finished jobs
         arguments.extend_from_slice(&["-F", "json", "-x"]);
+        // -t will also display elements of job arrays
+        if has_arrays {
+            arguments.push("-t");
+        }
 
         let allocation_ids: Vec<AllocationId> =
             allocations.iter().map(|alloc| alloc.id.clone()).collect();
@@ -146,6 +188,29 @@ impl QueueHandler for PbsHandler {
     }
 }
 
+/// Returns the ID of the job array to which the given allocation belongs, if it is an element
+/// of a job array (e.g. `123[].server` for `123[4].server`).
+fn get_array_job_id(allocation_id: &str) -> Option<String> {
+    let start = allocation_id.find('[')?;
+    let end = start + allocation_id[start..].find(']')?;
+    if end == start + 1 {
+        return None;
+    }
+    Some(format!(
+        "{}[]{}",
+        &allocation_id[..start],
+        &allocation_id[end + 1..]
+    ))
+}
+
+/// Returns the ID of an element of a job array (e.g. `123[4].server` for `123[].server`).
+fn get_array_element_id(array_id: &str, index: usize) -> AutoAllocResult<AllocationId> {
+    if !array_id.contains("[]") {
+        anyhow::bail!("Invalid PBS job array ID {array_id}");
+    }
+    Ok(array_id.replacen("[]", &format!("[{index}]"), 1))
+}
+
 fn parse_allocation_status(
     allocation: &serde_json::Value,
 ) -> AutoAllocResult<AllocationExternalStatus> {
@@ -196,6 +261,7 @@ fn build_pbs_submit_script(
     qsub_args: &str,
     worker_cmd: &str,
     mode: SubmitMode,
+    array_size: Option<u64>,
 ) -> String {
     let mut script = format!(
         r##"#!/bin/bash
@@ -212,6 +278,9 @@ fn build_pbs_submit_script(
         walltime = format_pbs_duration(&timelimit)
     );
 
+    if let Some(array_size) = array_size {
+        writeln!(script, "#PBS -J 0-{}", array_size - 1).unwrap();
+    }
     if !qsub_args.is_empty() {
         writeln!(script, "#PBS {}", qsub_args).unwrap();
     }
@@ -240,3 +309,27 @@ fn get_json_number(value: &serde_json::Value, context: &str) -> AutoAllocResult<
         .as_u64()
         .ok_or_else(|| anyhow::anyhow!("JSON key {} not found", context))
 }
+
+#[cfg(test)]
+mod test {
+    use crate::server::autoalloc::queue::pbs::{get_array_element_id, get_array_job_id};
+
+    #[test]
+    fn test_get_array_job_id() {
+        assert_eq!(
+            get_array_job_id("123[4].server"),
+            Some("123[].server".to_string())
+        );
+        assert_eq!(get_array_job_id("123[].server"), None);
+        assert_eq!(get_array_job_id("123.server"), None);
+    }
+
+    #[test]
+    fn test_get_array_element_id() {
+        assert_eq!(
+            get_array_element_id("123[].server", 4).unwrap(),
+            "123[4].server"
+        );
+        assert!(get_array_element_id("123.server", 4).is_err());
+    }
+}
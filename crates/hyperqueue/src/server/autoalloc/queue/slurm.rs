@@ -13,8 +13,10 @@ use crate::common::manager::slurm::{
     format_slurm_duration, get_scontrol_items, parse_slurm_datetime,
 };
 use crate::common::utils::time::local_to_system_time;
+use crate::server::autoalloc::logs::{ALLOCATION_STDERR_FILE, ALLOCATION_STDOUT_FILE};
 use crate::server::autoalloc::queue::common::{
-    build_worker_args, create_allocation_dir, create_command, submit_script, ExternalHandler,
+    build_worker_args, create_allocation_dir, create_array_element_dirs, create_command,
+    submit_script, ExternalHandler,
 };
 use crate::server::autoalloc::queue::{
     common, AllocationExternalStatus, AllocationStatusMap, AllocationSubmissionResult,
@@ -39,6 +41,7 @@ impl QueueHandler for SlurmHandler {
         queue_id: QueueId,
         queue_info: &QueueInfo,
         worker_count: u64,
+        allocation_count: u64,
         _mode: SubmitMode,
     ) -> Pin<Box<dyn Future<Output = AutoAllocResult<AllocationSubmissionResult>>>> {
         let queue_info = queue_info.clone();
@@ -58,25 +61,53 @@ impl QueueHandler for SlurmHandler {
 
             let worker_args =
                 build_worker_args(&hq_path, ManagerType::Slurm, &server_directory, &queue_info);
+            let is_array = allocation_count > 1;
+            // Each array element writes its output into its own directory
+            let output_dir = if is_array {
+                working_dir.join("%a")
+            } else {
+                working_dir.clone()
+            };
             let script = build_slurm_submit_script(
                 worker_count,
                 timelimit,
                 &format!("hq-alloc-{}", queue_id),
-                &working_dir.join("stdout").display().to_string(),
-                &working_dir.join("stderr").display().to_string(),
+                &output_dir
+                    .join(ALLOCATION_STDOUT_FILE)
+                    .display()
+                    .to_string(),
+                &output_dir
+                    .join(ALLOCATION_STDERR_FILE)
+                    .display()
+                    .to_string(),
                 &queue_info.additional_args.join(" "),
                 &worker_args,
+                is_array.then_some(allocation_count),
             );
-            let id = submit_script(script, "sbatch", &working_dir, |output| {
+            let submit = submit_script(script, "sbatch", &working_dir, |output| {
                 output
                     .split(' ')
                     .nth(3)
                     .ok_or_else(|| anyhow::anyhow!("Missing job id in sbatch output"))
                     .map(|id| id.to_string())
-            })
-            .await;
+            });
 
-            Ok(AllocationSubmissionResult::new(id, working_dir))
+            if is_array {
+                let element_dirs = create_array_element_dirs(&working_dir, allocation_count)?;
+                let allocations = submit.await.map(|array_id| {
+                    element_dirs
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, dir)| (format!("{array_id}_{index}"), dir))
+                        .collect()
+                });
+                Ok(AllocationSubmissionResult::new_array(
+                    allocations,
+                    working_dir,
+                ))
+            } else {
+                Ok(AllocationSubmissionResult::new(submit.await, working_dir))
+            }
         })
     }
 
@@ -93,9 +124,39 @@ impl QueueHandler for SlurmHandler {
 
         Box::pin(async move {
             let mut result = Map::with_capacity(allocation_ids.len());
-            for allocation_id in allocation_ids {
-                let status = get_allocation_status(&allocation_id, &workdir).await;
-                result.insert(allocation_id, status);
+
+            // Elements of a job array are queried together using the ID of the array
+            let mut arrays: Map<&str, Vec<&AllocationId>> = Map::new();
+            for allocation_id in &allocation_ids {
+                let job_id = get_array_job_id(allocation_id).unwrap_or(allocation_id);
+                arrays.entry(job_id).or_default().push(allocation_id);
+            }
+
+            for (job_id, allocation_ids) in arrays {
+                match get_job_statuses(job_id, &workdir).await {
+                    Ok(statuses) => {
+                        for allocation_id in allocation_ids {
+                            let status = statuses
+                                .iter()
+                                .find(|(ids, _)| ids.contains(allocation_id))
+                                .map(|(_, status)| match status {
+                                    Ok(status) => Ok(status.clone()),
+                                    Err(error) => Err(anyhow::anyhow!("{error:?}")),
+                                })
+                                .unwrap_or_else(|| {
+                                    Err(anyhow::anyhow!(
+                                        "Slurm job {allocation_id} not found in scontrol output"
+                                    ))
+                                });
+                            result.insert(allocation_id.clone(), status);
+                        }
+                    }
+                    Err(error) => {
+                        for allocation_id in allocation_ids {
+                            result.insert(allocation_id.clone(), Err(anyhow::anyhow!("{error:?}")));
+                        }
+                    }
+                }
             }
 
             Ok(result)
@@ -120,11 +181,19 @@ impl QueueHandler for SlurmHandler {
     }
 }
 
-async fn get_allocation_status(
-    allocation_id: &str,
-    workdir: &Path,
-) -> AutoAllocResult<AllocationExternalStatus> {
-    let arguments = vec!["scontrol", "show", "job", allocation_id];
+/// Returns the ID of the job array to which the given allocation belongs, if it is an element
+/// of a job array (e.g. `123` for `123_4`).
+fn get_array_job_id(allocation_id: &str) -> Option<&str> {
+    allocation_id.split_once('_').map(|(job_id, _)| job_id)
+}
+
+type JobStatuses = Vec<(Vec<AllocationId>, AutoAllocResult<AllocationExternalStatus>)>;
+
+/// Returns the statuses of a Slurm job or of all elements of a Slurm job array.
+/// Each status is paired with the allocation IDs that it describes; pending array elements
+/// can be described by a single scontrol record.
+async fn get_job_statuses(job_id: &str, workdir: &Path) -> AutoAllocResult<JobStatuses> {
+    let arguments = vec!["scontrol", "show", "job", job_id];
     log::debug!("Running Slurm command `{}`", arguments.join(" "));
 
     let mut command = create_command(arguments, workdir);
@@ -135,8 +204,44 @@ async fn get_allocation_status(
         .stdout
         .to_str()
         .map_err(|err| anyhow::anyhow!("Invalid UTF-8 in scontrol output: {:?}", err))?;
-    let items = get_scontrol_items(output);
-    parse_slurm_status(items)
+    Ok(parse_scontrol_records(output))
+}
+
+fn parse_scontrol_records(output: &str) -> JobStatuses {
+    output
+        .split("\n\n")
+        .filter(|record| !record.trim().is_empty())
+        .map(|record| {
+            let items = get_scontrol_items(record);
+            let ids = match (items.get("ArrayJobId"), items.get("ArrayTaskId")) {
+                (Some(array_id), Some(task_ids)) => parse_array_task_ids(task_ids)
+                    .into_iter()
+                    .map(|index| format!("{array_id}_{index}"))
+                    .collect(),
+                _ => items
+                    .get("JobId")
+                    .map(|id| vec![id.to_string()])
+                    .unwrap_or_default(),
+            };
+            (ids, parse_slurm_status(items))
+        })
+        .collect()
+}
+
+/// Parses array task IDs from scontrol output, e.g. `4`, `2-5`, `1,3-4` or `1-8%2`.
+fn parse_array_task_ids(value: &str) -> Vec<u64> {
+    let value = value.split('%').next().unwrap_or_default();
+    let value = value.trim_start_matches('[').trim_end_matches(']');
+
+    let mut ids = vec![];
+    for range in value.split(',') {
+        let (start, end) = range.split_once('-').unwrap_or((range, range));
+        match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) => ids.extend(start..=end),
+            _ => log::warn!("Cannot parse Slurm array task ID `{range}`"),
+        }
+    }
+    ids
 }
 
 fn parse_slurm_status(items: Map<&str, &str>) -> AutoAllocResult<AllocationExternalStatus> {
@@ -188,6 +293,7 @@ fn build_slurm_submit_script(
     stderr: &str,
     sbatch_args: &str,
     worker_cmd: &str,
+    array_size: Option<u64>,
 ) -> String {
     let mut script = format!(
         r##"#!/bin/bash
@@ -204,6 +310,9 @@ fn build_slurm_submit_script(
         walltime = format_slurm_duration(&timelimit)
     );
 
+    if let Some(array_size) = array_size {
+        writeln!(script, "#SBATCH --array=0-{}", array_size - 1).unwrap();
+    }
     if !sbatch_args.is_empty() {
         writeln!(script, "#SBATCH {}", sbatch_args).unwrap();
     }
@@ -212,3 +321,56 @@ fn build_slurm_submit_script(
     write!(script, "\n{prefix}{worker_cmd}").unwrap();
     script
 }
+
+#[cfg(test)]
+mod test {
+    use crate::server::autoalloc::queue::slurm::{
+        get_array_job_id, parse_array_task_ids, parse_scontrol_records,
+    };
+    use crate::server::autoalloc::queue::AllocationExternalStatus;
+
+    #[test]
+    fn test_get_array_job_id() {
+        assert_eq!(get_array_job_id("123_4"), Some("123"));
+        assert_eq!(get_array_job_id("123"), None);
+    }
+
+    #[test]
+    fn test_parse_array_task_ids() {
+        assert_eq!(parse_array_task_ids("4"), vec![4]);
+        assert_eq!(parse_array_task_ids("2-4"), vec![2, 3, 4]);
+        assert_eq!(parse_array_task_ids("1,3-4"), vec![1, 3, 4]);
+        assert_eq!(parse_array_task_ids("[0-1%2]"), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_parse_array_scontrol_records() {
+        let output = "JobId=124 ArrayJobId=123 ArrayTaskId=0 JobName=hq-alloc-1
+   JobState=RUNNING Reason=None Dependency=(null)
+   StartTime=2021-10-07T11:15:26 EndTime=2021-10-07T11:30:26 Deadline=N/A
+
+JobId=123 ArrayJobId=123 ArrayTaskId=1-2 JobName=hq-alloc-1
+   JobState=PENDING Reason=Priority Dependency=(null)
+   StartTime=Unknown EndTime=Unknown Deadline=N/A
+";
+        let records = parse_scontrol_records(output);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].0, vec!["123_0".to_string()]);
+        assert!(matches!(
+            records[0].1,
+            Ok(AllocationExternalStatus::Running)
+        ));
+        assert_eq!(records[1].0, vec!["123_1".to_string(), "123_2".to_string()]);
+        assert!(matches!(records[1].1, Ok(AllocationExternalStatus::Queued)));
+    }
+
+    #[test]
+    fn test_parse_single_job_scontrol_record() {
+        let output = "JobId=124 JobName=hq-alloc-1
+   JobState=PENDING Reason=Priority Dependency=(null)
+";
+        let records = parse_scontrol_records(output);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].0, vec!["124".to_string()]);
+    }
+}
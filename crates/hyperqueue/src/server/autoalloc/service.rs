@@ -11,11 +11,15 @@ use crate::common::rpc::{initiate_request, make_rpc_queue, ResponseToken, RpcSen
 use crate::common::serverdir::ServerDir;
 use crate::server::autoalloc::process::autoalloc_process;
 use crate::server::autoalloc::state::AutoAllocState;
-use crate::server::autoalloc::{Allocation, QueueId};
+use crate::server::autoalloc::{Allocation, ArchivedOutput, QueueId};
+use crate::server::rpc::Backend;
 use crate::server::state::StateRef;
 use crate::transfer::messages::{AllocationQueueParams, QueueData};
 use crate::JobId;
 
+/// Allocation together with its archived output, if its directory was already removed.
+pub type ArchivedAllocation = (Allocation, Option<ArchivedOutput>);
+
 #[derive(Debug)]
 pub enum AutoAllocMessage {
     // Events
@@ -44,6 +48,11 @@ pub enum AutoAllocMessage {
         response: ResponseToken<anyhow::Result<()>>,
     },
     GetAllocations(QueueId, ResponseToken<anyhow::Result<Vec<Allocation>>>),
+    GetAllocationHistory(
+        QueueId,
+        ResponseToken<anyhow::Result<Vec<ArchivedAllocation>>>,
+    ),
+    GetAllocation(String, ResponseToken<anyhow::Result<ArchivedAllocation>>),
 }
 
 pub struct AutoAllocService {
@@ -136,6 +145,28 @@ impl AutoAllocService {
         async move { fut.await.unwrap() }
     }
 
+    pub fn get_allocation_history(
+        &self,
+        id: QueueId,
+    ) -> impl Future<Output = anyhow::Result<Vec<ArchivedAllocation>>> {
+        let fut = initiate_request(|token| {
+            self.sender
+                .send(AutoAllocMessage::GetAllocationHistory(id, token))
+        });
+        async move { fut.await.unwrap() }
+    }
+
+    pub fn get_allocation(
+        &self,
+        allocation_id: String,
+    ) -> impl Future<Output = anyhow::Result<ArchivedAllocation>> {
+        let fut = initiate_request(|token| {
+            self.sender
+                .send(AutoAllocMessage::GetAllocation(allocation_id, token))
+        });
+        async move { fut.await.unwrap() }
+    }
+
     fn send(&self, msg: AutoAllocMessage) {
         let _ = self.sender.send(msg);
     }
@@ -143,10 +174,11 @@ impl AutoAllocService {
 
 pub fn create_autoalloc_service(
     state_ref: StateRef,
+    backend: Backend,
 ) -> (AutoAllocService, impl Future<Output = ()>) {
     let (tx, rx) = make_rpc_queue();
     let autoalloc = AutoAllocState::new();
-    let process = autoalloc_process(state_ref, autoalloc, rx);
+    let process = autoalloc_process(state_ref, backend, autoalloc, rx);
     let service = AutoAllocService { sender: tx };
     (service, process)
 }
@@ -11,7 +11,8 @@ use tako::WorkerId;
 use crate::common::idcounter::IdCounter;
 use crate::common::manager::info::ManagerType;
 use crate::common::utils::time::now_monotonic;
-use crate::server::autoalloc::config::MAX_KEPT_DIRECTORIES;
+use crate::server::autoalloc::config::max_kept_directories;
+use crate::server::autoalloc::logs::ArchivedOutput;
 use crate::server::autoalloc::queue::QueueHandler;
 use crate::server::autoalloc::QueueInfo;
 use crate::Map;
@@ -21,6 +22,8 @@ pub struct AutoAllocState {
     allocation_to_queue: Map<AllocationId, QueueId>,
     queues: Map<QueueId, AllocationQueue>,
     inactive_allocation_directories: VecDeque<PathBuf>,
+    /// Directories with archived output of allocations, the oldest ones are at the front
+    archived_output_directories: VecDeque<(QueueId, AllocationId, PathBuf)>,
     max_kept_directories: usize,
     queue_id_counter: IdCounter,
 }
@@ -31,7 +34,8 @@ impl AutoAllocState {
             allocation_to_queue: Default::default(),
             queues: Default::default(),
             inactive_allocation_directories: Default::default(),
-            max_kept_directories: MAX_KEPT_DIRECTORIES,
+            archived_output_directories: Default::default(),
+            max_kept_directories: max_kept_directories(),
             queue_id_counter: IdCounter::new(1),
         }
     }
@@ -84,6 +88,10 @@ impl AutoAllocState {
         self.queues.iter().map(|(k, v)| (*k, v))
     }
 
+    pub fn queues_mut(&mut self) -> impl Iterator<Item = (QueueId, &mut AllocationQueue)> {
+        self.queues.iter_mut().map(|(k, v)| (*k, v))
+    }
+
     pub fn add_inactive_directory(&mut self, directory: PathBuf) {
         self.inactive_allocation_directories.push_back(directory);
     }
@@ -98,6 +106,31 @@ impl AutoAllocState {
         to_remove
     }
 
+    pub fn add_archived_output_directory(
+        &mut self,
+        queue_id: QueueId,
+        allocation_id: AllocationId,
+        directory: PathBuf,
+    ) {
+        self.archived_output_directories
+            .push_back((queue_id, allocation_id, directory));
+    }
+
+    /// Returns directories with archived output of allocations that are scheduled for removal.
+    /// Only the last lines of the output of these allocations remain available.
+    pub fn get_archived_output_directories_for_removal(&mut self) -> Vec<PathBuf> {
+        let mut to_remove = Vec::new();
+        while self.archived_output_directories.len() > self.max_kept_directories {
+            let (queue_id, allocation_id, directory) =
+                self.archived_output_directories.pop_front().unwrap();
+            if let Some(queue) = self.queues.get_mut(&queue_id) {
+                queue.forget_archived_output_directory(&allocation_id);
+            }
+            to_remove.push(directory);
+        }
+        to_remove
+    }
+
     #[cfg(test)]
     pub fn set_max_kept_directories(&mut self, count: usize) {
         self.max_kept_directories = count;
@@ -131,6 +164,11 @@ pub struct AllocationQueue {
     name: Option<String>,
     handler: Box<dyn QueueHandler>,
     rate_limiter: RateLimiter,
+    /// Workers that belong to the minimal worker pool of this queue and thus should not be
+    /// stopped because of their idle timeout.
+    idle_exempt_workers: Set<WorkerId>,
+    /// Output of allocations whose directories were already removed.
+    archived_outputs: Map<AllocationId, ArchivedOutput>,
 }
 
 impl AllocationQueue {
@@ -149,6 +187,8 @@ impl AllocationQueue {
             handler,
             allocations: Default::default(),
             rate_limiter,
+            idle_exempt_workers: Default::default(),
+            archived_outputs: Default::default(),
         }
     }
 
@@ -203,10 +243,31 @@ impl AllocationQueue {
         self.allocations.get_mut(id)
     }
 
+    pub fn get_allocation(&self, id: &str) -> Option<&Allocation> {
+        self.allocations.get(id)
+    }
+
     pub fn all_allocations(&self) -> impl Iterator<Item = &Allocation> {
         self.allocations.values()
     }
 
+    /// Stores the output of an allocation whose directory is being removed.
+    pub fn archive_output(&mut self, id: AllocationId, output: ArchivedOutput) {
+        self.archived_outputs.insert(id, output);
+    }
+
+    /// Marks the full archived output of an allocation as removed, only its last lines are kept.
+    pub fn forget_archived_output_directory(&mut self, id: &str) {
+        if let Some(output) = self.archived_outputs.get_mut(id) {
+            output.directory = None;
+        }
+    }
+
+    /// Returns the archived output of an allocation, if its directory was already removed.
+    pub fn archived_output(&self, id: &str) -> Option<&ArchivedOutput> {
+        self.archived_outputs.get(id)
+    }
+
     pub fn queued_allocations(&self) -> impl Iterator<Item = &Allocation> {
         self.all_allocations()
             .filter(|alloc| matches!(alloc.status, AllocationState::Queued))
@@ -214,6 +275,78 @@ impl AllocationQueue {
     pub fn active_allocations(&self) -> impl Iterator<Item = &Allocation> {
         self.all_allocations().filter(|alloc| alloc.is_active())
     }
+
+    /// Recomputes which connected workers should be exempt from their idle timeout, so that
+    /// at least `min_workers` workers of this queue stay alive.
+    ///
+    /// Returns the workers whose exemption has changed, along with their new exemption status.
+    pub fn update_idle_exempt_workers(&mut self) -> Vec<(WorkerId, bool)> {
+        let mut connected: Vec<WorkerId> = self
+            .all_allocations()
+            .filter_map(|allocation| match &allocation.status {
+                AllocationState::Running {
+                    connected_workers, ..
+                } => Some(connected_workers.iter().copied()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        connected.sort_unstable();
+
+        let min_workers = self.info.min_workers() as usize;
+        // Keep workers that are already exempt, so that their exemption is not flipped needlessly
+        let mut exempt: Set<WorkerId> = connected
+            .iter()
+            .copied()
+            .filter(|worker_id| self.idle_exempt_workers.contains(worker_id))
+            .take(min_workers)
+            .collect();
+        for worker_id in &connected {
+            if exempt.len() >= min_workers {
+                break;
+            }
+            exempt.insert(*worker_id);
+        }
+
+        let mut changes: Vec<(WorkerId, bool)> = connected
+            .iter()
+            .filter_map(|worker_id| {
+                match (
+                    self.idle_exempt_workers.contains(worker_id),
+                    exempt.contains(worker_id),
+                ) {
+                    (false, true) => Some((*worker_id, true)),
+                    (true, false) => Some((*worker_id, false)),
+                    _ => None,
+                }
+            })
+            .collect();
+        changes.sort_unstable();
+        self.idle_exempt_workers = exempt;
+        changes
+    }
+
+    /// Returns the amount of node-hours consumed by allocations of this queue.
+    /// If the queue has a budget with a time window, only the node-hours consumed within the
+    /// window (ending at `now`) are taken into account.
+    pub fn consumed_node_hours(&self, now: SystemTime) -> f64 {
+        let since = self
+            .info
+            .budget()
+            .and_then(|budget| budget.window)
+            .and_then(|window| now.checked_sub(window));
+        self.all_allocations()
+            .map(|allocation| allocation.consumed_node_hours(since, now))
+            .sum()
+    }
+
+    /// Returns the amount of node-hours that remain in the budget of this queue, or `None` if
+    /// the queue does not have a budget.
+    pub fn remaining_budget(&self, now: SystemTime) -> Option<f64> {
+        self.info
+            .budget()
+            .map(|budget| (budget.limit - self.consumed_node_hours(now)).max(0.0))
+    }
 }
 
 // Allocation
@@ -251,6 +384,74 @@ impl Allocation {
     pub fn is_running(&self) -> bool {
         matches!(self.status, AllocationState::Running { .. })
     }
+
+    /// Returns IDs of all workers that have connected to this allocation.
+    pub fn worker_ids(&self) -> Vec<WorkerId> {
+        let mut workers: Vec<WorkerId> = match &self.status {
+            AllocationState::Queued => vec![],
+            AllocationState::Running {
+                connected_workers,
+                disconnected_workers,
+                ..
+            }
+            | AllocationState::Invalid {
+                connected_workers,
+                disconnected_workers,
+                ..
+            } => connected_workers
+                .iter()
+                .chain(disconnected_workers.keys())
+                .copied()
+                .collect(),
+            AllocationState::Finished {
+                disconnected_workers,
+                ..
+            } => disconnected_workers.keys().copied().collect(),
+        };
+        workers.sort_unstable();
+        workers
+    }
+
+    /// Returns the amount of node-hours consumed by this allocation since `since` (or since its
+    /// start, if `since` is `None`) until `now`.
+    pub fn consumed_node_hours(&self, since: Option<SystemTime>, now: SystemTime) -> f64 {
+        let (started_at, finished_at) = match self.status {
+            AllocationState::Queued => return 0.0,
+            AllocationState::Running { started_at, .. } => (started_at, now),
+            AllocationState::Finished {
+                started_at,
+                finished_at,
+                ..
+            } => (started_at, finished_at),
+            AllocationState::Invalid {
+                started_at: Some(started_at),
+                finished_at,
+                ..
+            } => (started_at, finished_at),
+            AllocationState::Invalid {
+                started_at: None, ..
+            } => return 0.0,
+        };
+        compute_node_hours(self.target_worker_count, started_at, finished_at, since)
+    }
+}
+
+/// Computes node-hours consumed by `worker_count` nodes between `started_at` and `finished_at`.
+/// If `since` is specified, only the time after `since` is taken into account.
+pub fn compute_node_hours(
+    worker_count: u64,
+    started_at: SystemTime,
+    finished_at: SystemTime,
+    since: Option<SystemTime>,
+) -> f64 {
+    let started_at = match since {
+        Some(since) => started_at.max(since),
+        None => started_at,
+    };
+    let duration = finished_at
+        .duration_since(started_at)
+        .unwrap_or(Duration::ZERO);
+    worker_count as f64 * duration.as_secs_f64() / 3600.0
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -412,11 +613,13 @@ mod tests {
     use crate::server::autoalloc::queue::{
         AllocationStatusMap, AllocationSubmissionResult, QueueHandler, SubmitMode,
     };
-    use crate::server::autoalloc::state::{AllocationQueue, AutoAllocState, RateLimiter};
+    use crate::server::autoalloc::state::{
+        compute_node_hours, AllocationQueue, AutoAllocState, RateLimiter,
+    };
     use crate::server::autoalloc::{Allocation, AutoAllocResult, QueueId, QueueInfo};
     use std::future::Future;
     use std::pin::Pin;
-    use std::time::Duration;
+    use std::time::{Duration, SystemTime};
     use tako::worker::ServerLostPolicy;
 
     struct NullHandler;
@@ -426,6 +629,7 @@ mod tests {
             _queue_id: QueueId,
             _queue_info: &QueueInfo,
             _worker_count: u64,
+            _allocation_count: u64,
             _mode: SubmitMode,
         ) -> Pin<Box<dyn Future<Output = AutoAllocResult<AllocationSubmissionResult>>>> {
             unreachable!()
@@ -461,7 +665,12 @@ mod tests {
                 None,
                 vec![],
                 None,
+                0,
                 None,
+                None,
+                None,
+                false,
+                false,
             ),
             None,
             Box::new(NullHandler),
@@ -473,4 +682,15 @@ mod tests {
         state.remove_queue(id);
         assert_eq!(state.allocation_to_queue.len(), 0);
     }
+
+    #[test]
+    fn compute_node_hours_in_window() {
+        let hour = Duration::from_secs(60 * 60);
+        let start = SystemTime::UNIX_EPOCH + hour * 10;
+        let end = start + hour * 3;
+
+        assert_eq!(compute_node_hours(2, start, end, None), 6.0);
+        assert_eq!(compute_node_hours(2, start, end, Some(start + hour)), 4.0);
+        assert_eq!(compute_node_hours(2, start, end, Some(end + hour)), 0.0);
+    }
 }
@@ -1,10 +1,11 @@
 use std::future::Future;
-use std::net::{Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 
 use anyhow::{anyhow, Context};
+use futures::future::Either;
 use tokio::net::TcpListener;
 use tokio::sync::Notify;
 use tokio::task::LocalSet;
@@ -12,11 +13,14 @@ use tokio::task::LocalSet;
 use crate::client::globalsettings::GlobalSettings;
 use crate::common::serverdir::{default_server_directory, AccessRecord, ServerDir, SYMLINK_PATH};
 use crate::server::autoalloc::create_autoalloc_service;
+use crate::server::event::hooks::{start_hook_runner, EventHook, EventHooks, HookRunnerConfig};
 use crate::server::event::log::start_event_streaming;
-use crate::server::event::log::EventLogWriter;
+use crate::server::event::log::{EventLogRotation, EventLogWriter};
 use crate::server::event::storage::EventStorage;
+use crate::server::metrics::serve_metrics;
 use crate::server::rpc::Backend;
 use crate::server::state::StateRef;
+use crate::server::stats_history::stats_history_process;
 use crate::transfer::auth::generate_key;
 use crate::transfer::connection::ClientSession;
 use rand::distributions::Alphanumeric;
@@ -35,6 +39,11 @@ pub struct ServerConfig {
     pub worker_port: Option<u16>,
     pub event_buffer_size: usize,
     pub event_log_path: Option<PathBuf>,
+    pub event_log_rotation: EventLogRotation,
+    pub metrics_host: IpAddr,
+    pub metrics_port: Option<u16>,
+    pub hooks: Vec<EventHook>,
+    pub hook_runner: HookRunnerConfig,
 }
 
 /// This function initializes the HQ server.
@@ -127,15 +136,23 @@ pub async fn initialize_server(
     .with_context(|| "Cannot create HQ server socket".to_string())?;
     let server_port = client_listener.local_addr()?.port();
 
+    let metrics_listener = match server_cfg.metrics_port {
+        Some(port) => {
+            let listener = TcpListener::bind(SocketAddr::new(server_cfg.metrics_host, port))
+                .await
+                .with_context(|| format!("Cannot create metrics socket at port {port}"))?;
+            log::info!("Serving metrics at {}", listener.local_addr()?);
+            Some(listener)
+        }
+        None => None,
+    };
+
     let hq_secret_key = Arc::new(generate_key());
     let tako_secret_key = Arc::new(generate_key());
     let server_uid = generate_server_uid();
 
     let (event_storage, event_stream_fut) = prepare_event_management(&server_cfg).await?;
     let state_ref = StateRef::new(event_storage, server_uid.clone());
-    let (autoalloc_service, autoalloc_process) = create_autoalloc_service(state_ref.clone());
-    // TODO: remove this hack
-    state_ref.get_mut().autoalloc_service = Some(autoalloc_service);
 
     let (tako_server, tako_future) = Backend::start(
         state_ref.clone(),
@@ -145,6 +162,11 @@ pub async fn initialize_server(
     )
     .await?;
 
+    let (autoalloc_service, autoalloc_process) =
+        create_autoalloc_service(state_ref.clone(), tako_server.clone());
+    // TODO: remove this hack
+    state_ref.get_mut().autoalloc_service = Some(autoalloc_service);
+
     let record = AccessRecord::new(
         server_cfg.host,
         server_uid,
@@ -175,6 +197,17 @@ pub async fn initialize_server(
         log::info!("Stopping server");
     };
 
+    let metrics_fut = match metrics_listener {
+        Some(listener) => Either::Left(serve_metrics(
+            listener,
+            state_ref.clone(),
+            tako_server.clone(),
+        )),
+        None => Either::Right(futures::future::pending()),
+    };
+
+    let stats_history_fut = stats_history_process(state_ref.clone(), tako_server.clone());
+
     let key = hq_secret_key;
     let fut = async move {
         tokio::pin! {
@@ -194,6 +227,8 @@ pub async fn initialize_server(
                 key
             ) => { Ok(()) }
             _ = &mut autoalloc_process => { Ok(()) }
+            _ = metrics_fut => { Ok(()) }
+            _ = stats_history_fut => { Ok(()) }
             r = tako_future => { r.map_err(|e| e.into()) }
         };
 
@@ -216,25 +251,37 @@ pub async fn initialize_server(
 async fn prepare_event_management(
     server_cfg: &ServerConfig,
 ) -> anyhow::Result<(EventStorage, Pin<Box<dyn Future<Output = ()>>>)> {
-    Ok(if let Some(ref log_path) = server_cfg.event_log_path {
-        let writer = EventLogWriter::create(log_path).await.map_err(|error| {
-            anyhow!(
-                "Cannot create event log file at `{}`: {error:?}",
-                log_path.display()
-            )
-        })?;
+    let (stream_sender, stream_fut): (_, Pin<Box<dyn Future<Output = ()>>>) =
+        if let Some(ref log_path) = server_cfg.event_log_path {
+            let writer = if server_cfg.event_log_rotation.is_enabled() {
+                EventLogWriter::create_rotating(log_path, server_cfg.event_log_rotation.clone())
+                    .await
+            } else {
+                EventLogWriter::create(log_path).await
+            };
+            let writer = writer.map_err(|error| {
+                anyhow!(
+                    "Cannot create event log file at `{}`: {error:?}",
+                    log_path.display()
+                )
+            })?;
+
+            let (tx, stream_fut) = start_event_streaming(writer);
+            (Some(tx), Box::pin(stream_fut))
+        } else {
+            (None, Box::pin(futures::future::ready(())))
+        };
 
-        let (tx, stream_fut) = start_event_streaming(writer);
-        (
-            EventStorage::new(server_cfg.event_buffer_size, Some(tx)),
-            Box::pin(stream_fut),
-        )
-    } else {
-        (
-            EventStorage::new(server_cfg.event_buffer_size, None),
-            Box::pin(futures::future::ready(())),
-        )
-    })
+    // Hooks can also be added later by individual jobs, so the runner is always started
+    let (hook_sender, hook_fut) = start_hook_runner(server_cfg.hook_runner.clone());
+    let hooks = EventHooks::new(server_cfg.hooks.clone(), hook_sender);
+
+    Ok((
+        EventStorage::new(server_cfg.event_buffer_size, stream_sender, Some(hooks)),
+        Box::pin(async move {
+            futures::future::join(stream_fut, hook_fut).await;
+        }),
+    ))
 }
 
 async fn start_server(
@@ -263,6 +310,8 @@ pub async fn print_server_info(gsettings: &GlobalSettings) -> anyhow::Result<()>
 
 #[cfg(test)]
 mod tests {
+    use std::net::Ipv4Addr;
+
     use tempdir::TempDir;
     use tokio::sync::Notify;
 
@@ -270,6 +319,7 @@ mod tests {
     use crate::server::bootstrap::{
         get_client_session, get_server_status, initialize_server, ServerConfig,
     };
+    use crate::server::event::hooks::HookRunnerConfig;
 
     use super::ServerStatus;
     use crate::client::globalsettings::GlobalSettings;
@@ -280,6 +330,7 @@ mod tests {
     use std::future::Future;
     use std::path::Path;
     use std::sync::Arc;
+    use std::time::Duration;
 
     pub async fn init_test_server(
         tmp_dir: &Path,
@@ -296,6 +347,14 @@ mod tests {
             worker_port: None,
             event_buffer_size: 1_000_000,
             event_log_path: None,
+            event_log_rotation: Default::default(),
+            metrics_host: Ipv4Addr::LOCALHOST.into(),
+            metrics_port: None,
+            hooks: vec![],
+            hook_runner: HookRunnerConfig {
+                timeout: Duration::from_secs(60),
+                max_concurrent: 1,
+            },
         };
         initialize_server(&gsettings, server_cfg).await.unwrap()
     }
@@ -1,8 +1,12 @@
 use crate::common::serverdir::ServerDir;
-use crate::server::autoalloc::try_submit_allocation;
+use crate::server::autoalloc::{
+    read_allocation_output, read_allocation_output_tail, try_submit_allocation, ArchivedOutput,
+    ALLOCATION_OUTPUT_TAIL_LINES,
+};
 use crate::server::state::StateRef;
 use crate::transfer::messages::{
-    AutoAllocListResponse, AutoAllocRequest, AutoAllocResponse, ToClientMessage,
+    AllocationHistoryEntry, AllocationLog, AutoAllocListResponse, AutoAllocRequest,
+    AutoAllocResponse, ToClientMessage,
 };
 
 pub async fn handle_autoalloc_message(
@@ -85,5 +89,55 @@ pub async fn handle_autoalloc_message(
                 Err(error) => ToClientMessage::Error(error.to_string()),
             }
         }
+        AutoAllocRequest::History { queue_id } => {
+            let result = state_ref.get().autoalloc().get_allocation_history(queue_id);
+            match result.await {
+                Ok(allocations) => {
+                    let entries = allocations
+                        .into_iter()
+                        .map(|(allocation, archived_output)| {
+                            let output_tail = archived_output
+                                .map(|archived| archived.tail)
+                                .unwrap_or_else(|| {
+                                    read_allocation_output_tail(
+                                        &allocation.working_dir,
+                                        ALLOCATION_OUTPUT_TAIL_LINES,
+                                    )
+                                });
+                            AllocationHistoryEntry {
+                                allocation,
+                                output_tail,
+                            }
+                        })
+                        .collect();
+                    ToClientMessage::AutoAllocResponse(AutoAllocResponse::History(entries))
+                }
+                Err(error) => ToClientMessage::Error(error.to_string()),
+            }
+        }
+        AutoAllocRequest::Log { allocation_id } => {
+            let result = state_ref.get().autoalloc().get_allocation(allocation_id);
+            match result.await {
+                Ok((allocation, archived_output)) => {
+                    let (output, truncated) = match archived_output {
+                        Some(ArchivedOutput {
+                            directory: Some(directory),
+                            ..
+                        }) => (read_allocation_output(&directory).await, false),
+                        Some(ArchivedOutput {
+                            tail,
+                            directory: None,
+                        }) => (tail, true),
+                        None => (read_allocation_output(&allocation.working_dir).await, false),
+                    };
+                    ToClientMessage::AutoAllocResponse(AutoAllocResponse::Log(AllocationLog {
+                        allocation,
+                        output,
+                        truncated,
+                    }))
+                }
+                Err(error) => ToClientMessage::Error(error.to_string()),
+            }
+        }
     }
 }
@@ -1,4 +1,7 @@
 use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
 
 use futures::{Sink, SinkExt, Stream, StreamExt};
 use orion::kdf::SecretKey;
@@ -12,14 +15,18 @@ use crate::client::status::{job_status, Status};
 use crate::common::serverdir::ServerDir;
 use crate::server::event::MonitoringEvent;
 use crate::server::job::JobTaskCounters;
+use crate::server::job_stats::compute_job_stats_from_job;
 use crate::server::rpc::Backend;
 use crate::server::state::{State, StateRef};
+use crate::server::stats_history::STATS_SAMPLE_INTERVAL;
 use crate::stream::server::control::StreamServerControlMessage;
 use crate::transfer::connection::ServerConnection;
 use crate::transfer::messages::WaitForJobsResponse;
 use crate::transfer::messages::{
-    CancelJobResponse, FromClientMessage, IdSelector, JobDetail, JobInfoResponse, StatsResponse,
-    StopWorkerResponse, TaskSelector, ToClientMessage, WorkerListResponse,
+    CancelJobResponse, FromClientMessage, IdSelector, JobDetail, JobInfoResponse,
+    StatsHistoryResponse, StatsResponse, StopWorkerResponse, StreamEventsRequest,
+    TaskOutputRequest, TaskSelector, ToClientMessage, WorkerListResponse, WorkerRpcRequest,
+    WorkerRpcResponse,
 };
 use crate::{JobId, JobTaskCount, WorkerId};
 
@@ -69,6 +76,52 @@ async fn handle_client(
     Ok(())
 }
 
+/// Sends events to the client until it disconnects.
+async fn stream_events<
+    Tx: Sink<ToClientMessage> + Unpin,
+    Rx: Stream<Item = crate::Result<FromClientMessage>> + Unpin,
+>(
+    state_ref: &StateRef,
+    tx: &mut Tx,
+    rx: &mut Rx,
+    request: StreamEventsRequest,
+) {
+    let (stored_events, mut receiver) = state_ref.get_mut().subscribe_events(request);
+    for event in stored_events {
+        if tx.send(ToClientMessage::Event(event)).await.is_err() {
+            log::debug!("Event subscriber has disconnected");
+            return;
+        }
+    }
+    loop {
+        tokio::select! {
+            event = receiver.recv() => {
+                match event {
+                    Some(event) => {
+                        if tx.send(ToClientMessage::Event(event)).await.is_err() {
+                            break;
+                        }
+                    }
+                    None => {
+                        // The server has dropped the subscription, because the client has
+                        // fallen too far behind
+                        let error = "Client did not receive events fast enough".to_string();
+                        let _ = tx.send(ToClientMessage::Error(error)).await;
+                        break;
+                    }
+                }
+            }
+            message = rx.next() => {
+                match message {
+                    Some(_) => log::warn!("Ignoring client message received during event streaming"),
+                    None => break,
+                }
+            }
+        }
+    }
+    log::debug!("Event subscriber has disconnected");
+}
+
 pub async fn client_rpc_loop<
     Tx: Sink<ToClientMessage> + Unpin,
     Rx: Stream<Item = crate::Result<FromClientMessage>> + Unpin,
@@ -88,6 +141,9 @@ pub async fn client_rpc_loop<
                         submit::handle_submit(&state_ref, &tako_ref, msg).await
                     }
                     FromClientMessage::JobInfo(msg) => compute_job_info(&state_ref, &msg.selector),
+                    FromClientMessage::JobStats(msg) => {
+                        compute_job_stats(&state_ref, &msg.selector)
+                    }
                     FromClientMessage::Resubmit(msg) => {
                         submit::handle_resubmit(&state_ref, &tako_ref, msg).await
                     }
@@ -109,6 +165,9 @@ pub async fn client_rpc_loop<
                         compute_job_detail(&state_ref, msg.job_id_selector, msg.task_selector)
                     }
                     FromClientMessage::Stats => compose_server_stats(&state_ref, &tako_ref).await,
+                    FromClientMessage::StatsHistory(msg) => {
+                        compose_stats_history(&state_ref, msg.window)
+                    }
                     FromClientMessage::AutoAlloc(msg) => {
                         autoalloc::handle_autoalloc_message(&server_dir, &state_ref, msg).await
                     }
@@ -124,6 +183,13 @@ pub async fn client_rpc_loop<
                             .collect();
                         ToClientMessage::MonitoringEventsResponse(events)
                     }
+                    FromClientMessage::StreamEvents(request) => {
+                        stream_events(&state_ref, &mut tx, &mut rx, request).await;
+                        break;
+                    }
+                    FromClientMessage::TaskOutput(request) => {
+                        handle_task_output(&state_ref, &tako_ref, request).await
+                    }
                 };
                 assert!(tx.send(response).await.is_ok());
             }
@@ -238,6 +304,10 @@ async fn handle_worker_stop(
             responses.push((worker_id, StopWorkerResponse::InvalidWorker));
             continue;
         }
+        state_ref
+            .get_mut()
+            .event_storage_mut()
+            .on_worker_stop_requested(worker_id);
         let response = tako_ref
             .clone()
             .send_tako_message(FromGatewayMessage::StopWorker(StopWorkerRequest {
@@ -299,6 +369,21 @@ fn get_job_ids(state: &State, selector: &IdSelector) -> Vec<JobId> {
     }
 }
 
+fn compute_job_stats(state_ref: &StateRef, selector: &IdSelector) -> ToClientMessage {
+    let state = state_ref.get();
+    let now = Utc::now();
+    let response = get_job_ids(&state, selector)
+        .into_iter()
+        .map(|job_id| {
+            let stats = state
+                .get_job(job_id)
+                .map(|job| compute_job_stats_from_job(job, now));
+            (job_id, stats)
+        })
+        .collect();
+    ToClientMessage::JobStatsResponse(response)
+}
+
 async fn compose_server_stats(_state_ref: &StateRef, backend: &Backend) -> ToClientMessage {
     let stream_stats = {
         let (sender, receiver) = oneshot::channel();
@@ -308,6 +393,16 @@ async fn compose_server_stats(_state_ref: &StateRef, backend: &Backend) -> ToCli
     ToClientMessage::StatsResponse(StatsResponse { stream_stats })
 }
 
+fn compose_stats_history(state_ref: &StateRef, window: Duration) -> ToClientMessage {
+    ToClientMessage::StatsHistoryResponse(StatsHistoryResponse {
+        interval: STATS_SAMPLE_INTERVAL,
+        samples: state_ref
+            .get()
+            .stats_history()
+            .samples_within(Utc::now(), window),
+    })
+}
+
 fn compute_job_info(state_ref: &StateRef, selector: &IdSelector) -> ToClientMessage {
     let state = state_ref.get();
 
@@ -380,11 +475,11 @@ async fn handle_job_cancel(
         };
 
         let mut state = state_ref.get_mut();
-        let job = state.get_job_mut(job_id).unwrap();
-        let canceled_ids: Vec<_> = canceled_tasks
-            .iter()
-            .map(|tako_id| job.set_cancel_state(*tako_id, tako_ref))
-            .collect();
+        if !canceled_tasks.is_empty() {
+            state.event_storage_mut().on_job_canceled(job_id);
+        }
+        let canceled_ids = state.set_tasks_canceled(job_id, &canceled_tasks, tako_ref);
+        let job = state.get_job(job_id).unwrap();
         let already_finished = job.n_tasks() - canceled_ids.len() as JobTaskCount;
         responses.push((
             job_id,
@@ -407,6 +502,47 @@ async fn handle_worker_list(state_ref: &StateRef) -> ToClientMessage {
     })
 }
 
+/// Fetches a part of the spooled output of a task from the worker that has executed it.
+async fn handle_task_output(
+    state_ref: &StateRef,
+    tako_ref: &Backend,
+    request: TaskOutputRequest,
+) -> ToClientMessage {
+    let worker_id = {
+        let state = state_ref.get();
+        let job = match state.get_job(request.job_id) {
+            Some(job) => job,
+            None => return ToClientMessage::Error(format!("Job {} not found", request.job_id)),
+        };
+        let worker_id = job
+            .iter_task_states()
+            .find(|(_, task_id, _)| *task_id == request.task_id)
+            .map(|(_, _, state)| state.get_workers().and_then(|workers| workers.first()));
+        match worker_id {
+            Some(Some(worker_id)) => *worker_id,
+            Some(None) => {
+                return ToClientMessage::Error(format!(
+                    "Task {} has not been started yet",
+                    request.task_id
+                ))
+            }
+            None => return ToClientMessage::Error(format!("Task {} not found", request.task_id)),
+        }
+    };
+
+    match tako_ref
+        .send_worker_request(worker_id, &WorkerRpcRequest::TaskOutput(request))
+        .await
+    {
+        Ok(WorkerRpcResponse::TaskOutput(response)) => {
+            ToClientMessage::TaskOutputResponse(response)
+        }
+        Err(error) => ToClientMessage::Error(format!(
+            "Cannot fetch output from worker {worker_id}: {error}"
+        )),
+    }
+}
+
 async fn handle_worker_info(state_ref: &StateRef, worker_id: WorkerId) -> ToClientMessage {
     let state = state_ref.get();
 
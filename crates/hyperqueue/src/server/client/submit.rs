@@ -21,6 +21,7 @@ use crate::common::placeholders::{
 use crate::server::job::Job;
 use crate::server::rpc::Backend;
 use crate::server::state::{State, StateRef};
+use crate::stream::compression::LogCompression;
 use crate::stream::server::control::StreamServerControlMessage;
 use crate::transfer::messages::{
     JobDescription, ResubmitRequest, SubmitRequest, SubmitResponse, TaskBody, TaskDescription,
@@ -47,6 +48,8 @@ pub async fn handle_submit(
         max_fails,
         submit_dir,
         log,
+        log_compression,
+        on_finish,
     } = message;
 
     let job_ctx = JobContext {
@@ -81,6 +84,7 @@ pub async fn handle_submit(
         name,
         max_fails,
         log.clone(),
+        on_finish,
         submit_dir,
     );
     let job_detail = job.make_job_detail(Some(&TaskSelector {
@@ -90,7 +94,7 @@ pub async fn handle_submit(
     state_ref.get_mut().add_job(job);
 
     if let Some(log) = log {
-        start_log_streaming(tako_ref, job_id, log).await;
+        start_log_streaming(tako_ref, job_id, log, log_compression).await;
     }
 
     match tako_ref
@@ -194,6 +198,8 @@ pub async fn handle_resubmit(
                 max_fails: job.max_fails,
                 submit_dir: std::env::current_dir().expect("Cannot get current working directory"),
                 log: None, // TODO: Reuse log configuration
+                log_compression: Default::default(),
+                on_finish: job.on_finish.clone(),
             }
         } else {
             return ToClientMessage::Error("Invalid job_id".to_string());
@@ -202,11 +208,17 @@ pub async fn handle_resubmit(
     handle_submit(state_ref, tako_ref, msg_submit).await
 }
 
-async fn start_log_streaming(tako_ref: &Backend, job_id: JobId, path: PathBuf) {
+async fn start_log_streaming(
+    tako_ref: &Backend,
+    job_id: JobId,
+    path: PathBuf,
+    compression: LogCompression,
+) {
     let (sender, receiver) = oneshot::channel();
     tako_ref.send_stream_control(StreamServerControlMessage::RegisterStream {
         job_id,
         path,
+        compression,
         response: sender,
     });
     assert!(receiver.await.is_ok());
@@ -245,6 +257,8 @@ fn serialize_task_body(
         task_dir: task_desc.task_dir,
         job_id: ctx.job_id,
         task_id,
+        output_limit: task_desc.output_limit,
+        spool: task_desc.spool,
     };
     let body = tako::comm::serialize(&body_msg).expect("Could not serialize task body");
     // Make sure that `into_boxed_slice` is a no-op.
@@ -508,6 +522,8 @@ mod tests {
             time_limit,
             priority,
             crash_limit: 5,
+            output_limit: None,
+            spool: false,
         }
     }
 
@@ -3,13 +3,15 @@ use crate::server::autoalloc::QueueId;
 use crate::transfer::messages::AllocationQueueParams;
 use crate::transfer::messages::JobDescription;
 use crate::WorkerId;
-use crate::{JobId, JobTaskCount, TakoTaskId};
+use crate::{JobId, JobTaskCount, JobTaskId, TakoTaskId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tako::gateway::LostWorkerReason;
 use tako::static_assert_size;
+use tako::task::TaskResourceUsage;
 use tako::worker::{WorkerConfiguration, WorkerOverview};
+use tako::InstanceId;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum MonitoringEventPayload {
@@ -17,25 +19,51 @@ pub enum MonitoringEventPayload {
     WorkerConnected(WorkerId, Box<WorkerConfiguration>),
     /// Worker has disconnected from the server
     WorkerLost(WorkerId, LostWorkerReason),
+    /// A user has requested the worker to stop
+    WorkerStopRequested(WorkerId),
     /// Worker has proactively send its overview (task status and HW utilization report) to the server
-    WorkerOverviewReceived(WorkerOverview),
+    WorkerOverviewReceived(Box<WorkerOverview>),
     /// A Job was submitted by the user.
     JobCreated(JobId, Box<JobInfo>),
     /// All tasks of the job have finished.
     JobCompleted(JobId, DateTime<Utc>),
+    /// A user has canceled the job
+    JobCanceled(JobId),
     /// Task has started to execute on some worker
     TaskStarted {
         task_id: TakoTaskId,
+        instance_id: InstanceId,
         worker_id: WorkerId,
     },
     /// Task has been finished
-    TaskFinished(TakoTaskId),
+    TaskFinished {
+        task_id: TakoTaskId,
+        resource_usage: Option<Box<TaskResourceUsage>>,
+    },
     // Task that failed to execute
-    TaskFailed(TakoTaskId),
+    TaskFailed {
+        task: TaskIds,
+        instance_id: InstanceId,
+        error: String,
+        resource_usage: Option<Box<TaskResourceUsage>>,
+    },
+    /// Task was canceled before it has finished
+    TaskCanceled(TaskIds),
+    /// Task was running on a worker that was lost, it will be executed again
+    TaskRestarted {
+        task: TaskIds,
+        /// Instance that was running on the lost worker
+        instance_id: InstanceId,
+        worker_id: WorkerId,
+    },
     /// New allocation queue has been created
     AllocationQueueCreated(QueueId, Box<AllocationQueueParams>),
     /// Allocation queue has been removed
     AllocationQueueRemoved(QueueId),
+    /// Allocation queue has been paused, either by a user or automatically
+    AllocationQueuePaused { queue_id: QueueId, reason: String },
+    /// Allocation queue has been resumed by a user
+    AllocationQueueResumed(QueueId),
     /// Allocation was submitted into PBS/Slurm
     AllocationQueued {
         queue_id: QueueId,
@@ -48,6 +76,64 @@ pub enum MonitoringEventPayload {
     AllocationFinished(QueueId, AllocationId),
 }
 
+/// Names of all event types, as returned by [`MonitoringEventPayload::type_name`].
+pub const EVENT_TYPES: &[&str] = &[
+    "worker-connected",
+    "worker-lost",
+    "worker-stop-requested",
+    "worker-overview",
+    "job-created",
+    "job-completed",
+    "job-canceled",
+    "task-started",
+    "task-finished",
+    "task-failed",
+    "task-canceled",
+    "task-restarted",
+    "autoalloc-queue-created",
+    "autoalloc-queue-removed",
+    "autoalloc-queue-paused",
+    "autoalloc-queue-resumed",
+    "autoalloc-allocation-queued",
+    "autoalloc-allocation-started",
+    "autoalloc-allocation-finished",
+];
+
+impl MonitoringEventPayload {
+    /// Name of the event type, used e.g. in exported events and in event filters.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            MonitoringEventPayload::WorkerConnected(..) => "worker-connected",
+            MonitoringEventPayload::WorkerLost(..) => "worker-lost",
+            MonitoringEventPayload::WorkerStopRequested(_) => "worker-stop-requested",
+            MonitoringEventPayload::WorkerOverviewReceived(_) => "worker-overview",
+            MonitoringEventPayload::JobCreated(..) => "job-created",
+            MonitoringEventPayload::JobCompleted(..) => "job-completed",
+            MonitoringEventPayload::JobCanceled(_) => "job-canceled",
+            MonitoringEventPayload::TaskStarted { .. } => "task-started",
+            MonitoringEventPayload::TaskFinished { .. } => "task-finished",
+            MonitoringEventPayload::TaskFailed { .. } => "task-failed",
+            MonitoringEventPayload::TaskCanceled(_) => "task-canceled",
+            MonitoringEventPayload::TaskRestarted { .. } => "task-restarted",
+            MonitoringEventPayload::AllocationQueueCreated(..) => "autoalloc-queue-created",
+            MonitoringEventPayload::AllocationQueueRemoved(_) => "autoalloc-queue-removed",
+            MonitoringEventPayload::AllocationQueuePaused { .. } => "autoalloc-queue-paused",
+            MonitoringEventPayload::AllocationQueueResumed(_) => "autoalloc-queue-resumed",
+            MonitoringEventPayload::AllocationQueued { .. } => "autoalloc-allocation-queued",
+            MonitoringEventPayload::AllocationStarted(..) => "autoalloc-allocation-started",
+            MonitoringEventPayload::AllocationFinished(..) => "autoalloc-allocation-finished",
+        }
+    }
+}
+
+/// Identifies a task both by its server-wide ID and by its ID within a job.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct TaskIds {
+    pub task_id: TakoTaskId,
+    pub job_id: JobId,
+    pub job_task_id: JobTaskId,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct JobInfo {
     pub name: String,
@@ -62,4 +148,4 @@ pub struct JobInfo {
 }
 
 // Keep the size of the event structure in check
-static_assert_size!(MonitoringEventPayload, 136);
+static_assert_size!(MonitoringEventPayload, 48);
@@ -0,0 +1,262 @@
+use crate::server::event::events::MonitoringEventPayload;
+use crate::transfer::messages::EventFilter;
+use crate::{JobId, JobTaskCount, Map, Set, TakoTaskId};
+
+/// Decides which events pass an [`EventFilter`].
+///
+/// Some events (e.g. task started) do not contain the job ID directly, so the matcher has to see
+/// all events in order (including those that do not pass the filter) to be able to assign them
+/// to the filtered jobs and workers.
+pub struct EventMatcher {
+    filter: EventFilter,
+    /// Tako task IDs of the filtered jobs (first task ID and task count)
+    job_tasks: Map<JobId, (TakoTaskId, JobTaskCount)>,
+    /// Tasks that have been started on one of the filtered workers
+    worker_tasks: Set<TakoTaskId>,
+}
+
+impl EventMatcher {
+    pub fn new(filter: EventFilter) -> Self {
+        Self {
+            filter,
+            job_tasks: Default::default(),
+            worker_tasks: Default::default(),
+        }
+    }
+
+    /// Registers tasks of a job whose creation event will not be passed to the matcher.
+    pub fn add_job_tasks(
+        &mut self,
+        job_id: JobId,
+        base_task_id: TakoTaskId,
+        n_tasks: JobTaskCount,
+    ) {
+        if self.filter.job_ids.contains(&job_id) {
+            self.job_tasks.insert(job_id, (base_task_id, n_tasks));
+        }
+    }
+
+    pub fn matches(&mut self, payload: &MonitoringEventPayload) -> bool {
+        // Job and worker matching has to be performed even if the event type is filtered out,
+        // because it tracks which tasks belong to the filtered jobs and workers.
+        let job_matches = self.filter.job_ids.is_empty() || self.matches_job(payload);
+        let worker_matches = self.filter.worker_ids.is_empty() || self.matches_worker(payload);
+        let type_matches = self.filter.event_types.is_empty()
+            || self
+                .filter
+                .event_types
+                .iter()
+                .any(|name| name == payload.type_name());
+        job_matches && worker_matches && type_matches
+    }
+
+    fn matches_job(&mut self, payload: &MonitoringEventPayload) -> bool {
+        match payload {
+            MonitoringEventPayload::JobCreated(job_id, info) => {
+                self.add_job_tasks(
+                    *job_id,
+                    info.base_task_id,
+                    info.task_ids.len() as JobTaskCount,
+                );
+                self.filter.job_ids.contains(job_id)
+            }
+            MonitoringEventPayload::JobCompleted(job_id, _)
+            | MonitoringEventPayload::JobCanceled(job_id) => self.filter.job_ids.contains(job_id),
+            MonitoringEventPayload::TaskFailed { task, .. }
+            | MonitoringEventPayload::TaskCanceled(task)
+            | MonitoringEventPayload::TaskRestarted { task, .. } => {
+                self.filter.job_ids.contains(&task.job_id)
+            }
+            MonitoringEventPayload::TaskStarted { task_id, .. }
+            | MonitoringEventPayload::TaskFinished { task_id, .. } => {
+                let task_id = task_id.as_num();
+                self.job_tasks.values().any(|(base_task_id, n_tasks)| {
+                    let base_task_id = base_task_id.as_num();
+                    task_id >= base_task_id && task_id < base_task_id + *n_tasks
+                })
+            }
+            _ => false,
+        }
+    }
+
+    fn matches_worker(&mut self, payload: &MonitoringEventPayload) -> bool {
+        let worker_ids = &self.filter.worker_ids;
+        match payload {
+            MonitoringEventPayload::WorkerConnected(worker_id, _)
+            | MonitoringEventPayload::WorkerLost(worker_id, _)
+            | MonitoringEventPayload::WorkerStopRequested(worker_id) => {
+                worker_ids.contains(worker_id)
+            }
+            MonitoringEventPayload::WorkerOverviewReceived(overview) => {
+                worker_ids.contains(&overview.id)
+            }
+            MonitoringEventPayload::TaskStarted {
+                task_id, worker_id, ..
+            } if worker_ids.contains(worker_id) => {
+                self.worker_tasks.insert(*task_id);
+                true
+            }
+            MonitoringEventPayload::TaskRestarted {
+                task, worker_id, ..
+            } => {
+                self.worker_tasks.remove(&task.task_id);
+                worker_ids.contains(worker_id)
+            }
+            MonitoringEventPayload::TaskFinished { task_id, .. } => {
+                self.worker_tasks.remove(task_id)
+            }
+            MonitoringEventPayload::TaskFailed { task, .. }
+            | MonitoringEventPayload::TaskCanceled(task) => self.worker_tasks.remove(&task.task_id),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::time::SystemTime;
+
+    use crate::server::event::events::{JobInfo, MonitoringEventPayload};
+    use crate::server::event::filter::EventMatcher;
+    use crate::server::event::MonitoringEvent;
+    use crate::transfer::messages::{EventFilter, JobDescription};
+
+    pub fn event(payload: MonitoringEventPayload) -> MonitoringEvent {
+        MonitoringEvent {
+            id: 0,
+            time: SystemTime::now(),
+            payload,
+        }
+    }
+
+    pub fn task_started(task_id: u32, worker_id: u32) -> MonitoringEvent {
+        event(MonitoringEventPayload::TaskStarted {
+            task_id: task_id.into(),
+            instance_id: 0.into(),
+            worker_id: worker_id.into(),
+        })
+    }
+
+    pub fn task_finished(task_id: u32) -> MonitoringEvent {
+        event(MonitoringEventPayload::TaskFinished {
+            task_id: task_id.into(),
+            resource_usage: None,
+        })
+    }
+
+    fn count_matching(matcher: &mut EventMatcher, events: &[MonitoringEvent]) -> usize {
+        events
+            .iter()
+            .filter(|event| matcher.matches(&event.payload))
+            .count()
+    }
+
+    #[test]
+    fn filter_by_type() {
+        let mut matcher = EventMatcher::new(EventFilter {
+            event_types: vec!["task-finished".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            count_matching(&mut matcher, &[task_started(1, 1), task_finished(1)]),
+            1
+        );
+    }
+
+    #[test]
+    fn filter_by_job() {
+        let mut matcher = EventMatcher::new(EventFilter {
+            job_ids: vec![2.into()],
+            ..Default::default()
+        });
+        matcher.add_job_tasks(1.into(), 0.into(), 10);
+        matcher.add_job_tasks(2.into(), 10.into(), 5);
+        assert_eq!(
+            count_matching(
+                &mut matcher,
+                &[
+                    task_started(9, 1),
+                    task_started(10, 1),
+                    task_finished(14),
+                    task_finished(15)
+                ]
+            ),
+            2
+        );
+    }
+
+    #[test]
+    fn filter_by_job_and_type() {
+        let mut matcher = EventMatcher::new(EventFilter {
+            event_types: vec!["task-finished".to_string()],
+            job_ids: vec![1.into()],
+            ..Default::default()
+        });
+        let job_created = event(MonitoringEventPayload::JobCreated(
+            1.into(),
+            Box::new(JobInfo {
+                name: "job".to_string(),
+                job_desc: JobDescription::Graph { tasks: vec![] },
+                base_task_id: 5.into(),
+                task_ids: vec![0.into(), 1.into()],
+                max_fails: None,
+                log: None,
+                submission_date: Default::default(),
+            }),
+        ));
+        assert_eq!(
+            count_matching(
+                &mut matcher,
+                &[
+                    job_created,
+                    task_started(5, 1),
+                    task_finished(4),
+                    task_finished(5),
+                    task_finished(6)
+                ]
+            ),
+            2
+        );
+    }
+
+    #[test]
+    fn filter_by_worker() {
+        let mut matcher = EventMatcher::new(EventFilter {
+            worker_ids: vec![2.into()],
+            ..Default::default()
+        });
+        assert_eq!(
+            count_matching(
+                &mut matcher,
+                &[
+                    task_started(1, 1),
+                    task_started(2, 2),
+                    task_finished(1),
+                    task_finished(2)
+                ]
+            ),
+            2
+        );
+    }
+
+    #[test]
+    fn filter_by_worker_and_type() {
+        let mut matcher = EventMatcher::new(EventFilter {
+            event_types: vec!["task-finished".to_string()],
+            worker_ids: vec![2.into()],
+            ..Default::default()
+        });
+        assert_eq!(
+            count_matching(
+                &mut matcher,
+                &[
+                    task_started(1, 1),
+                    task_started(2, 2),
+                    task_finished(1),
+                    task_finished(2)
+                ]
+            ),
+            1
+        );
+    }
+}
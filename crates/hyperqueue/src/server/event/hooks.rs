@@ -0,0 +1,457 @@
+//! User commands (hooks) that are executed when events of a given type are created.
+//!
+//! [`EventHooks`] decides which hooks should be executed for each event created by the server.
+//! The hooks are then executed in a separate thread by [`start_hook_runner`], so that slow
+//! commands do not block the server.
+use std::future::Future;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Utc};
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use nix::sys::signal::{killpg, Signal};
+use nix::unistd::Pid;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::sync::oneshot;
+
+use crate::client::commands::event::output::format_event;
+use crate::common::env::{
+    HQ_EVENT_ID, HQ_EVENT_TIME, HQ_EVENT_TYPE, HQ_JOB_ID, HQ_TASK_ID, HQ_WORKER_ID,
+};
+use crate::server::event::events::{MonitoringEventPayload, EVENT_TYPES};
+use crate::server::event::MonitoringEvent;
+use crate::{JobId, Map};
+
+/// Command that is executed whenever an event of the given type is created.
+/// It is specified as `<event-type>=<command>` on the command line.
+#[derive(Clone, Debug)]
+pub struct EventHook {
+    pub event_type: &'static str,
+    pub command: String,
+}
+
+impl FromStr for EventHook {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (event_type, command) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Hook has to be specified as `<event-type>=<command>`"))?;
+        let event_type = EVENT_TYPES
+            .iter()
+            .find(|name| **name == event_type)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown event type `{event_type}`, possible values are: {}",
+                    EVENT_TYPES.join(", ")
+                )
+            })?;
+        if command.trim().is_empty() {
+            bail!("Hook command for event type `{event_type}` is empty");
+        }
+        Ok(Self {
+            event_type,
+            command: command.to_string(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct HookInvocation {
+    command: String,
+    event: MonitoringEvent,
+}
+
+/// Maximum number of hook invocations that can wait for their execution.
+/// Invocations of hooks for events created while the queue is full are dropped.
+const HOOK_QUEUE_SIZE: usize = 4096;
+
+pub type HookSender = Sender<HookInvocation>;
+type HookReceiver = Receiver<HookInvocation>;
+
+/// Selects hooks that should be executed for newly created events.
+pub struct EventHooks {
+    hooks: Vec<EventHook>,
+    /// Commands that are executed when the corresponding job completes (`hq submit --on-finish`)
+    job_hooks: Map<JobId, String>,
+    sender: HookSender,
+    /// Number of hook invocations that were dropped because the hook queue was full
+    dropped_invocations: u64,
+}
+
+impl EventHooks {
+    pub fn new(hooks: Vec<EventHook>, sender: HookSender) -> Self {
+        Self {
+            hooks,
+            job_hooks: Default::default(),
+            sender,
+            dropped_invocations: 0,
+        }
+    }
+
+    pub fn add_job_hook(&mut self, job_id: JobId, command: String) {
+        self.job_hooks.insert(job_id, command);
+    }
+
+    pub fn on_event(&mut self, event: &MonitoringEvent) {
+        let event_type = event.payload.type_name();
+        let commands: Vec<String> = self
+            .hooks
+            .iter()
+            .filter(|hook| hook.event_type == event_type)
+            .map(|hook| hook.command.clone())
+            .collect();
+        for command in commands {
+            self.execute(command, event);
+        }
+        if let MonitoringEventPayload::JobCompleted(job_id, _) = event.payload {
+            if let Some(command) = self.job_hooks.remove(&job_id) {
+                self.execute(command, event);
+            }
+        }
+    }
+
+    fn execute(&mut self, command: String, event: &MonitoringEvent) {
+        let invocation = HookInvocation {
+            command,
+            event: event.clone(),
+        };
+        match self.sender.try_send(invocation) {
+            Ok(()) => {}
+            Err(TrySendError::Full(invocation)) => {
+                self.dropped_invocations += 1;
+                log::warn!(
+                    "Hook queue is full, hook `{}` for event `{}` will not be executed ({} hooks dropped so far)",
+                    invocation.command,
+                    invocation.event.payload.type_name(),
+                    self.dropped_invocations
+                );
+            }
+            Err(TrySendError::Closed(_)) => log::error!("Hook queue has been closed."),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct HookRunnerConfig {
+    /// Hooks that run for longer than this duration are killed
+    pub timeout: Duration,
+    /// Maximum number of hooks that are executed at the same time
+    pub max_concurrent: usize,
+}
+
+/// Starts a thread that executes hooks sent to the returned sender.
+/// The returned future resolves once the sender is dropped and all hooks have finished.
+pub fn start_hook_runner(config: HookRunnerConfig) -> (HookSender, impl Future<Output = ()>) {
+    let (tx, rx) = channel(HOOK_QUEUE_SIZE);
+    let (end_tx, end_rx) = oneshot::channel();
+
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(hook_process(rx, config));
+        log::debug!("Hook execution has finished");
+        end_tx.send(()).ok();
+    });
+    let end_fut = async move {
+        // The sender is dropped without sending a message if the thread panics
+        if end_rx.await.is_err() {
+            log::error!("Hook execution thread has crashed");
+        }
+    };
+    (tx, end_fut)
+}
+
+async fn hook_process(mut receiver: HookReceiver, config: HookRunnerConfig) {
+    let max_concurrent = config.max_concurrent.max(1);
+    let mut running = FuturesUnordered::new();
+
+    loop {
+        tokio::select! {
+            invocation = receiver.recv(), if running.len() < max_concurrent => {
+                match invocation {
+                    Some(invocation) => running.push(run_hook(invocation, config.timeout)),
+                    None => break,
+                }
+            }
+            Some(_) = running.next() => {}
+        }
+    }
+    while running.next().await.is_some() {}
+}
+
+async fn run_hook(invocation: HookInvocation, timeout: Duration) {
+    let HookInvocation { command, event } = invocation;
+    let event_type = event.payload.type_name();
+
+    let mut process = Command::new("sh");
+    process
+        .arg("-c")
+        .arg(&command)
+        .envs(hook_env(&event))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    unsafe {
+        process.pre_exec(|| {
+            // The hook gets its own process group, so that all processes started by the hook
+            // can be killed when it times out.
+            nix::unistd::setsid()?;
+            Ok(())
+        });
+    }
+    let input = serde_json::to_vec(&format_event(event)).unwrap();
+
+    let mut child = match process.spawn() {
+        Ok(child) => child,
+        Err(error) => {
+            log::error!("Cannot execute hook `{command}` for event `{event_type}`: {error:?}");
+            return;
+        }
+    };
+    let pid = child.id();
+
+    let result = tokio::time::timeout(timeout, async move {
+        if let Some(mut stdin) = child.stdin.take() {
+            // The hook does not have to read its input, so errors are ignored
+            stdin.write_all(&input).await.ok();
+        }
+        child.wait_with_output().await
+    })
+    .await;
+
+    if result.is_err() {
+        // `kill_on_drop` only kills the shell, processes started by it have to be killed
+        // through the process group of the hook
+        if let Some(pid) = pid {
+            if let Err(error) = killpg(Pid::from_raw(pid as i32), Signal::SIGKILL) {
+                log::debug!("Cannot kill process group of hook `{command}`: {error:?}");
+            }
+        }
+    }
+
+    match result {
+        Ok(Ok(output)) if output.status.success() => {
+            log::debug!("Hook `{command}` for event `{event_type}` has finished");
+        }
+        Ok(Ok(output)) => log::error!(
+            "Hook `{command}` for event `{event_type}` has failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Ok(Err(error)) => {
+            log::error!("Cannot execute hook `{command}` for event `{event_type}`: {error:?}")
+        }
+        Err(_) => log::error!(
+            "Hook `{command}` for event `{event_type}` has timed out after {}",
+            humantime::format_duration(timeout)
+        ),
+    }
+}
+
+/// Environment variables that describe the event to the hook.
+/// The complete event is passed to the hook as JSON on its standard input.
+fn hook_env(event: &MonitoringEvent) -> Vec<(&'static str, String)> {
+    let time: DateTime<Utc> = event.time.into();
+    let mut env = vec![
+        (HQ_EVENT_TYPE, event.payload.type_name().to_string()),
+        (HQ_EVENT_ID, event.id.to_string()),
+        (HQ_EVENT_TIME, time.to_rfc3339()),
+    ];
+    match &event.payload {
+        MonitoringEventPayload::WorkerConnected(worker_id, _)
+        | MonitoringEventPayload::WorkerLost(worker_id, _)
+        | MonitoringEventPayload::WorkerStopRequested(worker_id) => {
+            env.push((HQ_WORKER_ID, worker_id.to_string()));
+        }
+        MonitoringEventPayload::WorkerOverviewReceived(overview) => {
+            env.push((HQ_WORKER_ID, overview.id.to_string()));
+        }
+        MonitoringEventPayload::JobCreated(job_id, _)
+        | MonitoringEventPayload::JobCompleted(job_id, _)
+        | MonitoringEventPayload::JobCanceled(job_id) => {
+            env.push((HQ_JOB_ID, job_id.to_string()));
+        }
+        MonitoringEventPayload::TaskStarted { worker_id, .. } => {
+            env.push((HQ_WORKER_ID, worker_id.to_string()));
+        }
+        MonitoringEventPayload::TaskFailed { task, .. }
+        | MonitoringEventPayload::TaskCanceled(task) => {
+            env.push((HQ_JOB_ID, task.job_id.to_string()));
+            env.push((HQ_TASK_ID, task.job_task_id.to_string()));
+        }
+        MonitoringEventPayload::TaskRestarted {
+            task, worker_id, ..
+        } => {
+            env.push((HQ_JOB_ID, task.job_id.to_string()));
+            env.push((HQ_TASK_ID, task.job_task_id.to_string()));
+            env.push((HQ_WORKER_ID, worker_id.to_string()));
+        }
+        MonitoringEventPayload::TaskFinished { .. }
+        | MonitoringEventPayload::AllocationQueueCreated(..)
+        | MonitoringEventPayload::AllocationQueueRemoved(_)
+        | MonitoringEventPayload::AllocationQueuePaused { .. }
+        | MonitoringEventPayload::AllocationQueueResumed(_)
+        | MonitoringEventPayload::AllocationQueued { .. }
+        | MonitoringEventPayload::AllocationStarted(..)
+        | MonitoringEventPayload::AllocationFinished(..) => {}
+    }
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use chrono::Utc;
+    use tempdir::TempDir;
+    use tokio::sync::mpsc::channel;
+
+    use crate::server::event::events::MonitoringEventPayload;
+    use crate::server::event::filter::tests::task_finished;
+    use crate::server::event::hooks::{
+        hook_process, EventHook, EventHooks, HookInvocation, HookRunnerConfig,
+    };
+    use crate::server::event::MonitoringEvent;
+
+    fn job_completed(job_id: u32) -> MonitoringEvent {
+        MonitoringEvent {
+            id: 1,
+            time: SystemTime::now(),
+            payload: MonitoringEventPayload::JobCompleted(job_id.into(), Utc::now()),
+        }
+    }
+
+    #[test]
+    fn parse_hook() {
+        let hook: EventHook = "job-completed=/bin/notify.sh --all".parse().unwrap();
+        assert_eq!(hook.event_type, "job-completed");
+        assert_eq!(hook.command, "/bin/notify.sh --all");
+
+        assert!("job-completed".parse::<EventHook>().is_err());
+        assert!("job-done=/bin/notify.sh".parse::<EventHook>().is_err());
+        assert!("worker-lost= ".parse::<EventHook>().is_err());
+    }
+
+    #[test]
+    fn select_hooks() {
+        let (tx, mut rx) = channel(10);
+        let mut hooks = EventHooks::new(
+            vec![
+                "job-completed=a".parse().unwrap(),
+                "task-finished=b".parse().unwrap(),
+                "job-completed=c".parse().unwrap(),
+            ],
+            tx,
+        );
+        hooks.add_job_hook(2.into(), "d".to_string());
+
+        hooks.on_event(&job_completed(1));
+        hooks.on_event(&task_finished(1));
+        hooks.on_event(&job_completed(2));
+        hooks.on_event(&job_completed(2));
+
+        let mut commands = vec![];
+        while let Ok(invocation) = rx.try_recv() {
+            commands.push(invocation.command);
+        }
+        assert_eq!(commands, vec!["a", "c", "b", "a", "c", "d", "a", "c"]);
+    }
+
+    #[test]
+    fn drop_hooks_when_queue_is_full() {
+        let (tx, mut rx) = channel(2);
+        let mut hooks = EventHooks::new(vec!["job-completed=a".parse().unwrap()], tx);
+
+        hooks.on_event(&job_completed(1));
+        hooks.on_event(&job_completed(2));
+        hooks.on_event(&job_completed(3));
+        assert_eq!(hooks.dropped_invocations, 1);
+
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn run_hooks() {
+        let dir = TempDir::new("hq").unwrap();
+        let path = dir.path().join("out");
+        let (tx, rx) = channel(10);
+        tx.try_send(HookInvocation {
+            command: format!(
+                "echo $HQ_EVENT_TYPE $HQ_JOB_ID >> {0}; cat >> {0}",
+                path.display()
+            ),
+            event: job_completed(3),
+        })
+        .unwrap();
+        tx.try_send(HookInvocation {
+            command: "sleep 10".to_string(),
+            event: job_completed(3),
+        })
+        .unwrap();
+        drop(tx);
+
+        let config = HookRunnerConfig {
+            timeout: Duration::from_millis(500),
+            max_concurrent: 2,
+        };
+        tokio::time::timeout(Duration::from_secs(5), hook_process(rx, config))
+            .await
+            .unwrap();
+
+        let output = std::fs::read_to_string(&path).unwrap();
+        let (env, input) = output.split_once('\n').unwrap();
+        assert_eq!(env, "job-completed 3");
+        let input: serde_json::Value = serde_json::from_str(input).unwrap();
+        assert_eq!(input["event"]["type"], "job-completed");
+        assert_eq!(input["event"]["job-id"], 3);
+    }
+
+    #[tokio::test]
+    async fn kill_processes_of_timeouted_hook() {
+        let dir = TempDir::new("hq").unwrap();
+        let path = dir.path().join("pid");
+        let (tx, rx) = channel(10);
+        tx.try_send(HookInvocation {
+            command: format!("sleep 10 & echo $! > {}; wait", path.display()),
+            event: job_completed(1),
+        })
+        .unwrap();
+        drop(tx);
+
+        let config = HookRunnerConfig {
+            timeout: Duration::from_millis(500),
+            max_concurrent: 1,
+        };
+        tokio::time::timeout(Duration::from_secs(5), hook_process(rx, config))
+            .await
+            .unwrap();
+
+        let pid = std::fs::read_to_string(&path).unwrap();
+        let stat_path = format!("/proc/{}/stat", pid.trim());
+        let is_running = || {
+            // Killed processes can remain as zombies until they are reaped
+            std::fs::read_to_string(&stat_path)
+                .map(|stat| !stat.contains(") Z "))
+                .unwrap_or(false)
+        };
+        for _ in 0..50 {
+            if !is_running() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("Process started by the hook is still running");
+    }
+}
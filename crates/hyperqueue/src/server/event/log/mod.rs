@@ -1,16 +1,20 @@
 mod read;
+mod segment;
 mod stream;
 mod write;
 
-pub use read::EventLogReader;
+pub use read::{EventLogQuery, EventLogReader, EventLogSummary};
+pub use segment::resolve_log_files;
 pub use stream::{start_event_streaming, EventStreamSender};
-pub use write::EventLogWriter;
+pub use write::{EventLogRotation, EventLogWriter};
 
 use bstr::BString;
 use serde::{Deserialize, Serialize};
 
 const HQ_LOG_HEADER: &[u8] = b"hq-event-log";
-const HQ_LOG_VERSION: u32 = 0;
+/// Version 1 adds resource usage of tasks, extended worker overviews and events for task
+/// cancellations, restarts, worker stops and queue pauses.
+const HQ_LOG_VERSION: u32 = 1;
 
 fn canonical_header() -> LogFileHeader {
     LogFileHeader {
@@ -1,48 +1,151 @@
+use crate::server::event::filter::EventMatcher;
 use crate::server::event::log::{canonical_header, LogFileHeader};
 use crate::server::event::MonitoringEvent;
+use crate::transfer::messages::EventFilter;
 use anyhow::anyhow;
 use flate2::read::GzDecoder;
 use rmp_serde::decode::Error;
+use std::collections::{BTreeMap, VecDeque};
 use std::fs::File;
 use std::io::ErrorKind;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-/// Reads events from a file in a streaming fashion.
+/// Reads events from a file (or from a sequence of files) in a streaming fashion.
 pub struct EventLogReader {
     source: GzDecoder<File>,
+    /// Files that will be read after the current one is exhausted
+    remaining: VecDeque<PathBuf>,
 }
 
 impl EventLogReader {
     pub fn open(path: &Path) -> anyhow::Result<Self> {
-        let mut file = File::open(path)?;
-        let header: LogFileHeader = rmp_serde::from_read(&mut file)
-            .map_err(|error| anyhow!("Cannot load HQ event log file header: {error:?}"))?;
-
-        let expected_header = canonical_header();
-        if header != expected_header {
-            return Err(anyhow!(
-                "Invalid HQ event log file header.\nFound: {header:?}\nExpected: {expected_header:?}"
-            ));
+        Ok(Self {
+            source: open_log_file(path)?,
+            remaining: Default::default(),
+        })
+    }
+
+    /// Reads events from the given files in order, as if they were a single event log
+    /// (e.g. segments of a rotated event log).
+    /// Headers of all files are checked before any events are read.
+    pub fn open_all(paths: Vec<PathBuf>) -> anyhow::Result<Self> {
+        let mut paths = VecDeque::from(paths);
+        let first = paths
+            .pop_front()
+            .ok_or_else(|| anyhow!("No event log files were provided"))?;
+        for path in &paths {
+            open_log_file(path)
+                .map_err(|error| anyhow!("Cannot open `{}`: {error:?}", path.display()))?;
         }
+        Ok(Self {
+            source: open_log_file(&first)
+                .map_err(|error| anyhow!("Cannot open `{}`: {error:?}", first.display()))?,
+            remaining: paths,
+        })
+    }
+
+    /// Returns only the events that pass the given query.
+    /// Decoding errors are passed through unchanged.
+    pub fn query(
+        self,
+        query: EventLogQuery,
+    ) -> impl Iterator<Item = Result<MonitoringEvent, rmp_serde::decode::Error>> {
+        let EventLogQuery {
+            filter,
+            since,
+            until,
+        } = query;
+        let mut matcher = EventMatcher::new(filter);
+        self.filter(move |event| match event {
+            Ok(event) => {
+                // The matcher has to see all events, even those outside of the time range
+                let matches = matcher.matches(&event.payload);
+                matches
+                    && since.map(|since| event.time >= since).unwrap_or(true)
+                    && until.map(|until| event.time <= until).unwrap_or(true)
+            }
+            Err(_) => true,
+        })
+    }
+}
+
+/// Selects events from an event log file.
+#[derive(Default)]
+pub struct EventLogQuery {
+    pub filter: EventFilter,
+    /// Only return events created at this time or later.
+    pub since: Option<SystemTime>,
+    /// Only return events created at this time or earlier.
+    pub until: Option<SystemTime>,
+}
+
+/// Aggregated statistics of events read from an event log file.
+#[derive(Default)]
+pub struct EventLogSummary {
+    pub event_count: u64,
+    /// Number of events per event type
+    pub type_counts: BTreeMap<&'static str, u64>,
+    pub first_event_time: Option<SystemTime>,
+    pub last_event_time: Option<SystemTime>,
+}
 
-        let source = GzDecoder::new(file);
-        Ok(Self { source })
+impl EventLogSummary {
+    pub fn add(&mut self, event: &MonitoringEvent) {
+        self.event_count += 1;
+        *self
+            .type_counts
+            .entry(event.payload.type_name())
+            .or_default() += 1;
+        self.first_event_time = Some(
+            self.first_event_time
+                .map_or(event.time, |time| time.min(event.time)),
+        );
+        self.last_event_time = Some(
+            self.last_event_time
+                .map_or(event.time, |time| time.max(event.time)),
+        );
     }
 }
 
+fn open_log_file(path: &Path) -> anyhow::Result<GzDecoder<File>> {
+    let mut file = File::open(path)?;
+    let header: LogFileHeader = rmp_serde::from_read(&mut file)
+        .map_err(|error| anyhow!("Cannot load HQ event log file header: {error:?}"))?;
+
+    let expected_header = canonical_header();
+    if header != expected_header {
+        return Err(anyhow!(
+            "Invalid HQ event log file header.\nFound: {header:?}\nExpected: {expected_header:?}"
+        ));
+    }
+    Ok(GzDecoder::new(file))
+}
+
 impl Iterator for EventLogReader {
     type Item = Result<MonitoringEvent, rmp_serde::decode::Error>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        match rmp_serde::from_read(&mut self.source) {
-            Ok(event) => Some(Ok(event)),
-            Err(Error::InvalidMarkerRead(error))
-                if matches!(error.kind(), ErrorKind::UnexpectedEof) =>
-            {
-                None
+        loop {
+            match rmp_serde::from_read(&mut self.source) {
+                Ok(event) => return Some(Ok(event)),
+                Err(Error::InvalidMarkerRead(error))
+                    if matches!(error.kind(), ErrorKind::UnexpectedEof) =>
+                {
+                    let path = self.remaining.pop_front()?;
+                    match open_log_file(&path) {
+                        Ok(source) => self.source = source,
+                        Err(error) => {
+                            return Some(Err(Error::InvalidDataRead(std::io::Error::new(
+                                ErrorKind::Other,
+                                format!("Cannot open `{}`: {error:?}", path.display()),
+                            ))))
+                        }
+                    }
+                }
+                Err(error) => return Some(Err(error)),
             }
-            Err(error) => Some(Err(error)),
         }
     }
 }
@@ -51,12 +154,15 @@ impl Iterator for EventLogReader {
 mod tests {
     use crate::server::event::events::MonitoringEventPayload;
     use crate::server::event::log::{
-        EventLogReader, EventLogWriter, LogFileHeader, HQ_LOG_HEADER, HQ_LOG_VERSION,
+        resolve_log_files, EventLogQuery, EventLogReader, EventLogRotation, EventLogSummary,
+        EventLogWriter, LogFileHeader, HQ_LOG_HEADER, HQ_LOG_VERSION,
     };
     use crate::server::event::MonitoringEvent;
+    use crate::transfer::messages::EventFilter;
     use std::fs::File;
     use std::io::Write;
-    use std::time::SystemTime;
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
     use tako::gateway::LostWorkerReason;
     use tempdir::TempDir;
 
@@ -164,4 +270,207 @@ mod tests {
             MonitoringEventPayload::AllocationFinished(0, _)
         ));
     }
+
+    async fn write_worker_events(path: &Path, start: SystemTime) {
+        let mut writer = EventLogWriter::create(path).await.unwrap();
+        for id in 0..10 {
+            let payload = if id % 2 == 0 {
+                MonitoringEventPayload::WorkerStopRequested(id.into())
+            } else {
+                MonitoringEventPayload::WorkerLost(id.into(), LostWorkerReason::ConnectionLost)
+            };
+            writer
+                .store(MonitoringEvent {
+                    id,
+                    time: start + Duration::from_secs(id as u64),
+                    payload,
+                })
+                .await
+                .unwrap();
+        }
+        writer.finish().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn query_by_time() {
+        let tmpdir = TempDir::new("hq").unwrap();
+        let path = tmpdir.path().join("foo");
+        let start = SystemTime::now();
+        write_worker_events(&path, start).await;
+
+        let reader = EventLogReader::open(&path).unwrap();
+        let ids: Vec<_> = reader
+            .query(EventLogQuery {
+                since: Some(start + Duration::from_secs(3)),
+                until: Some(start + Duration::from_secs(6)),
+                ..Default::default()
+            })
+            .map(|event| event.unwrap().id)
+            .collect();
+        assert_eq!(ids, vec![3, 4, 5, 6]);
+    }
+
+    #[tokio::test]
+    async fn query_by_type_and_worker() {
+        let tmpdir = TempDir::new("hq").unwrap();
+        let path = tmpdir.path().join("foo");
+        let start = SystemTime::now();
+        write_worker_events(&path, start).await;
+
+        let reader = EventLogReader::open(&path).unwrap();
+        let ids: Vec<_> = reader
+            .query(EventLogQuery {
+                filter: EventFilter {
+                    event_types: vec!["worker-lost".to_string()],
+                    worker_ids: vec![2.into(), 3.into(), 5.into()],
+                    ..Default::default()
+                },
+                since: Some(start + Duration::from_secs(4)),
+                ..Default::default()
+            })
+            .map(|event| event.unwrap().id)
+            .collect();
+        assert_eq!(ids, vec![5]);
+    }
+
+    #[tokio::test]
+    async fn summarize_events() {
+        let tmpdir = TempDir::new("hq").unwrap();
+        let path = tmpdir.path().join("foo");
+        let start = SystemTime::now();
+        write_worker_events(&path, start).await;
+
+        let mut summary = EventLogSummary::default();
+        for event in EventLogReader::open(&path).unwrap() {
+            summary.add(&event.unwrap());
+        }
+        assert_eq!(summary.event_count, 10);
+        assert_eq!(
+            summary.type_counts.into_iter().collect::<Vec<_>>(),
+            vec![("worker-lost", 5), ("worker-stop-requested", 5)]
+        );
+        assert_eq!(summary.first_event_time, Some(start));
+        assert_eq!(
+            summary.last_event_time,
+            Some(start + Duration::from_secs(9))
+        );
+    }
+
+    fn worker_lost(id: u32) -> MonitoringEvent {
+        MonitoringEvent {
+            id,
+            time: SystemTime::now(),
+            payload: MonitoringEventPayload::WorkerLost(
+                id.into(),
+                LostWorkerReason::ConnectionLost,
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn rotate_by_size() {
+        let tmpdir = TempDir::new("hq").unwrap();
+        let path = tmpdir.path().join("events");
+        {
+            let mut writer = EventLogWriter::create_rotating(
+                &path,
+                EventLogRotation {
+                    max_size: Some(1),
+                    interval: None,
+                },
+            )
+            .await
+            .unwrap();
+            for id in 0..10000 {
+                writer.store(worker_lost(id)).await.unwrap();
+            }
+            writer.finish().await.unwrap();
+        }
+
+        let files = resolve_log_files(&path).unwrap();
+        assert!(files.len() > 1);
+        assert_eq!(files[0], tmpdir.path().join("events.1"));
+        // Each segment is a complete event log
+        let mut segment = EventLogReader::open(&files[1]).unwrap();
+        assert!(segment.next().unwrap().unwrap().id > 0);
+
+        let ids: Vec<_> = EventLogReader::open_all(files)
+            .unwrap()
+            .map(|event| event.unwrap().id)
+            .collect();
+        assert_eq!(ids, (0..10000).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn rotate_by_time() {
+        let tmpdir = TempDir::new("hq").unwrap();
+        let path = tmpdir.path().join("events");
+        {
+            let mut writer = EventLogWriter::create_rotating(
+                &path,
+                EventLogRotation {
+                    max_size: None,
+                    interval: Some(Duration::from_millis(100)),
+                },
+            )
+            .await
+            .unwrap();
+            writer.store(worker_lost(0)).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            writer.store(worker_lost(1)).await.unwrap();
+            writer.store(worker_lost(2)).await.unwrap();
+            writer.finish().await.unwrap();
+        }
+
+        let files = resolve_log_files(&path).unwrap();
+        assert_eq!(files.len(), 2);
+        let ids: Vec<_> = EventLogReader::open_all(files)
+            .unwrap()
+            .map(|event| event.unwrap().id)
+            .collect();
+        assert_eq!(ids, vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn rotation_continues_numbering() {
+        let tmpdir = TempDir::new("hq").unwrap();
+        let path = tmpdir.path().join("events");
+        for id in 0..2 {
+            let mut writer = EventLogWriter::create_rotating(
+                &path,
+                EventLogRotation {
+                    max_size: Some(1024 * 1024),
+                    interval: None,
+                },
+            )
+            .await
+            .unwrap();
+            writer.store(worker_lost(id)).await.unwrap();
+            writer.finish().await.unwrap();
+        }
+
+        let files = resolve_log_files(&path).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                tmpdir.path().join("events.1"),
+                tmpdir.path().join("events.2")
+            ]
+        );
+        let ids: Vec<_> = EventLogReader::open_all(files)
+            .unwrap()
+            .map(|event| event.unwrap().id)
+            .collect();
+        assert_eq!(ids, vec![0, 1]);
+    }
+
+    #[test]
+    fn open_all_checks_headers() {
+        let tmpdir = TempDir::new("hq").unwrap();
+        let path = tmpdir.path().join("foo");
+        File::create(&path).unwrap();
+
+        assert!(EventLogReader::open_all(vec![]).is_err());
+        assert!(EventLogReader::open_all(vec![path]).is_err());
+    }
 }
@@ -0,0 +1,192 @@
+//! Naming and discovery of event log segments.
+//!
+//! When the event log is rotated, events are written into numbered files (segments) named
+//! `<event-log-path>.<index>`, starting with index 1. Each segment is a complete event log file
+//! with its own header, so it can also be read on its own.
+use anyhow::{anyhow, bail};
+use std::path::{Path, PathBuf};
+
+pub(super) fn segment_path(base: &Path, index: u64) -> PathBuf {
+    let mut name = base.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{index}"));
+    base.with_file_name(name)
+}
+
+/// Returns the existing segments of the event log at `base`, ordered by their index.
+pub(super) fn find_segments(base: &Path) -> std::io::Result<Vec<(u64, PathBuf)>> {
+    let name = match base.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return Ok(vec![]),
+    };
+    let directory = match base.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if !directory.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut segments = vec![];
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let index = entry
+            .file_name()
+            .to_str()
+            .and_then(|file_name| file_name.strip_prefix(name))
+            .and_then(|suffix| suffix.strip_prefix('.'))
+            .and_then(|index| index.parse::<u64>().ok());
+        if let Some(index) = index {
+            segments.push((index, base.with_file_name(entry.file_name())));
+        }
+    }
+    segments.sort_unstable();
+    Ok(segments)
+}
+
+/// Finds the event log files that should be read for the given `path`, in the order in which
+/// they were written.
+///
+/// The path can point to a single event log file, to a directory that contains event log
+/// files, to the base path of a rotated event log (whose segments are then read) or it can be
+/// a glob pattern (e.g. `logs/events.bin.*`).
+pub fn resolve_log_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = if path.is_dir() {
+        let mut files = vec![];
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                files.push(entry.path());
+            }
+        }
+        files
+    } else {
+        let segments = find_segments(path)?;
+        if !segments.is_empty() {
+            return Ok(segments.into_iter().map(|(_, path)| path).collect());
+        }
+        let pattern = path
+            .to_str()
+            .ok_or_else(|| anyhow!("Invalid path `{}`", path.display()))?;
+        glob::glob(pattern)?
+            .filter_map(|entry| entry.ok())
+            .filter(|path| path.is_file())
+            .collect()
+    };
+    if files.is_empty() {
+        bail!("No event log files were found at `{}`", path.display());
+    }
+    files.sort_unstable_by_key(|path| log_file_order(path));
+    Ok(files)
+}
+
+/// Segments of the same event log are ordered by their numeric index rather than by name
+/// (so that `events.bin.10` comes after `events.bin.9`).
+fn log_file_order(path: &Path) -> (PathBuf, Option<u64>) {
+    if let Some((base, index)) = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.rsplit_once('.'))
+    {
+        if let Ok(index) = index.parse::<u64>() {
+            return (path.with_file_name(base), Some(index));
+        }
+    }
+    (path.to_path_buf(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::event::log::segment::{find_segments, resolve_log_files, segment_path};
+    use std::fs::File;
+    use std::path::{Path, PathBuf};
+    use tempdir::TempDir;
+
+    fn create_files(dir: &Path, names: &[&str]) {
+        for name in names {
+            File::create(dir.join(name)).unwrap();
+        }
+    }
+
+    fn names(paths: Vec<PathBuf>) -> Vec<String> {
+        paths
+            .into_iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn segment_names() {
+        assert_eq!(
+            segment_path(Path::new("/tmp/events.bin"), 3),
+            PathBuf::from("/tmp/events.bin.3")
+        );
+    }
+
+    #[test]
+    fn find_segments_by_index() {
+        let dir = TempDir::new("hq").unwrap();
+        create_files(
+            dir.path(),
+            &[
+                "events.bin.2",
+                "events.bin.10",
+                "events.bin.1",
+                "events.bin.x",
+                "other.1",
+            ],
+        );
+        let segments = find_segments(&dir.path().join("events.bin")).unwrap();
+        assert_eq!(
+            segments.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            vec![1, 2, 10]
+        );
+    }
+
+    #[test]
+    fn resolve_single_file() {
+        let dir = TempDir::new("hq").unwrap();
+        create_files(dir.path(), &["events.bin", "events.bin.1"]);
+        let files = resolve_log_files(&dir.path().join("events.bin")).unwrap();
+        assert_eq!(names(files), vec!["events.bin"]);
+    }
+
+    #[test]
+    fn resolve_directory() {
+        let dir = TempDir::new("hq").unwrap();
+        create_files(
+            dir.path(),
+            &["events.bin.10", "events.bin.9", "events.bin.1"],
+        );
+        let files = resolve_log_files(dir.path()).unwrap();
+        assert_eq!(
+            names(files),
+            vec!["events.bin.1", "events.bin.9", "events.bin.10"]
+        );
+    }
+
+    #[test]
+    fn resolve_rotated_base_path() {
+        let dir = TempDir::new("hq").unwrap();
+        create_files(dir.path(), &["events.bin.2", "events.bin.1", "foo"]);
+        let files = resolve_log_files(&dir.path().join("events.bin")).unwrap();
+        assert_eq!(names(files), vec!["events.bin.1", "events.bin.2"]);
+    }
+
+    #[test]
+    fn resolve_glob() {
+        let dir = TempDir::new("hq").unwrap();
+        create_files(dir.path(), &["a.bin.11", "a.bin.2", "b.bin.1"]);
+        let files = resolve_log_files(&dir.path().join("a.bin.*")).unwrap();
+        assert_eq!(names(files), vec!["a.bin.2", "a.bin.11"]);
+    }
+
+    #[test]
+    fn resolve_missing() {
+        let dir = TempDir::new("hq").unwrap();
+        assert!(resolve_log_files(&dir.path().join("events.bin")).is_err());
+        assert!(resolve_log_files(&dir.path().join("*.bin")).is_err());
+    }
+}
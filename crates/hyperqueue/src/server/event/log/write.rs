@@ -1,32 +1,75 @@
 use crate::server::event::log::canonical_header;
+use crate::server::event::log::segment::{find_segments, segment_path};
 use crate::server::event::MonitoringEvent;
 use async_compression::tokio::write::GzipEncoder;
 use async_compression::Level;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 
+/// Describes when the event log should roll over to a new file (segment).
+#[derive(Clone, Debug, Default)]
+pub struct EventLogRotation {
+    /// Start a new segment once the current one has reached this size (in bytes)
+    pub max_size: Option<u64>,
+    /// Start a new segment once the current one has been written for this duration
+    pub interval: Option<Duration>,
+}
+
+impl EventLogRotation {
+    pub fn is_enabled(&self) -> bool {
+        self.max_size.is_some() || self.interval.is_some()
+    }
+}
+
+struct RotationState {
+    rotation: EventLogRotation,
+    base_path: PathBuf,
+    /// Index of the segment that is currently being written
+    segment: u64,
+    segment_created: Instant,
+}
+
 /// Streams monitoring events into a file on disk.
 pub struct EventLogWriter {
     file: GzipEncoder<File>,
     buffer: Vec<u8>,
+    rotation: Option<RotationState>,
 }
 
 const BUF_MAX_SIZE: usize = 16 * 1024;
 
 impl EventLogWriter {
     pub async fn create(path: &Path) -> anyhow::Result<Self> {
-        let mut file = File::create(path).await?;
-        let header = rmp_serde::encode::to_vec(&canonical_header())?;
-        file.write_all(&header).await?;
-        file.flush().await?;
-
-        let file = GzipEncoder::with_quality(file, Level::Fastest);
+        let file = create_log_file(path).await?;
 
         // Keep buffer capacity larger than max size to avoid reallocation if we overflow
         // the buffer.
         let buffer = Vec::with_capacity(BUF_MAX_SIZE * 2);
-        Ok(Self { file, buffer })
+        Ok(Self {
+            file,
+            buffer,
+            rotation: None,
+        })
+    }
+
+    /// Creates a writer that stores events into numbered segments `<path>.<index>`.
+    /// Numbering continues after the last existing segment, so that segments written by a
+    /// previous server are not overwritten.
+    pub async fn create_rotating(path: &Path, rotation: EventLogRotation) -> anyhow::Result<Self> {
+        let segment = find_segments(path)?
+            .last()
+            .map(|(index, _)| index + 1)
+            .unwrap_or(1);
+        let mut writer = Self::create(&segment_path(path, segment)).await?;
+        writer.rotation = Some(RotationState {
+            rotation,
+            base_path: path.to_path_buf(),
+            segment,
+            segment_created: Instant::now(),
+        });
+        Ok(writer)
     }
 
     #[inline]
@@ -34,6 +77,12 @@ impl EventLogWriter {
         rmp_serde::encode::write(&mut self.buffer, &event)?;
         if self.is_buffer_full() {
             self.write_buffer().await?;
+            if self.should_rotate_by_size().await? {
+                self.rotate().await?;
+            }
+        }
+        if self.should_rotate_by_time() {
+            self.rotate().await?;
         }
         Ok(())
     }
@@ -52,6 +101,55 @@ impl EventLogWriter {
         Ok(())
     }
 
+    /// Finishes the current segment and continues writing into a new one.
+    async fn rotate(&mut self) -> anyhow::Result<()> {
+        let next_path = match self.rotation.as_mut() {
+            Some(state) => {
+                state.segment += 1;
+                state.segment_created = Instant::now();
+                segment_path(&state.base_path, state.segment)
+            }
+            None => return Ok(()),
+        };
+        let next_file = create_log_file(&next_path).await?;
+
+        if !self.buffer.is_empty() {
+            self.write_buffer().await?;
+        }
+        let mut previous = std::mem::replace(&mut self.file, next_file);
+        previous.shutdown().await?;
+        log::debug!("Event log continues in {}", next_path.display());
+        Ok(())
+    }
+
+    async fn should_rotate_by_size(&self) -> tokio::io::Result<bool> {
+        match self
+            .rotation
+            .as_ref()
+            .and_then(|state| state.rotation.max_size)
+        {
+            Some(max_size) => {
+                // Data that is still buffered in the encoder is not counted, so segments
+                // can be slightly larger than the limit
+                let size = self.file.get_ref().metadata().await?.len();
+                Ok(size >= max_size)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn should_rotate_by_time(&self) -> bool {
+        self.rotation
+            .as_ref()
+            .and_then(|state| {
+                state
+                    .rotation
+                    .interval
+                    .map(|interval| state.segment_created.elapsed() >= interval)
+            })
+            .unwrap_or(false)
+    }
+
     async fn write_buffer(&mut self) -> tokio::io::Result<()> {
         self.file.write_all(&self.buffer).await?;
         self.buffer.clear();
@@ -63,3 +161,12 @@ impl EventLogWriter {
         self.buffer.len() >= BUF_MAX_SIZE
     }
 }
+
+async fn create_log_file(path: &Path) -> anyhow::Result<GzipEncoder<File>> {
+    let mut file = File::create(path).await?;
+    let header = rmp_serde::encode::to_vec(&canonical_header())?;
+    file.write_all(&header).await?;
+    file.flush().await?;
+
+    Ok(GzipEncoder::with_quality(file, Level::Fastest))
+}
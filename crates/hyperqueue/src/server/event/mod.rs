@@ -1,6 +1,9 @@
 pub mod events;
+pub mod filter;
+pub mod hooks;
 pub mod log;
 pub mod storage;
+pub mod subscription;
 
 use events::MonitoringEventPayload;
 use serde::{Deserialize, Serialize};
@@ -1,6 +1,8 @@
 use crate::server::autoalloc::{AllocationId, QueueId};
-use crate::server::event::events::{JobInfo, MonitoringEventPayload};
+use crate::server::event::events::{JobInfo, MonitoringEventPayload, TaskIds};
+use crate::server::event::hooks::EventHooks;
 use crate::server::event::log::EventStreamSender;
+use crate::server::event::subscription::EventSubscriber;
 use crate::server::event::{MonitoringEvent, MonitoringEventId};
 use crate::transfer::messages::AllocationQueueParams;
 use crate::{JobId, TakoTaskId, WorkerId};
@@ -8,14 +10,17 @@ use chrono::{DateTime, Utc};
 use std::collections::vec_deque::VecDeque;
 use std::time::SystemTime;
 use tako::gateway::LostWorkerReason;
+use tako::task::TaskResourceUsage;
 use tako::worker::{WorkerConfiguration, WorkerOverview};
-use tako::TaskId;
+use tako::{InstanceId, TaskId};
 
 pub struct EventStorage {
     event_store_size: usize,
     event_queue: VecDeque<MonitoringEvent>,
     last_event_id: u32,
     stream_sender: Option<EventStreamSender>,
+    subscribers: Vec<EventSubscriber>,
+    hooks: Option<EventHooks>,
 }
 
 impl Default for EventStorage {
@@ -25,17 +30,25 @@ impl Default for EventStorage {
             event_queue: Default::default(),
             last_event_id: 0,
             stream_sender: None,
+            subscribers: Default::default(),
+            hooks: None,
         }
     }
 }
 
 impl EventStorage {
-    pub fn new(event_store_size: usize, stream_sender: Option<EventStreamSender>) -> Self {
+    pub fn new(
+        event_store_size: usize,
+        stream_sender: Option<EventStreamSender>,
+        hooks: Option<EventHooks>,
+    ) -> Self {
         Self {
             event_store_size,
             event_queue: VecDeque::new(),
             last_event_id: 0,
             stream_sender,
+            subscribers: Default::default(),
+            hooks,
         }
     }
 
@@ -52,6 +65,50 @@ impl EventStorage {
             .take_while(move |event| event.id > id)
     }
 
+    /// Registers a subscriber that will receive all newly created events.
+    /// Returns stored events with an ID larger than `after_id` that pass the filter of the
+    /// subscriber, ordered by their ID.
+    pub fn subscribe(
+        &mut self,
+        mut subscriber: EventSubscriber,
+        after_id: Option<MonitoringEventId>,
+    ) -> Vec<MonitoringEvent> {
+        let mut events = vec![];
+        if let Some(after_id) = after_id {
+            let mut stored: Vec<&MonitoringEvent> = self.get_events_after(after_id).collect();
+            stored.sort_unstable_by_key(|event| event.id);
+            events = stored
+                .into_iter()
+                .filter(|event| subscriber.matches(event))
+                .cloned()
+                .collect();
+        }
+        self.subscribers.push(subscriber);
+        events
+    }
+
+    /// Registers a command that will be executed when the given job completes.
+    pub fn add_job_hook(&mut self, job_id: JobId, command: String) {
+        match self.hooks {
+            Some(ref mut hooks) => hooks.add_job_hook(job_id, command),
+            None => log::warn!("Hooks are not enabled, hook of job {job_id} will not be executed"),
+        }
+    }
+
+    /// Number of events that are currently stored in memory.
+    pub fn len(&self) -> usize {
+        self.event_queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.event_queue.is_empty()
+    }
+
+    /// Number of events that were created since the server has started.
+    pub fn event_count(&self) -> u64 {
+        self.last_event_id as u64
+    }
+
     pub fn on_worker_added(&mut self, id: WorkerId, configuration: WorkerConfiguration) {
         self.insert_event(MonitoringEventPayload::WorkerConnected(
             id,
@@ -63,11 +120,15 @@ impl EventStorage {
         self.insert_event(MonitoringEventPayload::WorkerLost(id, reason));
     }
 
+    pub fn on_worker_stop_requested(&mut self, id: WorkerId) {
+        self.insert_event(MonitoringEventPayload::WorkerStopRequested(id));
+    }
+
     #[inline]
     pub fn on_overview_received(&mut self, worker_overview: WorkerOverview) {
-        self.insert_event(MonitoringEventPayload::WorkerOverviewReceived(
+        self.insert_event(MonitoringEventPayload::WorkerOverviewReceived(Box::new(
             worker_overview,
-        ));
+        )));
     }
 
     #[inline]
@@ -83,19 +144,63 @@ impl EventStorage {
         self.insert_event(MonitoringEventPayload::JobCompleted(job_id, at_time));
     }
 
+    pub fn on_job_canceled(&mut self, job_id: JobId) {
+        self.insert_event(MonitoringEventPayload::JobCanceled(job_id));
+    }
+
     #[inline]
-    pub fn on_task_started(&mut self, task_id: TakoTaskId, worker_id: WorkerId) {
-        self.insert_event(MonitoringEventPayload::TaskStarted { task_id, worker_id });
+    pub fn on_task_started(
+        &mut self,
+        task_id: TakoTaskId,
+        instance_id: InstanceId,
+        worker_id: WorkerId,
+    ) {
+        self.insert_event(MonitoringEventPayload::TaskStarted {
+            task_id,
+            instance_id,
+            worker_id,
+        });
     }
 
     #[inline]
-    pub fn on_task_finished(&mut self, task_id: TaskId) {
-        self.insert_event(MonitoringEventPayload::TaskFinished(task_id));
+    pub fn on_task_finished(&mut self, task_id: TaskId, resource_usage: Option<TaskResourceUsage>) {
+        self.insert_event(MonitoringEventPayload::TaskFinished {
+            task_id,
+            resource_usage: resource_usage.map(Box::new),
+        });
+    }
+
+    pub fn on_task_failed(
+        &mut self,
+        task: TaskIds,
+        instance_id: InstanceId,
+        error: String,
+        resource_usage: Option<TaskResourceUsage>,
+    ) {
+        self.insert_event(MonitoringEventPayload::TaskFailed {
+            task,
+            instance_id,
+            error,
+            resource_usage: resource_usage.map(Box::new),
+        });
     }
 
     #[inline]
-    pub fn on_task_failed(&mut self, task_id: TaskId) {
-        self.insert_event(MonitoringEventPayload::TaskFailed(task_id));
+    pub fn on_task_canceled(&mut self, task: TaskIds) {
+        self.insert_event(MonitoringEventPayload::TaskCanceled(task));
+    }
+
+    pub fn on_task_restarted(
+        &mut self,
+        task: TaskIds,
+        instance_id: InstanceId,
+        worker_id: WorkerId,
+    ) {
+        self.insert_event(MonitoringEventPayload::TaskRestarted {
+            task,
+            instance_id,
+            worker_id,
+        });
     }
 
     pub fn on_allocation_queue_created(&mut self, id: QueueId, parameters: AllocationQueueParams) {
@@ -109,6 +214,14 @@ impl EventStorage {
         self.insert_event(MonitoringEventPayload::AllocationQueueRemoved(id))
     }
 
+    pub fn on_allocation_queue_paused(&mut self, queue_id: QueueId, reason: String) {
+        self.insert_event(MonitoringEventPayload::AllocationQueuePaused { queue_id, reason })
+    }
+
+    pub fn on_allocation_queue_resumed(&mut self, id: QueueId) {
+        self.insert_event(MonitoringEventPayload::AllocationQueueResumed(id))
+    }
+
     pub fn on_allocation_queued(
         &mut self,
         queue_id: QueueId,
@@ -145,6 +258,11 @@ impl EventStorage {
             time: SystemTime::now(),
         };
         self.stream_event(&event);
+        if let Some(ref mut hooks) = self.hooks {
+            hooks.on_event(&event);
+        }
+        self.subscribers
+            .retain_mut(|subscriber| subscriber.send(&event));
         self.event_queue.push_back(event);
 
         if self.event_queue.len() > self.event_store_size {
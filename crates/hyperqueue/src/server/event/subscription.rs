@@ -0,0 +1,90 @@
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::Sender;
+
+use crate::server::event::filter::EventMatcher;
+use crate::server::event::MonitoringEvent;
+use crate::transfer::messages::EventFilter;
+use crate::{JobId, JobTaskCount, TakoTaskId};
+
+/// Maximum number of events that can wait to be sent to a single subscriber.
+/// Subscribers that do not receive events fast enough are disconnected once this limit is reached.
+pub const SUBSCRIBER_QUEUE_SIZE: usize = 64 * 1024;
+
+/// Client that receives events from the server as soon as they are created.
+pub struct EventSubscriber {
+    matcher: EventMatcher,
+    sender: Sender<MonitoringEvent>,
+}
+
+impl EventSubscriber {
+    pub fn new(filter: EventFilter, sender: Sender<MonitoringEvent>) -> Self {
+        Self {
+            matcher: EventMatcher::new(filter),
+            sender,
+        }
+    }
+
+    /// Registers tasks of a job that existed before the subscription was created.
+    pub fn add_job_tasks(
+        &mut self,
+        job_id: JobId,
+        base_task_id: TakoTaskId,
+        n_tasks: JobTaskCount,
+    ) {
+        self.matcher.add_job_tasks(job_id, base_task_id, n_tasks);
+    }
+
+    /// Returns `true` if the event passes the filter of the subscriber.
+    pub fn matches(&mut self, event: &MonitoringEvent) -> bool {
+        self.matcher.matches(&event.payload)
+    }
+
+    /// Sends the event to the subscriber if it passes its filter.
+    /// Returns `false` if the subscriber has disconnected or if it has fallen too far behind.
+    pub fn send(&mut self, event: &MonitoringEvent) -> bool {
+        if !self.matches(event) {
+            return true;
+        }
+        match self.sender.try_send(event.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                log::warn!(
+                    "Event subscriber has not received {SUBSCRIBER_QUEUE_SIZE} events, disconnecting it"
+                );
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::channel;
+
+    use crate::server::event::filter::tests::task_finished;
+    use crate::server::event::subscription::EventSubscriber;
+
+    #[test]
+    fn send_to_closed_subscriber() {
+        let (tx, rx) = channel(10);
+        let mut subscriber = EventSubscriber::new(Default::default(), tx);
+        assert!(subscriber.send(&task_finished(1)));
+        drop(rx);
+        assert!(!subscriber.send(&task_finished(1)));
+    }
+
+    #[test]
+    fn send_to_full_subscriber() {
+        let (tx, mut rx) = channel(2);
+        let mut subscriber = EventSubscriber::new(Default::default(), tx);
+        assert!(subscriber.send(&task_finished(1)));
+        assert!(subscriber.send(&task_finished(2)));
+        assert!(!subscriber.send(&task_finished(3)));
+        drop(subscriber);
+
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+    }
+}
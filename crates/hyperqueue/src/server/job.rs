@@ -12,10 +12,10 @@ use chrono::{DateTime, Utc};
 use smallvec::SmallVec;
 use std::path::PathBuf;
 use tako::comm::deserialize;
-use tako::task::SerializedTaskContext;
-use tako::ItemId;
+use tako::task::{SerializedTaskContext, TaskResourceUsage};
 use tako::Set;
 use tako::TaskId;
+use tako::{InstanceId, ItemId};
 use tokio::sync::oneshot;
 
 /// State of a task that has been started at least once.
@@ -25,6 +25,7 @@ pub struct StartedTaskData {
     pub start_date: DateTime<Utc>,
     pub context: RunningTaskContext,
     pub worker_ids: SmallVec<[WorkerId; 1]>,
+    pub instance_id: InstanceId,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -36,11 +37,17 @@ pub enum JobTaskState {
     Finished {
         started_data: StartedTaskData,
         end_date: DateTime<Utc>,
+        /// Resources consumed by the task, if they were measured by the worker
+        resource_usage: Option<TaskResourceUsage>,
+        /// Note about the execution of the task (e.g. that its output was truncated)
+        note: Option<String>,
     },
     Failed {
         started_data: StartedTaskData,
         end_date: DateTime<Utc>,
         error: String,
+        /// Resources consumed by the task, if they were measured by the worker
+        resource_usage: Option<TaskResourceUsage>,
     },
     Canceled {
         started_data: Option<StartedTaskData>,
@@ -118,6 +125,8 @@ pub struct Job {
     pub tasks: Map<TakoTaskId, JobTaskInfo>,
 
     pub log: Option<PathBuf>,
+    /// Command executed by the server when the job completes
+    pub on_finish: Option<String>,
 
     pub job_desc: JobDescription,
     pub name: String,
@@ -143,6 +152,7 @@ impl Job {
         name: String,
         max_fails: Option<JobTaskCount>,
         log: Option<PathBuf>,
+        on_finish: Option<String>,
         submit_dir: PathBuf,
     ) -> Self {
         let base = base_task_id.as_num();
@@ -184,6 +194,7 @@ impl Job {
             tasks,
             max_fails,
             log,
+            on_finish,
             submission_date: Utc::now(),
             completion_date: None,
             submit_dir,
@@ -245,6 +256,10 @@ impl Job {
         self.counters.is_terminated(self.n_tasks())
     }
 
+    pub fn job_task_id(&self, tako_task_id: TakoTaskId) -> JobTaskId {
+        self.tasks[&tako_task_id].task_id
+    }
+
     pub fn get_task_state_mut(
         &mut self,
         tako_task_id: TakoTaskId,
@@ -277,6 +292,7 @@ impl Job {
         tako_task_id: TakoTaskId,
         workers: SmallVec<[WorkerId; 1]>,
         context: SerializedTaskContext,
+        instance_id: InstanceId,
     ) {
         let (_, state) = self.get_task_state_mut(tako_task_id);
 
@@ -289,6 +305,7 @@ impl Job {
                     start_date: Utc::now(),
                     context,
                     worker_ids: workers,
+                    instance_id,
                 },
             };
             self.counters.n_running_tasks += 1;
@@ -309,7 +326,13 @@ impl Job {
         }
     }
 
-    pub fn set_finished_state(&mut self, tako_task_id: TakoTaskId, backend: &Backend) {
+    pub fn set_finished_state(
+        &mut self,
+        tako_task_id: TakoTaskId,
+        resource_usage: Option<TaskResourceUsage>,
+        note: Option<String>,
+        backend: &Backend,
+    ) {
         let (_, state) = self.get_task_state_mut(tako_task_id);
         let now = Utc::now();
         match state {
@@ -317,6 +340,8 @@ impl Job {
                 *state = JobTaskState::Finished {
                     started_data: started_data.clone(),
                     end_date: now,
+                    resource_usage,
+                    note,
                 };
                 self.counters.n_running_tasks -= 1;
                 self.counters.n_finished_tasks += 1;
@@ -326,29 +351,44 @@ impl Job {
         self.check_termination(backend, now);
     }
 
-    pub fn set_waiting_state(&mut self, tako_task_id: TakoTaskId) {
+    /// Returns the data of the task instance that was running before.
+    pub fn set_waiting_state(&mut self, tako_task_id: TakoTaskId) -> StartedTaskData {
         let (_, state) = self.get_task_state_mut(tako_task_id);
-        assert!(matches!(state, JobTaskState::Running { .. }));
-        *state = JobTaskState::Waiting;
+        let started_data = match std::mem::replace(state, JobTaskState::Waiting) {
+            JobTaskState::Running { started_data } => started_data,
+            state => panic!("Invalid task state, expected Running, got {:?}", state),
+        };
         self.counters.n_running_tasks -= 1;
+        started_data
     }
 
-    pub fn set_failed_state(&mut self, tako_task_id: TakoTaskId, error: String, backend: &Backend) {
+    /// Returns the instance of the task that has failed.
+    pub fn set_failed_state(
+        &mut self,
+        tako_task_id: TakoTaskId,
+        error: String,
+        resource_usage: Option<TaskResourceUsage>,
+        backend: &Backend,
+    ) -> InstanceId {
         let (_, state) = self.get_task_state_mut(tako_task_id);
         let now = Utc::now();
-        match state {
+        let instance_id = match state {
             JobTaskState::Running { started_data } => {
+                let instance_id = started_data.instance_id;
                 *state = JobTaskState::Failed {
                     error,
                     started_data: started_data.clone(),
                     end_date: now,
+                    resource_usage,
                 };
                 self.counters.n_running_tasks -= 1;
                 self.counters.n_failed_tasks += 1;
+                instance_id
             }
             _ => panic!("Invalid worker state, expected Running, got {:?}", state),
-        }
+        };
         self.check_termination(backend, now);
+        instance_id
     }
 
     pub fn set_cancel_state(&mut self, tako_task_id: TakoTaskId, backend: &Backend) -> JobTaskId {
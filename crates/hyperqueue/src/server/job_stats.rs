@@ -0,0 +1,423 @@
+//! Statistics of task execution within a job.
+//!
+//! Statistics can be computed either from the current state of a job on the server, or offline
+//! by replaying events from an event log file (see [`EventLogJobStats`]).
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::client::status::Status;
+use crate::server::event::events::{MonitoringEventPayload, TaskIds};
+use crate::server::event::MonitoringEvent;
+use crate::server::job::{Job, JobTaskCounters, JobTaskState};
+use crate::transfer::messages::{
+    DurationStats, IdSelector, JobStats, JobStatsResponse, ThroughputInterval,
+};
+use crate::{JobId, JobTaskCount, TakoTaskId, WorkerId};
+
+/// Maximum number of intervals of the throughput histogram.
+const THROUGHPUT_INTERVALS: u32 = 10;
+
+/// Execution state of a single task, as needed for computing statistics.
+#[derive(Clone)]
+pub struct TaskRecord {
+    pub status: Status,
+    pub start_date: Option<DateTime<Utc>>,
+    pub end_date: Option<DateTime<Utc>>,
+    pub worker_id: Option<WorkerId>,
+}
+
+impl Default for TaskRecord {
+    fn default() -> Self {
+        Self {
+            status: Status::Waiting,
+            start_date: None,
+            end_date: None,
+            worker_id: None,
+        }
+    }
+}
+
+impl From<&JobTaskState> for TaskRecord {
+    fn from(state: &JobTaskState) -> Self {
+        let started_data = state.started_data();
+        let (status, end_date) = match state {
+            JobTaskState::Waiting => (Status::Waiting, None),
+            JobTaskState::Running { .. } => (Status::Running, None),
+            JobTaskState::Finished { end_date, .. } => (Status::Finished, Some(*end_date)),
+            JobTaskState::Failed { end_date, .. } => (Status::Failed, Some(*end_date)),
+            JobTaskState::Canceled { cancelled_date, .. } => {
+                (Status::Canceled, Some(*cancelled_date))
+            }
+        };
+        Self {
+            status,
+            start_date: started_data.map(|data| data.start_date),
+            end_date,
+            worker_id: started_data.and_then(|data| data.worker_ids.first().copied()),
+        }
+    }
+}
+
+pub fn compute_job_stats_from_job(job: &Job, now: DateTime<Utc>) -> JobStats {
+    let tasks: Vec<TaskRecord> = job
+        .tasks
+        .values()
+        .map(|task| TaskRecord::from(&task.state))
+        .collect();
+    compute_job_stats(
+        job.job_id,
+        job.name.clone(),
+        job.submission_date,
+        &tasks,
+        now,
+    )
+}
+
+/// Computes statistics of a job from the records of its tasks.
+/// `now` is used as the current time for estimating the remaining time of unfinished jobs.
+pub fn compute_job_stats(
+    job_id: JobId,
+    name: String,
+    submission_date: DateTime<Utc>,
+    tasks: &[TaskRecord],
+    now: DateTime<Utc>,
+) -> JobStats {
+    let mut counters = JobTaskCounters::default();
+    let mut durations = vec![];
+    let mut wait_times = vec![];
+    let mut completion_dates = vec![];
+    let mut failures_per_worker: BTreeMap<WorkerId, JobTaskCount> = Default::default();
+
+    for task in tasks {
+        match task.status {
+            Status::Waiting => {}
+            Status::Running => counters.n_running_tasks += 1,
+            Status::Finished => counters.n_finished_tasks += 1,
+            Status::Failed => counters.n_failed_tasks += 1,
+            Status::Canceled => counters.n_canceled_tasks += 1,
+        }
+        if let Some(start_date) = task.start_date {
+            wait_times.push(to_duration(start_date - submission_date));
+        }
+        if matches!(task.status, Status::Finished | Status::Failed) {
+            if let Some(end_date) = task.end_date {
+                completion_dates.push(end_date);
+                if let Some(start_date) = task.start_date {
+                    durations.push(to_duration(end_date - start_date));
+                }
+            }
+        }
+        if let (Status::Failed, Some(worker_id)) = (&task.status, task.worker_id) {
+            *failures_per_worker.entry(worker_id).or_default() += 1;
+        }
+    }
+
+    let n_tasks = tasks.len() as JobTaskCount;
+    let first_start = tasks.iter().filter_map(|task| task.start_date).min();
+    let remaining = counters.n_running_tasks + counters.n_waiting_tasks(n_tasks);
+    let end = if remaining == 0 {
+        completion_dates.iter().max().copied().unwrap_or(now)
+    } else {
+        now
+    };
+
+    let estimated_time_to_completion = if remaining == 0 {
+        Some(Duration::ZERO)
+    } else {
+        first_start.and_then(|start| {
+            let elapsed = to_duration(end - start).as_secs_f64();
+            let completed = completion_dates.len() as f64;
+            (completed > 0.0 && elapsed > 0.0)
+                .then(|| Duration::from_secs_f64(remaining as f64 * elapsed / completed))
+        })
+    };
+
+    JobStats {
+        job_id,
+        name,
+        n_tasks,
+        counters,
+        task_duration: DurationStats::from_durations(durations),
+        wait_time: DurationStats::from_durations(wait_times),
+        throughput: first_start
+            .map(|start| compute_throughput(start, end, &completion_dates))
+            .unwrap_or_default(),
+        failures_per_worker: failures_per_worker.into_iter().collect(),
+        estimated_time_to_completion,
+    }
+}
+
+/// Splits the time between `start` and `end` into (at most [`THROUGHPUT_INTERVALS`]) intervals
+/// and counts how many tasks have completed in each of them.
+fn compute_throughput(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    completion_dates: &[DateTime<Utc>],
+) -> Vec<ThroughputInterval> {
+    let span = to_duration(end - start);
+    let interval = Duration::from_secs(
+        ((span.as_secs_f64() / THROUGHPUT_INTERVALS as f64).ceil() as u64).max(1),
+    );
+    let count = ((span.as_secs_f64() / interval.as_secs_f64()).ceil() as u32).max(1);
+
+    let mut intervals: Vec<ThroughputInterval> = (0..count)
+        .map(|index| ThroughputInterval {
+            start: start + chrono::Duration::from_std(interval * index).unwrap(),
+            duration: interval,
+            n_completed_tasks: 0,
+        })
+        .collect();
+    for date in completion_dates {
+        let offset = to_duration(*date - start);
+        let index =
+            ((offset.as_secs_f64() / interval.as_secs_f64()) as usize).min(intervals.len() - 1);
+        intervals[index].n_completed_tasks += 1;
+    }
+    intervals
+}
+
+impl DurationStats {
+    fn from_durations(mut durations: Vec<Duration>) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort_unstable();
+        // Nearest-rank percentile
+        let percentile = |p: f64| {
+            let rank = (p * durations.len() as f64).ceil() as usize;
+            durations[rank.clamp(1, durations.len()) - 1]
+        };
+        Some(Self {
+            min: durations[0],
+            median: percentile(0.5),
+            p95: percentile(0.95),
+            max: durations[durations.len() - 1],
+        })
+    }
+}
+
+fn to_duration(duration: chrono::Duration) -> Duration {
+    duration.to_std().unwrap_or_default()
+}
+
+struct EventLogJob {
+    name: String,
+    submission_date: DateTime<Utc>,
+    base_task_id: TakoTaskId,
+    tasks: Vec<TaskRecord>,
+}
+
+/// Reconstructs task states of jobs from events stored in an event log file, so that
+/// job statistics can be computed without a running server.
+#[derive(Default)]
+pub struct EventLogJobStats {
+    jobs: BTreeMap<JobId, EventLogJob>,
+    /// Maps the first Tako task ID of each job to its job
+    base_task_ids: BTreeMap<TakoTaskId, JobId>,
+    last_time: Option<DateTime<Utc>>,
+}
+
+impl EventLogJobStats {
+    pub fn add_event(&mut self, event: &MonitoringEvent) {
+        let time: DateTime<Utc> = event.time.into();
+        self.last_time = Some(self.last_time.map_or(time, |last| last.max(time)));
+
+        match &event.payload {
+            MonitoringEventPayload::JobCreated(job_id, info) => {
+                self.base_task_ids.insert(info.base_task_id, *job_id);
+                self.jobs.insert(
+                    *job_id,
+                    EventLogJob {
+                        name: info.name.clone(),
+                        submission_date: info.submission_date,
+                        base_task_id: info.base_task_id,
+                        tasks: vec![TaskRecord::default(); info.task_ids.len()],
+                    },
+                );
+            }
+            MonitoringEventPayload::TaskStarted {
+                task_id, worker_id, ..
+            } => {
+                if let Some(task) = self.get_task(*task_id) {
+                    *task = TaskRecord {
+                        status: Status::Running,
+                        start_date: Some(time),
+                        end_date: None,
+                        worker_id: Some(*worker_id),
+                    };
+                }
+            }
+            MonitoringEventPayload::TaskFinished { task_id, .. } => {
+                self.end_task(*task_id, Status::Finished, time);
+            }
+            MonitoringEventPayload::TaskFailed {
+                task: TaskIds { task_id, .. },
+                ..
+            } => {
+                self.end_task(*task_id, Status::Failed, time);
+            }
+            MonitoringEventPayload::TaskCanceled(TaskIds { task_id, .. }) => {
+                self.end_task(*task_id, Status::Canceled, time);
+            }
+            MonitoringEventPayload::TaskRestarted {
+                task: TaskIds { task_id, .. },
+                ..
+            } => {
+                if let Some(task) = self.get_task(*task_id) {
+                    *task = TaskRecord::default();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Computes statistics of the selected jobs.
+    /// The time of the last event is used as the current time.
+    pub fn compute(&self, selector: &IdSelector) -> JobStatsResponse {
+        let job_ids: Vec<JobId> = match selector {
+            IdSelector::All => self.jobs.keys().copied().collect(),
+            IdSelector::LastN(n) => {
+                let mut ids: Vec<JobId> =
+                    self.jobs.keys().rev().take(*n as usize).copied().collect();
+                ids.reverse();
+                ids
+            }
+            IdSelector::Specific(array) => array.iter().map(JobId::new).collect(),
+        };
+        let now = self.last_time.unwrap_or_else(Utc::now);
+        job_ids
+            .into_iter()
+            .map(|job_id| {
+                let stats = self.jobs.get(&job_id).map(|job| {
+                    compute_job_stats(
+                        job_id,
+                        job.name.clone(),
+                        job.submission_date,
+                        &job.tasks,
+                        now,
+                    )
+                });
+                (job_id, stats)
+            })
+            .collect()
+    }
+
+    fn get_task(&mut self, task_id: TakoTaskId) -> Option<&mut TaskRecord> {
+        let (_, job_id) = self.base_task_ids.range(..=task_id).next_back()?;
+        let job = self.jobs.get_mut(job_id)?;
+        let index = task_id.as_num().checked_sub(job.base_task_id.as_num())? as usize;
+        job.tasks.get_mut(index)
+    }
+
+    fn end_task(&mut self, task_id: TakoTaskId, status: Status, time: DateTime<Utc>) {
+        if let Some(task) = self.get_task(task_id) {
+            task.status = status;
+            task.end_date = Some(time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use crate::client::status::Status;
+    use crate::server::job_stats::{compute_job_stats, TaskRecord};
+    use crate::transfer::messages::DurationStats;
+
+    fn time(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp(1_000_000 + secs, 0)
+    }
+
+    fn task(status: Status, start: Option<i64>, end: Option<i64>, worker: u32) -> TaskRecord {
+        TaskRecord {
+            status,
+            start_date: start.map(time),
+            end_date: end.map(time),
+            worker_id: start.map(|_| worker.into()),
+        }
+    }
+
+    #[test]
+    fn duration_percentiles() {
+        let durations: Vec<Duration> = (1..=100).map(Duration::from_secs).collect();
+        let stats = DurationStats::from_durations(durations).unwrap();
+        assert_eq!(stats.min, Duration::from_secs(1));
+        assert_eq!(stats.median, Duration::from_secs(50));
+        assert_eq!(stats.p95, Duration::from_secs(95));
+        assert_eq!(stats.max, Duration::from_secs(100));
+
+        let stats = DurationStats::from_durations(vec![Duration::from_secs(3)]).unwrap();
+        assert_eq!(stats.median, Duration::from_secs(3));
+        assert_eq!(stats.p95, Duration::from_secs(3));
+        assert!(DurationStats::from_durations(vec![]).is_none());
+    }
+
+    #[test]
+    fn stats_of_running_job() {
+        let tasks = vec![
+            task(Status::Finished, Some(10), Some(20), 1),
+            task(Status::Failed, Some(10), Some(30), 2),
+            task(Status::Failed, Some(20), Some(40), 2),
+            task(Status::Running, Some(40), None, 1),
+            task(Status::Waiting, None, None, 0),
+        ];
+        let stats = compute_job_stats(1.into(), "job".to_string(), time(0), &tasks, time(50));
+        assert_eq!(stats.n_tasks, 5);
+        assert_eq!(stats.counters.n_finished_tasks, 1);
+        assert_eq!(stats.counters.n_failed_tasks, 2);
+        assert_eq!(stats.counters.n_running_tasks, 1);
+
+        let duration = stats.task_duration.unwrap();
+        assert_eq!(duration.min, Duration::from_secs(10));
+        assert_eq!(duration.median, Duration::from_secs(20));
+        assert_eq!(duration.max, Duration::from_secs(20));
+
+        let wait_time = stats.wait_time.unwrap();
+        assert_eq!(wait_time.min, Duration::from_secs(10));
+        assert_eq!(wait_time.max, Duration::from_secs(40));
+
+        assert_eq!(stats.failures_per_worker, vec![(2.into(), 2)]);
+
+        // 3 tasks completed in 40 seconds, 2 tasks remain
+        let estimate = stats.estimated_time_to_completion.unwrap();
+        assert!((estimate.as_secs_f64() - 80.0 / 3.0).abs() < 1e-6);
+
+        let throughput: Vec<_> = stats
+            .throughput
+            .iter()
+            .map(|interval| interval.n_completed_tasks)
+            .collect();
+        assert_eq!(throughput, vec![0, 0, 1, 0, 0, 1, 0, 1, 0, 0]);
+        assert!(stats
+            .throughput
+            .iter()
+            .all(|interval| interval.duration == Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn stats_of_finished_job() {
+        let tasks = vec![
+            task(Status::Finished, Some(0), Some(5), 1),
+            task(Status::Canceled, None, Some(5), 0),
+        ];
+        let stats = compute_job_stats(1.into(), "job".to_string(), time(0), &tasks, time(100));
+        assert_eq!(stats.estimated_time_to_completion, Some(Duration::ZERO));
+        assert_eq!(stats.throughput.len(), 5);
+        assert_eq!(stats.throughput[4].n_completed_tasks, 1);
+    }
+
+    #[test]
+    fn stats_without_started_tasks() {
+        let tasks = vec![task(Status::Waiting, None, None, 0)];
+        let stats = compute_job_stats(1.into(), "job".to_string(), time(0), &tasks, time(100));
+        assert!(stats.task_duration.is_none());
+        assert!(stats.wait_time.is_none());
+        assert!(stats.throughput.is_empty());
+        assert!(stats.estimated_time_to_completion.is_none());
+    }
+}
@@ -0,0 +1,511 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use tako::gateway::{FromGatewayMessage, ServerInfo, ToGatewayMessage};
+use tako::worker::TaskResourceAllocationValue;
+use tako::{Map, TaskGroup};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::client::status::{job_status, Status};
+use crate::server::autoalloc::{Allocation, AllocationState, QueueId};
+use crate::server::job::JobTaskCounters;
+use crate::server::rpc::Backend;
+use crate::server::state::{State, StateRef};
+use crate::transfer::messages::{QueueData, QueueState};
+
+/// Maximum size of the header of a HTTP request.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// How long do we wait for a client to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long do we wait before accepting connections again after a failed accept (e.g. when the
+/// server has run out of file descriptors).
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_millis(100);
+
+/*
+    Metrics are served over plain HTTP in the Prometheus text format (version 0.0.4).
+    Only `GET /metrics` is supported, the connection is closed after each response.
+*/
+
+pub async fn serve_metrics(listener: TcpListener, state_ref: StateRef, backend: Backend) {
+    let group = TaskGroup::default();
+    loop {
+        let connection = match group.run_until(listener.accept()).await {
+            Ok((connection, _)) => connection,
+            Err(error) => {
+                log::warn!("Cannot accept metrics connection: {error:?}");
+                group
+                    .run_until(tokio::time::sleep(ACCEPT_ERROR_DELAY))
+                    .await;
+                continue;
+            }
+        };
+        let state_ref = state_ref.clone();
+        let backend = backend.clone();
+        group.add_task(async move {
+            if let Err(e) = handle_request(connection, &state_ref, &backend).await {
+                log::debug!("Metrics request failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle_request(
+    mut socket: TcpStream,
+    state_ref: &StateRef,
+    backend: &Backend,
+) -> anyhow::Result<()> {
+    let request = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut socket)).await??;
+    let response = match parse_request_path(&request) {
+        Some(("GET", "/metrics")) => {
+            let metrics = gather_metrics(state_ref, backend).await?;
+            http_response("200 OK", "text/plain; version=0.0.4", &metrics)
+        }
+        Some(("GET", _)) => http_response("404 Not Found", "text/plain", "Not found\n"),
+        _ => http_response(
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n",
+        ),
+    };
+    socket.write_all(response.as_bytes()).await?;
+    socket.shutdown().await?;
+    Ok(())
+}
+
+/// Reads the request line and headers of a HTTP request.
+async fn read_request(socket: &mut TcpStream) -> anyhow::Result<String> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 1024];
+    while !buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+        let size = socket.read(&mut chunk).await?;
+        if size == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..size]);
+        if buffer.len() > MAX_REQUEST_SIZE {
+            anyhow::bail!("Request is too large");
+        }
+    }
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+/// Returns the method and the path (without query) of a HTTP request.
+fn parse_request_path(request: &str) -> Option<(&str, &str)> {
+    let mut parts = request.lines().next()?.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    let path = target.split('?').next().unwrap_or(target);
+    Some((method, path))
+}
+
+fn http_response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+async fn gather_metrics(state_ref: &StateRef, backend: &Backend) -> anyhow::Result<String> {
+    let server_info = match backend
+        .send_tako_message(FromGatewayMessage::ServerInfo)
+        .await?
+    {
+        ToGatewayMessage::ServerInfo(info) => info,
+        message => anyhow::bail!("Unexpected response from the scheduler: {message:?}"),
+    };
+
+    let queues = state_ref.get().autoalloc().get_queues();
+    let queues = queues.await;
+    let mut allocations = Map::new();
+    for queue_id in queues.keys() {
+        let queue_allocations = state_ref.get().autoalloc().get_allocations(*queue_id);
+        // The queue might have been removed in the meantime
+        if let Ok(queue_allocations) = queue_allocations.await {
+            allocations.insert(*queue_id, queue_allocations);
+        }
+    }
+
+    Ok(render_metrics(
+        &state_ref.get(),
+        &server_info,
+        &queues,
+        &allocations,
+    ))
+}
+
+fn render_metrics(
+    state: &State,
+    server_info: &ServerInfo,
+    queues: &Map<QueueId, QueueData>,
+    allocations: &Map<QueueId, Vec<Allocation>>,
+) -> String {
+    let mut writer = MetricsWriter::default();
+
+    // Jobs and tasks
+    let mut counters = JobTaskCounters::default();
+    let mut n_waiting_tasks = 0;
+    let mut job_counts: Map<&str, u64> = Map::new();
+    for job in state.jobs() {
+        counters = counters + job.counters;
+        n_waiting_tasks += job.counters.n_waiting_tasks(job.n_tasks());
+        *job_counts
+            .entry(status_name(&job_status(&job.make_job_info())))
+            .or_default() += 1;
+    }
+    writer.family("hq_tasks", "gauge", "Number of tasks by their state");
+    for (state, count) in [
+        ("waiting", n_waiting_tasks),
+        ("running", counters.n_running_tasks),
+        ("finished", counters.n_finished_tasks),
+        ("failed", counters.n_failed_tasks),
+        ("canceled", counters.n_canceled_tasks),
+    ] {
+        writer.sample("hq_tasks", &[("state", state)], count);
+    }
+    writer.family("hq_jobs", "gauge", "Number of jobs by their status");
+    for status in ["waiting", "running", "finished", "failed", "canceled"] {
+        let count = job_counts.get(status).copied().unwrap_or(0);
+        writer.sample("hq_jobs", &[("status", status)], count as f64);
+    }
+
+    // Workers
+    let workers: Vec<_> = state
+        .get_workers()
+        .values()
+        .filter(|worker| worker.is_running())
+        .collect();
+    writer.family(
+        "hq_workers_connected",
+        "gauge",
+        "Number of connected workers",
+    );
+    writer.sample("hq_workers_connected", &[], workers.len() as f64);
+
+    writer.family(
+        "hq_worker_running_tasks",
+        "gauge",
+        "Number of tasks running on a worker",
+    );
+    writer.family(
+        "hq_worker_allocated_resources",
+        "gauge",
+        "Amount of resources of a worker allocated to running tasks",
+    );
+    writer.family(
+        "hq_worker_cpu_usage_ratio",
+        "gauge",
+        "Average utilization of the CPU cores of a worker",
+    );
+    writer.family(
+        "hq_worker_memory_total_bytes",
+        "gauge",
+        "Total memory of a worker",
+    );
+    writer.family(
+        "hq_worker_memory_used_bytes",
+        "gauge",
+        "Used memory of a worker",
+    );
+    writer.family(
+        "hq_worker_network_received_bytes_total",
+        "counter",
+        "Bytes received by a worker",
+    );
+    writer.family(
+        "hq_worker_network_transmitted_bytes_total",
+        "counter",
+        "Bytes transmitted by a worker",
+    );
+    for worker in workers {
+        let overview = match worker.overview() {
+            Some(overview) => overview,
+            None => continue,
+        };
+        let worker_id = worker.worker_id().to_string();
+        let labels = [
+            ("worker_id", worker_id.as_str()),
+            ("hostname", worker.configuration().hostname.as_str()),
+        ];
+        writer.sample(
+            "hq_worker_running_tasks",
+            &labels,
+            overview.running_tasks.len() as f64,
+        );
+
+        let mut allocated: Map<&str, u64> = Map::new();
+        for (_, allocation) in &overview.running_tasks {
+            for resource in &allocation.resources {
+                let amount = match &resource.value {
+                    TaskResourceAllocationValue::Indices(indices) => indices.len() as u64,
+                    TaskResourceAllocationValue::Sum(amount) => *amount,
+                };
+                *allocated.entry(resource.resource.as_str()).or_default() += amount;
+            }
+        }
+        for descriptor in &worker.configuration().resources.resources {
+            let amount = allocated
+                .get(descriptor.name.as_str())
+                .copied()
+                .unwrap_or(0);
+            writer.sample(
+                "hq_worker_allocated_resources",
+                &[labels[0], labels[1], ("resource", descriptor.name.as_str())],
+                amount as f64,
+            );
+        }
+
+        if let Some(hw_state) = &overview.hw_state {
+            let hw_state = &hw_state.state;
+            let cpu_usage = &hw_state.worker_cpu_usage.cpu_per_core_percent_usage;
+            if !cpu_usage.is_empty() {
+                let average = cpu_usage.iter().map(|usage| *usage as f64).sum::<f64>()
+                    / cpu_usage.len() as f64;
+                writer.sample("hq_worker_cpu_usage_ratio", &labels, average / 100.0);
+            }
+            let memory = &hw_state.worker_memory_usage;
+            writer.sample("hq_worker_memory_total_bytes", &labels, memory.total as f64);
+            writer.sample(
+                "hq_worker_memory_used_bytes",
+                &labels,
+                memory.total.saturating_sub(memory.free) as f64,
+            );
+            let network = &hw_state.worker_network_usage;
+            writer.sample(
+                "hq_worker_network_received_bytes_total",
+                &labels,
+                network.rx_bytes as f64,
+            );
+            writer.sample(
+                "hq_worker_network_transmitted_bytes_total",
+                &labels,
+                network.tx_bytes as f64,
+            );
+        }
+    }
+
+    // Automatic allocation
+    writer.family(
+        "hq_autoalloc_queues",
+        "gauge",
+        "Number of allocation queues by their state",
+    );
+    let n_paused = queues
+        .values()
+        .filter(|queue| matches!(queue.state, QueueState::Paused))
+        .count();
+    writer.sample(
+        "hq_autoalloc_queues",
+        &[("state", "running")],
+        (queues.len() - n_paused) as f64,
+    );
+    writer.sample(
+        "hq_autoalloc_queues",
+        &[("state", "paused")],
+        n_paused as f64,
+    );
+    writer.family(
+        "hq_autoalloc_allocations",
+        "gauge",
+        "Number of allocations of an allocation queue by their state",
+    );
+    for (queue_id, allocations) in allocations {
+        let mut counts: Map<&str, u64> = Map::new();
+        for allocation in allocations {
+            *counts
+                .entry(allocation_state_name(&allocation.status))
+                .or_default() += 1;
+        }
+        let queue_id = queue_id.to_string();
+        for state in ["queued", "running", "finished", "failed"] {
+            writer.sample(
+                "hq_autoalloc_allocations",
+                &[("queue_id", queue_id.as_str()), ("state", state)],
+                counts.get(state).copied().unwrap_or(0) as f64,
+            );
+        }
+    }
+
+    // Scheduler
+    let scheduler = &server_info.scheduler;
+    writer.family(
+        "hq_scheduler_runs_total",
+        "counter",
+        "Number of scheduler invocations",
+    );
+    writer.sample("hq_scheduler_runs_total", &[], scheduler.runs as f64);
+    writer.family(
+        "hq_scheduler_duration_seconds_total",
+        "counter",
+        "Total time spent in the scheduler",
+    );
+    writer.sample(
+        "hq_scheduler_duration_seconds_total",
+        &[],
+        scheduler.total_duration.as_secs_f64(),
+    );
+    writer.family(
+        "hq_scheduler_last_duration_seconds",
+        "gauge",
+        "Duration of the last scheduler invocation",
+    );
+    writer.sample(
+        "hq_scheduler_last_duration_seconds",
+        &[],
+        scheduler.last_duration.as_secs_f64(),
+    );
+
+    // Events
+    let events = state.event_storage();
+    writer.family(
+        "hq_events_stored",
+        "gauge",
+        "Number of events stored in the memory of the server",
+    );
+    writer.sample("hq_events_stored", &[], events.len() as f64);
+    writer.family(
+        "hq_events_total",
+        "counter",
+        "Number of events created by the server",
+    );
+    writer.sample("hq_events_total", &[], events.event_count() as f64);
+
+    writer.finish()
+}
+
+fn status_name(status: &Status) -> &'static str {
+    match status {
+        Status::Waiting => "waiting",
+        Status::Running => "running",
+        Status::Finished => "finished",
+        Status::Failed => "failed",
+        Status::Canceled => "canceled",
+    }
+}
+
+fn allocation_state_name(state: &AllocationState) -> &'static str {
+    match state {
+        AllocationState::Queued => "queued",
+        AllocationState::Running { .. } => "running",
+        AllocationState::Finished { .. } => "finished",
+        AllocationState::Invalid { .. } => "failed",
+    }
+}
+
+/// Writes metrics in the Prometheus text format.
+/// Samples of a metric family are always written together, even if they are not added in order.
+#[derive(Default)]
+struct MetricsWriter {
+    families: Vec<MetricFamily>,
+}
+
+struct MetricFamily {
+    name: String,
+    kind: &'static str,
+    help: &'static str,
+    samples: String,
+}
+
+impl MetricsWriter {
+    fn family(&mut self, name: &str, kind: &'static str, help: &'static str) {
+        self.families.push(MetricFamily {
+            name: name.to_string(),
+            kind,
+            help,
+            samples: String::new(),
+        });
+    }
+
+    fn sample<V: Into<f64>>(&mut self, name: &str, labels: &[(&str, &str)], value: V) {
+        let family = self
+            .families
+            .iter_mut()
+            .find(|family| family.name == name)
+            .expect("Metric family was not defined");
+        let output = &mut family.samples;
+        output.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{key}=\"{}\"", escape_label_value(value)))
+                .collect();
+            write!(output, "{{{}}}", labels.join(",")).unwrap();
+        }
+        writeln!(output, " {}", value.into()).unwrap();
+    }
+
+    fn finish(self) -> String {
+        let mut output = String::new();
+        for family in self.families {
+            writeln!(output, "# HELP {} {}", family.name, family.help).unwrap();
+            writeln!(output, "# TYPE {} {}", family.name, family.kind).unwrap();
+            output.push_str(&family.samples);
+        }
+        output
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use tako::gateway::ServerInfo;
+    use tako::Map;
+
+    use crate::server::metrics::{parse_request_path, render_metrics, MetricsWriter};
+    use crate::tests::utils::create_hq_state;
+
+    #[test]
+    fn write_metrics() {
+        let mut writer = MetricsWriter::default();
+        writer.family("hq_foo", "gauge", "Foo");
+        writer.family("hq_bar", "counter", "Bar");
+        writer.sample("hq_foo", &[], 1);
+        writer.sample("hq_bar", &[], 2);
+        writer.sample("hq_foo", &[("a", "x\"y\\z"), ("b", "1")], 0.5);
+        assert_eq!(
+            writer.finish(),
+            r#"# HELP hq_foo Foo
+# TYPE hq_foo gauge
+hq_foo 1
+hq_foo{a="x\"y\\z",b="1"} 0.5
+# HELP hq_bar Bar
+# TYPE hq_bar counter
+hq_bar 2
+"#
+        );
+    }
+
+    #[test]
+    fn parse_request() {
+        assert_eq!(
+            parse_request_path("GET /metrics?x=1 HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+            Some(("GET", "/metrics"))
+        );
+        assert_eq!(parse_request_path(""), None);
+    }
+
+    #[test]
+    fn render_empty_state() {
+        let state = create_hq_state();
+        let server_info = ServerInfo {
+            worker_listen_port: 0,
+            scheduler: Default::default(),
+            n_tasks: 0,
+        };
+        let metrics = render_metrics(&state.get(), &server_info, &Map::new(), &Map::new());
+        assert!(metrics.contains("hq_tasks{state=\"waiting\"} 0\n"));
+        assert!(metrics.contains("hq_jobs{status=\"finished\"} 0\n"));
+        assert!(metrics.contains("hq_workers_connected 0\n"));
+        assert!(metrics.contains("hq_autoalloc_queues{state=\"paused\"} 0\n"));
+        assert!(metrics.contains("hq_scheduler_runs_total 0\n"));
+        assert!(metrics.contains("hq_events_total 0\n"));
+    }
+}
@@ -3,6 +3,9 @@ pub mod bootstrap;
 pub mod client;
 pub mod event;
 pub mod job;
+pub mod job_stats;
+pub mod metrics;
 pub mod rpc;
 pub mod state;
+pub mod stats_history;
 pub mod worker;
@@ -4,7 +4,7 @@ use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
 
 use orion::kdf::SecretKey;
-use tako::gateway::{FromGatewayMessage, ToGatewayMessage};
+use tako::gateway::{FromGatewayMessage, ToGatewayMessage, WorkerRequest};
 use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio::sync::oneshot;
 use tokio::time::Duration;
@@ -13,13 +13,23 @@ use crate::common::error::error;
 use crate::server::state::StateRef;
 use crate::stream::server::control::StreamServerControlMessage;
 use crate::stream::server::rpc::start_stream_server;
-use crate::WrappedRcRefCell;
+use crate::transfer::messages::{WorkerRpcRequest, WorkerRpcResponse};
+use crate::{Map, WorkerId, WrappedRcRefCell};
+
+/// How long does the server wait for a response of a worker to a request.
+const WORKER_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+type WorkerResponseSender = oneshot::Sender<Result<Vec<u8>, String>>;
 
 struct Inner {
     tako_sender: UnboundedSender<FromGatewayMessage>,
     tako_responses: VecDeque<oneshot::Sender<ToGatewayMessage>>,
     stream_server_control: UnboundedSender<StreamServerControlMessage>,
 
+    /// Requests sent to workers that wait for a response
+    worker_requests: Map<u64, (WorkerId, WorkerResponseSender)>,
+    worker_request_id: u64,
+
     worker_port: u16,
 }
 
@@ -46,6 +56,60 @@ impl Backend {
         Ok(rx.await.unwrap())
     }
 
+    /// Sends a request to a worker and waits for its response.
+    pub async fn send_worker_request(
+        &self,
+        worker_id: WorkerId,
+        request: &WorkerRpcRequest,
+    ) -> crate::Result<WorkerRpcResponse> {
+        let (sx, rx) = oneshot::channel();
+        let request_id = {
+            let mut inner = self.inner.get_mut();
+            inner.worker_request_id += 1;
+            let request_id = inner.worker_request_id;
+            inner.worker_requests.insert(request_id, (worker_id, sx));
+            request_id
+        };
+        let response = self
+            .send_tako_message(FromGatewayMessage::WorkerRequest(WorkerRequest {
+                worker_id,
+                request_id,
+                data: tako::comm::serialize(request)?,
+            }))
+            .await?;
+        if let ToGatewayMessage::Error(e) = response {
+            self.inner.get_mut().worker_requests.remove(&request_id);
+            return error(e.message);
+        }
+
+        let result = match tokio::time::timeout(WORKER_REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(format!("Worker {worker_id} was lost")),
+            Err(_) => {
+                self.inner.get_mut().worker_requests.remove(&request_id);
+                Err(format!("Worker {worker_id} did not respond in time"))
+            }
+        };
+        match result {
+            Ok(data) => Ok(tako::comm::deserialize(&data)?),
+            Err(e) => error(e),
+        }
+    }
+
+    fn on_worker_response(&self, request_id: u64, result: Result<Vec<u8>, String>) {
+        if let Some((_, sender)) = self.inner.get_mut().worker_requests.remove(&request_id) {
+            let _ = sender.send(result);
+        }
+    }
+
+    /// Drops requests of a lost worker, their senders are notified that the worker was lost.
+    fn on_worker_lost(&self, worker_id: WorkerId) {
+        self.inner
+            .get_mut()
+            .worker_requests
+            .retain(|_, (id, _)| *id != worker_id);
+    }
+
     pub fn send_stream_control(&self, message: StreamServerControlMessage) {
         assert!(self.inner.get().stream_server_control.send(message).is_ok())
     }
@@ -85,6 +149,8 @@ impl Backend {
                 tako_responses: Default::default(),
                 worker_port: server_ref.get_worker_listen_port(),
                 stream_server_control,
+                worker_requests: Default::default(),
+                worker_request_id: 0,
             }),
         };
         let server2 = server.clone();
@@ -104,14 +170,21 @@ impl Backend {
                         ToGatewayMessage::NewWorker(msg) => {
                             state_ref.get_mut().process_worker_new(msg)
                         }
-                        ToGatewayMessage::LostWorker(msg) => state_ref
-                            .get_mut()
-                            .process_worker_lost(&state_ref, &server2, msg),
-                        ToGatewayMessage::WorkerOverview(overview) => {
+                        ToGatewayMessage::LostWorker(msg) => {
+                            server2.on_worker_lost(msg.worker_id);
                             state_ref
                                 .get_mut()
-                                .event_storage_mut()
-                                .on_overview_received(overview);
+                                .process_worker_lost(&state_ref, &server2, msg)
+                        }
+                        ToGatewayMessage::WorkerResponse(msg) => {
+                            server2.on_worker_response(msg.request_id, msg.result)
+                        }
+                        ToGatewayMessage::WorkerOverview(overview) => {
+                            let mut state = state_ref.get_mut();
+                            if let Some(worker) = state.get_worker_mut(overview.id) {
+                                worker.set_overview(overview.clone());
+                            }
+                            state.event_storage_mut().on_overview_received(overview);
                         }
                         ToGatewayMessage::NewTasksResponse(_)
                         | ToGatewayMessage::CancelTasksResponse(_)
@@ -119,6 +192,8 @@ impl Backend {
                         | ToGatewayMessage::Error(_)
                         | ToGatewayMessage::ServerInfo(_)
                         | ToGatewayMessage::WorkerStopped
+                        | ToGatewayMessage::IdleTimeoutExemptionUpdated
+                        | ToGatewayMessage::WorkerRequestSent
                         | ToGatewayMessage::NewWorkerAllocationQueryResponse(_) => {
                             let response =
                                 server2.inner.get_mut().tako_responses.pop_front().unwrap();
@@ -185,7 +260,7 @@ mod tests {
         run_concurrent(fut, async move {
             assert!(
                 matches!(server.send_tako_message(FromGatewayMessage::ServerInfo).await.unwrap(),
-                    ToGatewayMessage::ServerInfo(ServerInfo { worker_listen_port })
+                    ToGatewayMessage::ServerInfo(ServerInfo { worker_listen_port, .. })
                     if worker_listen_port == server.worker_port()
                 )
             );
@@ -6,16 +6,21 @@ use tako::gateway::{
 };
 
 use crate::server::autoalloc::AutoAllocService;
-use crate::server::event::events::JobInfo;
+use crate::server::event::events::{JobInfo, TaskIds};
 use crate::server::event::storage::EventStorage;
+use crate::server::event::subscription::{EventSubscriber, SUBSCRIBER_QUEUE_SIZE};
+use crate::server::event::MonitoringEvent;
 use crate::server::job::Job;
 use crate::server::rpc::Backend;
+use crate::server::stats_history::StatsHistory;
 use crate::server::worker::Worker;
+use crate::transfer::messages::StreamEventsRequest;
 use crate::WrappedRcRefCell;
-use crate::{JobId, JobTaskCount, Map, TakoTaskId, WorkerId};
+use crate::{JobId, JobTaskCount, JobTaskId, Map, TakoTaskId, WorkerId};
 use std::cmp::min;
 use tako::ItemId;
 use tako::{define_wrapped_type, TaskId};
+use tokio::sync::mpsc::{channel, Receiver};
 
 pub struct State {
     jobs: crate::Map<JobId, Job>,
@@ -40,6 +45,7 @@ pub struct State {
 
     pub(crate) autoalloc_service: Option<AutoAllocService>,
     event_storage: EventStorage,
+    stats_history: StatsHistory,
 
     server_uid: String,
 }
@@ -64,13 +70,11 @@ fn cancel_tasks_from_callback(
 
         match response {
             ToGatewayMessage::CancelTasksResponse(msg) => {
-                let mut state = state_ref.get_mut();
-                let job = state.get_job_mut(job_id).unwrap();
                 log::debug!("Tasks {:?} canceled", msg.cancelled_tasks);
                 log::debug!("Tasks {:?} already finished", msg.already_finished);
-                for tako_id in msg.cancelled_tasks {
-                    job.set_cancel_state(tako_id, &tako_ref);
-                }
+                state_ref
+                    .get_mut()
+                    .set_tasks_canceled(job_id, &msg.cancelled_tasks, &tako_ref);
             }
             ToGatewayMessage::Error(msg) => {
                 log::debug!("Canceling job {} failed: {}", job_id, msg.message);
@@ -118,6 +122,9 @@ impl State {
                 submission_date: job.submission_date,
             },
         );
+        if let Some(ref command) = job.on_finish {
+            self.event_storage.add_job_hook(job_id, command.clone());
+        }
         assert!(self.jobs.insert(job_id, job).is_none());
 
         if let Some(autoalloc) = &self.autoalloc_service {
@@ -185,15 +192,24 @@ impl State {
     ) {
         log::debug!("Task id={} failed: {:?}", msg.id, msg.info);
 
-        let job = self.get_job_mut_by_tako_task_id(msg.id).unwrap();
-        for task_id in msg.cancelled_tasks {
+        let job_id = self.get_job_mut_by_tako_task_id(msg.id).unwrap().job_id;
+        for task_id in &msg.cancelled_tasks {
             log::debug!(
                 "Task id={} canceled because of task dependency fails",
                 task_id
             );
-            job.set_cancel_state(task_id, tako_ref);
         }
-        job.set_failed_state(msg.id, msg.info.message, tako_ref);
+        self.set_tasks_canceled(job_id, &msg.cancelled_tasks, tako_ref);
+
+        let job = self.jobs.get_mut(&job_id).unwrap();
+        let job_task_id = job.job_task_id(msg.id);
+        let instance_id = job.set_failed_state(
+            msg.id,
+            msg.info.message.clone(),
+            msg.info.resource_usage,
+            tako_ref,
+        );
+        let is_job_terminated = job.is_terminated();
 
         if let Some(max_fails) = job.max_fails {
             if job.counters.n_failed_tasks > max_fails {
@@ -201,7 +217,46 @@ impl State {
                 cancel_tasks_from_callback(state_ref, tako_ref, job.job_id, task_ids);
             }
         }
-        self.event_storage.on_task_failed(msg.id);
+        self.event_storage.on_task_failed(
+            TaskIds {
+                task_id: msg.id,
+                job_id,
+                job_task_id,
+            },
+            instance_id,
+            msg.info.message,
+            msg.info.resource_usage,
+        );
+        if is_job_terminated {
+            self.event_storage
+                .on_job_completed(job_id, chrono::offset::Utc::now());
+        }
+    }
+
+    /// Marks the given tasks of a job as canceled.
+    /// Returns the IDs of the canceled tasks within the job.
+    pub fn set_tasks_canceled(
+        &mut self,
+        job_id: JobId,
+        tako_task_ids: &[TakoTaskId],
+        backend: &Backend,
+    ) -> Vec<JobTaskId> {
+        let job = self.jobs.get_mut(&job_id).unwrap();
+        let mut canceled_ids = Vec::with_capacity(tako_task_ids.len());
+        for &task_id in tako_task_ids {
+            let job_task_id = job.set_cancel_state(task_id, backend);
+            self.event_storage.on_task_canceled(TaskIds {
+                task_id,
+                job_id,
+                job_task_id,
+            });
+            canceled_ids.push(job_task_id);
+        }
+        if !canceled_ids.is_empty() && job.is_terminated() {
+            self.event_storage
+                .on_job_completed(job_id, chrono::offset::Utc::now());
+        }
+        canceled_ids
     }
 
     pub fn process_task_update(&mut self, msg: TaskUpdate, backend: &Backend) {
@@ -211,19 +266,24 @@ impl State {
             TaskState::Running {
                 worker_ids,
                 context,
+                instance_id,
             } => {
                 let job = self.get_job_mut_by_tako_task_id(msg.id).unwrap();
-                job.set_running_state(msg.id, worker_ids.clone(), context);
+                job.set_running_state(msg.id, worker_ids.clone(), context, instance_id);
 
                 // TODO: Prepare it for multi-node tasks
                 // This (incomplete) version just takes the first worker as "the worker" for task
-                self.event_storage.on_task_started(msg.id, worker_ids[0]);
+                self.event_storage
+                    .on_task_started(msg.id, instance_id, worker_ids[0]);
             }
-            TaskState::Finished => {
+            TaskState::Finished {
+                resource_usage,
+                note,
+            } => {
                 let job = self.get_job_mut_by_tako_task_id(msg.id).unwrap();
-                job.set_finished_state(msg.id, backend);
+                job.set_finished_state(msg.id, resource_usage, note, backend);
                 (job_id, is_job_terminated) = (Some(job.job_id), job.is_terminated());
-                self.event_storage.on_task_finished(msg.id);
+                self.event_storage.on_task_finished(msg.id, resource_usage);
             }
             TaskState::Waiting => {
                 let job = self.get_job_mut_by_tako_task_id(msg.id).unwrap();
@@ -268,7 +328,14 @@ impl State {
 
         for task_id in msg.running_tasks {
             let job = self.get_job_mut_by_tako_task_id(task_id).unwrap();
-            job.set_waiting_state(task_id);
+            let started_data = job.set_waiting_state(task_id);
+            let task = TaskIds {
+                task_id,
+                job_id: job.job_id,
+                job_task_id: job.job_task_id(task_id),
+            };
+            self.event_storage
+                .on_task_restarted(task, started_data.instance_id, msg.worker_id);
         }
 
         self.event_storage.on_worker_lost(msg.worker_id, msg.reason);
@@ -279,6 +346,14 @@ impl State {
         self.autoalloc_service = None;
     }
 
+    pub fn stats_history(&self) -> &StatsHistory {
+        &self.stats_history
+    }
+
+    pub fn stats_history_mut(&mut self) -> &mut StatsHistory {
+        &mut self.stats_history
+    }
+
     pub fn server_uid(&self) -> &str {
         &self.server_uid
     }
@@ -290,6 +365,22 @@ impl State {
         &mut self.event_storage
     }
 
+    /// Creates a new event subscription.
+    /// Returns stored events requested by `after_id` and a receiver of newly created events.
+    /// Only events that pass the filter of the request are returned.
+    pub fn subscribe_events(
+        &mut self,
+        request: StreamEventsRequest,
+    ) -> (Vec<MonitoringEvent>, Receiver<MonitoringEvent>) {
+        let (tx, rx) = channel(SUBSCRIBER_QUEUE_SIZE);
+        let mut subscriber = EventSubscriber::new(request.filter, tx);
+        for job in self.jobs.values() {
+            subscriber.add_job_tasks(job.job_id, job.base_task_id, job.n_tasks());
+        }
+        let events = self.event_storage.subscribe(subscriber, request.after_id);
+        (events, rx)
+    }
+
     pub fn autoalloc(&self) -> &AutoAllocService {
         self.autoalloc_service.as_ref().unwrap()
     }
@@ -305,6 +396,7 @@ impl StateRef {
             task_id_counter: 1,
             autoalloc_service: None,
             event_storage,
+            stats_history: Default::default(),
             server_uid: uid,
         }))
     }
@@ -348,6 +440,8 @@ mod tests {
                 time_limit: None,
                 priority: 0,
                 crash_limit: 5,
+                output_limit: None,
+                spool: false,
             },
         };
         Job::new(
@@ -357,6 +451,7 @@ mod tests {
             "".to_string(),
             None,
             None,
+            None,
             Default::default(),
         )
     }
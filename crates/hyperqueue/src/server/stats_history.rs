@@ -0,0 +1,277 @@
+//! Rolling time-series of aggregated server statistics.
+//!
+//! The server periodically samples the state of tasks, workers and the scheduler into a
+//! [`StatsHistory`], so that recent trends can be displayed by `hq server stats` without
+//! requiring an event log.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tako::gateway::{FromGatewayMessage, SchedulerStats, ToGatewayMessage};
+
+use crate::server::job::JobTaskCounters;
+use crate::server::rpc::Backend;
+use crate::server::state::{State, StateRef};
+use crate::transfer::messages::StatsSample;
+
+/// How often is a new sample taken.
+pub const STATS_SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many samples are kept (one day of history).
+const STATS_HISTORY_LENGTH: usize = 24 * 60;
+
+/// Current values of server statistics, from which a sample is computed.
+#[derive(Default)]
+pub struct StatsSnapshot {
+    /// Total number of tasks that have finished since the server has started
+    pub tasks_finished_total: u64,
+    pub tasks_waiting: u64,
+    pub tasks_running: u64,
+    pub workers: u64,
+    pub cpu_usage: Option<f64>,
+    pub scheduler: SchedulerStats,
+}
+
+impl StatsSnapshot {
+    pub fn from_state(state: &State, scheduler: SchedulerStats) -> Self {
+        let mut counters = JobTaskCounters::default();
+        let mut tasks_waiting = 0;
+        for job in state.jobs() {
+            counters = counters + job.counters;
+            tasks_waiting += job.counters.n_waiting_tasks(job.n_tasks());
+        }
+
+        let workers: Vec<_> = state
+            .get_workers()
+            .values()
+            .filter(|worker| worker.is_running())
+            .collect();
+        let cpu_usages: Vec<f64> = workers
+            .iter()
+            .filter_map(|worker| worker.overview()?.hw_state.as_ref())
+            .filter_map(|hw_state| {
+                let usage = &hw_state.state.worker_cpu_usage.cpu_per_core_percent_usage;
+                (!usage.is_empty()).then(|| {
+                    usage.iter().map(|usage| *usage as f64).sum::<f64>() / usage.len() as f64
+                })
+            })
+            .collect();
+        let cpu_usage = (!cpu_usages.is_empty())
+            .then(|| cpu_usages.iter().sum::<f64>() / cpu_usages.len() as f64);
+
+        Self {
+            tasks_finished_total: counters.n_finished_tasks as u64,
+            tasks_waiting: tasks_waiting as u64,
+            tasks_running: counters.n_running_tasks as u64,
+            workers: workers.len() as u64,
+            cpu_usage,
+            scheduler,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct StatsHistory {
+    samples: VecDeque<StatsSample>,
+    /// Cumulative counters of the previous snapshot, used to compute per-sample increments
+    last_snapshot: Option<(u64, SchedulerStats)>,
+}
+
+impl StatsHistory {
+    pub fn add_snapshot(&mut self, time: DateTime<Utc>, snapshot: StatsSnapshot) {
+        let (tasks_finished, scheduler_runs, scheduler_time) = match &self.last_snapshot {
+            Some((last_finished, last_scheduler)) => (
+                snapshot.tasks_finished_total.saturating_sub(*last_finished),
+                snapshot.scheduler.runs.saturating_sub(last_scheduler.runs),
+                snapshot
+                    .scheduler
+                    .total_duration
+                    .saturating_sub(last_scheduler.total_duration),
+            ),
+            None => (0, 0, Duration::ZERO),
+        };
+        let scheduler_duration =
+            (scheduler_runs > 0).then(|| scheduler_time / scheduler_runs as u32);
+
+        if self.samples.len() == STATS_HISTORY_LENGTH {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(StatsSample {
+            time,
+            tasks_finished,
+            tasks_waiting: snapshot.tasks_waiting,
+            tasks_running: snapshot.tasks_running,
+            workers: snapshot.workers,
+            cpu_usage: snapshot.cpu_usage,
+            scheduler_runs,
+            scheduler_duration,
+        });
+        self.last_snapshot = Some((snapshot.tasks_finished_total, snapshot.scheduler));
+    }
+
+    /// Returns samples taken at `since` or later.
+    pub fn samples_since(&self, since: DateTime<Utc>) -> Vec<StatsSample> {
+        self.samples
+            .iter()
+            .filter(|sample| sample.time >= since)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns samples taken during the `window` that ends at `now`.
+    /// All samples are returned if the start of the window cannot be represented.
+    pub fn samples_within(&self, now: DateTime<Utc>, window: Duration) -> Vec<StatsSample> {
+        match chrono::Duration::from_std(window)
+            .ok()
+            .and_then(|window| now.checked_sub_signed(window))
+        {
+            Some(since) => self.samples_since(since),
+            None => self.samples.iter().cloned().collect(),
+        }
+    }
+}
+
+/// Periodically samples the state of the server into its [`StatsHistory`].
+pub async fn stats_history_process(state_ref: StateRef, backend: Backend) {
+    let mut interval = tokio::time::interval(STATS_SAMPLE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let scheduler = match backend
+            .send_tako_message(FromGatewayMessage::ServerInfo)
+            .await
+        {
+            Ok(ToGatewayMessage::ServerInfo(info)) => info.scheduler,
+            Ok(message) => {
+                log::error!("Unexpected response from the scheduler: {message:?}");
+                continue;
+            }
+            Err(error) => {
+                log::error!("Cannot gather server statistics: {error:?}");
+                continue;
+            }
+        };
+        let mut state = state_ref.get_mut();
+        let snapshot = StatsSnapshot::from_state(&state, scheduler);
+        state.stats_history_mut().add_snapshot(Utc::now(), snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use chrono::{DateTime, Utc};
+    use tako::gateway::SchedulerStats;
+
+    use crate::server::stats_history::{StatsHistory, StatsSnapshot, STATS_HISTORY_LENGTH};
+
+    fn time(minute: u64) -> DateTime<Utc> {
+        (UNIX_EPOCH + Duration::from_secs(minute * 60)).into()
+    }
+
+    fn snapshot(finished: u64, runs: u64, scheduler_ms: u64) -> StatsSnapshot {
+        StatsSnapshot {
+            tasks_finished_total: finished,
+            tasks_waiting: 5,
+            tasks_running: 2,
+            workers: 1,
+            cpu_usage: Some(50.0),
+            scheduler: SchedulerStats {
+                runs,
+                total_duration: Duration::from_millis(scheduler_ms),
+                last_duration: Duration::ZERO,
+            },
+        }
+    }
+
+    #[test]
+    fn compute_increments() {
+        let mut history = StatsHistory::default();
+        history.add_snapshot(time(0), snapshot(10, 4, 40));
+        history.add_snapshot(time(1), snapshot(25, 8, 80));
+        history.add_snapshot(time(2), snapshot(25, 8, 80));
+
+        let samples = history.samples_since(time(0));
+        assert_eq!(
+            samples
+                .iter()
+                .map(|sample| sample.tasks_finished)
+                .collect::<Vec<_>>(),
+            vec![0, 15, 0]
+        );
+        assert_eq!(
+            samples
+                .iter()
+                .map(|sample| sample.scheduler_runs)
+                .collect::<Vec<_>>(),
+            vec![0, 4, 0]
+        );
+        assert_eq!(
+            samples
+                .iter()
+                .map(|sample| sample.scheduler_duration)
+                .collect::<Vec<_>>(),
+            vec![None, Some(Duration::from_millis(10)), None]
+        );
+        assert_eq!(samples[1].tasks_waiting, 5);
+        assert_eq!(samples[1].cpu_usage, Some(50.0));
+    }
+
+    #[test]
+    fn select_window() {
+        let mut history = StatsHistory::default();
+        for minute in 0..10 {
+            history.add_snapshot(time(minute), snapshot(0, 0, 0));
+        }
+        let samples = history.samples_since(time(7));
+        assert_eq!(
+            samples.iter().map(|sample| sample.time).collect::<Vec<_>>(),
+            vec![time(7), time(8), time(9)]
+        );
+    }
+
+    #[test]
+    fn select_window_ending_now() {
+        let mut history = StatsHistory::default();
+        for minute in 0..10 {
+            history.add_snapshot(time(minute), snapshot(0, 0, 0));
+        }
+        let times = |window: Duration| {
+            history
+                .samples_within(time(9), window)
+                .iter()
+                .map(|sample| sample.time)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(times(Duration::ZERO), vec![time(9)]);
+        assert_eq!(times(Duration::from_secs(119)), vec![time(8), time(9)]);
+        assert_eq!(
+            times(Duration::from_secs(120)),
+            vec![time(7), time(8), time(9)]
+        );
+        assert_eq!(times(Duration::from_secs(9 * 60)).len(), 10);
+        assert_eq!(times(Duration::from_secs(3600)).len(), 10);
+    }
+
+    #[test]
+    fn select_window_out_of_range() {
+        let mut history = StatsHistory::default();
+        for minute in 0..10 {
+            history.add_snapshot(time(minute), snapshot(0, 0, 0));
+        }
+        let year = Duration::from_secs(365 * 24 * 3600);
+        assert_eq!(history.samples_within(time(9), year * 300_000).len(), 10);
+        assert_eq!(history.samples_within(time(9), Duration::MAX).len(), 10);
+    }
+
+    #[test]
+    fn drop_old_samples() {
+        let mut history = StatsHistory::default();
+        for minute in 0..(STATS_HISTORY_LENGTH as u64 + 5) {
+            history.add_snapshot(time(minute), snapshot(0, 0, 0));
+        }
+        let samples = history.samples_since(time(0));
+        assert_eq!(samples.len(), STATS_HISTORY_LENGTH);
+        assert_eq!(samples[0].time, time(5));
+    }
+}
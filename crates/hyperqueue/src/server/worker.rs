@@ -1,6 +1,6 @@
 use chrono::Utc;
 use tako::gateway::LostWorkerReason;
-use tako::worker::WorkerConfiguration;
+use tako::worker::{WorkerConfiguration, WorkerOverview};
 
 use crate::server::worker::WorkerState::Offline;
 use crate::transfer::messages::{WorkerExitInfo, WorkerInfo};
@@ -15,6 +15,8 @@ pub struct Worker {
     worker_id: WorkerId,
     state: WorkerState,
     pub(crate) configuration: WorkerConfiguration,
+    /// The last overview received from the worker
+    overview: Option<WorkerOverview>,
 }
 
 impl Worker {
@@ -23,6 +25,7 @@ impl Worker {
             worker_id,
             configuration,
             state: WorkerState::Online,
+            overview: None,
         }
     }
 
@@ -34,7 +37,16 @@ impl Worker {
         &self.configuration
     }
 
+    pub fn overview(&self) -> Option<&WorkerOverview> {
+        self.overview.as_ref()
+    }
+
+    pub fn set_overview(&mut self, overview: WorkerOverview) {
+        self.overview = Some(overview);
+    }
+
     pub fn set_offline_state(&mut self, reason: LostWorkerReason) {
+        self.overview = None;
         self.state = Offline(WorkerExitInfo {
             ended_at: Utc::now(),
             reason,
@@ -53,6 +65,10 @@ impl Worker {
                 WorkerState::Online => None,
                 Offline(d) => Some(d.clone()),
             },
+            hw_state: self
+                .overview
+                .as_ref()
+                .and_then(|overview| overview.hw_state.clone()),
         }
     }
 }
@@ -0,0 +1,111 @@
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+/// Compression of data chunks stored in a stream log file.
+/// The compression method is stored in the header of the log file.
+#[derive(clap::ArgEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogCompression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+const ZSTD_COMPRESSION_LEVEL: i32 = 3;
+
+impl LogCompression {
+    /// Identifier of the compression method that is stored in the log file header.
+    pub fn id(&self) -> u64 {
+        match self {
+            LogCompression::None => 0,
+            LogCompression::Gzip => 1,
+            LogCompression::Zstd => 2,
+        }
+    }
+
+    pub fn from_id(id: u64) -> anyhow::Result<Self> {
+        Ok(match id {
+            0 => LogCompression::None,
+            1 => LogCompression::Gzip,
+            2 => LogCompression::Zstd,
+            _ => anyhow::bail!("Unknown log compression: {}", id),
+        })
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        !matches!(self, LogCompression::None)
+    }
+
+    pub fn compress(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            LogCompression::None => data.to_vec(),
+            LogCompression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+            LogCompression::Zstd => zstd::bulk::compress(data, ZSTD_COMPRESSION_LEVEL)?,
+        })
+    }
+
+    /// Decompresses `data` and appends the result to `output`.
+    /// `size` is the expected size of the decompressed data.
+    pub fn decompress_into(
+        &self,
+        data: &[u8],
+        size: usize,
+        output: &mut Vec<u8>,
+    ) -> anyhow::Result<()> {
+        match self {
+            LogCompression::None => output.extend_from_slice(data),
+            LogCompression::Gzip => {
+                output.reserve(size);
+                flate2::read::GzDecoder::new(data).read_to_end(output)?;
+            }
+            LogCompression::Zstd => {
+                output.extend_from_slice(&zstd::bulk::decompress(data, size)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for LogCompression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LogCompression::None => "none",
+            LogCompression::Gzip => "gzip",
+            LogCompression::Zstd => "zstd",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stream::compression::LogCompression;
+
+    #[test]
+    fn compression_roundtrip() {
+        let data = b"Hello world\n".repeat(100);
+        for compression in [
+            LogCompression::None,
+            LogCompression::Gzip,
+            LogCompression::Zstd,
+        ] {
+            let compressed = compression.compress(&data).unwrap();
+            if compression.is_compressed() {
+                assert!(compressed.len() < data.len());
+            }
+            let mut output = b"prefix".to_vec();
+            compression
+                .decompress_into(&compressed, data.len(), &mut output)
+                .unwrap();
+            assert_eq!(&output[..6], b"prefix");
+            assert_eq!(&output[6..], &data[..]);
+        }
+    }
+}
@@ -1,2 +1,3 @@
+pub mod compression;
 pub mod reader;
 pub mod server;
@@ -1,6 +1,11 @@
-use crate::client::commands::log::{CatOpts, Channel, ExportOpts, ShowOpts};
+use crate::client::commands::log::{CatOpts, Channel, ExportOpts, ShowOpts, SplitOpts};
 use crate::common::arraydef::IntArray;
-use crate::transfer::stream::ChannelId;
+use crate::common::placeholders::{
+    fill_placeholders_log_split, get_unknown_log_split_placeholders, parse_resolvable_string,
+    StringPart, INSTANCE_ID_PLACEHOLDER,
+};
+use crate::stream::compression::LogCompression;
+use crate::transfer::stream::{ChannelId, StreamEndStatus};
 use crate::{JobTaskCount, JobTaskId, Map, Set};
 use byteorder::ReadBytesExt;
 use colored::{Color, Colorize};
@@ -11,32 +16,156 @@ use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
 use std::path::Path;
+use std::time::Duration;
 use tako::InstanceId;
 
 pub const HQ_LOG_HEADER: &[u8] = b"HQ:log";
-pub const HQ_LOG_VERSION: u32 = 0;
+/// Version 1 adds:
+/// - an index of all chunks at the end of the file
+/// - compression of chunks, which is stored in the header. Chunks of compressed files also
+///   contain the size of their uncompressed data.
+/// - the end status of tasks to end blocks
+///
+/// Files with older versions (or unfinished files) are indexed by scanning all blocks.
+pub const HQ_LOG_VERSION: u32 = 1;
+/// Size of the header (magic, version, compression and reserved bytes)
+pub const HQ_LOG_HEADER_SIZE: u64 = 26;
 
 pub const BLOCK_STREAM_START: u8 = 0;
 pub const BLOCK_STREAM_CHUNK: u8 = 1;
 pub const BLOCK_STREAM_END: u8 = 2;
+pub const BLOCK_INDEX: u8 = 3;
 
+/// The last bytes of a log file that contains an index.
+/// It is preceded by the position of the index block (u64).
+pub const HQ_LOG_INDEX_TRAILER: &[u8] = b"HQ:index";
+const INDEX_FOOTER_SIZE: u64 = 8 + HQ_LOG_INDEX_TRAILER.len() as u64;
+
+/// How often is a followed log file checked for new data.
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Serialize, Deserialize)]
 pub struct ChunkInfo {
     position: u64,
     size: u32, // Currently chunk is actually limited to 128kB
+    /// Size of the data before compression
+    original_size: u32,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct InstanceInfo {
     instance_id: InstanceId,
     channels: [Vec<ChunkInfo>; 2],
-    finished: bool,
+    /// Position of the end of the stream, `None` if the stream was not finished
+    end_position: Option<u64>,
+    /// How has the task ended, `None` if the stream was not finished or if the log file
+    /// was created by an older version that did not store the status
+    end_status: Option<StreamEndStatus>,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct TaskInfo {
     instances: SmallVec<[InstanceInfo; 1]>,
 }
 
+/// Maps tasks (and their instances and channels) to positions of their chunks in a log file.
+#[derive(Serialize, Deserialize, Default)]
+pub struct LogIndex {
+    tasks: BTreeMap<JobTaskId, TaskInfo>,
+}
+
+impl LogIndex {
+    pub fn on_stream_start(
+        &mut self,
+        task_id: JobTaskId,
+        instance_id: InstanceId,
+    ) -> anyhow::Result<()> {
+        let task_info = self.tasks.entry(task_id).or_insert_with(|| TaskInfo {
+            instances: Default::default(),
+        });
+        task_info.new_instance(instance_id)
+    }
+
+    /// `position` is the position of the data of the chunk in the file.
+    pub fn on_stream_chunk(
+        &mut self,
+        task_id: JobTaskId,
+        instance_id: InstanceId,
+        channel_id: ChannelId,
+        position: u64,
+        size: u32,
+        original_size: u32,
+    ) -> anyhow::Result<()> {
+        if channel_id >= 2 {
+            anyhow::bail!("Invalid channel id");
+        }
+        match self.instance_mut(task_id, instance_id) {
+            Some(instance) => {
+                instance.channels[channel_id as usize].push(ChunkInfo {
+                    position,
+                    size,
+                    original_size,
+                });
+                Ok(())
+            }
+            None => anyhow::bail!("Data chunk for invalid task"),
+        }
+    }
+
+    /// `position` is the position of the end of the end block in the file.
+    pub fn on_stream_end(
+        &mut self,
+        task_id: JobTaskId,
+        instance_id: InstanceId,
+        position: u64,
+        status: Option<StreamEndStatus>,
+    ) -> anyhow::Result<()> {
+        match self.instance_mut(task_id, instance_id) {
+            Some(instance) => {
+                instance.end_position = Some(position);
+                instance.end_status = status;
+                Ok(())
+            }
+            None => anyhow::bail!("Termination of an invalid task"),
+        }
+    }
+
+    fn instance_mut(
+        &mut self,
+        task_id: JobTaskId,
+        instance_id: InstanceId,
+    ) -> Option<&mut InstanceInfo> {
+        self.tasks
+            .get_mut(&task_id)
+            .and_then(|task_info| task_info.instance_mut(instance_id))
+    }
+
+    /// Serializes the index into a block that is written at the end of a log file,
+    /// followed by its position and the index trailer.
+    pub fn serialize_footer(&self, position: u64) -> anyhow::Result<Vec<u8>> {
+        let mut data = vec![BLOCK_INDEX];
+        bincode::serialize_into(&mut data, self)?;
+        data.extend_from_slice(&position.to_be_bytes());
+        data.extend_from_slice(HQ_LOG_INDEX_TRAILER);
+        Ok(data)
+    }
+}
+
 impl InstanceInfo {
+    fn finished(&self) -> bool {
+        self.end_position.is_some()
+    }
+
+    /// Size of the (uncompressed) data of a channel
     fn channel_size(&self, channel_id: ChannelId) -> u64 {
+        self.channels[channel_id as usize]
+            .iter()
+            .map(|x| x.original_size as u64)
+            .sum()
+    }
+
+    /// Size of the data of a channel stored in the file
+    fn stored_channel_size(&self, channel_id: ChannelId) -> u64 {
         self.channels[channel_id as usize]
             .iter()
             .map(|x| x.size as u64)
@@ -78,7 +207,8 @@ impl TaskInfo {
                 InstanceInfo {
                     instance_id,
                     channels: [Vec::new(), Vec::new()],
-                    finished: false,
+                    end_position: None,
+                    end_status: None,
                 },
             ),
         }
@@ -86,11 +216,30 @@ impl TaskInfo {
     }
 }
 
+/// Properties of a log file that are stored in its header.
+#[derive(Clone, Copy)]
+struct LogFormat {
+    version: u32,
+    compression: LogCompression,
+}
+
 pub struct LogFile {
     file: BufReader<File>,
+    format: LogFormat,
     index: BTreeMap<JobTaskId, TaskInfo>,
-    start_pos: u64,
     current_pos: u64,
+    /// Position of the end of the last complete stream block
+    data_end: u64,
+    /// Size of an incomplete block at the end of the file (e.g. when the server has crashed
+    /// while writing it)
+    truncated_size: u64,
+}
+
+/// Result of scanning all blocks of a log file.
+struct LogScan {
+    index: LogIndex,
+    data_end: u64,
+    truncated_size: u64,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -103,6 +252,13 @@ pub struct Summary {
     pub n_superseded: u64,
     pub superseded_stdout_size: u64,
     pub superseded_stderr_size: u64,
+    pub compression: LogCompression,
+    pub compressed_stdout_size: u64,
+    pub compressed_stderr_size: u64,
+    /// Streams that were never closed and whose end was added by repairing the log file
+    pub n_incomplete: u64,
+    /// Size of an incomplete block at the end of the file that was ignored
+    pub truncated_size: u64,
 }
 
 #[allow(clippy::enum_variant_names)]
@@ -116,48 +272,166 @@ enum Block {
         instance_id: InstanceId,
         channel_id: ChannelId,
         size: u32,
+        original_size: u32,
     },
     StreamEnd {
         task_id: JobTaskId,
         instance_id: InstanceId,
+        status: Option<StreamEndStatus>,
+    },
+    Index,
+}
+
+/// Event of a stream that is printed by `show`.
+enum ShowEvent<'a> {
+    Chunk {
+        task_id: JobTaskId,
+        channel_id: ChannelId,
+        chunk: &'a ChunkInfo,
+    },
+    End {
+        task_id: JobTaskId,
+        /// The end of the stream was added by repairing the log file
+        incomplete: bool,
+    },
+}
+
+/// Output of a task that was read from a followed log file.
+enum FollowEvent<'a> {
+    Chunk {
+        task_id: JobTaskId,
+        channel_id: ChannelId,
+        data: &'a [u8],
+    },
+    End {
+        task_id: JobTaskId,
     },
 }
 
+const COLORS: [Color; 6] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+];
+
 impl LogFile {
+    /// Opens a log file without reading its index.
+    /// It can be used only to follow the file using `cat` or `show` with the `follow` flag.
+    pub fn open_for_follow(path: &Path) -> anyhow::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let format = LogFile::check_header(&mut file)?;
+        let current_pos = file.stream_position()?;
+        Ok(LogFile {
+            file,
+            format,
+            index: Default::default(),
+            current_pos,
+            data_end: current_pos,
+            truncated_size: 0,
+        })
+    }
+
     pub fn open(path: &Path) -> anyhow::Result<Self> {
         let mut file = BufReader::new(File::open(path)?);
-        LogFile::check_header(&mut file)?;
+        let format = LogFile::check_header(&mut file)?;
         let start_pos = file.stream_position()?;
-        let index = LogFile::make_index(&mut file)?;
-        file.seek(SeekFrom::Start(start_pos))?;
+        let index = match format.version {
+            // Older versions do not contain an index
+            0 => None,
+            _ => LogFile::read_index(&mut file)?,
+        };
+        let scan = match index {
+            Some((index, index_pos)) => LogScan {
+                index,
+                data_end: index_pos,
+                truncated_size: 0,
+            },
+            None => {
+                log::debug!("Log file does not contain an index, scanning the whole file");
+                file.seek(SeekFrom::Start(start_pos))?;
+                LogFile::make_index(&mut file, format)?
+            }
+        };
+        if scan.truncated_size > 0 {
+            log::warn!(
+                "Log file ends with an incomplete block ({} bytes), which was ignored. \
+                If the server has crashed while writing the log, you can fix the file using `hq log {} repair`",
+                scan.truncated_size,
+                path.display()
+            );
+        }
+        let current_pos = file.stream_position()?;
         Ok(LogFile {
             file,
-            index,
-            start_pos,
-            current_pos: start_pos,
+            format,
+            index: scan.index.tasks,
+            current_pos,
+            data_end: scan.data_end,
+            truncated_size: scan.truncated_size,
         })
     }
 
-    fn check_header(file: &mut BufReader<File>) -> anyhow::Result<()> {
+    /// Checks the header of the file and returns its version and compression.
+    fn check_header(file: &mut BufReader<File>) -> anyhow::Result<LogFormat> {
         let mut header = [0u8; 6];
         file.read_exact(&mut header)?;
         if header != HQ_LOG_HEADER {
             anyhow::bail!("Invalid file format");
         }
         let version = file.read_u32::<byteorder::BigEndian>()?;
-        if version != HQ_LOG_VERSION {
+        if version > HQ_LOG_VERSION {
             anyhow::bail!("Invalid version log file version: {}", version);
         }
+        // Reserved bytes in older versions
+        let compression = LogCompression::from_id(file.read_u64::<byteorder::BigEndian>()?)?;
         let _ = file.read_u64::<byteorder::BigEndian>()?; // Reserved bytes
-        let _ = file.read_u64::<byteorder::BigEndian>()?; // Reserved bytes
-        Ok(())
+        Ok(LogFormat {
+            version,
+            compression,
+        })
+    }
+
+    /// Reads the index (and its position) from the end of the file.
+    /// Returns `None` if the file does not end with an index (e.g. it is not finished yet).
+    fn read_index(file: &mut BufReader<File>) -> anyhow::Result<Option<(LogIndex, u64)>> {
+        let file_size = file.seek(SeekFrom::End(0))?;
+        if file_size < HQ_LOG_HEADER_SIZE + INDEX_FOOTER_SIZE + 1 {
+            return Ok(None);
+        }
+        file.seek(SeekFrom::End(-(INDEX_FOOTER_SIZE as i64)))?;
+        let index_pos = file.read_u64::<byteorder::BigEndian>()?;
+        let mut trailer = [0u8; HQ_LOG_INDEX_TRAILER.len()];
+        file.read_exact(&mut trailer)?;
+        if trailer != HQ_LOG_INDEX_TRAILER
+            || index_pos < HQ_LOG_HEADER_SIZE
+            || index_pos >= file_size - INDEX_FOOTER_SIZE
+        {
+            return Ok(None);
+        }
+
+        file.seek(SeekFrom::Start(index_pos))?;
+        if file.read_u8()? != BLOCK_INDEX {
+            anyhow::bail!("Invalid index position");
+        }
+        let index_size = file_size - INDEX_FOOTER_SIZE - index_pos - 1;
+        let index = bincode::deserialize_from(file.by_ref().take(index_size))?;
+        Ok(Some((index, index_pos)))
     }
 
     pub fn summary(&self) -> Summary {
         let n_opened = self
             .index
             .values()
-            .map(|infos| if infos.last_instance().finished { 0 } else { 1 })
+            .map(|infos| {
+                if infos.last_instance().finished() {
+                    0
+                } else {
+                    1
+                }
+            })
             .sum::<u64>();
 
         let n_streams = self
@@ -170,11 +444,22 @@ impl LogFile {
         let mut stderr_size = 0u64;
         let mut superseded_stdout_size = 0u64;
         let mut superseded_stderr_size = 0u64;
+        let mut compressed_stdout_size = 0u64;
+        let mut compressed_stderr_size = 0u64;
+        let mut n_incomplete = 0u64;
 
         for task_info in self.index.values() {
+            n_incomplete += task_info
+                .instances
+                .iter()
+                .filter(|info| info.end_status == Some(StreamEndStatus::Incomplete))
+                .count() as u64;
+
             let info = task_info.last_instance();
             stdout_size += info.channel_size(0);
             stderr_size += info.channel_size(1);
+            compressed_stdout_size += info.stored_channel_size(0);
+            compressed_stderr_size += info.stored_channel_size(1);
 
             for info in task_info.superseded() {
                 superseded_stdout_size += info.channel_size(0);
@@ -191,9 +476,64 @@ impl LogFile {
             stderr_size,
             superseded_stderr_size,
             superseded_stdout_size,
+            compression: self.format.compression,
+            compressed_stdout_size,
+            compressed_stderr_size,
+            n_incomplete,
+            truncated_size: self.truncated_size,
         }
     }
 
+    /// Writes a valid log file that contains all complete blocks of this file into `output`.
+    /// Streams that were not closed are ended with the `Incomplete` status.
+    ///
+    /// Returns the number of streams that were closed.
+    pub fn repair(&mut self, output: &Path) -> anyhow::Result<u64> {
+        if self.format.version < HQ_LOG_VERSION {
+            anyhow::bail!(
+                "The log file was created by an older version of HyperQueue and it cannot be repaired"
+            );
+        }
+        let mut writer = BufWriter::new(File::create(output).map_err(|error| {
+            anyhow::anyhow!("Cannot create file {}: {}", output.display(), error)
+        })?);
+
+        // The header and all blocks before `data_end` are valid, so they are copied without changes
+        self.file.seek(SeekFrom::Start(0))?;
+        let copied = std::io::copy(&mut self.file.by_ref().take(self.data_end), &mut writer)?;
+        self.current_pos = self.file.stream_position()?;
+        if copied != self.data_end {
+            anyhow::bail!("Log file was modified while it was being repaired");
+        }
+
+        let mut position = self.data_end;
+        let mut closed = 0;
+        for (&task_id, task_info) in self.index.iter_mut() {
+            for instance in task_info.instances.iter_mut() {
+                if instance.finished() {
+                    continue;
+                }
+                let mut block = vec![BLOCK_STREAM_END];
+                block.extend_from_slice(&u32::from(task_id).to_be_bytes());
+                block.extend_from_slice(&u32::from(instance.instance_id).to_be_bytes());
+                block.push(StreamEndStatus::Incomplete.id());
+                writer.write_all(&block)?;
+                position += block.len() as u64;
+                instance.end_position = Some(position);
+                instance.end_status = Some(StreamEndStatus::Incomplete);
+                closed += 1;
+            }
+        }
+
+        let index = LogIndex {
+            tasks: std::mem::take(&mut self.index),
+        };
+        writer.write_all(&index.serialize_footer(position)?)?;
+        writer.flush()?;
+        self.index = index.tasks;
+        Ok(closed)
+    }
+
     fn _gather_infos<'a>(
         index: &'a BTreeMap<JobTaskId, TaskInfo>,
         tasks: &Option<IntArray>,
@@ -233,27 +573,44 @@ impl LogFile {
         Ok(())
     }
 
+    /// Reads the data of a chunk, decompresses it and appends it to `output`.
+    fn read_chunk(
+        file: &mut BufReader<File>,
+        current_pos: &mut u64,
+        compression: LogCompression,
+        chunk: &ChunkInfo,
+        output: &mut Vec<u8>,
+    ) -> anyhow::Result<()> {
+        if compression.is_compressed() {
+            let mut data = vec![0u8; chunk.size as usize];
+            Self::read_buffer(file, current_pos, chunk.position, &mut data)?;
+            compression.decompress_into(&data, chunk.original_size as usize, output)
+        } else {
+            let start = output.len();
+            output.resize(start + chunk.size as usize, 0u8);
+            Self::read_buffer(file, current_pos, chunk.position, &mut output[start..])
+        }
+    }
+
     pub fn export(&mut self, opts: &ExportOpts) -> anyhow::Result<()> {
         let task_infos = Self::_gather_infos(&self.index, &opts.task)?;
         let mut result = Vec::new();
         let mut buffer = Vec::new();
         for (task_id, instance) in &task_infos {
-            buffer.resize(instance.channel_size(0) as usize, 0u8);
-            let mut buf_pos = 0;
+            buffer.clear();
             for chunk in &instance.channels[0] {
-                let size = chunk.size as usize;
-                Self::read_buffer(
+                Self::read_chunk(
                     &mut self.file,
                     &mut self.current_pos,
-                    chunk.position,
-                    &mut buffer[buf_pos..buf_pos + size],
+                    self.format.compression,
+                    chunk,
+                    &mut buffer,
                 )?;
-                buf_pos += size;
             }
-            debug_assert!(buf_pos == buffer.len());
+            debug_assert!(buffer.len() as u64 == instance.channel_size(0));
             result.push(json!({
                 "id": task_id,
-                "finished": instance.finished,
+                "finished": instance.finished(),
                 "stdout": String::from_utf8_lossy(&buffer),
             }));
         }
@@ -265,6 +622,95 @@ impl LogFile {
         Ok(())
     }
 
+    /// Writes the output of the selected tasks into separate files.
+    /// Returns the number of created files.
+    pub fn split(&mut self, opts: &SplitOpts) -> anyhow::Result<u64> {
+        let unknown = get_unknown_log_split_placeholders(&opts.pattern);
+        if !unknown.is_empty() {
+            anyhow::bail!(
+                "Unknown placeholder(s) in the pattern: {}",
+                unknown.join(", ")
+            );
+        }
+        if opts.include_superseded
+            && !parse_resolvable_string(&opts.pattern)
+                .contains(&StringPart::Placeholder(INSTANCE_ID_PLACEHOLDER))
+        {
+            anyhow::bail!(
+                "The pattern has to contain the %{{{}}} placeholder when superseded instances are included",
+                INSTANCE_ID_PLACEHOLDER
+            );
+        }
+        if opts.failed && self.format.version < 1 {
+            anyhow::bail!(
+                "The log file was created by an older version of HyperQueue and it does not contain the status of tasks"
+            );
+        }
+
+        let mut task_infos = Vec::new();
+        match &opts.task {
+            Some(array) => {
+                for task_id in array.iter() {
+                    let task_id = JobTaskId::new(task_id);
+                    match self.index.get(&task_id) {
+                        Some(task_info) => task_infos.push((task_id, task_info)),
+                        None => anyhow::bail!("Task {} not found", task_id),
+                    }
+                }
+            }
+            None => task_infos.extend(self.index.iter().map(|(&id, info)| (id, info))),
+        };
+
+        let mut buffer = Vec::new();
+        let mut count = 0;
+        for (task_id, task_info) in task_infos {
+            if opts.failed && task_info.last_instance().end_status != Some(StreamEndStatus::Failed)
+            {
+                continue;
+            }
+            let instances = if opts.include_superseded {
+                &task_info.instances[..]
+            } else {
+                &task_info.instances[task_info.instances.len() - 1..]
+            };
+            for instance in instances {
+                for (channel_id, chunks) in instance.channels.iter().enumerate() {
+                    // Empty channels do not create any files
+                    if chunks.is_empty() {
+                        continue;
+                    }
+                    let channel = if channel_id == 0 { "stdout" } else { "stderr" };
+                    let path = opts.output_dir.join(fill_placeholders_log_split(
+                        &opts.pattern,
+                        task_id,
+                        instance.instance_id,
+                        channel,
+                    ));
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    let mut output = BufWriter::new(File::create(&path).map_err(|error| {
+                        anyhow::anyhow!("Cannot create file {}: {}", path.display(), error)
+                    })?);
+                    for chunk in chunks {
+                        buffer.clear();
+                        Self::read_chunk(
+                            &mut self.file,
+                            &mut self.current_pos,
+                            self.format.compression,
+                            chunk,
+                            &mut buffer,
+                        )?;
+                        output.write_all(&buffer)?;
+                    }
+                    output.flush()?;
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
     pub fn cat(&mut self, opts: &CatOpts) -> anyhow::Result<()> {
         let selected_channel_id = match opts.channel {
             Channel::Stdout => 0,
@@ -274,13 +720,32 @@ impl LogFile {
         let stdout = std::io::stdout();
         let mut stdout_buf = BufWriter::new(stdout.lock());
 
+        if opts.follow {
+            let tasks = opts
+                .task
+                .as_ref()
+                .map(|array| array.iter().map(JobTaskId::new).collect());
+            return self.follow(
+                tasks,
+                Some(selected_channel_id as ChannelId),
+                &mut stdout_buf,
+                |out, event| {
+                    if let FollowEvent::Chunk { data, .. } = event {
+                        out.write_all(data)?;
+                    }
+                    Ok(())
+                },
+            );
+        }
+
         let mut print_instance = |instance: &InstanceInfo| -> anyhow::Result<()> {
             for chunk in &instance.channels[selected_channel_id] {
-                buffer.resize(chunk.size as usize, 0u8);
-                Self::read_buffer(
+                buffer.clear();
+                Self::read_chunk(
                     &mut self.file,
                     &mut self.current_pos,
-                    chunk.position,
+                    self.format.compression,
+                    chunk,
                     &mut buffer,
                 )?;
                 stdout_buf.write_all(&buffer)?;
@@ -292,7 +757,7 @@ impl LogFile {
 
         if !opts.allow_unfinished {
             for (task_id, instance) in &task_infos {
-                if !instance.finished {
+                if !instance.finished() {
                     anyhow::bail!("Stream for task {} is not finished", task_id);
                 }
             }
@@ -305,7 +770,7 @@ impl LogFile {
         Ok(())
     }
 
-    fn read_block(file: &mut BufReader<File>) -> anyhow::Result<Option<Block>> {
+    fn read_block(file: &mut BufReader<File>, format: LogFormat) -> anyhow::Result<Option<Block>> {
         match file.read_u8() {
             Ok(BLOCK_STREAM_START) => {
                 let task_id: JobTaskId = file.read_u32::<byteorder::BigEndian>()?.into();
@@ -321,21 +786,34 @@ impl LogFile {
                 let instance_id: InstanceId = file.read_u32::<byteorder::BigEndian>()?.into();
                 let channel_id = file.read_u32::<byteorder::BigEndian>()?;
                 let size = file.read_u32::<byteorder::BigEndian>()?;
+                let original_size = if format.compression.is_compressed() {
+                    file.read_u32::<byteorder::BigEndian>()?
+                } else {
+                    size
+                };
                 Ok(Some(Block::StreamChunk {
                     task_id,
                     instance_id,
                     channel_id,
                     size,
+                    original_size,
                 }))
             }
             Ok(BLOCK_STREAM_END) => {
                 let task_id: JobTaskId = file.read_u32::<byteorder::BigEndian>()?.into();
                 let instance_id: InstanceId = file.read_u32::<byteorder::BigEndian>()?.into();
+                let status = if format.version >= 1 {
+                    Some(StreamEndStatus::from_id(file.read_u8()?)?)
+                } else {
+                    None
+                };
                 Ok(Some(Block::StreamEnd {
                     task_id,
                     instance_id,
+                    status,
                 }))
             }
+            Ok(BLOCK_INDEX) => Ok(Some(Block::Index)),
             Err(e) => match e.kind() {
                 ErrorKind::UnexpectedEof => Ok(None),
                 _ => Err(e.into()),
@@ -345,23 +823,16 @@ impl LogFile {
     }
 
     pub fn show(&mut self, opts: &ShowOpts) -> anyhow::Result<()> {
+        if opts.follow {
+            return self.show_follow(opts);
+        }
         let id_width = if let Some(max_id) = self.index.keys().max() {
             max_id.to_string().len()
         } else {
             return Ok(());
         };
-        self.file.seek(SeekFrom::Start(self.start_pos))?;
         let mut buffer = Vec::new();
 
-        let colors = [
-            Color::Red,
-            Color::Green,
-            Color::Yellow,
-            Color::Blue,
-            Color::Magenta,
-            Color::Cyan,
-        ];
-
         let selected_channel_id = opts.channel.as_ref().map(|c| match c {
             Channel::Stdout => 0,
             Channel::Stderr => 1,
@@ -370,125 +841,555 @@ impl LogFile {
         let stdout = std::io::stdout();
         let mut stdout_buf = BufWriter::new(stdout.lock());
 
-        let active_instances: Map<_, _> = self
-            .index
-            .iter()
-            .map(|(job_id, info)| (*job_id, info.last_instance().instance_id))
-            .collect();
+        // Gather chunks of active instances ordered by their position in the file,
+        // which corresponds to the order in which they were received
+        let mut events: Vec<(u64, ShowEvent)> = Vec::new();
+        for (&task_id, task_info) in &self.index {
+            let instance = task_info.last_instance();
+            for (channel_id, chunks) in instance.channels.iter().enumerate() {
+                let channel_id = channel_id as ChannelId;
+                if selected_channel_id
+                    .map(|id| channel_id == id)
+                    .unwrap_or(true)
+                {
+                    events.extend(chunks.iter().map(|chunk| {
+                        (
+                            chunk.position,
+                            ShowEvent::Chunk {
+                                task_id,
+                                channel_id,
+                                chunk,
+                            },
+                        )
+                    }));
+                }
+            }
+            if let Some(position) = instance.end_position {
+                let incomplete = instance.end_status == Some(StreamEndStatus::Incomplete);
+                events.push((
+                    position,
+                    ShowEvent::End {
+                        task_id,
+                        incomplete,
+                    },
+                ));
+            }
+        }
+        events.sort_unstable_by_key(|(position, _)| *position);
 
         let mut has_content = Set::new();
 
+        for (_, event) in events {
+            match event {
+                ShowEvent::Chunk {
+                    task_id,
+                    channel_id,
+                    chunk,
+                } => {
+                    buffer.clear();
+                    Self::read_chunk(
+                        &mut self.file,
+                        &mut self.current_pos,
+                        self.format.compression,
+                        chunk,
+                        &mut buffer,
+                    )?;
+                    if buffer.last() != Some(&b'\n') {
+                        buffer.push(b'\n');
+                    }
+                    let color = COLORS[task_id.as_num() as usize % COLORS.len()];
+                    let header = format!("{:0width$}:{}>", task_id, channel_id, width = id_width,);
+                    write!(
+                        stdout_buf,
+                        "{} {}",
+                        header.on_color(color),
+                        String::from_utf8_lossy(&buffer)
+                    )?;
+                    has_content.insert(task_id);
+                }
+                ShowEvent::End {
+                    task_id,
+                    incomplete,
+                } => {
+                    if !opts.show_empty && !has_content.contains(&task_id) {
+                        continue;
+                    }
+                    let color = COLORS[task_id.as_num() as usize % COLORS.len()];
+                    let message = if incomplete {
+                        "stream incomplete"
+                    } else {
+                        "stream closed"
+                    };
+                    writeln!(
+                        stdout_buf,
+                        "{}",
+                        format!("{:0width$}: > {}", task_id, message, width = id_width)
+                            .on_color(color)
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn show_follow(&mut self, opts: &ShowOpts) -> anyhow::Result<()> {
+        let selected_channel_id = opts.channel.as_ref().map(|c| match c {
+            Channel::Stdout => 0,
+            Channel::Stderr => 1,
+        });
+        let stdout = std::io::stdout();
+        let mut stdout_buf = BufWriter::new(stdout.lock());
+        let mut has_content = Set::new();
+
+        self.follow(
+            None,
+            selected_channel_id,
+            &mut stdout_buf,
+            |out, event| match event {
+                FollowEvent::Chunk {
+                    task_id,
+                    channel_id,
+                    data,
+                } => {
+                    let color = COLORS[task_id.as_num() as usize % COLORS.len()];
+                    let header = format!("{}:{}>", task_id, channel_id);
+                    let newline = if data.last() != Some(&b'\n') {
+                        "\n"
+                    } else {
+                        ""
+                    };
+                    write!(
+                        out,
+                        "{} {}{}",
+                        header.on_color(color),
+                        String::from_utf8_lossy(data),
+                        newline
+                    )?;
+                    has_content.insert(task_id);
+                    Ok(())
+                }
+                FollowEvent::End { task_id } => {
+                    if opts.show_empty || has_content.contains(&task_id) {
+                        let color = COLORS[task_id.as_num() as usize % COLORS.len()];
+                        writeln!(
+                            out,
+                            "{}",
+                            format!("{}: > stream closed", task_id).on_color(color)
+                        )?;
+                    }
+                    Ok(())
+                }
+            },
+        )
+    }
+
+    /// Reads blocks from the current position of the file and waits for new blocks to be appended
+    /// to it. Output of the latest instance of the selected tasks is passed to `on_event`.
+    ///
+    /// Returns when the streams of all selected tasks are finished, or when the log file is complete
+    /// (its index was written).
+    fn follow<W: Write>(
+        &mut self,
+        tasks: Option<Set<JobTaskId>>,
+        channel: Option<ChannelId>,
+        out: &mut W,
+        mut on_event: impl FnMut(&mut W, FollowEvent) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let is_selected = |task_id: &JobTaskId| {
+            tasks
+                .as_ref()
+                .map(|tasks| tasks.contains(task_id))
+                .unwrap_or(true)
+        };
+
+        let mut latest_instances: Map<JobTaskId, InstanceId> = Map::new();
+        let mut finished: Set<JobTaskId> = Set::new();
+        let mut buffer = Vec::new();
+        let mut data = Vec::new();
+        let format = self.format;
+        let compression = format.compression;
+
         loop {
-            match Self::read_block(&mut self.file)? {
-                Some(Block::StreamStart { .. }) => {}
-                Some(Block::StreamChunk {
+            let position = self.file.stream_position()?;
+            let block = match Self::read_block(&mut self.file, format).and_then(|block| {
+                if let Some(Block::StreamChunk { size, .. }) = &block {
+                    buffer.resize(*size as usize, 0u8);
+                    self.file.read_exact(&mut buffer)?;
+                }
+                Ok(block)
+            }) {
+                Ok(Some(block)) => block,
+                Ok(None) => {
+                    out.flush()?;
+                    std::thread::sleep(FOLLOW_POLL_INTERVAL);
+                    continue;
+                }
+                Err(error) if is_unexpected_eof(&error) => {
+                    // The block was not fully written yet
+                    out.flush()?;
+                    std::thread::sleep(FOLLOW_POLL_INTERVAL);
+                    self.file.seek(SeekFrom::Start(position))?;
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+
+            match block {
+                Block::StreamStart {
+                    task_id,
+                    instance_id,
+                } => {
+                    let latest = latest_instances.entry(task_id).or_insert(instance_id);
+                    if *latest <= instance_id {
+                        *latest = instance_id;
+                        finished.remove(&task_id);
+                    }
+                }
+                Block::StreamChunk {
                     task_id,
                     instance_id,
                     channel_id,
-                    size,
-                }) => {
-                    if selected_channel_id
-                        .map(|id| channel_id == id)
-                        .unwrap_or(true)
-                        && *active_instances.get(&task_id).unwrap() == instance_id
+                    original_size,
+                    ..
+                } => {
+                    if is_selected(&task_id)
+                        && channel.map(|id| id == channel_id).unwrap_or(true)
+                        && latest_instances.get(&task_id) == Some(&instance_id)
                     {
-                        buffer.resize(size as usize, 0u8);
-                        self.file.read_exact(&mut buffer)?;
-                        if buffer.last() != Some(&b'\n') {
-                            buffer.push(b'\n');
-                        }
-                        let color = colors[task_id.as_num() as usize % colors.len()];
-                        let header =
-                            format!("{:0width$}:{}>", task_id, channel_id, width = id_width,);
-                        write!(
-                            stdout_buf,
-                            "{} {}",
-                            header.on_color(color),
-                            String::from_utf8_lossy(&buffer)
+                        let data = if compression.is_compressed() {
+                            data.clear();
+                            compression.decompress_into(
+                                &buffer,
+                                original_size as usize,
+                                &mut data,
+                            )?;
+                            &data
+                        } else {
+                            &buffer
+                        };
+                        on_event(
+                            out,
+                            FollowEvent::Chunk {
+                                task_id,
+                                channel_id,
+                                data,
+                            },
                         )?;
-                        has_content.insert(task_id);
-                    } else {
-                        self.file.seek_relative(size as i64)?;
                     }
                 }
-                Some(Block::StreamEnd {
+                Block::StreamEnd {
                     task_id,
                     instance_id,
-                }) => {
-                    if *active_instances.get(&task_id).unwrap() == instance_id {
-                        if !opts.show_empty && !has_content.contains(&task_id) {
-                            continue;
+                    ..
+                } => {
+                    if latest_instances.get(&task_id) == Some(&instance_id) {
+                        finished.insert(task_id);
+                        if is_selected(&task_id) {
+                            on_event(out, FollowEvent::End { task_id })?;
+                        }
+                    }
+                    if let Some(tasks) = &tasks {
+                        if tasks.iter().all(|task_id| finished.contains(task_id)) {
+                            break;
                         }
-                        let color = colors[task_id.as_num() as usize % colors.len()];
-                        writeln!(
-                            stdout_buf,
-                            "{}",
-                            format!("{:0width$}: > stream closed", task_id, width = id_width)
-                                .on_color(color)
-                        )?;
                     }
                 }
-                None => break,
+                Block::Index => break,
             }
         }
+        out.flush()?;
         Ok(())
     }
 
-    fn make_index(file: &mut BufReader<File>) -> anyhow::Result<BTreeMap<JobTaskId, TaskInfo>> {
-        //let position = file.stream_position()?;
-        // index: Map<JobTaskId, Vec<(u64, usize)>>,
-        let mut index = BTreeMap::new();
+    /// Scans blocks from the current position of the file and creates their index.
+    /// Scanning stops at an incomplete block at the end of the file.
+    fn make_index(file: &mut BufReader<File>, format: LogFormat) -> anyhow::Result<LogScan> {
+        let file_size = file.get_ref().metadata()?.len();
+        let mut index = LogIndex::default();
+        let mut position = file.stream_position()?;
         loop {
-            match Self::read_block(file)? {
+            let block = match Self::read_block(file, format) {
+                Ok(block) => block,
+                Err(error) if is_unexpected_eof(&error) => break,
+                Err(error) => return Err(error),
+            };
+            match block {
                 Some(Block::StreamStart {
                     task_id,
                     instance_id,
                 }) => {
                     log::debug!("Task {} started in stream", task_id);
-                    let task_info = index.entry(task_id).or_insert_with(|| TaskInfo {
-                        instances: Default::default(),
-                    });
-                    task_info.new_instance(instance_id)?;
+                    index.on_stream_start(task_id, instance_id)?;
                 }
                 Some(Block::StreamChunk {
                     task_id,
                     instance_id,
                     channel_id,
                     size,
+                    original_size,
                 }) => {
-                    if channel_id >= 2 {
-                        anyhow::bail!("Invalid channel id");
+                    let data_position = file.stream_position()?;
+                    if data_position + size as u64 > file_size {
+                        // The data of the chunk was not fully written
+                        break;
                     }
-                    if let Some(instance) = index
-                        .get_mut(&task_id)
-                        .and_then(|task_info| task_info.instance_mut(instance_id))
-                    {
-                        instance.channels[channel_id as usize].push(ChunkInfo {
-                            position: file.stream_position()?,
-                            size,
-                        });
-                    } else {
-                        anyhow::bail!("Data chunk for invalid task");
-                    };
+                    index.on_stream_chunk(
+                        task_id,
+                        instance_id,
+                        channel_id,
+                        data_position,
+                        size,
+                        original_size,
+                    )?;
                     file.seek_relative(size as i64)?;
                 }
                 Some(Block::StreamEnd {
                     task_id,
                     instance_id,
+                    status,
                 }) => {
                     log::debug!("Task {} finished in stream", task_id);
-                    if let Some(instance) = index
-                        .get_mut(&task_id)
-                        .and_then(|info| info.instance_mut(instance_id))
-                    {
-                        instance.finished = true;
-                    } else {
-                        anyhow::bail!("Termination of an invalid task");
-                    };
+                    index.on_stream_end(task_id, instance_id, file.stream_position()?, status)?;
+                }
+                Some(Block::Index) | None => {
+                    return Ok(LogScan {
+                        index,
+                        data_end: position,
+                        truncated_size: 0,
+                    })
                 }
-                None => break,
             };
+            position = file.stream_position()?;
         }
-        Ok(index)
+        Ok(LogScan {
+            index,
+            data_end: position,
+            truncated_size: file_size - position,
+        })
+    }
+}
+
+fn is_unexpected_eof(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<std::io::Error>()
+        .map(|error| error.kind() == ErrorKind::UnexpectedEof)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::commands::log::SplitOpts;
+    use crate::stream::compression::LogCompression;
+    use crate::stream::reader::logfile::{
+        FollowEvent, LogFile, BLOCK_STREAM_CHUNK, BLOCK_STREAM_END, BLOCK_STREAM_START,
+        HQ_LOG_HEADER, HQ_LOG_VERSION,
+    };
+    use crate::transfer::stream::StreamEndStatus;
+    use crate::{JobTaskId, Set};
+    use std::io::Write;
+    use std::time::Duration;
+    use tempdir::TempDir;
+
+    fn header() -> Vec<u8> {
+        let mut data = HQ_LOG_HEADER.to_vec();
+        data.extend_from_slice(&HQ_LOG_VERSION.to_be_bytes());
+        data.extend_from_slice(&[0; 16]);
+        data
+    }
+
+    fn block(kind: u8, task: u32, instance: u32) -> Vec<u8> {
+        let mut data = vec![kind];
+        data.extend_from_slice(&task.to_be_bytes());
+        data.extend_from_slice(&instance.to_be_bytes());
+        data
+    }
+
+    fn chunk(task: u32, instance: u32, channel: u32, content: &[u8]) -> Vec<u8> {
+        let mut data = block(BLOCK_STREAM_CHUNK, task, instance);
+        data.extend_from_slice(&channel.to_be_bytes());
+        data.extend_from_slice(&(content.len() as u32).to_be_bytes());
+        data.extend_from_slice(content);
+        data
+    }
+
+    fn end(task: u32, instance: u32, status: StreamEndStatus) -> Vec<u8> {
+        let mut data = block(BLOCK_STREAM_END, task, instance);
+        data.push(status.id());
+        data
+    }
+
+    #[test]
+    fn follow_appended_blocks() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        let path = tmp_dir.path().join("log");
+
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(&header()).unwrap();
+        file.write_all(&block(BLOCK_STREAM_START, 1, 0)).unwrap();
+        file.write_all(&block(BLOCK_STREAM_START, 2, 0)).unwrap();
+        file.write_all(&chunk(1, 0, 0, b"a")).unwrap();
+        file.flush().unwrap();
+
+        let writer = std::thread::spawn(move || {
+            let data = chunk(1, 0, 0, b"b");
+            // Write a partial block
+            file.write_all(&data[..5]).unwrap();
+            file.flush().unwrap();
+            std::thread::sleep(Duration::from_millis(600));
+            file.write_all(&data[5..]).unwrap();
+            file.write_all(&chunk(2, 0, 0, b"x")).unwrap();
+            file.write_all(&chunk(1, 0, 1, b"err")).unwrap();
+            file.write_all(&end(1, 0, StreamEndStatus::Finished))
+                .unwrap();
+            file.flush().unwrap();
+        });
+
+        let mut log_file = LogFile::open_for_follow(&path).unwrap();
+        let mut output = Vec::new();
+        let mut ended = vec![];
+        log_file
+            .follow(
+                Some(Set::from_iter([JobTaskId::new(1)])),
+                Some(0),
+                &mut output,
+                |out, event| {
+                    match event {
+                        FollowEvent::Chunk { data, .. } => out.extend_from_slice(data),
+                        FollowEvent::End { task_id } => ended.push(task_id),
+                    }
+                    Ok(())
+                },
+            )
+            .unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(output, b"ab");
+        assert_eq!(ended, vec![JobTaskId::new(1)]);
+    }
+
+    #[test]
+    fn follow_compressed_log() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        let path = tmp_dir.path().join("log");
+        let compression = LogCompression::Zstd;
+
+        let mut data = HQ_LOG_HEADER.to_vec();
+        data.extend_from_slice(&HQ_LOG_VERSION.to_be_bytes());
+        data.extend_from_slice(&compression.id().to_be_bytes());
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&block(BLOCK_STREAM_START, 1, 0));
+        for content in [b"hello".as_slice(), b"world"] {
+            let compressed = compression.compress(content).unwrap();
+            data.extend_from_slice(&block(BLOCK_STREAM_CHUNK, 1, 0));
+            data.extend_from_slice(&0u32.to_be_bytes());
+            data.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+            data.extend_from_slice(&(content.len() as u32).to_be_bytes());
+            data.extend_from_slice(&compressed);
+        }
+        data.extend_from_slice(&end(1, 0, StreamEndStatus::Finished));
+        std::fs::write(&path, data).unwrap();
+
+        let summary = LogFile::open(&path).unwrap().summary();
+        assert_eq!(summary.compression, compression);
+        assert_eq!(summary.stdout_size, 10);
+
+        let mut log_file = LogFile::open_for_follow(&path).unwrap();
+        let mut output = Vec::new();
+        log_file
+            .follow(
+                Some(Set::from_iter([JobTaskId::new(1)])),
+                None,
+                &mut output,
+                |out, event| {
+                    if let FollowEvent::Chunk { data, .. } = event {
+                        out.extend_from_slice(data);
+                    }
+                    Ok(())
+                },
+            )
+            .unwrap();
+        assert_eq!(output, b"helloworld");
+    }
+
+    #[test]
+    fn split_into_files() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        let path = tmp_dir.path().join("log");
+
+        let mut data = header();
+        data.extend_from_slice(&block(BLOCK_STREAM_START, 1, 0));
+        data.extend_from_slice(&chunk(1, 0, 0, b"superseded"));
+        data.extend_from_slice(&block(BLOCK_STREAM_START, 1, 1));
+        data.extend_from_slice(&block(BLOCK_STREAM_START, 2, 0));
+        data.extend_from_slice(&chunk(1, 1, 0, b"a"));
+        data.extend_from_slice(&chunk(2, 0, 1, b"err"));
+        data.extend_from_slice(&chunk(1, 1, 0, b"b"));
+        data.extend_from_slice(&end(1, 1, StreamEndStatus::Finished));
+        data.extend_from_slice(&end(2, 0, StreamEndStatus::Failed));
+        std::fs::write(&path, data).unwrap();
+
+        let split = |dir: &str, pattern: &str, include_superseded: bool, failed: bool| {
+            let output_dir = tmp_dir.path().join(dir);
+            let count = LogFile::open(&path).unwrap().split(&SplitOpts {
+                output_dir: output_dir.clone(),
+                pattern: pattern.to_string(),
+                task: None,
+                include_superseded,
+                failed,
+            });
+            (output_dir, count)
+        };
+
+        let (dir, count) = split("all", "%{TASK_ID}.%{CHANNEL}", false, false);
+        assert_eq!(count.unwrap(), 2);
+        assert_eq!(std::fs::read(dir.join("1.stdout")).unwrap(), b"ab");
+        assert_eq!(std::fs::read(dir.join("2.stderr")).unwrap(), b"err");
+        assert!(!dir.join("1.stderr").exists());
+
+        let (dir, count) = split("superseded", "%{TASK_ID}/%{INSTANCE_ID}", true, false);
+        assert_eq!(count.unwrap(), 3);
+        assert_eq!(std::fs::read(dir.join("1/0")).unwrap(), b"superseded");
+        assert_eq!(std::fs::read(dir.join("1/1")).unwrap(), b"ab");
+
+        let (dir, count) = split("failed", "%{TASK_ID}.%{CHANNEL}", false, true);
+        assert_eq!(count.unwrap(), 1);
+        assert!(dir.join("2.stderr").exists());
+
+        // Superseded instances would overwrite each other
+        let (_, count) = split("invalid", "%{TASK_ID}", true, false);
+        assert!(count.is_err());
+    }
+
+    #[test]
+    fn repair_truncated_log() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        let path = tmp_dir.path().join("log");
+
+        let mut data = header();
+        data.extend_from_slice(&block(BLOCK_STREAM_START, 1, 0));
+        data.extend_from_slice(&block(BLOCK_STREAM_START, 2, 0));
+        data.extend_from_slice(&chunk(1, 0, 0, b"a"));
+        data.extend_from_slice(&end(1, 0, StreamEndStatus::Finished));
+        data.extend_from_slice(&chunk(2, 0, 0, b"b"));
+        // Chunk whose data was not fully written
+        let partial = chunk(2, 0, 0, b"cdef");
+        data.extend_from_slice(&partial[..partial.len() - 2]);
+        std::fs::write(&path, data).unwrap();
+
+        let mut log_file = LogFile::open(&path).unwrap();
+        let summary = log_file.summary();
+        assert_eq!(summary.n_opened, 1);
+        assert_eq!(summary.stdout_size, 2);
+        assert_eq!(summary.truncated_size, partial.len() as u64 - 2);
+
+        let repaired = tmp_dir.path().join("repaired");
+        assert_eq!(log_file.repair(&repaired).unwrap(), 1);
+        assert!(std::fs::read(&repaired).unwrap().ends_with(b"HQ:index"));
+
+        let summary = LogFile::open(&repaired).unwrap().summary();
+        assert_eq!(summary.n_opened, 0);
+        assert_eq!(summary.n_incomplete, 1);
+        assert_eq!(summary.stdout_size, 2);
+        assert_eq!(summary.truncated_size, 0);
     }
 }
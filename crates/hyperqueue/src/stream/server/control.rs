@@ -1,3 +1,4 @@
+use crate::stream::compression::LogCompression;
 use crate::transfer::messages::StreamStats;
 use crate::JobId;
 use std::path::PathBuf;
@@ -8,6 +9,7 @@ pub enum StreamServerControlMessage {
     RegisterStream {
         job_id: JobId,
         path: PathBuf,
+        compression: LogCompression,
         response: oneshot::Sender<()>,
     },
     UnregisterStream(JobId),
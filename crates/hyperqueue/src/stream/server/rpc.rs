@@ -8,6 +8,7 @@ use tako::comm::{forward_queue_to_sealed_sink, open_message};
 use tako::server::ConnectionDescriptor;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::error::TryRecvError;
 use tokio::sync::mpsc::{
     channel, unbounded_channel, Receiver, Sender, UnboundedReceiver, UnboundedSender,
 };
@@ -16,13 +17,15 @@ use tokio_util::codec::{Framed, LengthDelimitedCodec};
 
 use super::control::StreamServerControlMessage;
 
+use crate::stream::compression::LogCompression;
 use crate::stream::reader::logfile::{
-    BLOCK_STREAM_CHUNK, BLOCK_STREAM_END, BLOCK_STREAM_START, HQ_LOG_HEADER, HQ_LOG_VERSION,
+    LogIndex, BLOCK_STREAM_CHUNK, BLOCK_STREAM_END, BLOCK_STREAM_START, HQ_LOG_HEADER,
+    HQ_LOG_HEADER_SIZE, HQ_LOG_VERSION,
 };
 use crate::transfer::messages::StreamStats;
 use crate::transfer::stream::{
-    EndTaskStreamMsg, EndTaskStreamResponseMsg, FromStreamerMessage, StreamRegistration,
-    ToStreamerMessage,
+    EndTaskStreamMsg, EndTaskStreamResponseMsg, FromStreamerMessage, StreamEndStatus,
+    StreamErrorMsg, ToStreamerMessage,
 };
 use crate::WrappedRcRefCell;
 use crate::{JobId, JobTaskId, Map, Set};
@@ -40,8 +43,8 @@ struct StreamServerState {
     /// Active streams
     streams: Map<JobId, Sender<StreamMessage>>,
 
-    /// Registered log filenames for each job
-    registrations: Map<JobId, PathBuf>,
+    /// Registered log filenames (and compression of their data) for each job
+    registrations: Map<JobId, (PathBuf, LogCompression)>,
 
     /// List of opened connections
     connections: Set<String>,
@@ -66,15 +69,15 @@ impl StreamServerStateRef {
         let mut state = self.get_mut();
         if let Some(s) = state.streams.get(&job_id) {
             Ok(s.clone())
-        } else if let Some(path) = state.registrations.get(&job_id).cloned() {
+        } else if let Some((path, compression)) = state.registrations.get(&job_id).cloned() {
             log::debug!("Starting new stream for job {}", job_id);
             let (sender, mut receiver) = channel(STREAM_BUFFER_SIZE);
             state.streams.insert(job_id, sender.clone());
             state.files.insert(path.clone());
             let state_ref = self.clone();
             tokio::task::spawn_local(async move {
-                if let Err(e) = file_writer(&mut receiver, &path).await {
-                    error_state(receiver, e.to_string()).await;
+                if let Err(e) = file_writer(&mut receiver, job_id, &path, compression).await {
+                    error_state(receiver, job_id, e.to_string()).await;
                 }
                 let mut state = state_ref.get_mut();
                 state.files.remove(&path);
@@ -86,9 +89,12 @@ impl StreamServerStateRef {
     }
 }
 
-fn send_error(sender: Option<UnboundedSender<Bytes>>, message: String) {
+fn send_error(sender: Option<UnboundedSender<Bytes>>, job_id: JobId, message: String) {
     if let Some(sender) = sender {
-        let msg = ToStreamerMessage::Error(message);
+        let msg = ToStreamerMessage::Error(StreamErrorMsg {
+            job: job_id,
+            message,
+        });
         let data = tako::comm::serialize(&msg).unwrap();
         if sender.send(data.into()).is_err() {
             log::debug!("Sending stream error failed");
@@ -96,27 +102,62 @@ fn send_error(sender: Option<UnboundedSender<Bytes>>, message: String) {
     }
 }
 
-async fn error_state(mut receiver: Receiver<StreamMessage>, message: String) {
+async fn error_state(mut receiver: Receiver<StreamMessage>, job_id: JobId, message: String) {
     while let Some(msg) = receiver.recv().await {
         match msg {
             StreamMessage::Message(_, response_sender) => {
-                send_error(response_sender, message.clone());
+                send_error(response_sender, job_id, message.clone());
             } //StreamMessage::Close => break,
         }
     }
 }
 
-async fn file_writer(receiver: &mut Receiver<StreamMessage>, path: &Path) -> anyhow::Result<()> {
+/// Updates the index of a log file, the index is dropped if the stream is not consistent.
+fn update_index(
+    index: &mut Option<LogIndex>,
+    update: impl FnOnce(&mut LogIndex) -> anyhow::Result<()>,
+) {
+    if let Some(log_index) = index {
+        if let Err(e) = update(log_index) {
+            log::warn!("Log file index cannot be created: {}", e);
+            *index = None;
+        }
+    }
+}
+
+async fn file_writer(
+    receiver: &mut Receiver<StreamMessage>,
+    job_id: JobId,
+    path: &Path,
+    compression: LogCompression,
+) -> anyhow::Result<()> {
     let mut file = BufWriter::new(File::create(path).await?);
     let mut buffer = BytesMut::with_capacity(24);
     buffer.put_slice(HQ_LOG_HEADER);
     buffer.put_u32(HQ_LOG_VERSION);
+    buffer.put_u64(compression.id());
     buffer.put_u64(0); // Reserved bytes
-    buffer.put_u64(0); // Reserved bytes
+    debug_assert_eq!(buffer.len() as u64, HQ_LOG_HEADER_SIZE);
     file.write_all(&buffer).await?;
     file.flush().await?; // Make sure that header is written to avoid empty files for long time
 
-    while let Some(msg) = receiver.recv().await {
+    let mut position = buffer.len() as u64;
+    let mut index = Some(LogIndex::default());
+
+    loop {
+        let msg = match receiver.try_recv() {
+            Ok(msg) => msg,
+            Err(TryRecvError::Empty) => {
+                // Flush buffered data when there are no more pending messages, so that
+                // the log file can be followed while the job is running
+                file.flush().await?;
+                match receiver.recv().await {
+                    Some(msg) => msg,
+                    None => break,
+                }
+            }
+            Err(TryRecvError::Disconnected) => break,
+        };
         buffer.clear();
         match msg {
             StreamMessage::Message(FromStreamerMessage::Start(s), response_sender) => {
@@ -124,38 +165,77 @@ async fn file_writer(receiver: &mut Receiver<StreamMessage>, path: &Path) -> any
                 buffer.put_u32(s.task.into());
                 buffer.put_u32(s.instance.into());
                 if let Err(e) = file.write_all(&buffer).await {
-                    send_error(response_sender, e.to_string());
+                    send_error(response_sender, job_id, e.to_string());
                     return Err(e.into());
                 }
+                position += buffer.len() as u64;
+                update_index(&mut index, |index| {
+                    index.on_stream_start(s.task, s.instance)
+                });
             }
             StreamMessage::Message(FromStreamerMessage::Data(s), response_sender) => {
+                let original_size = s.data.len();
+                let data = if compression.is_compressed() {
+                    match compression.compress(&s.data) {
+                        Ok(data) => data,
+                        Err(e) => {
+                            send_error(response_sender, job_id, e.to_string());
+                            return Err(e);
+                        }
+                    }
+                } else {
+                    s.data
+                };
                 buffer.put_u8(BLOCK_STREAM_CHUNK);
                 buffer.put_u32(s.task.into());
                 buffer.put_u32(s.instance.into());
                 buffer.put_u32(s.channel);
-                buffer.put_u32(s.data.len() as u32);
+                buffer.put_u32(data.len() as u32);
+                if compression.is_compressed() {
+                    buffer.put_u32(original_size as u32);
+                }
                 if let Err(e) = file.write_all(&buffer).await {
-                    send_error(response_sender, e.to_string());
+                    send_error(response_sender, job_id, e.to_string());
                     return Err(e.into());
                 }
-                if let Err(e) = file.write_all(&s.data).await {
-                    send_error(response_sender, e.to_string());
+                if let Err(e) = file.write_all(&data).await {
+                    send_error(response_sender, job_id, e.to_string());
                     return Err(e.into());
                 }
+                position += buffer.len() as u64;
+                update_index(&mut index, |index| {
+                    index.on_stream_chunk(
+                        s.task,
+                        s.instance,
+                        s.channel,
+                        position,
+                        data.len() as u32,
+                        original_size as u32,
+                    )
+                });
+                position += data.len() as u64;
             }
             StreamMessage::Message(FromStreamerMessage::End(s), response_sender) => {
                 buffer.put_u8(BLOCK_STREAM_END);
                 buffer.put_u32(s.task.into());
                 buffer.put_u32(s.instance.into());
+                buffer.put_u8(s.status.id());
                 if let Err(e) = file.write_all(&buffer).await {
-                    send_error(response_sender, e.to_string());
+                    send_error(response_sender, job_id, e.to_string());
                     return Err(e.into());
                 }
                 if let Err(e) = file.flush().await {
-                    send_error(response_sender, e.to_string());
+                    send_error(response_sender, job_id, e.to_string());
                     return Err(e.into());
                 }
-                let msg = ToStreamerMessage::EndResponse(EndTaskStreamResponseMsg { task: s.task });
+                position += buffer.len() as u64;
+                update_index(&mut index, |index| {
+                    index.on_stream_end(s.task, s.instance, position, Some(s.status))
+                });
+                let msg = ToStreamerMessage::EndResponse(EndTaskStreamResponseMsg {
+                    job: job_id,
+                    task: s.task,
+                });
                 let data = tako::comm::serialize(&msg).unwrap();
                 if let Some(response_sender) = response_sender {
                     let _ = response_sender.send(data.into());
@@ -163,6 +243,14 @@ async fn file_writer(receiver: &mut Receiver<StreamMessage>, path: &Path) -> any
             } //StreamMessage::Close => break,
         }
     }
+
+    // The stream was unregistered and all its connections were closed, so nothing
+    // else will be written to the file.
+    if let Some(index) = index {
+        log::debug!("Writing index into log file {}", path.display());
+        file.write_all(&index.serialize_footer(position)?).await?;
+    }
+    file.flush().await?;
     Ok(())
 }
 
@@ -181,24 +269,38 @@ pub fn start_stream_server() -> UnboundedSender<StreamServerControlMessage> {
     sender
 }
 
+/// Receives messages of all jobs that stream through a single worker connection and forwards
+/// them into the file writers of the corresponding jobs.
+/// A slow file writer of one job blocks reading from the connection, which propagates the
+/// backpressure to the worker.
 async fn receive_loop(
-    stream: &Sender<StreamMessage>,
-    opened_ids: &mut Set<(JobTaskId, InstanceId)>,
+    state_ref: &StreamServerStateRef,
+    opened_ids: &mut Set<(JobId, JobTaskId, InstanceId)>,
     mut receiver: SplitStream<Framed<tokio::net::TcpStream, LengthDelimitedCodec>>,
     mut opener: Option<StreamOpener>,
     response_sender: &UnboundedSender<Bytes>,
 ) -> anyhow::Result<()> {
     while let Some(data) = receiver.next().await {
         let message: FromStreamerMessage = open_message(&mut opener, &data?)?;
+        let job_id = message.job();
+
+        let stream = match state_ref.get_stream(job_id) {
+            Ok(stream) => stream,
+            Err(e) => {
+                // Streams of other jobs that share this connection are not affected
+                send_error(Some(response_sender.clone()), job_id, e.to_string());
+                continue;
+            }
+        };
 
         match &message {
             FromStreamerMessage::Start(m) => {
-                opened_ids.insert((m.task, m.instance));
+                opened_ids.insert((job_id, m.task, m.instance));
             }
             FromStreamerMessage::Data(_) => { /* Do nothing */ }
 
             FromStreamerMessage::End(m) => {
-                opened_ids.remove(&(m.task, m.instance));
+                opened_ids.remove(&(job_id, m.task, m.instance));
             }
         };
 
@@ -218,25 +320,18 @@ async fn receive_loop(
 
 async fn handle_connection(
     state_ref: &StreamServerStateRef,
-    mut connection: ConnectionDescriptor,
+    connection: ConnectionDescriptor,
 ) -> anyhow::Result<()> {
-    let register: StreamRegistration = if let Some(data) = connection.receiver.next().await {
-        open_message(&mut connection.opener, &data?)?
-    } else {
-        anyhow::bail!("Stream closed without registration");
-    };
-    log::debug!("Streamer for job {} connected", register.job);
-    let stream = state_ref.get_stream(register.job)?;
-
+    log::debug!("Streamer connected from {}", connection.address);
     let (sender, receiver) = unbounded_channel();
     let snd_loop = forward_queue_to_sealed_sink(receiver, connection.sender, connection.sealer);
     let mut open_ids = Set::new();
     tokio::select! {
         r = snd_loop => { log::debug!("Send queue for stream closed {:?}", r); },
-        r = receive_loop(&stream, &mut open_ids, connection.receiver, connection.opener, &sender) => {
+        r = receive_loop(state_ref, &mut open_ids, connection.receiver, connection.opener, &sender) => {
             log::debug!("Connection for stream closed {:?}", r);
             if let Err(e) = r {
-                send_error(Some(sender), e.to_string());
+                log::error!("Stream connection failed: {}", e);
             };
         },
     }
@@ -246,14 +341,21 @@ async fn handle_connection(
             "Closing streaming connection while {} ids are still open",
             open_ids.len()
         );
-        for (task, instance) in open_ids {
+        for (job, task, instance) in open_ids {
             /* This is a kind of emergency closing, so we do not care about result as we cannot do anything about it */
-            let _ = stream
-                .send(StreamMessage::Message(
-                    FromStreamerMessage::End(EndTaskStreamMsg { task, instance }),
-                    None,
-                ))
-                .await;
+            if let Ok(stream) = state_ref.get_stream(job) {
+                let _ = stream
+                    .send(StreamMessage::Message(
+                        FromStreamerMessage::End(EndTaskStreamMsg {
+                            job,
+                            task,
+                            instance,
+                            status: StreamEndStatus::Failed,
+                        }),
+                        None,
+                    ))
+                    .await;
+            }
         }
     }
 
@@ -270,11 +372,15 @@ async fn stream_server_main(mut control_receiver: UnboundedReceiver<StreamServer
             StreamServerControlMessage::RegisterStream {
                 job_id,
                 path,
+                compression,
                 response,
             } => {
                 log::debug!("Registering stream {}: {}", job_id, path.display());
                 let mut state = state_ref.get_mut();
-                assert!(state.registrations.insert(job_id, path).is_none());
+                assert!(state
+                    .registrations
+                    .insert(job_id, (path, compression))
+                    .is_none());
                 let _ = response.send(());
             }
             StreamServerControlMessage::UnregisterStream(job_id) => {
@@ -307,7 +413,7 @@ async fn stream_server_main(mut control_receiver: UnboundedReceiver<StreamServer
                     registrations: state
                         .registrations
                         .iter()
-                        .map(|(job_id, path)| (*job_id, path.clone()))
+                        .map(|(job_id, (path, _))| (*job_id, path.clone()))
                         .collect(),
                     files: state
                         .files
@@ -319,3 +425,118 @@ async fn stream_server_main(mut control_receiver: UnboundedReceiver<StreamServer
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::stream::compression::LogCompression;
+    use crate::stream::reader::logfile::{LogFile, Summary};
+    use crate::stream::server::rpc::{file_writer, StreamMessage};
+    use crate::transfer::stream::{
+        DataMsg, EndTaskStreamMsg, FromStreamerMessage, StartTaskStreamMsg, StreamEndStatus,
+    };
+    use std::path::Path;
+    use tempdir::TempDir;
+    use tokio::sync::mpsc::channel;
+
+    fn check_summary(path: &Path) {
+        let Summary {
+            n_tasks,
+            n_streams,
+            n_opened,
+            stdout_size,
+            stderr_size,
+            ..
+        } = LogFile::open(path).unwrap().summary();
+        assert_eq!(n_tasks, 2);
+        assert_eq!(n_streams, 2);
+        assert_eq!(n_opened, 1);
+        assert_eq!(stdout_size, 8);
+        assert_eq!(stderr_size, 3);
+    }
+
+    async fn write_log(path: &Path, compression: LogCompression) {
+        let (sender, mut receiver) = channel(32);
+        let messages = vec![
+            FromStreamerMessage::Start(StartTaskStreamMsg {
+                job: 1.into(),
+                task: 1.into(),
+                instance: 0.into(),
+            }),
+            FromStreamerMessage::Data(DataMsg {
+                job: 1.into(),
+                task: 1.into(),
+                instance: 0.into(),
+                channel: 0,
+                data: b"hello".to_vec(),
+            }),
+            FromStreamerMessage::Start(StartTaskStreamMsg {
+                job: 1.into(),
+                task: 2.into(),
+                instance: 0.into(),
+            }),
+            FromStreamerMessage::Data(DataMsg {
+                job: 1.into(),
+                task: 2.into(),
+                instance: 0.into(),
+                channel: 1,
+                data: b"err".to_vec(),
+            }),
+            FromStreamerMessage::Data(DataMsg {
+                job: 1.into(),
+                task: 1.into(),
+                instance: 0.into(),
+                channel: 0,
+                data: b"abc".to_vec(),
+            }),
+            FromStreamerMessage::End(EndTaskStreamMsg {
+                job: 1.into(),
+                task: 1.into(),
+                instance: 0.into(),
+                status: StreamEndStatus::Finished,
+            }),
+        ];
+        for message in messages {
+            assert!(sender
+                .send(StreamMessage::Message(message, None))
+                .await
+                .is_ok());
+        }
+        drop(sender);
+        file_writer(&mut receiver, 1.into(), path, compression)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn write_log_index() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        let path = tmp_dir.path().join("log");
+        write_log(&path, LogCompression::None).await;
+
+        // Read using the index
+        check_summary(&path);
+
+        // Remove the index, the file has to be scanned
+        let mut data = std::fs::read(&path).unwrap();
+        let footer = &data[data.len() - 16..];
+        assert_eq!(&footer[8..], b"HQ:index");
+        let index_pos = u64::from_be_bytes(footer[..8].try_into().unwrap());
+        data.truncate(index_pos as usize);
+        std::fs::write(&path, data).unwrap();
+        check_summary(&path);
+    }
+
+    #[tokio::test]
+    async fn write_compressed_log() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        for compression in [LogCompression::Gzip, LogCompression::Zstd] {
+            let path = tmp_dir.path().join(compression.to_string());
+            write_log(&path, compression).await;
+            check_summary(&path);
+
+            let summary = LogFile::open(&path).unwrap().summary();
+            assert_eq!(summary.compression, compression);
+            assert!(summary.compressed_stdout_size > 0);
+        }
+    }
+}
@@ -5,15 +5,21 @@ use serde::Serialize;
 use crate::client::status::Status;
 use crate::common::arraydef::IntArray;
 use crate::common::manager::info::ManagerType;
-use crate::server::autoalloc::{Allocation, QueueId, QueueInfo};
+use crate::common::schedule::ActiveSchedule;
+use crate::server::autoalloc::{
+    Allocation, AllocationId, AllocationOutput, NodeHourBudget, QueueId, QueueInfo,
+};
 use crate::server::job::{JobTaskCounters, JobTaskInfo};
 use crate::{JobId, JobTaskCount, JobTaskId, Map, WorkerId};
 use bstr::BString;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
-use crate::server::event::MonitoringEvent;
+use crate::server::event::{MonitoringEvent, MonitoringEventId};
+use crate::stream::compression::LogCompression;
+use crate::transfer::stream::ChannelId;
 use tako::gateway::{LostWorkerReason, MonitoringEventRequest, ResourceRequest};
+use tako::hwstats::WorkerHwStateMessage;
 use tako::program::ProgramDefinition;
 use tako::worker::{ServerLostPolicy, WorkerConfiguration};
 
@@ -26,14 +32,36 @@ pub enum FromClientMessage {
     Cancel(CancelRequest),
     JobDetail(JobDetailRequest),
     JobInfo(JobInfoRequest),
+    JobStats(JobStatsRequest),
     WorkerList,
     WorkerInfo(WorkerInfoRequest),
     Stats,
+    StatsHistory(StatsHistoryRequest),
     StopWorker(StopWorkerMessage),
     Stop,
     AutoAlloc(AutoAllocRequest),
     WaitForJobs(WaitForJobsRequest),
     MonitoringEvents(MonitoringEventRequest),
+    /// Subscribe to events. The server will send [`ToClientMessage::Event`] messages until the
+    /// client disconnects.
+    StreamEvents(StreamEventsRequest),
+    TaskOutput(TaskOutputRequest),
+}
+
+/// Selects which events are sent to an event subscriber.
+/// Empty lists do not restrict the events.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EventFilter {
+    pub event_types: Vec<String>,
+    pub job_ids: Vec<JobId>,
+    pub worker_ids: Vec<WorkerId>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StreamEventsRequest {
+    /// Stored events with an ID larger than this one are sent before live events
+    pub after_id: Option<MonitoringEventId>,
+    pub filter: EventFilter,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -43,6 +71,22 @@ pub enum PinMode {
     OpenMP,
 }
 
+/// What happens when a task exceeds its output size limit.
+#[derive(clap::ArgEnum, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputLimitPolicy {
+    /// The output is truncated and the task continues running
+    Truncate,
+    /// The output is truncated and the task fails
+    Fail,
+}
+
+/// Maximum size of each output channel (stdout/stderr) of a task.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OutputLimit {
+    pub max_size: u64,
+    pub policy: OutputLimitPolicy,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TaskBody {
     pub program: ProgramDefinition,
@@ -50,6 +94,8 @@ pub struct TaskBody {
     pub task_dir: bool,
     pub job_id: JobId,
     pub task_id: JobTaskId,
+    pub output_limit: Option<OutputLimit>,
+    pub spool: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -61,6 +107,9 @@ pub struct TaskDescription {
     pub time_limit: Option<Duration>,
     pub priority: tako::Priority,
     pub crash_limit: u32,
+    pub output_limit: Option<OutputLimit>,
+    /// Piped output is stored in the work directory of the worker instead of being streamed
+    pub spool: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -99,6 +148,9 @@ pub struct SubmitRequest {
     pub max_fails: Option<JobTaskCount>,
     pub submit_dir: PathBuf,
     pub log: Option<PathBuf>,
+    pub log_compression: LogCompression,
+    /// Command executed by the server when the job completes
+    pub on_finish: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -148,12 +200,45 @@ pub struct JobInfoRequest {
     pub selector: IdSelector,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JobStatsRequest {
+    pub selector: IdSelector,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct JobDetailRequest {
     pub job_id_selector: IdSelector,
     pub task_selector: Option<TaskSelector>,
 }
 
+/// Asks for a part of the output of a task that was spooled on a worker.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaskOutputRequest {
+    pub job_id: JobId,
+    pub task_id: JobTaskId,
+    pub channel: ChannelId,
+    pub offset: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaskOutputResponse {
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+    /// There is no more output available after this part
+    pub eof: bool,
+}
+
+/// Requests sent by the server to workers (through tako).
+#[derive(Serialize, Deserialize, Debug)]
+pub enum WorkerRpcRequest {
+    TaskOutput(TaskOutputRequest),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum WorkerRpcResponse {
+    TaskOutput(TaskOutputResponse),
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StopWorkerMessage {
     pub selector: IdSelector,
@@ -189,6 +274,12 @@ pub enum AutoAllocRequest {
     ResumeQueue {
         queue_id: QueueId,
     },
+    History {
+        queue_id: QueueId,
+    },
+    Log {
+        allocation_id: AllocationId,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -202,7 +293,12 @@ pub struct AllocationQueueParams {
     pub worker_cpu_arg: Option<String>,
     pub worker_resources_args: Vec<String>,
     pub max_worker_count: Option<u32>,
+    pub min_workers: u32,
     pub idle_timeout: Option<Duration>,
+    pub budget: Option<NodeHourBudget>,
+    pub active_schedule: Option<ActiveSchedule>,
+    pub cap_timelimit: bool,
+    pub job_arrays: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -216,15 +312,19 @@ pub struct WaitForJobsRequest {
 pub enum ToClientMessage {
     JobInfoResponse(JobInfoResponse),
     JobDetailResponse(Vec<(JobId, Option<JobDetail>)>),
+    JobStatsResponse(JobStatsResponse),
     SubmitResponse(SubmitResponse),
     WorkerListResponse(WorkerListResponse),
     WorkerInfoResponse(Option<WorkerInfo>),
     StatsResponse(StatsResponse),
+    StatsHistoryResponse(StatsHistoryResponse),
     StopWorkerResponse(Vec<(WorkerId, StopWorkerResponse)>),
     CancelJobResponse(Vec<(JobId, CancelJobResponse)>),
     AutoAllocResponse(AutoAllocResponse),
     WaitForJobsResponse(WaitForJobsResponse),
     MonitoringEventsResponse(Vec<MonitoringEvent>),
+    Event(MonitoringEvent),
+    TaskOutputResponse(TaskOutputResponse),
     Error(String),
 }
 
@@ -255,6 +355,36 @@ pub struct StatsResponse {
     pub stream_stats: StreamStats,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StatsHistoryRequest {
+    /// Only samples taken during this duration before now are returned
+    pub window: Duration,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StatsHistoryResponse {
+    /// Duration between two consecutive samples
+    pub interval: Duration,
+    pub samples: Vec<StatsSample>,
+}
+
+/// Aggregated state of the server at a single point in time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StatsSample {
+    pub time: DateTime<Utc>,
+    /// Number of tasks that have finished since the previous sample
+    pub tasks_finished: u64,
+    pub tasks_waiting: u64,
+    pub tasks_running: u64,
+    pub workers: u64,
+    /// Mean CPU utilization (in percent) of connected workers that have sent an overview
+    pub cpu_usage: Option<f64>,
+    /// Number of scheduler invocations since the previous sample
+    pub scheduler_runs: u64,
+    /// Mean duration of a scheduler invocation since the previous sample
+    pub scheduler_duration: Option<Duration>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SubmitResponse {
     pub job: JobDetail,
@@ -280,6 +410,8 @@ pub struct WorkerInfo {
     pub id: WorkerId,
     pub configuration: WorkerConfiguration,
     pub ended: Option<WorkerExitInfo>,
+    /// Hardware utilization from the last overview sent by the worker
+    pub hw_state: Option<WorkerHwStateMessage>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -303,6 +435,41 @@ pub struct JobDetail {
     pub completion_date_or_now: DateTime<Utc>,
 }
 
+/// Statistics of jobs, `None` if the job was not found.
+pub type JobStatsResponse = Vec<(JobId, Option<JobStats>)>;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JobStats {
+    pub job_id: JobId,
+    pub name: String,
+    pub n_tasks: JobTaskCount,
+    pub counters: JobTaskCounters,
+    /// Durations of tasks that have finished or failed
+    pub task_duration: Option<DurationStats>,
+    /// Time between the submission of the job and the start of its tasks
+    pub wait_time: Option<DurationStats>,
+    /// Number of tasks completed within consecutive time intervals
+    pub throughput: Vec<ThroughputInterval>,
+    pub failures_per_worker: Vec<(WorkerId, JobTaskCount)>,
+    /// Estimated based on the rate at which tasks of the job were completed so far
+    pub estimated_time_to_completion: Option<Duration>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DurationStats {
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ThroughputInterval {
+    pub start: DateTime<Utc>,
+    pub duration: Duration,
+    pub n_completed_tasks: JobTaskCount,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WorkerListResponse {
     pub workers: Vec<WorkerInfo>,
@@ -322,6 +489,24 @@ pub enum AutoAllocResponse {
     DryRunSuccessful,
     Info(Vec<Allocation>),
     List(AutoAllocListResponse),
+    History(Vec<AllocationHistoryEntry>),
+    Log(AllocationLog),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AllocationHistoryEntry {
+    pub allocation: Allocation,
+    /// Last lines of the output of the allocation
+    pub output_tail: AllocationOutput,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AllocationLog {
+    pub allocation: Allocation,
+    pub output: AllocationOutput,
+    /// The directory of the allocation was already removed, only the last lines of its output
+    /// are available.
+    pub truncated: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -341,6 +526,19 @@ pub struct QueueData {
     pub name: Option<String>,
     pub manager_type: ManagerType,
     pub state: QueueState,
+    /// Remaining node-hours, if the queue has a budget.
+    pub remaining_budget: Option<f64>,
+    /// Current state of the active schedule, if the queue has one.
+    pub schedule_state: Option<ScheduleState>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ScheduleState {
+    /// Allocations can be submitted until the given time (or indefinitely if it is `None`).
+    Active { until: Option<SystemTime> },
+    /// Allocations cannot be submitted until the given time.
+    /// It is `None` if the schedule will not open in the foreseeable future.
+    Inactive { opens_at: Option<SystemTime> },
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
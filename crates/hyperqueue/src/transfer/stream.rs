@@ -5,31 +5,65 @@ use tako::InstanceId;
 
 pub type ChannelId = u32;
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct StreamRegistration {
-    pub job: JobId,
-}
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StartTaskStreamMsg {
+    pub job: JobId,
     pub task: JobTaskId,
     pub instance: InstanceId,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DataMsg {
+    pub job: JobId,
     pub task: JobTaskId,
     pub instance: InstanceId,
     pub channel: ChannelId,
     pub data: Vec<u8>,
 }
 
+/// How has the task that produced a stream ended.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEndStatus {
+    Finished,
+    Failed,
+    Canceled,
+    /// The stream was never closed, its end was added when the log file was repaired
+    Incomplete,
+}
+
+impl StreamEndStatus {
+    /// Identifier of the status that is stored in the end block of a log file.
+    pub fn id(&self) -> u8 {
+        match self {
+            StreamEndStatus::Finished => 0,
+            StreamEndStatus::Failed => 1,
+            StreamEndStatus::Canceled => 2,
+            StreamEndStatus::Incomplete => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> anyhow::Result<Self> {
+        Ok(match id {
+            0 => StreamEndStatus::Finished,
+            1 => StreamEndStatus::Failed,
+            2 => StreamEndStatus::Canceled,
+            3 => StreamEndStatus::Incomplete,
+            _ => anyhow::bail!("Unknown stream end status: {}", id),
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EndTaskStreamMsg {
+    pub job: JobId,
     pub task: JobTaskId,
     pub instance: InstanceId,
+    pub status: StreamEndStatus,
 }
 
+/// Messages sent from a worker to the stream server.
+/// Streams of all jobs that run on a worker share a single connection, so each message
+/// identifies the job that it belongs to.
 #[derive(Serialize, Deserialize, Debug)]
 pub enum FromStreamerMessage {
     Start(StartTaskStreamMsg),
@@ -37,13 +71,31 @@ pub enum FromStreamerMessage {
     End(EndTaskStreamMsg),
 }
 
+impl FromStreamerMessage {
+    pub fn job(&self) -> JobId {
+        match self {
+            FromStreamerMessage::Start(msg) => msg.job,
+            FromStreamerMessage::Data(msg) => msg.job,
+            FromStreamerMessage::End(msg) => msg.job,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EndTaskStreamResponseMsg {
+    pub job: JobId,
     pub task: JobTaskId,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StreamErrorMsg {
+    pub job: JobId,
+    pub message: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum ToStreamerMessage {
-    Error(String),
+    /// Streaming of a job has failed, all its task streams should be terminated
+    Error(StreamErrorMsg),
     EndResponse(EndTaskStreamResponseMsg),
 }
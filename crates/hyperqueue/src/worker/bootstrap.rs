@@ -15,6 +15,8 @@ use tako::WorkerId;
 use crate::common::manager::info::{ManagerInfo, ManagerType};
 use crate::common::manager::{pbs, slurm};
 use crate::common::serverdir::ServerDir;
+use crate::worker::cgroup::{GetCgroupsEnabled, WorkerCgroup};
+use crate::worker::spool::{GetSpoolConfig, SpoolRef};
 use crate::worker::start::{HqTaskLauncher, WORKER_EXTRA_PROCESS_PID};
 use crate::worker::streamer::StreamerRef;
 
@@ -49,6 +51,14 @@ pub async fn initialize_worker(
         .next()
         .expect("Invalid server address");
 
+    // The worker process is moved into a different cgroup, so it has to happen before
+    // any task is started
+    let cgroup = if configuration.get_cgroups_enabled() {
+        WorkerCgroup::init()
+    } else {
+        None
+    };
+
     log::debug!("Starting streamer ...");
     let (streamer_ref, streamer_future) = StreamerRef::start(
         Duration::from_secs(10),
@@ -56,12 +66,22 @@ pub async fn initialize_worker(
         record.tako_secret_key().clone(),
     );
 
+    let (spool_ref, spool_future) = SpoolRef::start(
+        configuration.work_dir.join("spool"),
+        configuration.get_spool_config().unwrap_or_default(),
+    );
+
     log::debug!("Starting Tako worker ...");
     let ((worker_id, configuration), worker_future) = run_worker(
         server_addr,
         configuration,
         Some(record.tako_secret_key().clone()),
-        Box::new(HqTaskLauncher::new(record.server_uid(), streamer_ref)),
+        Box::new(HqTaskLauncher::new(
+            record.server_uid(),
+            streamer_ref,
+            spool_ref,
+            cgroup,
+        )),
     )
     .await?;
 
@@ -79,6 +99,7 @@ pub async fn initialize_worker(
                 tokio::select! {
                     res = worker_future => res,
                     () = streamer_future => { Ok(()) },
+                    () = spool_future => { Ok(()) },
                     () = stop_flag.notified() => { Ok(()) }
                 }
             })
@@ -129,6 +150,14 @@ pub fn try_get_slurm_info() -> anyhow::Result<ManagerInfo> {
         .map_err(|_| {
             anyhow!("SLURM_JOB_ID/SLURM_JOBID not found. The process is not running under SLURM")
         })?;
+    // Elements of job arrays are identified by `<array-job-id>_<array-task-id>`
+    let manager_job_id = match (
+        std::env::var("SLURM_ARRAY_JOB_ID"),
+        std::env::var("SLURM_ARRAY_TASK_ID"),
+    ) {
+        (Ok(array_job_id), Ok(array_task_id)) => format!("{array_job_id}_{array_task_id}"),
+        _ => manager_job_id,
+    };
 
     let duration = slurm::get_remaining_timelimit(&manager_job_id)
         .expect("Could not get remaining time from scontrol");
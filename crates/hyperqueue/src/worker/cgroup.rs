@@ -0,0 +1,476 @@
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use tako::launcher::TaskResult;
+use tako::resources::ResourceAmount;
+use tako::worker::WorkerConfiguration;
+
+use crate::common::format::human_size;
+
+pub const WORKER_EXTRA_CGROUPS_KEY: &str = "TaskCgroups";
+
+/// Controllers that are enabled for task cgroups (if they are available).
+/// The memory controller is required, the others are optional.
+const CONTROLLERS: [&str; 3] = ["memory", "cpu", "cpuset"];
+
+/// Period (in microseconds) used for `cpu.max`.
+const CPU_MAX_PERIOD: u64 = 100_000;
+
+/// How many times is the removal of a task cgroup attempted before giving up.
+const REMOVE_ATTEMPTS: u32 = 10;
+
+/*
+    When the worker runs in a cgroup (v2) that it can write to, it creates the following hierarchy
+    inside it:
+
+    <worker-cgroup>/hq-worker-<pid>/worker                          (the worker process itself)
+    <worker-cgroup>/hq-worker-<pid>/task-<job>-<task>-<instance>    (one cgroup per running task)
+
+    The worker has to be moved into a leaf cgroup, because processes cannot reside in a cgroup
+    that distributes resources to its children. Task processes move themselves into their cgroup
+    right before they execute the task program, so that no child process can escape it.
+*/
+
+pub trait GetCgroupsEnabled {
+    fn get_cgroups_enabled(&self) -> bool;
+}
+
+impl GetCgroupsEnabled for WorkerConfiguration {
+    fn get_cgroups_enabled(&self) -> bool {
+        self.extra
+            .get(WORKER_EXTRA_CGROUPS_KEY)
+            .and_then(|enabled| serde_json::from_str(enabled).ok())
+            .unwrap_or(false)
+    }
+}
+
+/// Cgroup subtree of a worker, in which cgroups of individual tasks are created.
+pub struct WorkerCgroup {
+    root: PathBuf,
+    cpu: bool,
+    cpuset: bool,
+}
+
+/// Limits applied to the cgroup of a single task.
+#[derive(Default, Debug)]
+pub struct TaskCgroupLimits {
+    /// Maximum memory (in bytes)
+    pub memory: Option<u64>,
+    /// Maximum CPU bandwidth (in number of CPUs)
+    pub cpus: Option<ResourceAmount>,
+    /// CPUs on which the task may run (e.g. `0,1,4`)
+    pub cpu_list: Option<String>,
+}
+
+pub struct TaskCgroup {
+    path: PathBuf,
+    memory_limit: Option<u64>,
+}
+
+impl WorkerCgroup {
+    /// Creates a cgroup subtree for tasks in the cgroup of the current process.
+    /// Returns `None` if cgroups v2 are not available or the cgroup cannot be written to;
+    /// tasks are then executed without any cgroup limits.
+    pub fn init() -> Option<Self> {
+        match Self::try_init() {
+            Ok(cgroup) => {
+                log::info!(
+                    "Tasks will be executed in cgroups under {}",
+                    cgroup.root.display()
+                );
+                Some(cgroup)
+            }
+            Err(error) => {
+                log::info!("Tasks will not be executed in cgroups: {error:?}");
+                None
+            }
+        }
+    }
+
+    fn try_init() -> anyhow::Result<Self> {
+        let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")?;
+        let mount = parse_cgroup2_mount(&mountinfo).context("cgroups v2 are not mounted")?;
+        let cgroup = std::fs::read_to_string("/proc/self/cgroup")?;
+        let path = parse_own_cgroup(&cgroup)
+            .context("The worker process does not belong to a cgroup v2 hierarchy")?;
+        Self::create(
+            &mount.join(path.trim_start_matches('/')),
+            std::process::id(),
+        )
+    }
+
+    /// Moves the process `pid` into a leaf cgroup inside `base` and enables controllers for
+    /// task cgroups.
+    fn create(base: &Path, pid: u32) -> anyhow::Result<Self> {
+        let available = read_file(&base.join("cgroup.controllers"))?;
+        let available: Vec<&str> = available.split_whitespace().collect();
+        let controllers: Vec<&str> = CONTROLLERS
+            .into_iter()
+            .filter(|c| available.contains(c))
+            .collect();
+        if !controllers.contains(&"memory") {
+            bail!(
+                "The memory controller is not available in {}",
+                base.display()
+            );
+        }
+
+        remove_stale_cgroups(base);
+
+        let root = base.join(format!("hq-worker-{pid}"));
+        let worker = root.join("worker");
+        std::fs::create_dir_all(&worker)
+            .with_context(|| format!("Cannot create cgroup {}", worker.display()))?;
+
+        let enable = |base: &Path| -> anyhow::Result<()> {
+            write_file(&worker.join("cgroup.procs"), &pid.to_string())?;
+            let enabled: Vec<String> = controllers.iter().map(|c| format!("+{c}")).collect();
+            let enabled = enabled.join(" ");
+            write_file(&base.join("cgroup.subtree_control"), &enabled)?;
+            write_file(&root.join("cgroup.subtree_control"), &enabled)?;
+            Ok(())
+        };
+        if let Err(error) = enable(base) {
+            // Return the worker back to its original cgroup
+            let _ = write_file(&base.join("cgroup.procs"), &pid.to_string());
+            let _ = std::fs::remove_dir(&worker);
+            let _ = std::fs::remove_dir(&root);
+            return Err(error);
+        }
+
+        Ok(Self {
+            root,
+            cpu: controllers.contains(&"cpu"),
+            cpuset: controllers.contains(&"cpuset"),
+        })
+    }
+
+    /// Creates a cgroup for a task and applies the given limits to it.
+    pub fn create_task_cgroup(
+        &self,
+        name: &str,
+        limits: &TaskCgroupLimits,
+    ) -> anyhow::Result<TaskCgroup> {
+        let path = self.root.join(name);
+        std::fs::create_dir(&path)
+            .with_context(|| format!("Cannot create cgroup {}", path.display()))?;
+        let cgroup = TaskCgroup {
+            path,
+            memory_limit: limits.memory,
+        };
+
+        let apply = || -> anyhow::Result<()> {
+            if let Some(memory) = limits.memory {
+                cgroup.write("memory.max", &memory.to_string())?;
+                // Without this, the task could exceed its limit by swapping
+                if cgroup.path.join("memory.swap.max").exists() {
+                    cgroup.write("memory.swap.max", "0")?;
+                }
+            }
+            if let (true, Some(cpus)) = (self.cpu, limits.cpus) {
+                cgroup.write(
+                    "cpu.max",
+                    &format!("{} {CPU_MAX_PERIOD}", cpus * CPU_MAX_PERIOD),
+                )?;
+            }
+            Ok(())
+        };
+        if let Err(error) = apply() {
+            let _ = std::fs::remove_dir(&cgroup.path);
+            return Err(error);
+        }
+        if let (true, Some(cpu_list)) = (self.cpuset, &limits.cpu_list) {
+            // CPU indices do not have to correspond to real CPUs (e.g. with `--cpus=<N>`)
+            if let Err(error) = cgroup.write("cpuset.cpus", cpu_list) {
+                log::debug!("Cannot restrict cgroup to CPUs {cpu_list}: {error:?}");
+            }
+        }
+        Ok(cgroup)
+    }
+}
+
+impl TaskCgroup {
+    /// Opens the file that is used to move a process into this cgroup
+    /// (by writing `0` to it from the process).
+    pub fn open_procs_file(&self) -> std::io::Result<File> {
+        OpenOptions::new()
+            .write(true)
+            .open(self.path.join("cgroup.procs"))
+    }
+
+    /// Returns true if a process of the task has been killed because the task has exceeded
+    /// its memory limit.
+    pub fn oom_killed(&self) -> bool {
+        match read_file(&self.path.join("memory.events")) {
+            Ok(events) => parse_oom_kill_count(&events) > 0,
+            Err(error) => {
+                log::debug!("{error:?}");
+                false
+            }
+        }
+    }
+
+    /// Replaces the error of a failed task by an error stating that the task has exceeded its
+    /// memory limit, if some of its processes were killed because of it.
+    pub fn check_task_result(&self, result: tako::Result<TaskResult>) -> tako::Result<TaskResult> {
+        match result {
+            Err(_) if self.oom_killed() => Err(self.memory_limit_error()),
+            Ok(TaskResult::Failed { resource_usage, .. }) if self.oom_killed() => {
+                Ok(TaskResult::Failed {
+                    error: self.memory_limit_error(),
+                    resource_usage,
+                })
+            }
+            result => result,
+        }
+    }
+
+    fn memory_limit_error(&self) -> tako::Error {
+        tako::Error::GenericError(format!(
+            "Task was killed because it has exceeded its memory limit{}",
+            self.memory_limit
+                .map(|limit| format!(" ({})", human_size(limit)))
+                .unwrap_or_default()
+        ))
+    }
+
+    /// Kills all remaining processes of the task and removes the cgroup.
+    pub async fn remove(self) {
+        let kill_file = self.path.join("cgroup.kill");
+        if kill_file.exists() {
+            if let Err(error) = write_file(&kill_file, "1") {
+                log::debug!("{error:?}");
+            }
+        }
+        // The cgroup can be removed only after all its processes have exited
+        for _ in 0..REMOVE_ATTEMPTS {
+            match std::fs::remove_dir(&self.path) {
+                Ok(()) => return,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => return,
+                Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+            }
+        }
+        log::warn!("Cannot remove cgroup {}", self.path.display());
+    }
+
+    fn write(&self, file: &str, value: &str) -> anyhow::Result<()> {
+        write_file(&self.path.join(file), value)
+    }
+}
+
+/// Removes empty cgroups left behind by workers that have already exited.
+fn remove_stale_cgroups(base: &Path) {
+    let entries = match std::fs::read_dir(base) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_worker_cgroup = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.starts_with("hq-worker-"))
+            .unwrap_or(false);
+        if !is_worker_cgroup || !path.is_dir() {
+            continue;
+        }
+        // Directories of populated cgroups cannot be removed
+        if let Ok(children) = std::fs::read_dir(&path) {
+            for child in children.flatten() {
+                if child.path().is_dir() {
+                    let _ = std::fs::remove_dir(child.path());
+                }
+            }
+        }
+        if std::fs::remove_dir(&path).is_ok() {
+            log::debug!("Removed stale cgroup {}", path.display());
+        }
+    }
+}
+
+fn read_file(path: &Path) -> anyhow::Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))
+}
+
+fn write_file(path: &Path, value: &str) -> anyhow::Result<()> {
+    std::fs::write(path, value)
+        .with_context(|| format!("Cannot write `{value}` into {}", path.display()))
+}
+
+/// Finds the mount point of the cgroup v2 filesystem in the content of `/proc/self/mountinfo`.
+fn parse_cgroup2_mount(mountinfo: &str) -> Option<PathBuf> {
+    mountinfo.lines().find_map(|line| {
+        let (mount, fs) = line.split_once(" - ")?;
+        if fs.split_whitespace().next()? != "cgroup2" {
+            return None;
+        }
+        mount.split_whitespace().nth(4).map(PathBuf::from)
+    })
+}
+
+/// Finds the cgroup v2 path of the process in the content of `/proc/self/cgroup`.
+fn parse_own_cgroup(cgroup: &str) -> Option<&str> {
+    cgroup.lines().find_map(|line| line.strip_prefix("0::"))
+}
+
+fn parse_oom_kill_count(events: &str) -> u64 {
+    events
+        .lines()
+        .find_map(|line| line.strip_prefix("oom_kill "))
+        .and_then(|count| count.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use tako::launcher::TaskResult;
+    use tako::task::TaskResourceUsage;
+    use tempdir::TempDir;
+
+    use crate::worker::cgroup::{
+        parse_cgroup2_mount, parse_oom_kill_count, parse_own_cgroup, TaskCgroupLimits, WorkerCgroup,
+    };
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn parse_proc_files() {
+        let mountinfo = "32 24 0:28 / /sys/fs/cgroup rw,relatime - tmpfs tmpfs rw,mode=755
+36 32 0:32 / /sys/fs/cgroup/memory rw,relatime - cgroup cgroup rw,memory
+42 32 0:38 / /sys/fs/cgroup/unified rw,relatime shared:9 - cgroup2 cgroup2 rw";
+        assert_eq!(
+            parse_cgroup2_mount(mountinfo),
+            Some(PathBuf::from("/sys/fs/cgroup/unified"))
+        );
+        assert_eq!(parse_cgroup2_mount(&mountinfo[..60]), None);
+
+        let cgroup = "4:memory:/foo\n0::/user.slice/hq.scope\n";
+        assert_eq!(parse_own_cgroup(cgroup), Some("/user.slice/hq.scope"));
+        assert_eq!(parse_own_cgroup("4:memory:/foo"), None);
+
+        let events = "low 0\nhigh 0\nmax 12\noom 1\noom_kill 1\noom_group_kill 0\n";
+        assert_eq!(parse_oom_kill_count(events), 1);
+        assert_eq!(parse_oom_kill_count("low 0\n"), 0);
+    }
+
+    #[test]
+    fn create_task_cgroup() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        let base = tmp_dir.path();
+        std::fs::write(base.join("cgroup.controllers"), "cpu io memory pids").unwrap();
+
+        let cgroup = WorkerCgroup::create(base, 123).unwrap();
+        assert_eq!(read(&base.join("hq-worker-123/worker/cgroup.procs")), "123");
+        assert_eq!(read(&base.join("cgroup.subtree_control")), "+memory +cpu");
+        assert_eq!(
+            read(&base.join("hq-worker-123/cgroup.subtree_control")),
+            "+memory +cpu"
+        );
+
+        let task = cgroup
+            .create_task_cgroup(
+                "task-1-2-0",
+                &TaskCgroupLimits {
+                    memory: Some(1024),
+                    cpus: Some(2),
+                    cpu_list: Some("0,1".to_string()),
+                },
+            )
+            .unwrap();
+        let path = base.join("hq-worker-123/task-1-2-0");
+        assert_eq!(read(&path.join("memory.max")), "1024");
+        assert_eq!(read(&path.join("cpu.max")), "200000 100000");
+        // The cpuset controller is not available
+        assert!(!path.join("cpuset.cpus").exists());
+
+        assert!(!task.oom_killed());
+        std::fs::write(path.join("memory.events"), "oom 1\noom_kill 1\n").unwrap();
+        assert!(task.oom_killed());
+    }
+
+    #[test]
+    fn report_exceeded_memory_limit() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        let base = tmp_dir.path();
+        std::fs::write(base.join("cgroup.controllers"), "memory").unwrap();
+
+        let cgroup = WorkerCgroup::create(base, 123).unwrap();
+        let task = cgroup
+            .create_task_cgroup(
+                "task-1-2-0",
+                &TaskCgroupLimits {
+                    memory: Some(1024),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let usage = TaskResourceUsage {
+            max_rss: 1024,
+            ..Default::default()
+        };
+        let failed = || {
+            Ok(TaskResult::Failed {
+                error: tako::Error::GenericError(
+                    "Program terminated with exit code 137".to_string(),
+                ),
+                resource_usage: Some(usage),
+            })
+        };
+        let error_message = |result: tako::Result<TaskResult>| match result {
+            Ok(TaskResult::Failed { error, .. }) | Err(error) => error.to_string(),
+            _ => panic!("Task has not failed"),
+        };
+
+        assert_eq!(
+            error_message(task.check_task_result(failed())),
+            "Error: Program terminated with exit code 137"
+        );
+
+        std::fs::write(
+            base.join("hq-worker-123/task-1-2-0/memory.events"),
+            "oom 1\noom_kill 1\n",
+        )
+        .unwrap();
+        match task.check_task_result(failed()) {
+            Ok(TaskResult::Failed {
+                error,
+                resource_usage,
+            }) => {
+                assert_eq!(
+                    error.to_string(),
+                    "Error: Task was killed because it has exceeded its memory limit (1024 B)"
+                );
+                assert_eq!(resource_usage, Some(usage));
+            }
+            _ => panic!("Task has not failed"),
+        }
+        assert_eq!(
+            error_message(task.check_task_result(Err(tako::Error::GenericError(
+                "Cannot read output".to_string()
+            )))),
+            "Error: Task was killed because it has exceeded its memory limit (1024 B)"
+        );
+        assert!(matches!(
+            task.check_task_result(Ok(TaskResult::Finished {
+                resource_usage: None,
+                note: None,
+            })),
+            Ok(TaskResult::Finished { .. })
+        ));
+    }
+
+    #[test]
+    fn require_memory_controller() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        let base = tmp_dir.path();
+        std::fs::write(base.join("cgroup.controllers"), "cpu cpuset").unwrap();
+        assert!(WorkerCgroup::create(base, 123).is_err());
+        assert!(!base.join("hq-worker-123").exists());
+    }
+}
@@ -1,5 +1,8 @@
 pub mod bootstrap;
+pub mod cgroup;
 pub mod hwdetect;
 pub mod parser;
+pub mod rusage;
+pub mod spool;
 pub mod start;
 pub mod streamer;
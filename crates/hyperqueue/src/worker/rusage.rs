@@ -0,0 +1,82 @@
+use std::io;
+use std::time::Duration;
+
+use nix::libc;
+use tako::task::TaskResourceUsage;
+use tokio::signal::unix::{signal, SignalKind};
+
+/// Size of a block in `ru_inblock` and `ru_oublock`
+const RUSAGE_BLOCK_SIZE: u64 = 512;
+
+/// Waits until the process with the given PID exits and returns the resources consumed by it
+/// (and by its children that it has waited for).
+///
+/// The process is not reaped, so its exit status has to be received afterwards
+/// (e.g. using `tokio::process::Child::wait`).
+pub async fn wait_for_resource_usage(pid: u32) -> io::Result<TaskResourceUsage> {
+    // The signal stream has to be created before the first check, so that no SIGCHLD is missed
+    let mut sigchld = signal(SignalKind::child())?;
+    loop {
+        if let Some(usage) = try_get_exit_resource_usage(pid)? {
+            return Ok(usage);
+        }
+        sigchld.recv().await;
+    }
+}
+
+/// Returns the resource usage of the process if it has already exited, without reaping it.
+fn try_get_exit_resource_usage(pid: u32) -> io::Result<Option<TaskResourceUsage>> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // The libc wrapper of `waitid` does not provide resource usage, so the syscall is used directly
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_waitid,
+            libc::P_PID,
+            pid as libc::id_t,
+            &mut info as *mut libc::siginfo_t,
+            libc::WEXITED | libc::WNOWAIT | libc::WNOHANG,
+            &mut usage as *mut libc::rusage,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // The process has not exited yet
+    if unsafe { info.si_pid() } == 0 {
+        return Ok(None);
+    }
+    Ok(Some(TaskResourceUsage {
+        user_time: timeval_to_duration(usage.ru_utime),
+        system_time: timeval_to_duration(usage.ru_stime),
+        max_rss: usage.ru_maxrss as u64 * 1024,
+        read_bytes: usage.ru_inblock as u64 * RUSAGE_BLOCK_SIZE,
+        write_bytes: usage.ru_oublock as u64 * RUSAGE_BLOCK_SIZE,
+    }))
+}
+
+fn timeval_to_duration(time: libc::timeval) -> Duration {
+    Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::worker::rusage::wait_for_resource_usage;
+
+    #[tokio::test]
+    async fn measure_exited_process() {
+        let mut child = tokio::process::Command::new("bash")
+            .arg("-c")
+            .arg("x=0; for i in $(seq 100000); do x=$((x+i)); done")
+            .spawn()
+            .unwrap();
+        let usage = wait_for_resource_usage(child.id().unwrap()).await.unwrap();
+        assert!(usage.cpu_time() > Duration::ZERO);
+        assert!(usage.max_rss > 0);
+
+        // The process was not reaped, so its exit status is still available
+        assert!(child.wait().await.unwrap().success());
+    }
+}
@@ -0,0 +1,331 @@
+use std::fs::File;
+use std::future::Future;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tako::define_wrapped_type;
+use tako::worker::WorkerConfiguration;
+
+use crate::transfer::messages::{TaskOutputRequest, TaskOutputResponse};
+use crate::transfer::stream::ChannelId;
+use crate::{JobId, JobTaskId, Set, WrappedRcRefCell};
+
+pub const WORKER_EXTRA_SPOOL_KEY: &str = "OutputSpool";
+
+/// Maximum size of output that is sent to the server in a single response.
+const READ_CHUNK_SIZE: u64 = 1024 * 1024; // 1 MiB
+
+/// Longest time between two cleanups of the spool directory.
+const MAX_CLEAN_INTERVAL: Duration = Duration::from_secs(60);
+
+/*
+    Spool stores the output of tasks submitted with `--spool` in the working directory of the
+    worker (<work-dir>/spool/<job-id>/<task-id>.<channel>).
+    The output of finished tasks is periodically removed when it is older than the retention
+    period or when the total size of the spool exceeds its limit (oldest output is removed first).
+*/
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SpoolConfig {
+    /// How long is the output of finished tasks kept
+    pub retention: Duration,
+    /// Maximum total size of the output of finished tasks
+    pub max_size: Option<u64>,
+}
+
+impl Default for SpoolConfig {
+    fn default() -> Self {
+        Self {
+            retention: Duration::from_secs(60 * 60),
+            max_size: None,
+        }
+    }
+}
+
+pub trait GetSpoolConfig {
+    fn get_spool_config(&self) -> Option<SpoolConfig>;
+}
+
+impl GetSpoolConfig for WorkerConfiguration {
+    fn get_spool_config(&self) -> Option<SpoolConfig> {
+        self.extra
+            .get(WORKER_EXTRA_SPOOL_KEY)
+            .and_then(|config| serde_json::from_str(config).ok())
+    }
+}
+
+pub struct Spool {
+    directory: PathBuf,
+    config: SpoolConfig,
+    /// Tasks whose output is being written, it is never removed
+    running: Set<(JobId, JobTaskId)>,
+}
+
+struct SpoolFile {
+    path: PathBuf,
+    modified: SystemTime,
+    size: u64,
+}
+
+fn channel_name(channel: ChannelId) -> &'static str {
+    if channel == 0 {
+        "stdout"
+    } else {
+        "stderr"
+    }
+}
+
+impl Spool {
+    fn new(directory: PathBuf, config: SpoolConfig) -> Self {
+        Self {
+            directory,
+            config,
+            running: Default::default(),
+        }
+    }
+
+    pub fn output_path(&self, job_id: JobId, task_id: JobTaskId, channel: ChannelId) -> PathBuf {
+        self.directory.join(job_id.to_string()).join(format!(
+            "{}.{}",
+            task_id,
+            channel_name(channel)
+        ))
+    }
+
+    pub fn task_started(&mut self, job_id: JobId, task_id: JobTaskId) {
+        self.running.insert((job_id, task_id));
+    }
+
+    pub fn task_finished(&mut self, job_id: JobId, task_id: JobTaskId) {
+        self.running.remove(&(job_id, task_id));
+    }
+
+    /// Reads a part of the output of a task, starting at the requested offset.
+    pub fn read_output(&self, request: &TaskOutputRequest) -> Result<TaskOutputResponse, String> {
+        let path = self.output_path(request.job_id, request.task_id, request.channel);
+        let mut file = File::open(&path).map_err(|e| {
+            format!(
+                "Output of task {} is not available on this worker: {}",
+                request.task_id, e
+            )
+        })?;
+        file.seek(SeekFrom::Start(request.offset))
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let mut data = Vec::new();
+        file.take(READ_CHUNK_SIZE)
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let eof = (data.len() as u64) < READ_CHUNK_SIZE;
+        Ok(TaskOutputResponse { data, eof })
+    }
+
+    /// Removes output of finished tasks that is expired or that exceeds the size limit.
+    fn clean(&self, now: SystemTime) {
+        let mut files = match self.collect_finished_files() {
+            Ok(files) => files,
+            Err(e) => {
+                log::warn!("Cannot read spool directory: {}", e);
+                return;
+            }
+        };
+        files.sort_unstable_by_key(|f| f.modified);
+
+        let mut total_size: u64 = files.iter().map(|f| f.size).sum();
+        for file in files {
+            let expired = now
+                .duration_since(file.modified)
+                .map(|age| age > self.config.retention)
+                .unwrap_or(false);
+            let over_limit = self
+                .config
+                .max_size
+                .map(|max_size| total_size > max_size)
+                .unwrap_or(false);
+            if expired || over_limit {
+                log::debug!("Removing spooled output {}", file.path.display());
+                if let Err(e) = std::fs::remove_file(&file.path) {
+                    log::warn!("Cannot remove {}: {}", file.path.display(), e);
+                }
+                total_size -= file.size;
+            }
+        }
+    }
+
+    fn collect_finished_files(&self) -> std::io::Result<Vec<SpoolFile>> {
+        let mut files = vec![];
+        if !self.directory.exists() {
+            return Ok(files);
+        }
+        for job_dir in std::fs::read_dir(&self.directory)? {
+            let job_dir = job_dir?.path();
+            let job_id: JobId = match parse_id(&job_dir, false) {
+                Some(id) => id.into(),
+                None => continue,
+            };
+            let mut is_empty = true;
+            for file in std::fs::read_dir(&job_dir)? {
+                is_empty = false;
+                let file = file?;
+                let path = file.path();
+                let task_id: JobTaskId = match parse_id(&path, true) {
+                    Some(id) => id.into(),
+                    None => continue,
+                };
+                if self.running.contains(&(job_id, task_id)) {
+                    continue;
+                }
+                let metadata = file.metadata()?;
+                files.push(SpoolFile {
+                    path,
+                    modified: metadata.modified()?,
+                    size: metadata.len(),
+                });
+            }
+            if is_empty {
+                let _ = std::fs::remove_dir(&job_dir);
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Parses the ID of a job (directory name) or of a task (file stem) from a spool path.
+fn parse_id(path: &Path, file: bool) -> Option<u32> {
+    let name = if file {
+        path.file_stem()
+    } else {
+        path.file_name()
+    };
+    name.and_then(|name| name.to_str())
+        .and_then(|name| name.parse().ok())
+}
+
+define_wrapped_type!(SpoolRef, Spool, pub);
+
+impl SpoolRef {
+    pub fn start(directory: PathBuf, config: SpoolConfig) -> (SpoolRef, impl Future<Output = ()>) {
+        let clean_interval = config
+            .retention
+            .clamp(Duration::from_secs(1), MAX_CLEAN_INTERVAL);
+        let spool_ref = Self(WrappedRcRefCell::wrap(Spool::new(directory, config)));
+
+        let spool_ref2 = spool_ref.clone();
+        let spool_future = async move {
+            let mut it = tokio::time::interval(clean_interval);
+            loop {
+                it.tick().await;
+                spool_ref2.get().clean(SystemTime::now());
+            }
+        };
+        (spool_ref, spool_future)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+    use std::time::{Duration, SystemTime};
+
+    use nix::sys::stat::utimes;
+    use nix::sys::time::{TimeVal, TimeValLike};
+    use tempdir::TempDir;
+
+    use crate::transfer::messages::TaskOutputRequest;
+    use crate::worker::spool::{Spool, SpoolConfig};
+
+    fn create_spool(path: &std::path::Path, max_size: Option<u64>) -> Spool {
+        Spool::new(
+            path.to_path_buf(),
+            SpoolConfig {
+                retention: Duration::from_secs(60),
+                max_size,
+            },
+        )
+    }
+
+    fn write_output(spool: &Spool, job: u32, task: u32, data: &[u8], modified: SystemTime) {
+        let path = spool.output_path(job.into(), task.into(), 0);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut file = File::create(&path).unwrap();
+        file.write_all(data).unwrap();
+
+        let modified = TimeVal::seconds(
+            modified
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64,
+        );
+        utimes(&path, &modified, &modified).unwrap();
+    }
+
+    fn has_output(spool: &Spool, job: u32, task: u32) -> bool {
+        spool.output_path(job.into(), task.into(), 0).exists()
+    }
+
+    #[test]
+    fn read_output() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        let spool = create_spool(tmp_dir.path(), None);
+        write_output(&spool, 1, 2, b"hello world", SystemTime::now());
+
+        let read = |offset| {
+            spool.read_output(&TaskOutputRequest {
+                job_id: 1.into(),
+                task_id: 2.into(),
+                channel: 0,
+                offset,
+            })
+        };
+        let response = read(0).unwrap();
+        assert_eq!(response.data, b"hello world");
+        assert!(response.eof);
+        assert_eq!(read(6).unwrap().data, b"world");
+        assert!(spool
+            .read_output(&TaskOutputRequest {
+                job_id: 1.into(),
+                task_id: 2.into(),
+                channel: 1,
+                offset: 0,
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn clean_expired_output() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        let mut spool = create_spool(tmp_dir.path(), None);
+        let now = SystemTime::now();
+        write_output(&spool, 1, 1, b"a", now - Duration::from_secs(120));
+        write_output(&spool, 1, 2, b"b", now - Duration::from_secs(10));
+        write_output(&spool, 1, 3, b"c", now - Duration::from_secs(120));
+        spool.task_started(1.into(), 3.into());
+
+        spool.clean(now);
+        assert!(!has_output(&spool, 1, 1));
+        assert!(has_output(&spool, 1, 2));
+        assert!(has_output(&spool, 1, 3));
+
+        spool.task_finished(1.into(), 3.into());
+        spool.clean(now + Duration::from_secs(100));
+        assert!(!has_output(&spool, 1, 2));
+        assert!(!has_output(&spool, 1, 3));
+    }
+
+    #[test]
+    fn clean_output_over_size_limit() {
+        let tmp_dir = TempDir::new("hq").unwrap();
+        let spool = create_spool(tmp_dir.path(), Some(10));
+        let now = SystemTime::now();
+        write_output(&spool, 1, 1, b"12345", now - Duration::from_secs(3));
+        write_output(&spool, 2, 1, b"12345", now - Duration::from_secs(1));
+        write_output(&spool, 1, 2, b"12345", now - Duration::from_secs(2));
+
+        spool.clean(now);
+        assert!(!has_output(&spool, 1, 1));
+        assert!(has_output(&spool, 1, 2));
+        assert!(has_output(&spool, 2, 1));
+    }
+}
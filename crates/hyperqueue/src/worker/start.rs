@@ -3,7 +3,7 @@ use std::future::Future;
 use std::io;
 use std::io::{BufWriter, ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::ExitStatus;
+use std::process::{ExitStatus, Stdio};
 use std::rc::Rc;
 use std::time::Duration;
 
@@ -18,26 +18,36 @@ use tokio::sync::oneshot;
 use tokio::sync::oneshot::Receiver;
 
 use tako::launcher::{
-    command_from_definitions, LaunchContext, StopReason, TaskLaunchData, TaskLauncher, TaskResult,
+    command_from_definitions, LaunchContext, StopReason, TaskFuture, TaskLaunchData, TaskLauncher,
+    TaskResult,
 };
+use tako::task::TaskResourceUsage;
 use tako::InstanceId;
 
 use crate::common::env::{
     HQ_CPUS, HQ_ERROR_FILENAME, HQ_INSTANCE_ID, HQ_NODE_FILE, HQ_PIN, HQ_SUBMIT_DIR, HQ_TASK_DIR,
 };
+use crate::common::format::human_size;
 use crate::common::placeholders::{
-    fill_placeholders_in_paths, CompletePlaceholderCtx, ResolvablePaths,
+    fill_placeholders_in_paths, normalize_path, CompletePlaceholderCtx, ResolvablePaths,
 };
 use crate::common::utils::fs::{bytes_to_path, is_implicit_path, path_has_extension};
-use crate::transfer::messages::{PinMode, TaskBody};
-use crate::transfer::stream::ChannelId;
+use crate::transfer::messages::{
+    OutputLimit, OutputLimitPolicy, PinMode, TaskBody, WorkerRpcRequest, WorkerRpcResponse,
+};
+use crate::transfer::stream::{ChannelId, StreamEndStatus};
+use crate::worker::cgroup::{TaskCgroupLimits, WorkerCgroup};
+use crate::worker::rusage::wait_for_resource_usage;
+use crate::worker::spool::SpoolRef;
 use crate::worker::streamer::StreamSender;
 use crate::worker::streamer::StreamerRef;
 use crate::{JobId, JobTaskId};
 use serde::{Deserialize, Serialize};
 use tako::comm::serialize;
 use tako::program::{ProgramDefinition, StdioDef};
-use tako::resources::{Allocation, CPU_RESOURCE_ID, CPU_RESOURCE_NAME, GPU_RESOURCE_NAME};
+use tako::resources::{
+    Allocation, CPU_RESOURCE_ID, CPU_RESOURCE_NAME, GPU_RESOURCE_NAME, MEM_RESOURCE_NAME,
+};
 
 const MAX_CUSTOM_ERROR_LENGTH: usize = 2048; // 2KiB
 
@@ -51,13 +61,22 @@ pub struct RunningTaskContext {
 pub struct HqTaskLauncher {
     server_uid: String,
     streamer_ref: StreamerRef,
+    spool_ref: SpoolRef,
+    cgroup: Option<WorkerCgroup>,
 }
 
 impl HqTaskLauncher {
-    pub fn new(server_uid: &str, streamer_ref: StreamerRef) -> Self {
+    pub fn new(
+        server_uid: &str,
+        streamer_ref: StreamerRef,
+        spool_ref: SpoolRef,
+        cgroup: Option<WorkerCgroup>,
+    ) -> Self {
         Self {
             server_uid: server_uid.to_string(),
             streamer_ref,
+            spool_ref,
+            cgroup,
         }
     }
 }
@@ -68,12 +87,14 @@ impl TaskLauncher for HqTaskLauncher {
         launch_ctx: LaunchContext,
         stop_receiver: Receiver<StopReason>,
     ) -> tako::Result<TaskLaunchData> {
-        let (program, job_id, job_task_id, instance_id, task_dir): (
+        let (program, job_id, job_task_id, instance_id, task_dir, output_limit, spool): (
             ProgramDefinition,
             JobId,
             JobTaskId,
             InstanceId,
             Option<TempDir>,
+            Option<OutputLimit>,
+            bool,
         ) = {
             log::debug!(
                 "Starting program launcher task_id={} res={:?} alloc={:?} body_len={}",
@@ -90,6 +111,8 @@ impl TaskLauncher for HqTaskLauncher {
                 task_dir,
                 job_id,
                 task_id,
+                output_limit,
+                spool,
             } = body;
 
             pin_program(&mut program, launch_ctx.allocation(), pin_mode)?;
@@ -141,16 +164,54 @@ impl TaskLauncher for HqTaskLauncher {
             let paths = ResolvablePaths::from_program_def(&mut program);
             fill_placeholders_in_paths(paths, ctx);
 
+            if spool {
+                // Piped output is written into the spool directory instead of being streamed
+                let spool = self.spool_ref.get();
+                for (channel, stdio) in [&mut program.stdout, &mut program.stderr]
+                    .into_iter()
+                    .enumerate()
+                {
+                    if matches!(stdio, StdioDef::Pipe) {
+                        *stdio = StdioDef::File(spool.output_path(
+                            job_id,
+                            task_id,
+                            channel as ChannelId,
+                        ));
+                    }
+                }
+            }
+
             create_directory_if_needed(&program.stdout)?;
             create_directory_if_needed(&program.stderr)?;
 
-            (program, job_id, task_id, launch_ctx.instance_id(), task_dir)
+            (
+                program,
+                job_id,
+                task_id,
+                launch_ctx.instance_id(),
+                task_dir,
+                output_limit,
+                spool,
+            )
         };
 
         let context = RunningTaskContext { instance_id };
         let serialized_context = serialize(&context)?;
 
-        let task_future = run_task(
+        let cgroup = self.cgroup.as_ref().and_then(|cgroup| {
+            let name = format!("task-{job_id}-{job_task_id}-{instance_id}");
+            cgroup
+                .create_task_cgroup(&name, &cgroup_limits(&launch_ctx))
+                .map_err(|error| {
+                    log::warn!("Task {job_id}/{job_task_id} will run without a cgroup: {error:?}")
+                })
+                .ok()
+        });
+        let cgroup_procs = match &cgroup {
+            Some(cgroup) => Some(cgroup.open_procs_file()?),
+            None => None,
+        };
+        let mut task_future: TaskFuture = Box::pin(run_task(
             self.streamer_ref.clone(),
             program,
             job_id,
@@ -158,12 +219,38 @@ impl TaskLauncher for HqTaskLauncher {
             instance_id,
             stop_receiver,
             task_dir,
-        );
+            output_limit,
+            cgroup_procs,
+        ));
+        if spool {
+            // Output of a running task must not be removed from the spool
+            self.spool_ref.get_mut().task_started(job_id, job_task_id);
+            let spool_ref = self.spool_ref.clone();
+            task_future = Box::pin(async move {
+                let result = task_future.await;
+                spool_ref.get_mut().task_finished(job_id, job_task_id);
+                result
+            });
+        }
+        if let Some(cgroup) = cgroup {
+            task_future = Box::pin(async move {
+                let result = cgroup.check_task_result(task_future.await);
+                cgroup.remove().await;
+                result
+            });
+        }
 
-        Ok(TaskLaunchData::new(
-            Box::pin(task_future),
-            serialized_context,
-        ))
+        Ok(TaskLaunchData::new(task_future, serialized_context))
+    }
+
+    fn handle_custom_request(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let request: WorkerRpcRequest = tako::comm::deserialize(data).map_err(|e| e.to_string())?;
+        let response = match request {
+            WorkerRpcRequest::TaskOutput(request) => {
+                WorkerRpcResponse::TaskOutput(self.spool_ref.get().read_output(&request)?)
+            }
+        };
+        tako::comm::serialize(&response).map_err(|e| e.to_string())
     }
 }
 
@@ -171,26 +258,177 @@ pub const WORKER_EXTRA_PROCESS_PID: &str = "ProcessPid";
 
 const STDIO_BUFFER_SIZE: usize = 16 * 1024; // 16kB
 
-async fn resend_stdio(
+/// Destination of an output channel of a task that is forwarded by the worker.
+enum OutputTarget {
+    Stream(Rc<StreamSender>),
+    File(tokio::io::BufWriter<tokio::fs::File>),
+}
+
+impl OutputTarget {
+    async fn write(&mut self, channel: ChannelId, data: Vec<u8>) -> tako::Result<()> {
+        match self {
+            OutputTarget::Stream(stream) => stream
+                .send_data(channel, data)
+                .await
+                .map_err(streamer_error),
+            OutputTarget::File(file) => Ok(file.write_all(&data).await?),
+        }
+    }
+
+    async fn finish(&mut self) -> tako::Result<()> {
+        if let OutputTarget::File(file) = self {
+            file.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Enforces the output size limit of a single output channel of a task.
+struct OutputLimiter {
+    limit: Option<OutputLimit>,
+    written: u64,
+    exceeded: bool,
+}
+
+impl OutputLimiter {
+    fn new(limit: Option<OutputLimit>) -> Self {
+        Self {
+            limit,
+            written: 0,
+            exceeded: false,
+        }
+    }
+
+    /// Returns the part of `data` that fits into the limit, followed by a truncation marker if
+    /// the limit has been exceeded by this data. Returns `None` if the data should be dropped.
+    fn process(&mut self, mut data: Vec<u8>) -> Option<Vec<u8>> {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return Some(data),
+        };
+        if self.exceeded {
+            return None;
+        }
+        let remaining = limit.max_size - self.written;
+        if data.len() as u64 <= remaining {
+            self.written += data.len() as u64;
+            return Some(data);
+        }
+        data.truncate(remaining as usize);
+        data.extend_from_slice(
+            format!(
+                "\n[HyperQueue: output truncated, it has exceeded the limit of {}]\n",
+                human_size(limit.max_size)
+            )
+            .as_bytes(),
+        );
+        self.written = limit.max_size;
+        self.exceeded = true;
+        Some(data)
+    }
+
+    /// Returns an error if the limit was exceeded and the task should fail because of it.
+    fn check_failure(&self, channel: ChannelId) -> tako::Result<()> {
+        match self.limit {
+            Some(limit) if self.exceeded && limit.policy == OutputLimitPolicy::Fail => {
+                Err(tako::Error::GenericError(format!(
+                    "Output limit exceeded: {} of the task has exceeded the limit of {}",
+                    channel_name(channel),
+                    human_size(limit.max_size)
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns a note for the task if its output was truncated.
+    fn truncation_note(&self, channel: ChannelId) -> Option<String> {
+        match self.limit {
+            Some(limit) if self.exceeded => Some(format!(
+                "Output truncated: {} of the task has exceeded the limit of {}",
+                channel_name(channel),
+                human_size(limit.max_size)
+            )),
+            _ => None,
+        }
+    }
+}
+
+fn channel_name(channel: ChannelId) -> &'static str {
+    if channel == 0 {
+        "stdout"
+    } else {
+        "stderr"
+    }
+}
+
+fn append_note(error: tako::Error, note: &str) -> tako::Error {
+    match error {
+        tako::Error::GenericError(message) => {
+            tako::Error::GenericError(format!("{message}\n{note}"))
+        }
+        error => tako::Error::GenericError(format!("{error}\n{note}")),
+    }
+}
+
+/// Joins notes about the execution of a task into a single message.
+fn join_notes(notes: [Option<String>; 2]) -> Option<String> {
+    let notes: Vec<String> = notes.into_iter().flatten().collect();
+    (!notes.is_empty()).then(|| notes.join("\n"))
+}
+
+/// Forwards the output of a task into the given target.
+/// Returns a note for the task if its output was truncated.
+async fn forward_output(
     job_id: JobId,
     job_task_id: JobTaskId,
     channel: ChannelId,
     stdio: Option<impl tokio::io::AsyncRead + Unpin>,
-    stream: Rc<StreamSender>,
-) -> tako::Result<()> {
-    if let Some(mut stdio) = stdio {
-        log::debug!("Starting stream {}/{}/{}", job_id, job_task_id, channel);
-        loop {
-            let mut buffer = vec![0; STDIO_BUFFER_SIZE];
-            let size = stdio.read(&mut buffer[..]).await?;
-            if size == 0 {
-                break;
-            };
-            buffer.truncate(size);
-            stream.send_data(channel, buffer).await?;
+    target: Option<OutputTarget>,
+    limit: Option<OutputLimit>,
+) -> tako::Result<Option<String>> {
+    let (mut stdio, mut target) = match (stdio, target) {
+        (Some(stdio), Some(target)) => (stdio, target),
+        _ => return Ok(None),
+    };
+    log::debug!(
+        "Starting output forwarding {}/{}/{}",
+        job_id,
+        job_task_id,
+        channel
+    );
+    let mut limiter = OutputLimiter::new(limit);
+    loop {
+        let mut buffer = vec![0; STDIO_BUFFER_SIZE];
+        let size = stdio.read(&mut buffer[..]).await?;
+        if size == 0 {
+            break;
+        };
+        buffer.truncate(size);
+        // The output is still read after the limit is exceeded, so that the task does not block
+        if let Some(data) = limiter.process(buffer) {
+            target.write(channel, data).await?;
+        }
+        if let Err(error) = limiter.check_failure(channel) {
+            target.finish().await?;
+            return Err(error);
         }
     }
-    Ok(())
+    target.finish().await?;
+    Ok(limiter.truncation_note(channel))
+}
+
+/// Opens a file into which will the worker forward the output of a task.
+fn open_output_file(path: &Path, cwd: &Path) -> tako::Result<OutputTarget> {
+    let file = File::create(normalize_path(path, cwd))
+        .map_err(|e| format!("Creating stream file failed: {}", e))?;
+    Ok(OutputTarget::File(tokio::io::BufWriter::new(
+        tokio::fs::File::from_std(file),
+    )))
+}
+
+fn streamer_error(e: tako::Error) -> tako::Error {
+    tako::Error::GenericError(format!("Streamer: {:?}", e.to_string()))
 }
 
 fn create_directory_if_needed(file: &StdioDef) -> io::Result<()> {
@@ -259,6 +497,21 @@ fn insert_resources_into_env(ctx: &LaunchContext, program: &mut ProgramDefinitio
     }
 }
 
+/// Derives the cgroup limits of a task from the resources allocated to it.
+fn cgroup_limits(ctx: &LaunchContext) -> TaskCgroupLimits {
+    let allocation = ctx.allocation();
+    let cpus = allocation.resource_allocation(CPU_RESOURCE_ID);
+    TaskCgroupLimits {
+        memory: ctx
+            .get_resource_map()
+            .get_index(MEM_RESOURCE_NAME)
+            .and_then(|id| allocation.resource_allocation(id))
+            .map(|alloc| alloc.value.amount()),
+        cpus: cpus.map(|alloc| alloc.value.amount()),
+        cpu_list: cpus.and_then(|alloc| alloc.value.to_comma_delimited_list()),
+    }
+}
+
 fn pin_program(
     program: &mut ProgramDefinition,
     allocation: &Allocation,
@@ -322,8 +575,13 @@ async fn run_task(
     _instance_id: InstanceId,
     _end_receiver: tokio::sync::oneshot::Receiver<StopReason>,
     _task_dir: Option<TempDir>,
+    _output_limit: Option<OutputLimit>,
+    _cgroup_procs: Option<File>,
 ) -> tako::Result<TaskResult> {
-    Ok(TaskResult::Finished)
+    Ok(TaskResult::Finished {
+        resource_usage: None,
+        note: None,
+    })
 }
 
 /// Provide a more detailed error message when a process fails to be spawned.
@@ -390,18 +648,31 @@ async fn write_stdin(mut stdin: tokio::process::ChildStdin, stdin_data: &[u8]) {
     futures::future::pending::<()>().await;
 }
 
+/// Waits until the child exits and returns its exit status and the resources that it has consumed.
 async fn child_wait(
     mut child: tokio::process::Child,
+    pid: u32,
     stdin_data: &[u8],
-) -> Result<ExitStatus, std::io::Error> {
-    if let Some(stdin) = child.stdin.take() {
-        let r = tokio::select! {
-            () = write_stdin(stdin, stdin_data) => { unreachable!() }
-            r = child.wait() => r
+) -> Result<(ExitStatus, Option<TaskResourceUsage>), std::io::Error> {
+    let stdin = child.stdin.take();
+    let wait_fut = async {
+        // Resource usage has to be read before the child is reaped by `wait`
+        let usage = match wait_for_resource_usage(pid).await {
+            Ok(usage) => Some(usage),
+            Err(error) => {
+                log::debug!("Cannot get resource usage of process {pid}: {error:?}");
+                None
+            }
         };
-        Ok(r?)
+        Ok((child.wait().await?, usage))
+    };
+    if let Some(stdin) = stdin {
+        tokio::select! {
+            () = write_stdin(stdin, stdin_data) => { unreachable!() }
+            r = wait_fut => r
+        }
     } else {
-        Ok(child.wait().await?)
+        wait_fut.await
     }
 }
 
@@ -424,6 +695,7 @@ fn check_error_filename(task_dir: TempDir) -> Option<tako::Error> {
 }
 
 #[cfg(not(feature = "zero-worker"))]
+#[allow(clippy::too_many_arguments)]
 async fn run_task(
     streamer_ref: StreamerRef,
     program: ProgramDefinition,
@@ -432,23 +704,58 @@ async fn run_task(
     instance_id: InstanceId,
     end_receiver: Receiver<StopReason>,
     task_dir: Option<TempDir>,
+    output_limit: Option<OutputLimit>,
+    cgroup_procs: Option<File>,
 ) -> tako::Result<TaskResult> {
     let mut command = command_from_definitions(&program)?;
 
-    let status_to_result = |status: ExitStatus| {
+    if let Some(procs) = cgroup_procs {
+        // The process moves itself into the cgroup of the task before the program is executed,
+        // so that all its descendants are also placed in the cgroup
+        unsafe {
+            command.pre_exec(move || (&procs).write_all(b"0"));
+        }
+    }
+
+    // Output redirected into files has to be forwarded by the worker to enforce its size limit
+    let output_file_target = |stdio: &StdioDef| -> tako::Result<Option<OutputTarget>> {
+        match (stdio, output_limit) {
+            (StdioDef::File(path), Some(_)) => Ok(Some(open_output_file(path, &program.cwd)?)),
+            _ => Ok(None),
+        }
+    };
+    let stdout_target = output_file_target(&program.stdout)?;
+    let stderr_target = output_file_target(&program.stderr)?;
+    if stdout_target.is_some() {
+        command.stdout(Stdio::piped());
+    }
+    if stderr_target.is_some() {
+        command.stderr(Stdio::piped());
+    }
+
+    let status_to_result = |((status, usage), stdout_note, stderr_note): (
+        (ExitStatus, Option<TaskResourceUsage>),
+        Option<String>,
+        Option<String>,
+    )| {
+        let note = join_notes([stdout_note, stderr_note]);
         if !status.success() {
             let code = status.code().unwrap_or(-1);
-            if let Some(dir) = task_dir {
-                if let Some(e) = check_error_filename(dir) {
-                    return Err(e);
-                }
-            }
-            Err(tako::Error::GenericError(format!(
-                "Program terminated with exit code {}",
-                code
-            )))
+            let error = task_dir.and_then(check_error_filename).unwrap_or_else(|| {
+                tako::Error::GenericError(format!("Program terminated with exit code {}", code))
+            });
+            Ok(TaskResult::Failed {
+                error: match note {
+                    Some(note) => append_note(error, &note),
+                    None => error,
+                },
+                resource_usage: usage,
+            })
         } else {
-            Ok(TaskResult::Finished)
+            Ok(TaskResult::Finished {
+                resource_usage: usage,
+                note,
+            })
         }
     };
 
@@ -459,12 +766,15 @@ async fn run_task(
         .map_err(|error| map_spawn_error(error, &program))?;
     let pid = match child.id() {
         Some(pid) => pid,
-        None => return Ok(TaskResult::Finished),
+        None => {
+            return Ok(TaskResult::Finished {
+                resource_usage: None,
+                note: None,
+            })
+        }
     };
 
     if matches!(program.stdout, StdioDef::Pipe) || matches!(program.stderr, StdioDef::Pipe) {
-        let streamer_error =
-            |e: tako::Error| tako::Error::GenericError(format!("Streamer: {:?}", e.to_string()));
         let (close_sender, close_responder) = oneshot::channel();
         let stream = Rc::new(streamer_ref.get_mut().get_stream(
             &streamer_ref,
@@ -476,18 +786,21 @@ async fn run_task(
 
         stream.send_stream_start().await.map_err(streamer_error)?;
 
-        let stream2 = stream.clone();
+        let stream_target = |stdio: &StdioDef| {
+            matches!(stdio, StdioDef::Pipe).then(|| OutputTarget::Stream(stream.clone()))
+        };
+        let stdout_target = stdout_target.or_else(|| stream_target(&program.stdout));
+        let stderr_target = stderr_target.or_else(|| stream_target(&program.stderr));
 
         let main_fut = async move {
             let stdout = child.stdout.take();
             let stderr = child.stderr.take();
             let response = tokio::try_join!(
-                child_wait(child, &program.stdin).map_err(tako::Error::from),
-                resend_stdio(job_id, job_task_id, 0, stdout, stream2.clone())
-                    .map_err(streamer_error),
-                resend_stdio(job_id, job_task_id, 1, stderr, stream2).map_err(streamer_error),
+                child_wait(child, pid, &program.stdin).map_err(tako::Error::from),
+                forward_output(job_id, job_task_id, 0, stdout, stdout_target, output_limit),
+                forward_output(job_id, job_task_id, 1, stderr, stderr_target, output_limit),
             );
-            status_to_result(response?.0)
+            status_to_result(response?)
         };
 
         let guard_fut = task_process(main_fut, pid, job_id, job_task_id, end_receiver);
@@ -496,7 +809,14 @@ async fn run_task(
         match futures::future::select(guard_fut, close_responder).await {
             Either::Left((result, close_responder)) => {
                 log::debug!("Waiting for stream termination");
-                stream.close().await.map_err(streamer_error)?;
+                let status = match &result {
+                    Ok(TaskResult::Finished { .. }) => StreamEndStatus::Finished,
+                    Ok(TaskResult::Canceled) => StreamEndStatus::Canceled,
+                    Ok(TaskResult::Failed { .. } | TaskResult::Timeouted) | Err(_) => {
+                        StreamEndStatus::Failed
+                    }
+                };
+                stream.close(status).await.map_err(streamer_error)?;
                 close_responder
                     .await
                     .map_err(|_| {
@@ -517,7 +837,16 @@ async fn run_task(
             }),
         }
     } else {
-        let task_fut = async move { status_to_result(child_wait(child, &program.stdin).await?) };
+        let task_fut = async move {
+            let stdout = child.stdout.take();
+            let stderr = child.stderr.take();
+            let response = tokio::try_join!(
+                child_wait(child, pid, &program.stdin).map_err(tako::Error::from),
+                forward_output(job_id, job_task_id, 0, stdout, stdout_target, output_limit),
+                forward_output(job_id, job_task_id, 1, stderr, stderr_target, output_limit),
+            );
+            status_to_result(response?)
+        };
         task_process(task_fut, pid, job_id, job_task_id, end_receiver).await
     }
 }
@@ -585,3 +914,135 @@ async fn task_process<F: Future<Output = tako::Result<TaskResult>>>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use crate::transfer::messages::{OutputLimit, OutputLimitPolicy};
+    use crate::worker::start::{forward_output, open_output_file, OutputLimiter};
+    use crate::{JobId, JobTaskId};
+
+    fn limit(max_size: u64, policy: OutputLimitPolicy) -> Option<OutputLimit> {
+        Some(OutputLimit { max_size, policy })
+    }
+
+    const MARKER: &str = "\n[HyperQueue: output truncated, it has exceeded the limit of 10 B]\n";
+
+    #[test]
+    fn limiter_without_limit() {
+        let mut limiter = OutputLimiter::new(None);
+        assert_eq!(limiter.process(vec![1; 100]), Some(vec![1; 100]));
+        assert!(limiter.check_failure(0).is_ok());
+        assert_eq!(limiter.truncation_note(0), None);
+    }
+
+    #[test]
+    fn limiter_truncate_at_limit() {
+        let mut limiter = OutputLimiter::new(limit(10, OutputLimitPolicy::Truncate));
+        assert_eq!(limiter.process(b"12345".to_vec()), Some(b"12345".to_vec()));
+        assert_eq!(limiter.process(b"67890".to_vec()), Some(b"67890".to_vec()));
+        assert_eq!(limiter.truncation_note(0), None);
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(MARKER.as_bytes());
+        assert_eq!(limiter.process(b"x".to_vec()), Some(expected));
+        assert_eq!(limiter.process(b"y".to_vec()), None);
+        assert!(limiter.check_failure(0).is_ok());
+    }
+
+    #[test]
+    fn limiter_truncate_inside_chunk() {
+        let mut limiter = OutputLimiter::new(limit(10, OutputLimitPolicy::Truncate));
+        let data = limiter.process(b"1234567890abcdef".to_vec()).unwrap();
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            format!("1234567890{MARKER}")
+        );
+        assert_eq!(
+            limiter.truncation_note(1).unwrap(),
+            "Output truncated: stderr of the task has exceeded the limit of 10 B"
+        );
+    }
+
+    #[test]
+    fn limiter_fail_policy() {
+        let mut limiter = OutputLimiter::new(limit(10, OutputLimitPolicy::Fail));
+        limiter.process(b"1234567890".to_vec());
+        assert!(limiter.check_failure(0).is_ok());
+        limiter.process(b"1".to_vec());
+        assert_eq!(
+            limiter.check_failure(0).unwrap_err().to_string(),
+            "Error: Output limit exceeded: stdout of the task has exceeded the limit of 10 B"
+        );
+    }
+
+    #[tokio::test]
+    async fn forward_truncated_output() {
+        let dir = TempDir::new("hq").unwrap();
+        let path = dir.path().join("out");
+        let data = vec![b'a'; 40000];
+        let note = forward_output(
+            JobId::new(1),
+            JobTaskId::new(0),
+            0,
+            Some(data.as_slice()),
+            Some(open_output_file(&path, dir.path()).unwrap()),
+            limit(20000, OutputLimitPolicy::Truncate),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            note.unwrap(),
+            "Output truncated: stdout of the task has exceeded the limit of 19.53 KiB"
+        );
+
+        let output = std::fs::read(&path).unwrap();
+        assert!(output[..20000].iter().all(|c| *c == b'a'));
+        assert_eq!(
+            String::from_utf8_lossy(&output[20000..]),
+            "\n[HyperQueue: output truncated, it has exceeded the limit of 19.53 KiB]\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn forward_output_below_limit() {
+        let dir = TempDir::new("hq").unwrap();
+        let path = dir.path().join("out");
+        let note = forward_output(
+            JobId::new(1),
+            JobTaskId::new(0),
+            0,
+            Some(b"hello".as_slice()),
+            Some(open_output_file(&path, dir.path()).unwrap()),
+            limit(10, OutputLimitPolicy::Fail),
+        )
+        .await
+        .unwrap();
+        assert_eq!(note, None);
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello");
+    }
+
+    #[tokio::test]
+    async fn forward_output_fail_policy() {
+        let dir = TempDir::new("hq").unwrap();
+        let path = dir.path().join("out");
+        let result = forward_output(
+            JobId::new(1),
+            JobTaskId::new(0),
+            1,
+            Some(b"1234567890abcdef".as_slice()),
+            Some(open_output_file(&path, dir.path()).unwrap()),
+            limit(10, OutputLimitPolicy::Fail),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Error: Output limit exceeded: stderr of the task has exceeded the limit of 10 B"
+        );
+        assert_eq!(
+            String::from_utf8(std::fs::read(&path).unwrap()).unwrap(),
+            format!("1234567890{MARKER}")
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::transfer::stream::{
-    ChannelId, DataMsg, EndTaskStreamMsg, FromStreamerMessage, StartTaskStreamMsg,
-    StreamRegistration, ToStreamerMessage,
+    ChannelId, DataMsg, EndTaskStreamMsg, FromStreamerMessage, StartTaskStreamMsg, StreamEndStatus,
+    ToStreamerMessage,
 };
 use crate::WrappedRcRefCell;
 use crate::{JobId, JobTaskId, Map};
@@ -17,8 +17,8 @@ use tako::comm::ConnectionRegistration;
 use tako::comm::{open_message, seal_message, serialize};
 use tako::server::ConnectionDescriptor;
 use tako::{define_wrapped_type, InstanceId};
-use tokio::sync::mpsc::channel;
 use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::{channel, Receiver};
 use tokio::sync::oneshot;
 use tokio::task::spawn_local;
 use tokio_util::codec::{Framed, LengthDelimitedCodec};
@@ -26,37 +26,49 @@ use tokio_util::codec::{Framed, LengthDelimitedCodec};
 const STREAMER_BUFFER_SIZE: usize = 128;
 
 /*
-    Streamer is connection manager that holds a connections to stream server.
-    In the current version, each job establishes own connection.
-    Tasks in the same job uses the same connection.
+    Streamer is connection manager that holds a connection to stream server.
+    Streams of all jobs and tasks running on the worker share the same connection,
+    messages are identified by (job, task, instance).
+    The connection is established when the first task starts streaming and it is
+    closed when it is not used by any task.
 */
 
-struct StreamInfo {
+type ConnectionId = u64;
+
+struct StreamConnection {
+    id: ConnectionId,
     sender: Sender<FromStreamerMessage>,
-    responses: Map<JobTaskId, oneshot::Sender<tako::Result<()>>>,
+}
+
+struct TaskStreamInfo {
+    connection_id: ConnectionId,
+    response: oneshot::Sender<tako::Result<()>>,
 }
 
 pub struct Streamer {
-    streams: Map<JobId, StreamInfo>,
+    connection: Option<StreamConnection>,
+    connection_id_counter: ConnectionId,
+    streams: Map<(JobId, JobTaskId), TaskStreamInfo>,
     server_address: SocketAddr,
     secret_key: Arc<SecretKey>,
 }
 
 async fn stream_receiver(
     streamer_ref: StreamerRef,
-    job_id: JobId,
     mut receiver: SplitStream<Framed<tokio::net::TcpStream, LengthDelimitedCodec>>,
     mut opener: Option<StreamOpener>,
 ) -> crate::Result<()> {
     while let Some(data) = receiver.next().await {
         let message = data?;
         let msg: ToStreamerMessage = open_message(&mut opener, &message)?;
+        let mut streamer = streamer_ref.get_mut();
         match msg {
             ToStreamerMessage::EndResponse(r) => {
-                let mut streamer = streamer_ref.get_mut();
-                streamer.close_task_stream(job_id, r.task);
+                streamer.close_task_stream(r.job, r.task);
+            }
+            ToStreamerMessage::Error(e) => {
+                streamer.send_error(|(job_id, _), _| *job_id == e.job, &e.message);
             }
-            ToStreamerMessage::Error(e) => return Err(e.into()),
         }
     }
     Ok(())
@@ -64,28 +76,51 @@ async fn stream_receiver(
 
 impl Streamer {
     /*
-        Sends an error message to each task stream that uses the same connection
+        Sends an error message to each selected task stream
     */
-    pub fn send_error(&mut self, job_id: JobId, error: String) {
-        let info = self.streams.remove(&job_id).unwrap();
-        for (_, response_sender) in info.responses {
-            let _ = response_sender.send(Err(error.as_str().into()));
+    fn send_error(
+        &mut self,
+        selector: impl Fn(&(JobId, JobTaskId), &TaskStreamInfo) -> bool,
+        error: &str,
+    ) {
+        let keys: Vec<_> = self
+            .streams
+            .iter()
+            .filter(|(key, info)| selector(key, info))
+            .map(|(key, _)| *key)
+            .collect();
+        for key in keys {
+            let info = self.streams.remove(&key).unwrap();
+            let _ = info.response.send(Err(error.into()));
         }
     }
 
     /*
-        Closes a task stream, if it is the last stream that uses a connection,
-        then the connection is closed
+        Closes a task stream, the connection itself is closed when it is not used by any task
     */
     pub fn close_task_stream(&mut self, job_id: JobId, task_id: JobTaskId) {
-        let info = self.streams.get_mut(&job_id).unwrap();
-        let _ = info.responses.remove(&task_id).unwrap().send(Ok(()));
-        if info.responses.is_empty() {
-            self.streams.remove(&job_id);
+        if let Some(info) = self.streams.remove(&(job_id, task_id)) {
+            let _ = info.response.send(Ok(()));
         }
     }
 
-    /* Get tasm stream input end, if a connection to a stream server is not established,
+    /*
+        Removes all task streams that were using a terminated connection
+    */
+    fn close_connection(&mut self, connection_id: ConnectionId, error: Option<String>) {
+        match error {
+            Some(error) => self.send_error(|_, info| info.connection_id == connection_id, &error),
+            // Dropping the response senders causes an error in the corresponding tasks
+            None => self
+                .streams
+                .retain(|_, info| info.connection_id != connection_id),
+        }
+        if matches!(&self.connection, Some(connection) if connection.id == connection_id) {
+            self.connection = None;
+        }
+    }
+
+    /* Get task stream input end, if a connection to a stream server is not established,
       the new one is created
 
       response_sender - error and finish confirmation mechanism. If an error occurs,
@@ -102,105 +137,96 @@ impl Streamer {
         response_sender: oneshot::Sender<tako::Result<()>>,
     ) -> StreamSender {
         log::debug!("New stream for {}/{}", job_id, job_task_id);
-        if let Some(ref mut info) = self.streams.get_mut(&job_id) {
-            assert!(info
-                .responses
-                .insert(job_task_id, response_sender)
-                .is_none());
-            StreamSender {
-                task_id: job_task_id,
-                instance_id,
-                sender: info.sender.clone(),
-            }
-        } else {
-            log::debug!("Starting a new stream connection for job_id = {}", job_id);
-            let (queue_sender, mut queue_receiver) = channel(STREAMER_BUFFER_SIZE);
-            let mut responses = Map::new();
-            responses.insert(job_task_id, response_sender);
-            self.streams.insert(
-                job_id,
-                StreamInfo {
-                    sender: queue_sender.clone(),
-                    responses,
-                },
-            );
-            let streamer_ref = streamer_ref.clone();
-            spawn_local(async move {
-                let (server_address, secret_key) = {
-                    let streamer = streamer_ref.get();
-                    (streamer.server_address, streamer.secret_key.clone())
-                };
-                let ConnectionDescriptor {
-                    mut sender,
-                    receiver,
-                    opener,
-                    mut sealer,
-                    ..
-                } = match connect_to_server_and_authenticate(server_address, &Some(secret_key))
-                    .await
-                {
-                    Ok(connection) => connection,
-                    Err(e) => {
-                        streamer_ref.get_mut().send_error(job_id, e.to_string());
-                        return;
-                    }
-                };
-
-                if let Err(e) = sender
-                    .send(seal_message(
-                        &mut sealer,
-                        serialize(&ConnectionRegistration::Custom).unwrap().into(),
-                    ))
-                    .await
-                {
-                    streamer_ref.get_mut().send_error(job_id, e.to_string());
-                    return;
-                }
+        if self.connection.is_none() {
+            self.connection_id_counter += 1;
+            let connection_id = self.connection_id_counter;
+            log::debug!("Starting a new stream connection {}", connection_id);
+            let (queue_sender, queue_receiver) = channel(STREAMER_BUFFER_SIZE);
+            spawn_local(stream_connection(
+                streamer_ref.clone(),
+                connection_id,
+                queue_receiver,
+            ));
+            self.connection = Some(StreamConnection {
+                id: connection_id,
+                sender: queue_sender,
+            });
+        }
+        let connection = self.connection.as_ref().unwrap();
+        let sender = StreamSender {
+            job_id,
+            task_id: job_task_id,
+            instance_id,
+            sender: connection.sender.clone(),
+        };
+        let info = TaskStreamInfo {
+            connection_id: connection.id,
+            response: response_sender,
+        };
+        assert!(self.streams.insert((job_id, job_task_id), info).is_none());
+        sender
+    }
+}
 
-                if let Err(e) = sender
-                    .send(seal_message(
-                        &mut sealer,
-                        serialize(&StreamRegistration { job: job_id })
-                            .unwrap()
-                            .into(),
-                    ))
-                    .await
-                {
-                    streamer_ref.get_mut().send_error(job_id, e.to_string());
-                    return;
-                }
+async fn stream_connection(
+    streamer_ref: StreamerRef,
+    connection_id: ConnectionId,
+    mut queue_receiver: Receiver<FromStreamerMessage>,
+) {
+    let (server_address, secret_key) = {
+        let streamer = streamer_ref.get();
+        (streamer.server_address, streamer.secret_key.clone())
+    };
+    let ConnectionDescriptor {
+        mut sender,
+        receiver,
+        opener,
+        mut sealer,
+        ..
+    } = match connect_to_server_and_authenticate(server_address, &Some(secret_key)).await {
+        Ok(connection) => connection,
+        Err(e) => {
+            streamer_ref
+                .get_mut()
+                .close_connection(connection_id, Some(e.to_string()));
+            return;
+        }
+    };
 
-                let send_loop = async {
-                    while let Some(data) = queue_receiver.recv().await {
-                        if let Err(e) = sender
-                            .send(seal_message(&mut sealer, serialize(&data).unwrap().into()))
-                            .await
-                        {
-                            log::debug!("Forwarding from queue failed");
-                            return Err(e.into());
-                        }
-                    }
-                    Ok(())
-                };
+    if let Err(e) = sender
+        .send(seal_message(
+            &mut sealer,
+            serialize(&ConnectionRegistration::Custom).unwrap().into(),
+        ))
+        .await
+    {
+        streamer_ref
+            .get_mut()
+            .close_connection(connection_id, Some(e.to_string()));
+        return;
+    }
 
-                let r = tokio::select! {
-                    r = send_loop => r,
-                    r = stream_receiver(streamer_ref.clone(), job_id, receiver, opener) => r,
-                };
-                log::debug!("Stream for job {} terminated {:?}", job_id, r);
-                if let Err(e) = r {
-                    streamer_ref.get_mut().send_error(job_id, e.to_string())
-                } else {
-                    streamer_ref.get_mut().streams.remove(&job_id);
-                }
-            });
-            StreamSender {
-                task_id: job_task_id,
-                sender: queue_sender,
-                instance_id,
+    let send_loop = async {
+        while let Some(data) = queue_receiver.recv().await {
+            if let Err(e) = sender
+                .send(seal_message(&mut sealer, serialize(&data).unwrap().into()))
+                .await
+            {
+                log::debug!("Forwarding from queue failed");
+                return Err(e.into());
             }
         }
-    }
+        Ok(())
+    };
+
+    let r = tokio::select! {
+        r = send_loop => r,
+        r = stream_receiver(streamer_ref.clone(), receiver, opener) => r,
+    };
+    log::debug!("Stream connection {} terminated {:?}", connection_id, r);
+    streamer_ref
+        .get_mut()
+        .close_connection(connection_id, r.err().map(|e| e.to_string()));
 }
 
 define_wrapped_type!(StreamerRef, Streamer, pub);
@@ -212,6 +238,8 @@ impl StreamerRef {
         secret_key: Arc<SecretKey>,
     ) -> (StreamerRef, impl Future<Output = ()>) {
         let streamer_ref = WrappedRcRefCell::wrap(Streamer {
+            connection: None,
+            connection_id_counter: 0,
             streams: Default::default(),
             secret_key,
             server_address,
@@ -223,9 +251,10 @@ impl StreamerRef {
             loop {
                 it.tick().await;
                 let mut streamer = streamer_ref2.get_mut();
-                streamer
-                    .streams
-                    .retain(|_, info| !info.responses.is_empty());
+                // The connection is closed once the stream senders of all tasks are dropped
+                if streamer.streams.is_empty() {
+                    streamer.connection = None;
+                }
             }
         };
         (Self(streamer_ref), streamer_future)
@@ -234,17 +263,20 @@ impl StreamerRef {
 
 pub struct StreamSender {
     sender: Sender<FromStreamerMessage>,
+    job_id: JobId,
     task_id: JobTaskId,
     instance_id: InstanceId,
 }
 
 impl StreamSender {
-    pub async fn close(&self) -> tako::Result<()> {
+    pub async fn close(&self, status: StreamEndStatus) -> tako::Result<()> {
         if self
             .sender
             .send(FromStreamerMessage::End(EndTaskStreamMsg {
+                job: self.job_id,
                 task: self.task_id,
                 instance: self.instance_id,
+                status,
             }))
             .await
             .is_err()
@@ -258,6 +290,7 @@ impl StreamSender {
         if self
             .sender
             .send(FromStreamerMessage::Data(DataMsg {
+                job: self.job_id,
                 task: self.task_id,
                 instance: self.instance_id,
                 channel,
@@ -275,6 +308,7 @@ impl StreamSender {
         if self
             .sender
             .send(FromStreamerMessage::Start(StartTaskStreamMsg {
+                job: self.job_id,
                 task: self.task_id,
                 instance: self.instance_id,
             }))
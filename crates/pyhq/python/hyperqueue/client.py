@@ -1,6 +1,7 @@
 import itertools
+import json
 import logging
-from typing import Dict, Optional, Sequence
+from typing import Any, Callable, Dict, Optional, Sequence
 
 from tqdm import tqdm
 
@@ -112,6 +113,37 @@ class Client:
         result = self.connection.get_failed_tasks([job.id])
         return result[job.id]
 
+    def stream_events(
+        self,
+        callback: Callable[[Dict[str, Any]], Optional[bool]],
+        event_types: Sequence[str] = (),
+        jobs: Sequence[SubmittedJob] = (),
+        worker_ids: Sequence[int] = (),
+        after_id: Optional[int] = None,
+    ):
+        """
+        Receives events from the server as soon as they are created and passes them to `callback`.
+        Each event is a dictionary with the same structure as the output of `hq event-log stream`.
+        This method blocks until `callback` returns `False`.
+
+        :param callback: Function that receives events. Return `False` from it to stop streaming.
+        :param event_types: Only receive events of these types (e.g. `task-finished`).
+        :param jobs: Only receive events related to these jobs.
+        :param worker_ids: Only receive events related to these workers.
+        :param after_id: Also receive events stored in the server with an ID larger than this one.
+        """
+
+        def cb(event: str) -> Optional[bool]:
+            return callback(json.loads(event))
+
+        self.connection.stream_events(
+            cb,
+            event_types=list(event_types),
+            job_ids=[job.id for job in jobs],
+            worker_ids=list(worker_ids),
+            after_id=after_id,
+        )
+
 
 def create_progress_callback():
     bar = None
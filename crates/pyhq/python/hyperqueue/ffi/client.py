@@ -1,5 +1,5 @@
 import dataclasses
-from typing import Dict, List, Optional, Sequence
+from typing import Callable, Dict, List, Optional, Sequence
 
 from . import JobId, TaskId, ffi
 from .protocol import JobDescription
@@ -25,6 +25,7 @@ TaskFailureMap = Dict[JobId, Dict[TaskId, FailedTaskContext]]
 
 class ClientConnection:
     def __init__(self, directory: Optional[str] = None):
+        self.directory = directory
         self.ctx: HqClientContext = ffi.connect_to_server(directory)
 
     def submit_job(self, job_description: JobDescription) -> JobId:
@@ -34,6 +35,22 @@ class ClientConnection:
         """Blocks until jobs are finished. Returns the number of failed tasks"""
         return ffi.wait_for_jobs(self.ctx, job_ids, callback)
 
+    def stream_events(
+        self,
+        callback: Callable[[str], Optional[bool]],
+        event_types: Sequence[str],
+        job_ids: Sequence[JobId],
+        worker_ids: Sequence[int],
+        after_id: Optional[int],
+    ):
+        """
+        Blocks and passes events (serialized to JSON) to `callback` until it returns `False`.
+        Uses a separate connection to the server.
+        """
+        return ffi.stream_events(
+            self.directory, event_types, job_ids, worker_ids, after_id, callback
+        )
+
     def stop_server(self):
         return ffi.stop_server(self.ctx)
 
@@ -0,0 +1,93 @@
+use pyo3::exceptions::PyException;
+use pyo3::types::PyTuple;
+use pyo3::{IntoPy, PyAny, PyErr, PyResult, Python};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use hyperqueue::client::commands::event::format_event;
+use hyperqueue::client::default_server_directory_path;
+use hyperqueue::server::bootstrap::get_client_session;
+use hyperqueue::server::event::events::EVENT_TYPES;
+use hyperqueue::transfer::messages::{
+    EventFilter, FromClientMessage, StreamEventsRequest, ToClientMessage,
+};
+
+use crate::utils::error::ToPyResult;
+use crate::{run_future, PyJobId, PyWorkerId};
+
+pub struct StreamEventsArgs {
+    pub event_types: Vec<String>,
+    pub job_ids: Vec<PyJobId>,
+    pub worker_ids: Vec<PyWorkerId>,
+    pub after_id: Option<u32>,
+}
+
+/// Subscribes to events of the server and passes each event (serialized to JSON) to `callback`.
+/// Streaming ends when the callback returns `False`.
+///
+/// A new connection is created for the subscription, because the server does not accept any
+/// other requests on a connection that streams events.
+pub(crate) fn stream_events_impl(
+    py: Python,
+    directory: Option<String>,
+    args: StreamEventsArgs,
+    callback: &PyAny,
+) -> PyResult<()> {
+    if let Some(event_type) = args
+        .event_types
+        .iter()
+        .find(|name| !EVENT_TYPES.contains(&name.as_str()))
+    {
+        return Err(PyException::new_err(format!(
+            "Unknown event type `{event_type}`, expected one of: {}",
+            EVENT_TYPES.join(", ")
+        )));
+    }
+    let directory = directory
+        .map(|p| -> PathBuf { p.into() })
+        .unwrap_or_else(default_server_directory_path);
+
+    run_future(async move {
+        let mut session = get_client_session(&directory).await?;
+        let connection = session.connection();
+        connection
+            .send(FromClientMessage::StreamEvents(StreamEventsRequest {
+                after_id: args.after_id,
+                filter: EventFilter {
+                    event_types: args.event_types,
+                    job_ids: args.job_ids.into_iter().map(|id| id.into()).collect(),
+                    worker_ids: args.worker_ids.into_iter().map(|id| id.into()).collect(),
+                },
+            }))
+            .await
+            .map_py_err()?;
+
+        // Periodically give Python a chance to handle signals (e.g. Ctrl+C)
+        let mut signal_check = tokio::time::interval(Duration::from_millis(100));
+        loop {
+            let message = tokio::select! {
+                message = connection.receive() => message,
+                _ = signal_check.tick() => {
+                    py.check_signals()?;
+                    continue;
+                }
+            };
+            let event = match message {
+                Some(Ok(ToClientMessage::Event(event))) => event,
+                Some(Ok(message)) => {
+                    return Err(PyException::new_err(format!(
+                        "Received an invalid message {:?}",
+                        message
+                    )))
+                }
+                Some(Err(error)) => return Err(error).map_py_err(),
+                None => return Ok(()),
+            };
+            let args = PyTuple::new(py, &[format_event(event).to_string().into_py(py)]);
+            let result: Option<bool> = callback.call1(args)?.extract()?;
+            if result == Some(false) {
+                return Ok::<(), PyErr>(());
+            }
+        }
+    })
+}
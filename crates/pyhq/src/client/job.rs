@@ -68,6 +68,8 @@ pub fn submit_job_impl(py: Python, ctx: ClientContextPtr, job: JobDescription) -
             max_fails: job.max_fails,
             submit_dir,
             log: None,
+            log_compression: Default::default(),
+            on_finish: None,
         });
 
         let mut ctx = borrow_mut!(py, ctx);
@@ -149,6 +151,8 @@ fn build_task_desc(desc: TaskDescription, submit_dir: &Path) -> anyhow::Result<H
         priority: desc.priority,
         time_limit: None,
         crash_limit: DEFAULT_CRASH_LIMIT,
+        output_limit: None,
+        spool: false,
     })
 }
 
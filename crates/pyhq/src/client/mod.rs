@@ -1,2 +1,3 @@
+pub mod event;
 pub mod job;
 pub mod server;
@@ -2,9 +2,12 @@ use hyperqueue::client::globalsettings::GlobalSettings;
 use hyperqueue::client::output::cli::CliOutput;
 use hyperqueue::common::utils::network::get_hostname;
 use hyperqueue::server::bootstrap::{initialize_server, ServerConfig};
+use hyperqueue::server::event::hooks::HookRunnerConfig;
+use std::net::Ipv4Addr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread::JoinHandle;
+use std::time::Duration;
 use tokio::sync::Notify;
 use tokio::task::LocalSet;
 
@@ -34,6 +37,14 @@ impl RunningServer {
                 worker_port: None,
                 event_buffer_size: 100,
                 event_log_path: None,
+                event_log_rotation: Default::default(),
+                metrics_host: Ipv4Addr::LOCALHOST.into(),
+                metrics_port: None,
+                hooks: vec![],
+                hook_runner: HookRunnerConfig {
+                    timeout: Duration::from_secs(60),
+                    max_concurrent: 4,
+                },
             };
 
             let main_future = async move {
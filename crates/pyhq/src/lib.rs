@@ -13,6 +13,7 @@ use hyperqueue::transfer::connection::ClientSession;
 use crate::client::job::FailedTaskMap;
 use crate::cluster::Cluster;
 use crate::utils::run_future;
+use client::event::{stream_events_impl, StreamEventsArgs};
 use client::job::{get_failed_tasks_impl, submit_job_impl, wait_for_jobs_impl, JobDescription};
 use client::server::{connect_to_server_impl, stop_server_impl};
 
@@ -33,6 +34,7 @@ type ClientContextPtr = Py<HqClientContext>;
 
 type PyJobId = u32;
 type PyTaskId = u32;
+type PyWorkerId = u32;
 
 #[pyfunction]
 fn connect_to_server(py: Python, directory: Option<String>) -> PyResult<ClientContextPtr> {
@@ -71,6 +73,33 @@ fn get_failed_tasks(
     get_failed_tasks_impl(py, ctx, job_ids)
 }
 
+/// Subscribe to events of the server.
+/// `callback` receives each event serialized as a JSON string, streaming ends when it returns
+/// `False`.
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+fn stream_events(
+    py: Python,
+    directory: Option<String>,
+    event_types: Vec<String>,
+    job_ids: Vec<PyJobId>,
+    worker_ids: Vec<PyWorkerId>,
+    after_id: Option<u32>,
+    callback: &PyAny,
+) -> PyResult<()> {
+    stream_events_impl(
+        py,
+        directory,
+        StreamEventsArgs {
+            event_types,
+            job_ids,
+            worker_ids,
+            after_id,
+        },
+        callback,
+    )
+}
+
 // Server code
 #[pyclass]
 struct HqClusterContext {
@@ -117,6 +146,7 @@ fn hyperqueue(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(submit_job, m)?)?;
     m.add_function(wrap_pyfunction!(wait_for_jobs, m)?)?;
     m.add_function(wrap_pyfunction!(get_failed_tasks, m)?)?;
+    m.add_function(wrap_pyfunction!(stream_events, m)?)?;
 
     // Cluster
     m.add_class::<HqClusterContext>()?;
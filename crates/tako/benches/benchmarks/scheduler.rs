@@ -3,8 +3,8 @@ use criterion::{BatchSize, BenchmarkGroup, BenchmarkId, Criterion};
 use std::time::Instant;
 
 use crate::{add_tasks, create_worker};
-use tako::gateway::LostWorkerReason;
-use tako::internal::messages::common::TaskFailInfo;
+use tako::gateway::{LostWorkerReason, WorkerResponseMessage};
+use tako::internal::messages::common::{TaskFailInfo, TaskResourceUsage};
 use tako::internal::messages::worker::ToWorkerMessage;
 use tako::internal::scheduler::metrics::compute_b_level_metric;
 use tako::internal::scheduler::state::SchedulerState;
@@ -12,7 +12,7 @@ use tako::internal::server::comm::Comm;
 use tako::internal::server::core::Core;
 use tako::task::SerializedTaskContext;
 use tako::worker::{WorkerConfiguration, WorkerOverview};
-use tako::{TaskId, WorkerId};
+use tako::{InstanceId, TaskId, WorkerId};
 
 fn bench_b_level(c: &mut BenchmarkGroup<WallTime>) {
     for task_count in [10, 1_000, 100_000] {
@@ -86,11 +86,18 @@ impl Comm for NullComm {
 
     fn ask_for_scheduling(&mut self) {}
 
-    fn send_client_task_finished(&mut self, _task_id: TaskId) {}
+    fn send_client_task_finished(
+        &mut self,
+        _task_id: TaskId,
+        _resource_usage: Option<TaskResourceUsage>,
+        _note: Option<String>,
+    ) {
+    }
 
     fn send_client_task_started(
         &mut self,
         _task_id: TaskId,
+        _instance_id: InstanceId,
         _worker_id: &[WorkerId],
         _context: SerializedTaskContext,
     ) {
@@ -120,4 +127,6 @@ impl Comm for NullComm {
     }
 
     fn send_client_worker_overview(&mut self, _overview: WorkerOverview) {}
+
+    fn send_client_worker_response(&mut self, _response: WorkerResponseMessage) {}
 }
@@ -34,7 +34,10 @@ impl TaskLauncher for BenchmarkTaskLauncher {
         _stop_receiver: tokio::sync::oneshot::Receiver<StopReason>,
     ) -> tako::Result<TaskLaunchData> {
         Ok(TaskLaunchData::from_future(Box::pin(async move {
-            Ok(TaskResult::Finished)
+            Ok(TaskResult::Finished {
+                resource_usage: None,
+                note: None,
+            })
         })))
     }
 }
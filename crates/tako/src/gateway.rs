@@ -1,11 +1,11 @@
 use serde::{Deserialize, Serialize, Serializer};
 
-use crate::internal::messages::common::TaskFailInfo;
+use crate::internal::messages::common::{TaskFailInfo, TaskResourceUsage};
 use crate::internal::messages::worker::WorkerOverview;
 use crate::internal::worker::configuration::WorkerConfiguration;
 use crate::resources::{AllocationRequest, NumOfNodes, ResourceDescriptor, CPU_RESOURCE_NAME};
 use crate::task::SerializedTaskContext;
-use crate::{Priority, TaskId, WorkerId};
+use crate::{InstanceId, Priority, TaskId, WorkerId};
 use smallvec::{smallvec, SmallVec};
 use std::time::Duration;
 
@@ -107,6 +107,24 @@ pub struct StopWorkerRequest {
     pub worker_id: WorkerId,
 }
 
+#[derive(Deserialize, Serialize, Debug)]
+pub struct IdleTimeoutExemptionRequest {
+    pub worker_id: WorkerId,
+    /// If true, the worker will not be stopped because of its idle timeout.
+    pub exempt: bool,
+}
+
+/// Custom request that is passed to the task launcher of the given worker.
+/// Its content is opaque for tako.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WorkerRequest {
+    pub worker_id: WorkerId,
+    /// Identifier chosen by the gateway user, it is returned in the corresponding `WorkerResponse`
+    pub request_id: u64,
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WorkerTypeQuery {
     pub descriptor: ResourceDescriptor,
@@ -146,7 +164,9 @@ pub enum FromGatewayMessage {
     GetTaskInfo(TaskInfoRequest),
     ServerInfo,
     StopWorker(StopWorkerRequest),
+    SetIdleTimeoutExemption(IdleTimeoutExemptionRequest),
     NewWorkerQuery(NewWorkerQuery),
+    WorkerRequest(WorkerRequest),
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -172,8 +192,15 @@ pub enum TaskState {
     Running {
         worker_ids: SmallVec<[WorkerId; 1]>,
         context: SerializedTaskContext,
+        /// Incremented each time the task is restarted (e.g. after its worker was lost)
+        instance_id: InstanceId,
+    },
+    Finished {
+        /// Resources consumed by the task, if they were measured by the worker
+        resource_usage: Option<TaskResourceUsage>,
+        /// Note about the execution of the task (e.g. that its output was truncated)
+        note: Option<String>,
     },
-    Finished,
 }
 
 impl Serialize for TaskState {
@@ -184,7 +211,7 @@ impl Serialize for TaskState {
         serializer.serialize_str(match self {
             TaskState::Invalid => "Invalid",
             TaskState::Waiting => "Waiting",
-            TaskState::Finished => "Finished",
+            TaskState::Finished { .. } => "Finished",
             TaskState::Running { .. } => "Running",
         })
     }
@@ -210,6 +237,19 @@ pub struct TaskFailedMessage {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ServerInfo {
     pub worker_listen_port: u16,
+    pub scheduler: SchedulerStats,
+    /// Number of tasks that are known to the server
+    pub n_tasks: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SchedulerStats {
+    /// How many times has the scheduler been invoked
+    pub runs: u64,
+    /// Total time spent in the scheduler
+    pub total_duration: Duration,
+    /// Duration of the last scheduler invocation
+    pub last_duration: Duration,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -267,6 +307,14 @@ pub struct LostWorkerMessage {
     pub reason: LostWorkerReason,
 }
 
+/// Result of a `WorkerRequest`, it is sent asynchronously after the worker has processed the request.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WorkerResponseMessage {
+    pub worker_id: WorkerId,
+    pub request_id: u64,
+    pub result: Result<Vec<u8>, String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MultiNodeAllocationResponse {
     pub worker_type: usize,
@@ -294,5 +342,8 @@ pub enum ToGatewayMessage {
     LostWorker(LostWorkerMessage),
     WorkerOverview(WorkerOverview),
     WorkerStopped,
+    IdleTimeoutExemptionUpdated,
     NewWorkerAllocationQueryResponse(NewWorkerAllocationResponse),
+    WorkerRequestSent,
+    WorkerResponse(WorkerResponseMessage),
 }
@@ -21,11 +21,50 @@ pub struct NetworkStats {
     pub tx_errors: u64,
 }
 
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct DiskStats {
+    /// Total number of bytes read from all disks
+    pub read_bytes: u64,
+    /// Total number of bytes written to all disks
+    pub write_bytes: u64,
+    /// Bytes read per second since the previous measurement
+    pub read_throughput: u64,
+    /// Bytes written per second since the previous measurement
+    pub write_throughput: u64,
+}
+
+/// Usage of the filesystem that contains the working directory of the worker.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct FilesystemStats {
+    pub total: u64,
+    pub free: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct LoadAverage {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct NumaNodeMemoryStats {
+    pub node: u32,
+    pub total: u64,
+    /// Free memory of the node, memory used by the page cache is not included
+    pub free: u64,
+}
+
 #[derive(Deserialize, Serialize, Debug, Default, Clone)]
 pub struct WorkerHwState {
     pub worker_cpu_usage: CpuStats,
     pub worker_memory_usage: MemoryStats,
     pub worker_network_usage: NetworkStats,
+    pub worker_disk_usage: DiskStats,
+    pub worker_filesystem_usage: Option<FilesystemStats>,
+    pub load_average: Option<LoadAverage>,
+    /// Empty if the node does not expose NUMA information
+    pub numa_memory_usage: Vec<NumaNodeMemoryStats>,
     pub timestamp: u64,
 }
 
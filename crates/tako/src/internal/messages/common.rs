@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TaskFailInfo {
@@ -12,6 +13,9 @@ pub struct TaskFailInfo {
     /*    #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]*/
     pub error_data: Vec<u8>,
+
+    /// Resources consumed by the failed task process, if they were measured by the worker
+    pub resource_usage: Option<TaskResourceUsage>,
 }
 
 impl TaskFailInfo {
@@ -20,6 +24,28 @@ impl TaskFailInfo {
             message,
             data_type: Default::default(),
             error_data: Default::default(),
+            resource_usage: None,
         }
     }
 }
+
+/// Resources consumed by a task process (including its children), measured when it has exited.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskResourceUsage {
+    /// CPU time spent in user mode
+    pub user_time: Duration,
+    /// CPU time spent in kernel mode
+    pub system_time: Duration,
+    /// Maximum resident set size (in bytes)
+    pub max_rss: u64,
+    /// Number of bytes read from block devices
+    pub read_bytes: u64,
+    /// Number of bytes written to block devices
+    pub write_bytes: u64,
+}
+
+impl TaskResourceUsage {
+    pub fn cpu_time(&self) -> Duration {
+        self.user_time + self.system_time
+    }
+}
@@ -3,7 +3,7 @@ use std::time::Duration;
 
 use crate::hwstats::WorkerHwStateMessage;
 use crate::internal::common::resources::{ResourceAmount, ResourceIndex};
-use crate::internal::messages::common::TaskFailInfo;
+use crate::internal::messages::common::{TaskFailInfo, TaskResourceUsage};
 use crate::task::SerializedTaskContext;
 use crate::{InstanceId, Priority};
 use crate::{TaskId, WorkerId};
@@ -57,6 +57,13 @@ pub struct TaskIdMsg {
     pub id: TaskId,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CustomRequestMsg {
+    pub id: u64,
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum ToWorkerMessage {
     ComputeTask(ComputeTaskMsg),
@@ -65,6 +72,8 @@ pub enum ToWorkerMessage {
     NewWorker(NewWorkerMsg),
     LostWorker(WorkerId),
     SetReservation(bool),
+    SetIdleTimeoutExemption(bool),
+    CustomRequest(CustomRequestMsg),
     Stop,
 }
 
@@ -72,6 +81,8 @@ pub enum ToWorkerMessage {
 pub struct TaskFinishedMsg {
     pub id: TaskId,
     pub size: u64,
+    pub resource_usage: Option<TaskResourceUsage>,
+    pub note: Option<String>,
     /*#[serde(with = "serde_bytes")]
     pub r#type: Vec<u8>,*/
 }
@@ -138,6 +149,12 @@ pub enum WorkerStopReason {
     TimeLimitReached,
 }
 
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CustomResponseMsg {
+    pub id: u64,
+    pub result: Result<Vec<u8>, String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 //#[serde(tag = "op")]
 pub enum FromWorkerMessage {
@@ -148,5 +165,6 @@ pub enum FromWorkerMessage {
     StealResponse(StealResponseMsg),
     Overview(WorkerOverview),
     Heartbeat,
+    CustomResponse(CustomResponseMsg),
     Stop(WorkerStopReason),
 }
@@ -58,6 +58,7 @@ pub(crate) async fn scheduler_loop(
         state.run_scheduling(&mut core, &mut *comm);
         comm.reset_scheduling_flag();
         last_schedule = Instant::now();
+        core.on_scheduling_finished(last_schedule - now);
         comm.call_after_scheduling_callbacks(&mut core);
     }
 }
@@ -5,7 +5,7 @@ use crate::gateway::{
     CancelTasksResponse, FromGatewayMessage, NewTasksMessage, NewTasksResponse,
     SharedTaskConfiguration, TaskInfo, TaskState, TaskUpdate, TasksInfoResponse, ToGatewayMessage,
 };
-use crate::internal::messages::worker::ToWorkerMessage;
+use crate::internal::messages::worker::{CustomRequestMsg, ToWorkerMessage};
 use crate::internal::server::comm::{Comm, CommSender, CommSenderRef};
 use crate::internal::server::core::{Core, CoreRef};
 use crate::internal::server::reactor::{on_cancel_tasks, on_new_tasks, on_set_observe_flag};
@@ -116,7 +116,10 @@ pub(crate) async fn process_client_message(
                         .send(ToGatewayMessage::TaskUpdate(TaskUpdate {
                             id: task_id,
                             state: if core.is_used_task_id(task_id) {
-                                TaskState::Finished
+                                TaskState::Finished {
+                                    resource_usage: None,
+                                    note: None,
+                                }
                             } else {
                                 TaskState::Invalid
                             },
@@ -158,7 +161,10 @@ pub(crate) async fn process_client_message(
                             TaskRuntimeState::Stealing(_, _) => TaskState::Waiting,
                             TaskRuntimeState::Running { .. } => TaskState::Waiting,
                             TaskRuntimeState::RunningMultiNode(_) => TaskState::Waiting,
-                            TaskRuntimeState::Finished(_) => TaskState::Finished,
+                            TaskRuntimeState::Finished(_) => TaskState::Finished {
+                                resource_usage: None,
+                                note: None,
+                            },
                         },
                     }
                 })
@@ -196,6 +202,39 @@ pub(crate) async fn process_client_message(
                 Some(format!("Worker with id {} not found", msg.worker_id))
             }
         }
+        FromGatewayMessage::SetIdleTimeoutExemption(msg) => {
+            if core_ref.get().get_worker_by_id(msg.worker_id).is_some() {
+                let mut comm = comm_ref.get_mut();
+                comm.send_worker_message(
+                    msg.worker_id,
+                    &ToWorkerMessage::SetIdleTimeoutExemption(msg.exempt),
+                );
+                assert!(client_sender
+                    .send(ToGatewayMessage::IdleTimeoutExemptionUpdated)
+                    .is_ok());
+                None
+            } else {
+                Some(format!("Worker with id {} not found", msg.worker_id))
+            }
+        }
+        FromGatewayMessage::WorkerRequest(msg) => {
+            if core_ref.get().get_worker_by_id(msg.worker_id).is_some() {
+                let mut comm = comm_ref.get_mut();
+                comm.send_worker_message(
+                    msg.worker_id,
+                    &ToWorkerMessage::CustomRequest(CustomRequestMsg {
+                        id: msg.request_id,
+                        data: msg.data,
+                    }),
+                );
+                assert!(client_sender
+                    .send(ToGatewayMessage::WorkerRequestSent)
+                    .is_ok());
+                None
+            } else {
+                Some(format!("Worker with id {} not found", msg.worker_id))
+            }
+        }
         FromGatewayMessage::NewWorkerQuery(msg) => {
             dbg!("!!!!!!!!!!!!!!!!!!!!!!");
             for query in &msg.worker_queries {
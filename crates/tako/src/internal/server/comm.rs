@@ -6,26 +6,32 @@ use tokio::sync::Notify;
 
 use crate::gateway::{
     LostWorkerMessage, LostWorkerReason, NewWorkerMessage, TaskFailedMessage, TaskState,
-    TaskUpdate, ToGatewayMessage,
+    TaskUpdate, ToGatewayMessage, WorkerResponseMessage,
 };
 use crate::internal::common::{Map, WrappedRcRefCell};
-use crate::internal::messages::common::TaskFailInfo;
+use crate::internal::messages::common::{TaskFailInfo, TaskResourceUsage};
 use crate::internal::messages::worker::{ToWorkerMessage, WorkerOverview};
 use crate::internal::server::core::Core;
 use crate::internal::transfer::auth::serialize;
 use crate::internal::worker::configuration::WorkerConfiguration;
 use crate::task::SerializedTaskContext;
-use crate::{TaskId, WorkerId};
+use crate::{InstanceId, TaskId, WorkerId};
 
 pub trait Comm {
     fn send_worker_message(&mut self, worker_id: WorkerId, message: &ToWorkerMessage);
     fn broadcast_worker_message(&mut self, message: &ToWorkerMessage);
     fn ask_for_scheduling(&mut self);
 
-    fn send_client_task_finished(&mut self, task_id: TaskId);
+    fn send_client_task_finished(
+        &mut self,
+        task_id: TaskId,
+        resource_usage: Option<TaskResourceUsage>,
+        note: Option<String>,
+    );
     fn send_client_task_started(
         &mut self,
         task_id: TaskId,
+        instance_id: InstanceId,
         worker_ids: &[WorkerId],
         context: SerializedTaskContext,
     );
@@ -44,6 +50,7 @@ pub trait Comm {
         reason: LostWorkerReason,
     );
     fn send_client_worker_overview(&mut self, overview: WorkerOverview);
+    fn send_client_worker_response(&mut self, response: WorkerResponseMessage);
 }
 
 type SchedulingCallback = Box<dyn FnOnce(&mut Core)>;
@@ -136,13 +143,21 @@ impl Comm for CommSender {
     }
 
     #[inline]
-    fn send_client_task_finished(&mut self, task_id: TaskId) {
+    fn send_client_task_finished(
+        &mut self,
+        task_id: TaskId,
+        resource_usage: Option<TaskResourceUsage>,
+        note: Option<String>,
+    ) {
         log::debug!("Informing client about finished task={}", task_id);
         if let Err(error) = self
             .client_sender
             .send(ToGatewayMessage::TaskUpdate(TaskUpdate {
                 id: task_id,
-                state: TaskState::Finished,
+                state: TaskState::Finished {
+                    resource_usage,
+                    note,
+                },
             }))
         {
             log::error!("Error while task finished message to client: {error:?}");
@@ -152,6 +167,7 @@ impl Comm for CommSender {
     fn send_client_task_started(
         &mut self,
         task_id: TaskId,
+        instance_id: InstanceId,
         worker_ids: &[WorkerId],
         context: SerializedTaskContext,
     ) {
@@ -163,6 +179,7 @@ impl Comm for CommSender {
                 state: TaskState::Running {
                     worker_ids: worker_ids.into(),
                     context,
+                    instance_id,
                 },
             }))
         {
@@ -225,4 +242,13 @@ impl Comm for CommSender {
             log::error!("Error while sending worker overview message to client: {error:?}");
         }
     }
+
+    fn send_client_worker_response(&mut self, response: WorkerResponseMessage) {
+        if let Err(error) = self
+            .client_sender
+            .send(ToGatewayMessage::WorkerResponse(response))
+        {
+            log::error!("Error while sending worker response message to client: {error:?}");
+        }
+    }
 }
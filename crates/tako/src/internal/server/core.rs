@@ -3,7 +3,7 @@ use std::time::Duration;
 
 use orion::aead::SecretKey;
 
-use crate::gateway::ServerInfo;
+use crate::gateway::{SchedulerStats, ServerInfo};
 use crate::internal::common::resources::map::{ResourceIdAllocator, ResourceMap};
 use crate::internal::common::resources::{ResourceId, ResourceRequest};
 use crate::internal::common::{Set, WrappedRcRefCell};
@@ -43,6 +43,8 @@ pub struct Core {
 
     idle_timeout: Option<Duration>,
 
+    scheduler_stats: SchedulerStats,
+
     secret_key: Option<Arc<SecretKey>>,
     custom_conn_handler: Option<CustomConnectionHandler>,
 }
@@ -155,9 +157,17 @@ impl Core {
     pub fn get_server_info(&self) -> ServerInfo {
         ServerInfo {
             worker_listen_port: self.worker_listen_port,
+            scheduler: self.scheduler_stats.clone(),
+            n_tasks: self.tasks.len(),
         }
     }
 
+    pub fn on_scheduling_finished(&mut self, duration: Duration) {
+        self.scheduler_stats.runs += 1;
+        self.scheduler_stats.total_duration += duration;
+        self.scheduler_stats.last_duration = duration;
+    }
+
     pub fn sn_ready_to_assign(&self) -> &[TaskId] {
         &self.single_node_ready_to_assign
     }
@@ -155,6 +155,7 @@ pub(crate) fn on_remove_worker(
                     message: format!("Task was running on a worker that was lost; the task has occurred {} times in this situation and limit was reached.", count),
                     data_type: "".to_string(),
                     error_data: vec![],
+                    resource_usage: None,
                 },
             );
     }
@@ -248,7 +249,7 @@ pub(crate) fn on_task_running(
         };
 
         if task.is_observed() {
-            comm.send_client_task_started(task_id, worker_ids, context);
+            comm.send_client_task_started(task_id, task.instance_id, worker_ids, context);
         }
     }
 }
@@ -316,7 +317,7 @@ pub(crate) fn on_task_finished(
             comm.ask_for_scheduling();
 
             if task.is_observed() {
-                comm.send_client_task_finished(task.id);
+                comm.send_client_task_finished(task.id, msg.resource_usage, msg.note);
             }
         } else {
             log::debug!("Unknown task finished id={}", msg.id);
@@ -439,7 +440,7 @@ pub(crate) fn on_set_observe_flag(
 ) -> bool {
     if let Some(task) = core.find_task_mut(task_id) {
         if value && task.is_finished() {
-            comm.send_client_task_finished(task_id);
+            comm.send_client_task_finished(task_id, None, None);
         }
         task.set_observed_flag(value);
         true
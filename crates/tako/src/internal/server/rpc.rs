@@ -10,7 +10,7 @@ use tokio::time::timeout;
 use tokio_util::codec::{Framed, LengthDelimitedCodec};
 
 use crate::comm::{ConnectionRegistration, RegisterWorker};
-use crate::gateway::LostWorkerReason;
+use crate::gateway::{LostWorkerReason, WorkerResponseMessage};
 use crate::internal::common::error::DsError;
 use crate::internal::common::taskgroup::TaskGroup;
 use crate::internal::messages::worker::{
@@ -286,6 +286,13 @@ pub(crate) async fn worker_receive_loop<
             FromWorkerMessage::Overview(overview) => {
                 comm.send_client_worker_overview(overview);
             }
+            FromWorkerMessage::CustomResponse(msg) => {
+                comm.send_client_worker_response(WorkerResponseMessage {
+                    worker_id,
+                    request_id: msg.id,
+                    result: msg.result,
+                });
+            }
             FromWorkerMessage::Stop(reason) => {
                 return Ok(Some(reason));
             }
@@ -88,7 +88,7 @@ pub enum TaskResult {
 
 impl TaskResult {
     pub fn is_finished(&self) -> bool {
-        matches!(self, TaskResult::Update(TaskState::Finished))
+        matches!(self, TaskResult::Update(TaskState::Finished { .. }))
     }
 
     pub fn is_invalid(&self) -> bool {
@@ -173,7 +173,7 @@ pub async fn wait_for_tasks<T: Into<TaskId>>(
                 if !tasks_orig.contains(&msg.id) {
                     continue;
                 }
-                if let TaskState::Finished | TaskState::Invalid = msg.state {
+                if let TaskState::Finished { .. } | TaskState::Invalid = msg.state {
                     assert!(tasks.remove(&msg.id));
                 }
                 result
@@ -276,7 +276,10 @@ impl TaskLauncher for TestTaskLauncher {
                 }
                 r = launcher_main(program) => {
                     r?;
-                    Ok(TaskResult::Finished)
+                    Ok(TaskResult::Finished {
+                        resource_usage: None,
+                        note: None,
+                    })
                 }
             }
         })))
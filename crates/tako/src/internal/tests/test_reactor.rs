@@ -256,6 +256,8 @@ fn test_assignments_and_finish() {
         TaskFinishedMsg {
             id: 15.into(),
             size: 301,
+            resource_usage: None,
+            note: None,
         },
     );
 
@@ -279,6 +281,8 @@ fn test_assignments_and_finish() {
         TaskFinishedMsg {
             id: 12.into(),
             size: 5000,
+            resource_usage: None,
+            note: None,
         },
     );
 
@@ -299,6 +303,8 @@ fn test_assignments_and_finish() {
         TaskFinishedMsg {
             id: 11.into(),
             size: 1000,
+            resource_usage: None,
+            note: None,
         },
     );
 
@@ -325,6 +331,8 @@ fn test_assignments_and_finish() {
         TaskFinishedMsg {
             id: 13.into(),
             size: 1000,
+            resource_usage: None,
+            note: None,
         },
     );
 
@@ -365,6 +373,7 @@ fn test_running_task_on_error() {
             message: "".to_string(),
             data_type: "".to_string(),
             error_data: vec![],
+            resource_usage: None,
         },
     );
     assert!(!worker_has_task(&core, 102, 13));
@@ -525,6 +534,8 @@ fn finish_task_without_outputs() {
         TaskFinishedMsg {
             id: 1.into(),
             size: 0,
+            resource_usage: None,
+            note: None,
         },
     );
     comm.check_need_scheduling();
@@ -713,6 +724,7 @@ fn test_task_mn_fail() {
             message: "".to_string(),
             data_type: "".to_string(),
             error_data: vec![],
+            resource_usage: None,
         },
     );
     core.sanity_check();
@@ -846,6 +858,8 @@ fn test_finished_before_steal_response() {
         TaskFinishedMsg {
             id: 1.into(),
             size: 0,
+            resource_usage: None,
+            note: None,
         },
     );
 
@@ -935,6 +949,8 @@ fn test_after_cancel_messages() {
         TaskFinishedMsg {
             id: 1.into(),
             size: 100,
+            resource_usage: None,
+            note: None,
         },
     );
     comm.emptiness_check();
@@ -968,6 +984,7 @@ fn test_after_cancel_messages() {
             message: "".to_string(),
             data_type: "".to_string(),
             error_data: vec![],
+            resource_usage: None,
         },
     );
     comm.emptiness_check();
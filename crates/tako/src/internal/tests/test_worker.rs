@@ -1,6 +1,7 @@
 use crate::internal::common::resources::request::{ResourceRequestEntries, ResourceRequestEntry};
 use crate::internal::messages::worker::{
-    ComputeTaskMsg, NewWorkerMsg, ToWorkerMessage, WorkerResourceCounts,
+    ComputeTaskMsg, CustomRequestMsg, FromWorkerMessage, NewWorkerMsg, ToWorkerMessage,
+    WorkerResourceCounts,
 };
 use crate::internal::server::workerload::WorkerResources;
 use crate::internal::worker::comm::WorkerComm;
@@ -122,6 +123,27 @@ fn test_worker_reservation() {
     assert_ne!(state.last_task_finish_time, finish_time);
 }
 
+#[test]
+fn test_worker_custom_request() {
+    let state_ref = create_test_worker_state(create_test_worker_config());
+    let mut state = state_ref.get_mut();
+    process_worker_message(
+        &mut state,
+        ToWorkerMessage::CustomRequest(CustomRequestMsg {
+            id: 12,
+            data: vec![1, 2, 3],
+        }),
+    );
+    let comm = state.comm().test();
+    let messages = comm.take_messages();
+    assert_eq!(messages.len(), 1);
+    assert!(matches!(
+        &messages[0],
+        FromWorkerMessage::CustomResponse(msg) if msg.id == 12 && msg.result.is_err()
+    ));
+    comm.check_emptiness();
+}
+
 #[test]
 fn test_worker_other_workers() {
     let state_ref = create_test_worker_state(create_test_worker_config());
@@ -1,9 +1,9 @@
-use crate::gateway::LostWorkerReason;
+use crate::gateway::{LostWorkerReason, WorkerResponseMessage};
 use crate::internal::common::index::ItemId;
 use crate::internal::common::resources::ResourceDescriptor;
 use crate::internal::common::utils::format_comma_delimited;
 use crate::internal::common::Map;
-use crate::internal::messages::common::TaskFailInfo;
+use crate::internal::messages::common::{TaskFailInfo, TaskResourceUsage};
 use crate::internal::messages::worker::{ToWorkerMessage, WorkerOverview};
 use crate::internal::scheduler::state::SchedulerState;
 use crate::internal::server::comm::Comm;
@@ -20,7 +20,7 @@ use crate::internal::transfer::auth::{deserialize, serialize};
 use crate::resources::{ResourceAmount, ResourceDescriptorItem, ResourceDescriptorKind};
 use crate::task::SerializedTaskContext;
 use crate::worker::{ServerLostPolicy, WorkerConfiguration};
-use crate::{TaskId, WorkerId};
+use crate::{InstanceId, TaskId, WorkerId};
 use std::time::Duration;
 
 pub struct TestEnv {
@@ -238,6 +238,7 @@ pub struct TestComm {
     pub new_workers: Vec<(WorkerId, WorkerConfiguration)>,
     pub lost_workers: Vec<(WorkerId, Vec<TaskId>)>,
     pub worker_overviews: Vec<WorkerOverview>,
+    pub worker_responses: Vec<WorkerResponseMessage>,
 
     pub need_scheduling: bool,
 }
@@ -330,13 +331,19 @@ impl Comm for TestComm {
         self.need_scheduling = true;
     }
 
-    fn send_client_task_finished(&mut self, task_id: TaskId) {
+    fn send_client_task_finished(
+        &mut self,
+        task_id: TaskId,
+        _resource_usage: Option<TaskResourceUsage>,
+        _note: Option<String>,
+    ) {
         self.client_task_finished.push(task_id);
     }
 
     fn send_client_task_started(
         &mut self,
         task_id: TaskId,
+        _instance_id: InstanceId,
         _worker_id: &[WorkerId],
         _context: SerializedTaskContext,
     ) {
@@ -369,6 +376,10 @@ impl Comm for TestComm {
     fn send_client_worker_overview(&mut self, overview: WorkerOverview) {
         self.worker_overviews.push(overview);
     }
+
+    fn send_client_worker_response(&mut self, response: WorkerResponseMessage) {
+        self.worker_responses.push(response);
+    }
 }
 
 pub fn create_test_comm() -> TestComm {
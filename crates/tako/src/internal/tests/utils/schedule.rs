@@ -140,6 +140,8 @@ pub fn finish_on_worker<W: Into<WorkerId>, T: Into<TaskId>>(
         TaskFinishedMsg {
             id: task_id.into(),
             size,
+            resource_usage: None,
+            note: None,
         },
     );
 }
@@ -21,6 +21,10 @@ impl TestWorkerComm {
         assert_eq!(self.start_task_notifications, 0);
     }
 
+    pub fn take_messages(&mut self) -> Vec<FromWorkerMessage> {
+        std::mem::take(&mut self.messages)
+    }
+
     pub fn take_start_task_notifications(&mut self) -> usize {
         std::mem::take(&mut self.start_task_notifications)
     }
@@ -1,19 +1,32 @@
-use crate::hwstats::{CpuStats, MemoryStats, NetworkStats, WorkerHwState};
+use crate::hwstats::{
+    CpuStats, DiskStats, FilesystemStats, LoadAverage, MemoryStats, NetworkStats,
+    NumaNodeMemoryStats, WorkerHwState,
+};
 use psutil::cpu::CpuPercentCollector;
 use psutil::network::NetIoCountersCollector;
-use std::time::SystemTime;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+/// Size of a sector in `/proc/diskstats`, independent of the actual sector size of the device
+const DISKSTATS_SECTOR_SIZE: u64 = 512;
 
 #[derive(Debug, Clone)]
 pub(crate) struct HwSampler {
     cpu_percent_collector: CpuPercentCollector,
     net_io_counters_collector: NetIoCountersCollector,
+    /// Filesystem usage is measured for the filesystem containing this directory
+    work_dir: PathBuf,
+    /// Time and disk counters (read, written bytes) of the previous measurement
+    last_disk_counters: Option<(Instant, u64, u64)>,
 }
 
 impl HwSampler {
-    pub fn init() -> Result<Self, psutil::Error> {
+    pub fn init(work_dir: PathBuf) -> Result<Self, psutil::Error> {
         Ok(Self {
             cpu_percent_collector: CpuPercentCollector::new()?,
             net_io_counters_collector: Default::default(),
+            work_dir,
+            last_disk_counters: None,
         })
     }
     pub fn fetch_hw_state(&mut self) -> Result<WorkerHwState, psutil::Error> {
@@ -43,7 +56,183 @@ impl HwSampler {
                 rx_errors: net_io_counters.err_in(),
                 tx_errors: net_io_counters.err_out(),
             },
+            worker_disk_usage: self.fetch_disk_stats(),
+            worker_filesystem_usage: fetch_filesystem_stats(&self.work_dir),
+            load_average: fetch_load_average(),
+            numa_memory_usage: fetch_numa_memory_stats(),
             timestamp,
         })
     }
+
+    fn fetch_disk_stats(&mut self) -> DiskStats {
+        let (read_bytes, write_bytes) = match std::fs::read_to_string("/proc/diskstats") {
+            Ok(content) => parse_diskstats(&content, is_physical_disk),
+            Err(error) => {
+                log::debug!("Cannot read disk statistics: {error:?}");
+                return DiskStats::default();
+            }
+        };
+        let now = Instant::now();
+        let (read_throughput, write_throughput) = match self.last_disk_counters {
+            Some((last_time, last_read, last_written)) => {
+                let elapsed = now.duration_since(last_time).as_secs_f64();
+                let throughput = |current: u64, last: u64| {
+                    if elapsed > 0.0 {
+                        (current.saturating_sub(last) as f64 / elapsed) as u64
+                    } else {
+                        0
+                    }
+                };
+                (
+                    throughput(read_bytes, last_read),
+                    throughput(write_bytes, last_written),
+                )
+            }
+            None => (0, 0),
+        };
+        self.last_disk_counters = Some((now, read_bytes, write_bytes));
+        DiskStats {
+            read_bytes,
+            write_bytes,
+            read_throughput,
+            write_throughput,
+        }
+    }
+}
+
+/// Only whole physical devices are counted, partitions and virtual devices (loop, device mapper,
+/// RAID) would count the same IO operations multiple times.
+fn is_physical_disk(name: &str) -> bool {
+    Path::new("/sys/block").join(name).join("device").exists()
+}
+
+/// Returns the total number of read and written bytes of disks selected by `is_disk`.
+fn parse_diskstats(content: &str, is_disk: impl Fn(&str) -> bool) -> (u64, u64) {
+    let mut read_bytes = 0;
+    let mut write_bytes = 0;
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 || !is_disk(fields[2]) {
+            continue;
+        }
+        let sectors_read: u64 = fields[5].parse().unwrap_or(0);
+        let sectors_written: u64 = fields[9].parse().unwrap_or(0);
+        read_bytes += sectors_read * DISKSTATS_SECTOR_SIZE;
+        write_bytes += sectors_written * DISKSTATS_SECTOR_SIZE;
+    }
+    (read_bytes, write_bytes)
+}
+
+fn fetch_filesystem_stats(work_dir: &Path) -> Option<FilesystemStats> {
+    match psutil::disk::disk_usage(work_dir) {
+        Ok(usage) => Some(FilesystemStats {
+            total: usage.total(),
+            free: usage.free(),
+        }),
+        Err(error) => {
+            log::debug!(
+                "Cannot read filesystem usage of {}: {error:?}",
+                work_dir.display()
+            );
+            None
+        }
+    }
+}
+
+fn fetch_load_average() -> Option<LoadAverage> {
+    std::fs::read_to_string("/proc/loadavg")
+        .ok()
+        .and_then(|content| parse_loadavg(&content))
+}
+
+fn parse_loadavg(content: &str) -> Option<LoadAverage> {
+    let mut fields = content.split_whitespace().map(|field| field.parse().ok());
+    Some(LoadAverage {
+        one: fields.next()??,
+        five: fields.next()??,
+        fifteen: fields.next()??,
+    })
+}
+
+fn fetch_numa_memory_stats() -> Vec<NumaNodeMemoryStats> {
+    let entries = match std::fs::read_dir("/sys/devices/system/node") {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut nodes: Vec<NumaNodeMemoryStats> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let node: u32 = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("node")?
+                .parse()
+                .ok()?;
+            let content = std::fs::read_to_string(entry.path().join("meminfo")).ok()?;
+            let (total, free) = parse_node_meminfo(&content)?;
+            Some(NumaNodeMemoryStats { node, total, free })
+        })
+        .collect();
+    nodes.sort_unstable_by_key(|node| node.node);
+    nodes
+}
+
+/// Parses total and free memory (in bytes) from the `meminfo` file of a NUMA node, which contains
+/// lines like `Node 0 MemTotal:       16310304 kB`.
+fn parse_node_meminfo(content: &str) -> Option<(u64, u64)> {
+    let mut total = None;
+    let mut free = None;
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 4 {
+            continue;
+        }
+        let value = fields[3].parse::<u64>().ok().map(|kb| kb * 1024);
+        match fields[2] {
+            "MemTotal:" => total = value,
+            "MemFree:" => free = value,
+            _ => {}
+        }
+    }
+    Some((total?, free?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_diskstats, parse_loadavg, parse_node_meminfo};
+
+    #[test]
+    fn test_parse_diskstats() {
+        let content = "   7       0 loop0 10 0 100 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+ 253       0 vda 7541 2316 612318 2531 56478 33221 3117800 52873 0 67796 57128 0 0 0 0 1745 1722
+ 253       1 vda1 7100 2316 600000 2400 56000 33221 3100000 52000 0 67000 56000 0 0 0 0 0 0
+ 253      16 vdb 100 0 2000 10 50 0 1000 20 0 30 30";
+        let (read, written) = parse_diskstats(content, |name| name.starts_with("vd"));
+        assert_eq!(read, (612318 + 600000 + 2000) * 512);
+        assert_eq!(written, (3117800 + 3100000 + 1000) * 512);
+
+        let (read, written) = parse_diskstats(content, |name| name == "vdb");
+        assert_eq!((read, written), (2000 * 512, 1000 * 512));
+    }
+
+    #[test]
+    fn test_parse_loadavg() {
+        let load = parse_loadavg("0.35 0.60 1.59 1/73 20586\n").unwrap();
+        assert_eq!(load.one, 0.35);
+        assert_eq!(load.five, 0.60);
+        assert_eq!(load.fifteen, 1.59);
+        assert!(parse_loadavg("0.35").is_none());
+    }
+
+    #[test]
+    fn test_parse_node_meminfo() {
+        let content = "Node 1 MemTotal:        6147400 kB
+Node 1 MemFree:         1004336 kB
+Node 1 MemUsed:         5143064 kB";
+        assert_eq!(
+            parse_node_meminfo(content),
+            Some((6147400 * 1024, 1004336 * 1024))
+        );
+        assert_eq!(parse_node_meminfo("Node 1 MemTotal: 10 kB"), None);
+    }
 }
@@ -85,9 +85,25 @@ async fn execute_task(task_future: TaskFuture, state_ref: WorkerStateRef, task_i
     };
     let mut state = state_ref.get_mut();
     match result {
-        Ok(TaskResult::Finished) => {
+        Ok(TaskResult::Finished {
+            resource_usage,
+            note,
+        }) => {
             log::debug!("Inner task finished id={}", task_id);
-            state.finish_task(task_id, 0);
+            state.finish_task(task_id, 0, resource_usage, note);
+        }
+        Ok(TaskResult::Failed {
+            error,
+            resource_usage,
+        }) => {
+            log::debug!("Inner task failed id={}, error={:?}", task_id, error);
+            state.finish_task_failed(
+                task_id,
+                TaskFailInfo {
+                    resource_usage,
+                    ..TaskFailInfo::from_string(error.to_string())
+                },
+            );
         }
         Ok(TaskResult::Canceled) => {
             log::debug!("Inner task canceled id={}", task_id);
@@ -18,8 +18,9 @@ use crate::internal::common::resources::map::ResourceMap;
 use crate::internal::common::resources::{Allocation, AllocationValue};
 use crate::internal::common::WrappedRcRefCell;
 use crate::internal::messages::worker::{
-    FromWorkerMessage, StealResponseMsg, TaskResourceAllocation, TaskResourceAllocationValue,
-    ToWorkerMessage, WorkerOverview, WorkerRegistrationResponse, WorkerStopReason,
+    CustomResponseMsg, FromWorkerMessage, StealResponseMsg, TaskResourceAllocation,
+    TaskResourceAllocationValue, ToWorkerMessage, WorkerOverview, WorkerRegistrationResponse,
+    WorkerStopReason,
 };
 use crate::internal::server::rpc::ConnectionDescriptor;
 use crate::internal::transfer::auth::{
@@ -319,7 +320,7 @@ async fn idle_timeout_process(idle_timeout: Duration, state_ref: WrappedRcRefCel
         interval.tick().await;
 
         let state = state_ref.get();
-        if !state.has_tasks() && !state.reservation {
+        if !state.has_tasks() && !state.reservation && !state.idle_timeout_exempt {
             let elapsed = state.last_task_finish_time.elapsed();
             if elapsed > idle_timeout {
                 break;
@@ -366,6 +367,22 @@ pub(crate) fn process_worker_message(state: &mut WorkerState, message: ToWorkerM
                 state.reset_idle_timer();
             }
         }
+        ToWorkerMessage::SetIdleTimeoutExemption(exempt) => {
+            state.idle_timeout_exempt = exempt;
+            if !exempt {
+                state.reset_idle_timer();
+            }
+        }
+        ToWorkerMessage::CustomRequest(msg) => {
+            log::debug!("Custom request {} received", msg.id);
+            let result = state.task_launcher.handle_custom_request(&msg.data);
+            state
+                .comm()
+                .send_message_to_server(FromWorkerMessage::CustomResponse(CustomResponseMsg {
+                    id: msg.id,
+                    result,
+                }));
+        }
         ToWorkerMessage::Stop => {
             log::info!("Received stop command");
             return true;
@@ -393,7 +410,8 @@ async fn worker_message_loop(
 }
 
 async fn send_overview_loop(state_ref: WorkerStateRef, interval: Duration) -> crate::Result<()> {
-    let mut sampler = HwSampler::init()?;
+    let work_dir = state_ref.get().configuration.work_dir.clone();
+    let mut sampler = HwSampler::init(work_dir)?;
     let mut poll_interval = tokio::time::interval(interval);
     loop {
         poll_interval.tick().await;
@@ -10,7 +10,7 @@ use crate::internal::common::resources::map::ResourceMap;
 use crate::internal::common::resources::Allocation;
 use crate::internal::common::stablemap::StableMap;
 use crate::internal::common::{Map, Set, WrappedRcRefCell};
-use crate::internal::messages::common::TaskFailInfo;
+use crate::internal::messages::common::{TaskFailInfo, TaskResourceUsage};
 use crate::internal::messages::worker::{
     FromWorkerMessage, NewWorkerMsg, StealResponse, TaskFailedMsg, TaskFinishedMsg,
 };
@@ -45,6 +45,7 @@ pub struct WorkerState {
     pub(crate) start_time: Instant,
 
     pub(crate) reservation: bool, // If true, idle timeout is blocked
+    pub(crate) idle_timeout_exempt: bool, // If true, idle timeout is blocked
     pub(crate) last_task_finish_time: Instant,
 
     resource_map: ResourceMap,
@@ -213,9 +214,20 @@ impl WorkerState {
         self.running_tasks.insert(task_id);
     }
 
-    pub fn finish_task(&mut self, task_id: TaskId, size: u64) {
+    pub fn finish_task(
+        &mut self,
+        task_id: TaskId,
+        size: u64,
+        resource_usage: Option<TaskResourceUsage>,
+        note: Option<String>,
+    ) {
         self.remove_task(task_id, true);
-        let message = FromWorkerMessage::TaskFinished(TaskFinishedMsg { id: task_id, size });
+        let message = FromWorkerMessage::TaskFinished(TaskFinishedMsg {
+            id: task_id,
+            size,
+            resource_usage,
+            note,
+        });
         self.comm.send_message_to_server(message);
     }
 
@@ -294,6 +306,7 @@ impl WorkerStateRef {
             resource_map,
             last_task_finish_time: now,
             reservation: false,
+            idle_timeout_exempt: false,
             worker_addresses: Default::default(),
         })
     }
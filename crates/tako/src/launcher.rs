@@ -6,6 +6,7 @@ use std::process::Stdio;
 
 use crate::internal::common::error::DsError::GenericError;
 use crate::internal::common::resources::{Allocation, ResourceRequest};
+use crate::internal::messages::common::TaskResourceUsage;
 use bstr::ByteSlice;
 use nix::libc;
 use tokio::process::Command;
@@ -19,7 +20,18 @@ use crate::task::SerializedTaskContext;
 use crate::{InstanceId, TaskId, WorkerId};
 
 pub enum TaskResult {
-    Finished,
+    /// The task has finished successfully, the resource usage of its process and a note about its
+    /// execution (e.g. that its output was truncated) are sent to the server if they are known
+    Finished {
+        resource_usage: Option<TaskResourceUsage>,
+        note: Option<String>,
+    },
+    /// The task has failed, the resource usage of its process is sent to the server together with
+    /// the error if it is known
+    Failed {
+        error: crate::Error,
+        resource_usage: Option<TaskResourceUsage>,
+    },
     Canceled,
     Timeouted,
 }
@@ -117,6 +129,13 @@ pub trait TaskLauncher {
         ctx: LaunchContext,
         stop_receiver: tokio::sync::oneshot::Receiver<StopReason>,
     ) -> crate::Result<TaskLaunchData>;
+
+    /// Handles a custom request that was sent to this worker by the gateway user.
+    /// The result is sent back to the gateway user.
+    /// It is called synchronously from the worker message loop, so it should not take long.
+    fn handle_custom_request(&self, _data: &[u8]) -> Result<Vec<u8>, String> {
+        Err("Custom requests are not supported by this worker".to_string())
+    }
 }
 
 /// Create an output stream file on the given path.
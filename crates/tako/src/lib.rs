@@ -49,7 +49,9 @@ pub mod server {
 }
 
 pub mod worker {
-    pub use crate::internal::messages::worker::WorkerOverview;
+    pub use crate::internal::messages::worker::{
+        TaskResourceAllocation, TaskResourceAllocationValue, WorkerOverview,
+    };
     pub use crate::internal::worker::configuration::ServerLostPolicy;
     pub use crate::internal::worker::configuration::WorkerConfiguration;
 
@@ -57,5 +59,7 @@ pub mod worker {
 }
 
 pub mod task {
+    pub use crate::internal::messages::common::TaskResourceUsage;
+
     pub type SerializedTaskContext = Vec<u8>;
 }
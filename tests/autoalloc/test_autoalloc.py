@@ -486,6 +486,45 @@ def test_refresh_allocation_fail_queued_job(hq_env: HqEnv, spec: ManagerSpec):
         wait_for_alloc(hq_env, "FAILED", job_id)
 
 
+@all_managers
+def test_allocation_history_after_directory_removal(hq_env: HqEnv, spec: ManagerSpec):
+    manager = spec.manager
+    with MockJobManager(hq_env, spec.handler()):
+        hq_env.start_server(
+            env={
+                "HQ_AUTOALLOC_REFRESH_INTERVAL_MS": "100",
+                "HQ_AUTOALLOC_STATUS_CHECK_INTERVAL_MS": "100",
+                "HQ_AUTOALLOC_MAX_KEPT_DIRECTORIES": "0",
+            }
+        )
+        prepare_tasks(hq_env)
+
+        job_id = manager.job_id(0)
+        add_queue(hq_env, manager=spec.manager_type(), name="foo")
+        wait_for_alloc(hq_env, "QUEUED", job_id)
+
+        table = hq_env.command(["alloc", "info", "1"], as_table=True)
+        index = table.get_column_value("ID").index(job_id)
+        working_dir = Path(table.get_column_value("Working directory")[index])
+        stdout = "".join(f"line {i}\n" for i in range(100))
+        (working_dir / "stdout").write_text(stdout)
+        (working_dir / "stderr").write_text("error\n")
+
+        manager.set_job_data(job_id, JobData.finished())
+        wait_for_alloc(hq_env, "FINISHED", job_id)
+        wait_until(lambda: not working_dir.exists())
+
+        history = hq_env.command(["alloc", "history", "1"], as_json=True)
+        entry = [e for e in history if e["id"] == job_id][0]
+        assert entry["stdout_tail"] == "\n".join(f"line {i}" for i in range(90, 100))
+        assert entry["stderr_tail"] == "error"
+
+        log = hq_env.command(["alloc", "log", job_id], as_json=True)
+        assert log["stdout"] == stdout
+        assert log["stderr"] == "error\n"
+        assert not log["truncated"]
+
+
 def dry_run_cmd(spec: ManagerSpec) -> List[str]:
     return ["alloc", "dry-run", spec.manager_type(), "--time-limit", "1h"]
 
@@ -701,6 +740,30 @@ def test_autoalloc_pause_resume_queue_status(hq_env: HqEnv):
     table.check_column_value("State", 0, "RUNNING")
 
 
+def test_autoalloc_queue_budget(hq_env: HqEnv):
+    hq_env.start_server()
+    add_queue(
+        hq_env,
+        manager="pbs",
+        additional_worker_args=["--budget", "100node-h", "--budget-window", "7d"],
+    )
+
+    table = hq_env.command(["alloc", "list"], as_table=True)
+    table.check_column_value("Budget", 0, "100.0/100 node-h per 7days")
+
+
+def test_autoalloc_queue_always_active_schedule(hq_env: HqEnv):
+    hq_env.start_server()
+    add_queue(
+        hq_env,
+        manager="pbs",
+        additional_worker_args=["--active", "Mon-Sun"],
+    )
+
+    table = hq_env.command(["alloc", "list"], as_table=True)
+    table.check_column_value("Schedule", 0, "active")
+
+
 @all_managers
 def test_do_not_submit_from_paused_queue(hq_env: HqEnv, spec: ManagerSpec):
     with MockJobManager(hq_env, spec.handler()):
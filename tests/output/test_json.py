@@ -4,7 +4,7 @@ import socket
 from typing import List
 
 import iso8601
-from schema import Schema
+from schema import Or, Schema
 
 from ..conftest import HqEnv
 from ..utils import wait_for_job_state
@@ -52,6 +52,7 @@ def test_print_worker_info(hq_env: HqEnv):
                 "on_server_lost": "stop",
             },
             "ended": None,
+            "hw_state": Or(None, dict),
             "id": 1,
         }
     )
@@ -144,11 +145,42 @@ def test_print_job_detail(hq_env: HqEnv):
             "tasks": list,
             "task_dir": bool,
             "crash_limit": int,
+            "output_limit": None,
+            "spool": False,
         }
     )
     schema.validate(output)
 
 
+def test_print_job_stats(hq_env: HqEnv):
+    hq_env.start_server()
+    hq_env.start_worker()
+    hq_env.command(["submit", "--array=1-2", "echo", "tt"])
+    wait_for_job_state(hq_env, 1, "FINISHED")
+    output = parse_json_output(hq_env, ["--output-mode=json", "job", "stats", "1"])
+
+    duration_schema = {"min": float, "median": float, "p95": float, "max": float}
+    schema = Schema(
+        [
+            {
+                "info": {
+                    "id": 1,
+                    "name": "echo",
+                    "task_count": 2,
+                    "task_stats": dict,
+                },
+                "task_duration": duration_schema,
+                "wait_time": duration_schema,
+                "throughput": [{"start": str, "duration": float, "completed_tasks": int}],
+                "failures_per_worker": {},
+                "estimated_time_to_completion": 0.0,
+            }
+        ]
+    )
+    schema.validate(output)
+    assert sum(interval["completed_tasks"] for interval in output[0]["throughput"]) == 2
+
+
 def test_print_job_tasks_in_job_detail(hq_env: HqEnv):
     hq_env.start_server()
     hq_env.command(["submit", "--array=1-4", "echo", "tt"])
@@ -192,6 +224,63 @@ def test_print_task_placeholders(hq_env: HqEnv):
         )
 
 
+def test_print_task_resource_usage(hq_env: HqEnv):
+    hq_env.start_server()
+    hq_env.start_worker()
+    hq_env.command(["submit", "echo", "tt"])
+    wait_for_job_state(hq_env, 1, "FINISHED")
+
+    output = parse_json_output(hq_env, ["--output-mode=json", "task", "info", "1", "0"])
+
+    schema = Schema(
+        [
+            {
+                "id": 0,
+                "state": "finished",
+                "resource_usage": {
+                    "user_time": float,
+                    "system_time": float,
+                    "max_rss": int,
+                    "read_bytes": int,
+                    "write_bytes": int,
+                },
+            }
+        ],
+        ignore_extra_keys=True,
+    )
+    schema.validate(output)
+    assert output[0]["resource_usage"]["max_rss"] > 0
+
+
+def test_print_failed_task_resource_usage(hq_env: HqEnv):
+    hq_env.start_server()
+    hq_env.start_worker()
+    hq_env.command(["submit", "--", "bash", "-c", "exit 1"])
+    wait_for_job_state(hq_env, 1, "FAILED")
+
+    output = parse_json_output(hq_env, ["--output-mode=json", "task", "info", "1", "0"])
+
+    schema = Schema(
+        [
+            {
+                "id": 0,
+                "state": "failed",
+                "error": "Error: Program terminated with exit code 1",
+                "resource_usage": {
+                    "user_time": float,
+                    "system_time": float,
+                    "max_rss": int,
+                    "read_bytes": int,
+                    "write_bytes": int,
+                },
+            }
+        ],
+        ignore_extra_keys=True,
+    )
+    schema.validate(output)
+    assert output[0]["resource_usage"]["max_rss"] > 0
+
+
 def test_print_hw(hq_env: HqEnv):
     hq_env.start_server()
     output = parse_json_output(hq_env, ["--output-mode=json", "worker", "hwdetect"])
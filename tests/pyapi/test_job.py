@@ -247,3 +247,24 @@ def test_priority_task(hq_env: HqEnv):
 
     table = hq_env.command(["task", "info", str(submitted_job.id), "0"], as_table=True)
     table.check_row_value("Priority", "2")
+
+
+def test_stream_events(hq_env: HqEnv):
+    (job, client) = prepare_job_client(hq_env)
+    job.program(args=["hostname"])
+    submitted_job = client.submit(job)
+    client.wait_for_jobs([submitted_job])
+
+    events = []
+
+    def callback(event):
+        events.append(event)
+        return event["event"]["type"] != "job-completed"
+
+    client.stream_events(callback, jobs=[submitted_job], after_id=0)
+    assert [e["event"]["type"] for e in events] == [
+        "job-created",
+        "task-started",
+        "task-finished",
+        "job-completed",
+    ]
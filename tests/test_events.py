@@ -1,7 +1,10 @@
 import json
+import os
 from typing import List
 
-from .conftest import HqEnv
+from .conftest import HqEnv, get_hq_binary
+from .utils import wait_for_job_state
+from .utils.wait import wait_until
 
 
 def test_worker_connected_event(hq_env: HqEnv):
@@ -23,19 +26,225 @@ def test_worker_lost_event(hq_env: HqEnv):
     assert event["reason"] == "Stopped"
 
 
+def test_worker_stop_requested_event(hq_env: HqEnv):
+    def body():
+        hq_env.start_worker()
+        hq_env.command(["worker", "stop", "1"])
+
+    events = get_events(hq_env, body)
+    assert find_events(events, "worker-stop-requested")[0]["id"] == 1
+
+
+def test_job_canceled_events(hq_env: HqEnv):
+    def body():
+        hq_env.command(["submit", "--array", "1-2", "--", "sleep", "1"])
+        hq_env.command(["job", "cancel", "1"])
+
+    events = get_events(hq_env, body)
+    assert find_events(events, "job-canceled")[0]["job-id"] == 1
+    canceled = find_events(events, "task-canceled")
+    assert sorted(e["task-id"] for e in canceled) == [1, 2]
+    assert all(e["job-id"] == 1 for e in canceled)
+
+
+def test_job_canceled_event_finished_job(hq_env: HqEnv):
+    def body():
+        hq_env.start_worker()
+        hq_env.command(["submit", "--", "hostname"])
+        wait_for_job_state(hq_env, 1, "FINISHED")
+        hq_env.command(["job", "cancel", "1"])
+        hq_env.command(["job", "cancel", "all"])
+
+    events = get_events(hq_env, body)
+    assert find_events(events, "job-canceled") == []
+
+
+def test_task_failed_event(hq_env: HqEnv):
+    def body():
+        hq_env.start_worker()
+        hq_env.command(["submit", "--", "bash", "-c", "exit 1"])
+        wait_for_job_state(hq_env, 1, "FAILED")
+
+    events = get_events(hq_env, body)
+    event = find_events(events, "task-failed")[0]
+    assert event["job-id"] == 1
+    assert event["task-id"] == 0
+    assert event["instance-id"] == 0
+    assert "exit code 1" in event["error"]
+
+
+def test_stream_events(hq_env: HqEnv):
+    hq_env.start_server()
+    hq_env.start_process(
+        "stream",
+        [
+            get_hq_binary(),
+            "--server-dir",
+            hq_env.server_dir,
+            "event-log",
+            "stream",
+            "--type",
+            "job-completed",
+        ],
+    )
+    hq_env.start_worker()
+    hq_env.command(["submit", "--", "hostname"])
+    wait_for_job_state(hq_env, 1, "FINISHED")
+
+    def get_streamed_events():
+        with open(os.path.join(hq_env.work_path, "stream.out")) as f:
+            return [json.loads(line) for line in f if line.startswith("{")]
+
+    wait_until(lambda: len(get_streamed_events()) == 1)
+    event = get_streamed_events()[0]["event"]
+    assert event["type"] == "job-completed"
+    assert event["job-id"] == 1
+
+
+def test_event_hooks(hq_env: HqEnv, tmp_path):
+    output = tmp_path / "hook.out"
+    hq_env.start_server(
+        args=[
+            "--hook",
+            f"job-completed=echo $HQ_EVENT_TYPE $HQ_JOB_ID >> {output}; cat >> {output}",
+        ]
+    )
+    hq_env.start_worker()
+    hq_env.command(["submit", "--", "bash", "-c", "exit 1"])
+    wait_for_job_state(hq_env, 1, "FAILED")
+
+    def get_output():
+        if not output.exists():
+            return None
+        lines = output.read_text().splitlines()
+        return lines if len(lines) == 2 else None
+
+    lines = wait_until(get_output)
+    assert lines[0] == "job-completed 1"
+    event = json.loads(lines[1])["event"]
+    assert event["type"] == "job-completed"
+    assert event["job-id"] == 1
+
+
+def test_job_on_finish_hook(hq_env: HqEnv, tmp_path):
+    output = tmp_path / "finished.out"
+    hq_env.start_server()
+    hq_env.start_worker()
+    hq_env.command(["submit", "--on-finish", f"echo $HQ_JOB_ID > {output}", "--", "hostname"])
+    hq_env.command(["submit", "--", "hostname"])
+    wait_for_job_state(hq_env, [1, 2], "FINISHED")
+
+    wait_until(lambda: output.exists() and output.read_text().strip() == "1")
+
+
 def find_events(events, type: str) -> List:
     return [e["event"] for e in events if e["event"]["type"] == type]
 
 
-def get_events(hq_env: HqEnv, callback):
+def test_export_chrome_trace(hq_env: HqEnv):
+    def body():
+        hq_env.start_worker(cpus=2)
+        hq_env.command(["submit", "--array", "1-2", "--", "sleep", "1"])
+        wait_for_job_state(hq_env, 1, "FINISHED")
+
+    log_path = record_events(hq_env, body)
+    output = hq_env.command(
+        ["event-log", "export", "--format", "chrome-trace", log_path], ignore_stderr=True
+    )
+    trace = json.loads(output)["traceEvents"]
+
+    tasks = sorted((e for e in trace if e["ph"] == "X"), key=lambda e: e["args"]["task"])
+    assert [e["name"] for e in tasks] == ["Job 1, task 1", "Job 1, task 2"]
+    assert all(e["pid"] == 1 for e in tasks)
+    assert all(e["args"]["status"] == "finished" for e in tasks)
+    # Tasks were running concurrently, so they should use different slots
+    assert sorted(e["tid"] for e in tasks) == [1, 2]
+
+    assert "Worker connected" in [e["name"] for e in trace if e["ph"] == "i"]
+
+
+def record_events(hq_env: HqEnv, callback) -> str:
     log_path = "events.log"
     process = hq_env.start_server(args=["--event-log-path", log_path])
     callback()
     hq_env.command(["server", "stop"])
     process.wait(timeout=5)
     hq_env.processes.clear()
+    return log_path
+
+
+def test_query_events(hq_env: HqEnv):
+    def body():
+        hq_env.start_worker()
+        hq_env.command(["submit", "--", "bash", "-c", "exit 1"])
+        wait_for_job_state(hq_env, 1, "FAILED")
+        hq_env.command(["submit", "--array", "1-2", "--", "true"])
+        wait_for_job_state(hq_env, 2, "FINISHED")
+
+    log_path = record_events(hq_env, body)
+
+    def query(args: List[str]):
+        return hq_env.command(["event-log", "query", log_path] + args, ignore_stderr=True)
+
+    events = parse_ndjson(query(["--job", "2", "--type", "task-started,task-finished", "--ndjson"]))
+    types = sorted(e["event"]["type"] for e in events)
+    assert types == ["task-finished"] * 2 + ["task-started"] * 2
+
+    events = json.loads(
+        query(["--type", "task-failed,job-completed", "--worker", "1", "--output-mode", "json"])
+    )
+    assert [e["event"]["type"] for e in events] == ["task-failed"]
 
+    assert parse_ndjson(query(["--until", "2000-01-01", "--ndjson"])) == []
+    assert len(parse_ndjson(query(["--since", "2000-01-01", "--ndjson"]))) > 0
+
+    summary = json.loads(query(["--job", "1,2", "--summary", "--output-mode", "json"]))
+    assert summary["types"]["job-created"] == 2
+    assert summary["types"]["task-failed"] == 1
+    assert summary["types"]["task-finished"] == 2
+    assert "worker-connected" not in summary["types"]
+
+
+def test_event_log_rotation(hq_env: HqEnv):
+    log_dir = hq_env.work_path / "logs"
+    log_dir.mkdir()
+    log_path = str(log_dir / "events.bin")
+
+    process = hq_env.start_server(
+        args=["--event-log-path", log_path, "--event-log-max-size", "1KiB"]
+    )
+    hq_env.start_worker()
+    hq_env.command(
+        ["submit", "--array", "1-2000", "--stdout", "none", "--stderr", "none", "--", "true"]
+    )
+    wait_for_job_state(hq_env, 1, "FINISHED")
+    hq_env.command(["server", "stop"])
+    process.wait(timeout=5)
+    hq_env.processes.clear()
+
+    files = sorted(os.listdir(log_dir))
+    assert len(files) > 1
+    assert all(f.startswith("events.bin.") for f in files)
+
+    def export(path: str):
+        return parse_ndjson(hq_env.command(["event-log", "export", path], ignore_stderr=True))
+
+    events = export(log_path)
+    ids = [e["id"] for e in events]
+    assert ids == sorted(ids)
+    assert len([e for e in events if e["event"]["type"] == "task-finished"]) == 2000
+
+    assert export(str(log_dir)) == events
+    assert export(f"{log_path}.*") == events
+
+
+def get_events(hq_env: HqEnv, callback):
+    log_path = record_events(hq_env, callback)
     output = hq_env.command(["event-log", "export", log_path], ignore_stderr=True)
+    return parse_ndjson(output)
+
+
+def parse_ndjson(output: str):
     events = []
     for line in output.splitlines(keepends=False):
         events.append(json.loads(line))
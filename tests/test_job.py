@@ -717,6 +717,42 @@ def test_job_last(hq_env: HqEnv):
     table.check_row_value("ID", "2")
 
 
+def test_job_stats(hq_env: HqEnv):
+    hq_env.start_server()
+    hq_env.start_worker()
+
+    hq_env.command(["submit", "--array=1-3", "--", "bash", "-c", "exit $((HQ_TASK_ID == 2))"])
+    wait_for_job_state(hq_env, 1, "FAILED")
+
+    table = hq_env.command(["job", "stats", "1"], as_table=True)
+    table.check_row_value("ID", "1")
+    table.check_row_value("Failures per worker", "Worker 1: 1")
+    table.check_row_value("Estimated time to completion", "Completed")
+
+
+def test_job_stats_from_event_log(hq_env: HqEnv):
+    log_path = "events.log"
+    process = hq_env.start_server(args=["--event-log-path", log_path])
+    hq_env.start_worker()
+    hq_env.command(["submit", "--array=1-3", "--", "bash", "-c", "exit $((HQ_TASK_ID == 2))"])
+    wait_for_job_state(hq_env, 1, "FAILED")
+    hq_env.command(["server", "stop"])
+    process.wait(timeout=5)
+    hq_env.processes.clear()
+
+    output = hq_env.command(
+        ["--output-mode=json", "job", "stats", "1", "--event-log", log_path],
+        ignore_stderr=True,
+        as_json=True,
+    )
+    assert len(output) == 1
+    stats = output[0]
+    assert stats["info"]["task_stats"]["finished"] == 2
+    assert stats["info"]["task_stats"]["failed"] == 1
+    assert stats["failures_per_worker"] == {"1": 1}
+    assert stats["estimated_time_to_completion"] == 0.0
+
+
 def test_job_resubmit_with_status(hq_env: HqEnv):
     hq_env.start_server()
     hq_env.command(
@@ -1342,6 +1378,59 @@ def test_custom_error_message(hq_env: HqEnv):
     # print(table)
 
 
+def test_output_limit_truncate(hq_env: HqEnv):
+    hq_env.start_server()
+    hq_env.start_worker()
+
+    hq_env.command(
+        [
+            "submit",
+            "--max-output-size",
+            "10",
+            "--",
+            "python",
+            "-c",
+            "print('a' * 100)",
+        ]
+    )
+    wait_for_job_state(hq_env, 1, "FINISHED")
+
+    assert (
+        read_file(default_task_output())
+        == "a" * 10
+        + "\n[HyperQueue: output truncated, it has exceeded the limit of 10 B]\n"
+    )
+    table = hq_env.command(["task", "info", "1", "0"], as_table=True)
+    table.check_row_value(
+        "Note", "Output truncated: stdout of the task has exceeded the limit of 10 B"
+    )
+
+
+def test_output_limit_fail(hq_env: HqEnv):
+    hq_env.start_server()
+    hq_env.start_worker()
+
+    hq_env.command(
+        [
+            "submit",
+            "--max-output-size",
+            "10",
+            "--on-output-limit",
+            "fail",
+            "--",
+            "python",
+            "-c",
+            "import sys; print('a' * 100, file=sys.stderr)",
+        ]
+    )
+    wait_for_job_state(hq_env, 1, "FAILED")
+
+    table = hq_env.command(["task", "list", "1", "-v"], as_table=True)
+    assert table.get_column_value("Error")[0] == (
+        "Error: Output limit exceeded: stderr of the task has exceeded the limit of 10 B"
+    )
+
+
 def test_long_custom_error_message(hq_env: HqEnv):
     hq_env.start_server()
     hq_env.start_worker()
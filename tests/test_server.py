@@ -82,6 +82,47 @@ def test_server_info(hq_env: HqEnv):
     assert len(table) == 8
 
 
+def test_server_stats_history(hq_env: HqEnv):
+    hq_env.start_server()
+
+    table = hq_env.command(["server", "stats"], as_table=True)
+    assert table.get_column_value("Statistic") == [
+        "Tasks finished / 1m",
+        "Waiting tasks",
+        "Running tasks",
+        "Workers",
+        "CPU utilization (%)",
+        "Scheduler runs / 1m",
+        "Scheduler duration (ms)",
+    ]
+
+    output = json.loads(
+        hq_env.command(["server", "stats", "--window", "10m", "--output-mode", "json"])
+    )
+    assert output["interval"] == 60.0
+    keys = [
+        "time",
+        "tasks_finished",
+        "tasks_waiting",
+        "tasks_running",
+        "workers",
+        "cpu_usage",
+        "scheduler_runs",
+        "scheduler_duration",
+    ]
+    assert sorted(output.keys()) == sorted(keys + ["interval"])
+    assert len(output["time"]) >= 1
+    for key in keys:
+        assert len(output[key]) == len(output["time"])
+    assert output["workers"][0] == 0
+
+    # The start of the window is out of range, all samples are returned
+    output = json.loads(
+        hq_env.command(["server", "stats", "--window", "300000years", "--output-mode", "json"])
+    )
+    assert len(output["time"]) >= 1
+
+
 def test_server_stop(hq_env: HqEnv):
     process = hq_env.start_server()
     hq_env.command(["server", "stop"])
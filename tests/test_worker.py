@@ -6,6 +6,7 @@ import pytest
 
 from .conftest import HqEnv
 from .utils import wait_for_job_state, wait_for_worker_state
+from .utils.wait import wait_until
 from .utils.table import Table
 
 
@@ -228,6 +229,23 @@ def test_worker_info(hq_env: HqEnv):
     table.check_row_value("Group", "default")
 
 
+def test_worker_info_hw_state(hq_env: HqEnv):
+    hq_env.start_server()
+    hq_env.start_worker()
+
+    def has_overview():
+        table = hq_env.command(["worker", "info", "1"], as_table=True)
+        return table.get_row_value("Memory usage") != "N/A"
+
+    wait_until(has_overview)
+
+    table = hq_env.command(["worker", "info", "1"], as_table=True)
+    assert table.get_row_value("CPU utilization").endswith("%")
+    assert table.get_row_value("Disk IO").startswith("read")
+    assert table.get_row_value("Load average") is not None
+    assert table.get_row_value("Work dir filesystem") is not None
+    assert table.get_row_value("NUMA memory usage") is not None
+
 def test_worker_group(hq_env: HqEnv):
     hq_env.start_server()
     hq_env.start_worker(cpus="10", args=["--group", "test_1"])